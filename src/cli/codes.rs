@@ -0,0 +1,552 @@
+/// Stable code of a kind of diagnostic, shown with the message so the diagnostic can be looked up with --explain (and
+/// searched for). Codes are never reused for a different kind of diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode
+{
+    E0001,
+    E0002,
+    E0003,
+    E0004,
+    E0005,
+    E0006,
+    E0007,
+    E0008,
+    E0009,
+    E0010,
+    E0011,
+    E0012,
+    E0013,
+    E0014,
+    E0015,
+    E0016,
+    E0017,
+    E0018,
+    E0019,
+    E0020,
+    E0021,
+    E0022,
+    E0023,
+    E0024,
+    E0025,
+    E0026,
+    E0027,
+    E0028,
+    E0029,
+    E0030,
+    E0031,
+    E0032,
+    E0033,
+    E0034,
+    E0035,
+    E0036,
+    E0037,
+    E0038,
+    E0039,
+    E0040,
+    W0001,
+    W0002,
+    W0003,
+    W0004,
+    W0005,
+    W0006,
+    W0007,
+    W0008,
+    W0009
+}
+
+/// Every code, in order
+static CODES: &[ErrorCode] = &[
+    ErrorCode::E0001, ErrorCode::E0002, ErrorCode::E0003, ErrorCode::E0004, ErrorCode::E0005, ErrorCode::E0006,
+    ErrorCode::E0007, ErrorCode::E0008, ErrorCode::E0009, ErrorCode::E0010, ErrorCode::E0011, ErrorCode::E0012,
+    ErrorCode::E0013, ErrorCode::E0014, ErrorCode::E0015, ErrorCode::E0016, ErrorCode::E0017, ErrorCode::E0018,
+    ErrorCode::E0019, ErrorCode::E0020, ErrorCode::E0021, ErrorCode::E0022, ErrorCode::E0023, ErrorCode::E0024,
+    ErrorCode::E0025, ErrorCode::E0026, ErrorCode::E0027, ErrorCode::E0028, ErrorCode::E0029, ErrorCode::E0030,
+    ErrorCode::E0031, ErrorCode::E0032, ErrorCode::E0033, ErrorCode::E0034, ErrorCode::E0035, ErrorCode::E0036,
+    ErrorCode::E0037, ErrorCode::E0038, ErrorCode::E0039, ErrorCode::E0040, ErrorCode::W0001, ErrorCode::W0002,
+    ErrorCode::W0003, ErrorCode::W0004, ErrorCode::W0005, ErrorCode::W0006, ErrorCode::W0007, ErrorCode::W0008,
+    ErrorCode::W0009
+    ];
+
+impl ErrorCode
+{
+    /// Get the code with the given name, as in E0012
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        CODES.iter().find(|code| code.name() == name.trim().to_uppercase()).copied()
+    }
+
+    /// Get the name of the code, as in E0012
+    pub fn name(&self) -> String
+    {
+        format!("{:?}", self)
+    }
+
+    /// Get the long form description of the code, with an example of code which produces it
+    pub fn explanation(&self) -> &'static str
+    {
+        match self
+        {
+            ErrorCode::E0001 => "\
+A token was found where the parser expected something else.
+
+    void main()
+    {
+        u8 x = 1
+        putchar(x);
+    }
+
+Here the declaration of x is missing its ';'. The parser skips to the end of the
+statement and carries on, so every syntax error in a file is shown at once.",
+            ErrorCode::E0002 => "\
+The file ended in the middle of a construct, most often a block which is never
+closed.
+
+    void main()
+    {
+        putchar(65);
+
+Close every '{' with a '}'.",
+            ErrorCode::E0003 => "\
+A preprocessor directive is malformed: a bad macro name, an unknown directive, an
+#else or #endif without an #ifdef, or an #ifdef which is never ended.
+
+    #ifdef 16MHZ
+    #define F_CPU 16000000
+    #endif
+
+Macro names must be identifiers, so 16MHZ can't be one.",
+            ErrorCode::E0004 => "\
+A file given to #include can't be found, or includes itself.
+
+    #include \"pins.pch\"
+
+Quoted files are searched for next to the including file and then in the
+directories given with -I, files in angle brackets only in those directories.",
+            ErrorCode::E0005 => "\
+An #error directive was compiled, which stops compilation with its message.
+
+    #ifndef F_CPU
+    #error F_CPU must be defined
+    #endif
+
+Define the macro (for example with -D F_CPU=16000000) or remove the directive.",
+            ErrorCode::E0006 => "\
+A module can't be imported: the file doesn't exist, its name isn't a valid
+module name, it is imported twice, or two modules have the same name.
+
+    import \"uart-driver.pc\";
+
+Module names are taken from the file name, which must be a valid identifier (as
+in uart_driver.pc).",
+            ErrorCode::E0007 => "\
+Modules import each other in a cycle, so they can't be ordered.
+
+    // a.pc
+    import \"b.pc\";
+
+    // b.pc
+    import \"a.pc\";
+
+Move the items both modules need into a third module imported by both.",
+            ErrorCode::E0008 => "\
+The same name is defined more than once, either as two globals or functions, as
+two methods of the same type, or by a use of a name which is already defined.
+
+    u8 count = 0;
+    u16 count = 0;
+
+Rename one of the definitions.",
+            ErrorCode::E0009 => "\
+A function has the same name as a builtin, which would make calls ambiguous.
+
+    u8 popcount(u8 x)
+    {
+        ...
+    }
+
+Rename the function, builtins such as popcount are always available.",
+            ErrorCode::E0010 => "\
+A path to an item of another module can't be resolved, because the module isn't
+imported or has no item with that name.
+
+    import \"uart.pc\";
+
+    void main()
+    {
+        uart::sned(65);
+    }
+
+Check the name of the module and of the item.",
+            ErrorCode::E0011 => "\
+A function marked #[noreturn] may return.
+
+    #[noreturn]
+    void halt()
+    {
+        putchar(33);
+    }
+
+Every path through a #[noreturn] function must end in a loop or in a call to
+another function which never returns.",
+            ErrorCode::E0012 => "\
+A name was used which isn't a variable, an argument or a global in scope.
+
+    void main()
+    {
+        coutn = 1;
+    }
+
+Check the spelling of the name, and that it is declared before it is used and
+in a block which encloses the use.",
+            ErrorCode::E0013 => "\
+A function was called which isn't defined, imported or declared.
+
+    void main()
+    {
+        pritnf(\"hi\");
+    }
+
+Check the spelling of the name, or import the module which defines it.",
+            ErrorCode::E0014 => "\
+A method was called on a type which has no method with that name.
+
+    impl u8* { u8 first(u8* self) { return *self; } }
+
+    void main(u8* p)
+    {
+        p.last();
+    }
+
+Methods are looked up by the type of the value they are called on.",
+            ErrorCode::E0015 => "\
+A method is defined badly: its first argument must be 'self' of the type of the
+impl, and it can't be generic.
+
+    impl u16* { u16 sum(u16* values, u16 n) { ... } }
+
+Name the first argument self.",
+            ErrorCode::E0016 => "\
+A function is marked with an attribute the compiler doesn't know.
+
+    #[no_return]
+    void halt() { loop {} }
+
+The only attribute is #[noreturn].",
+            ErrorCode::E0017 => "\
+A function or a builtin was called with the wrong number of arguments.
+
+    u8 add(u8 a, u8 b) { return a + b; }
+
+    void main()
+    {
+        add(1);
+    }
+
+Give every argument the function takes.",
+            ErrorCode::E0018 => "\
+Type arguments were given to a function which isn't generic, or the wrong number
+of them was given to a generic function.
+
+    T max<T>(T a, T b) { ... }
+
+    void main()
+    {
+        max<u8, u8>(1, 2);
+    }
+
+Give one type argument for each type parameter.",
+            ErrorCode::E0019 => "\
+The type of a value can't be worked out, either a variable declared with let
+from a value of unknown type or a type parameter of a generic call.
+
+    T zero<T>() { return 0; }
+
+    void main()
+    {
+        zero();
+    }
+
+Give the type explicitly, as in zero<u8>() or u8 x = ... instead of let.",
+            ErrorCode::E0020 => "\
+A type is written badly, such as an integer wider than the widest supported, a
+qualifier in the wrong place, or progmem on something other than a pointer or a
+global.
+
+    u200 x = 0;
+
+Integers can be at most 128 bits wide.",
+            ErrorCode::E0021 => "\
+A value can't be converted to the type it is used as, such as a pointer given
+where an integer is expected, a pointer into program memory given where a
+pointer into data memory is expected, or a value returned from a void function.
+Arguments of calls are only converted to wider types of the parameters, so a u8
+can be passed as a u16 or an i16, but an i8 can't be passed as a u16.
+
+    void main(u8* p)
+    {
+        u16 x = p;
+    }
+
+Convert the value explicitly with a cast if the conversion is intended.",
+            ErrorCode::E0022 => "\
+An operator was given operands it can't work on, such as two pointers added
+together, pointers to different types compared or subtracted, or something other
+than a pointer dereferenced.
+
+    void main(u8* a, u16* b)
+    {
+        if (a == b) {}
+    }
+
+Cast one of the operands so the types match.",
+            ErrorCode::E0023 => "\
+A value was indexed, sliced or had a member taken which doesn't support it, such
+as indexing an integer or taking the length of a pointer.
+
+    void main(u8 x)
+    {
+        putchar(x[0]);
+    }
+
+Only pointers, arrays and slices can be indexed, and only slices and arrays have
+a length.",
+            ErrorCode::E0024 => "\
+A write was made through a pointer into program memory, which is read only.
+
+    progmem u8 table[] = {1, 2, 3};
+
+    void main()
+    {
+        table[0] = 4;
+    }
+
+Copy the data into memory first if it has to change.",
+            ErrorCode::E0025 => "\
+An expression which gives no value was used as a value, such as a call to a
+void function.
+
+    void beep() {}
+
+    void main()
+    {
+        u8 x = beep();
+    }
+
+Only use calls to functions which return a value as values.",
+            ErrorCode::E0026 => "\
+A function returns a slice, which can't be returned.
+
+    u8[] name() { ... }
+
+Return a pointer and a length separately, or take the slice as an argument.",
+            ErrorCode::E0027 => "\
+A break or continue was used outside of a loop.
+
+    void main()
+    {
+        break;
+    }
+
+break and continue can only be used inside of while, do while and loop.",
+            ErrorCode::E0028 => "\
+A builtin was given an argument of the wrong kind, such as a pointer where an
+integer is expected.
+
+    void main(u8* p)
+    {
+        popcount(p);
+    }
+
+Check the arguments the builtin expects in the list of intrinsics.",
+            ErrorCode::E0029 => "\
+A bit or a range of bits is out of range for the integer it is taken from, or is
+taken from something other than an integer.
+
+    void main(u8 x)
+    {
+        bit_set(&x, 8);
+    }
+
+The bits of an N bit integer are numbered from 0 to N - 1.",
+            ErrorCode::E0030 => "\
+An expression which must be constant (such as the value of a global or the
+length of an array) uses something which isn't, such as a call, an assignment or
+another global which isn't constant.
+
+    u8 count = 1;
+    u8 buffer[count];
+
+Use an integer literal or a constant expression.",
+            ErrorCode::E0031 => "\
+A constant expression overflowed, divided by zero or shifted out of range.
+
+    u8 buffer[256 / 0];
+
+The value of a constant expression is worked out when compiling.",
+            ErrorCode::E0032 => "\
+An integer literal is too large for any type, or a literal was expected.
+
+    u8 x = 999999999999999999999999999999999999999999;
+
+Integer literals must fit in 128 bits.",
+            ErrorCode::E0033 => "\
+A global is defined badly, such as a progmem global without a value, an array
+without a length or values, or an initializer list for something which isn't
+an array.
+
+    progmem u8 table[4];
+
+Globals in program memory can't be written to, so they must be given values.",
+            ErrorCode::E0034 => "\
+A value in the initializer of a global doesn't fit in its type.
+
+    u8 values[] = {1, 2, 300};
+
+Use a wider type or a smaller value.",
+            ErrorCode::E0035 => "\
+The code uses something the selected code generator doesn't support.
+
+    u8 rotate(u8 x, u8 n) { return rotate_left(x, n); }
+
+compiled with -g avrasm, which only rotates by constant amounts. Use the llvm
+code generator, or rewrite the code.",
+            ErrorCode::E0036 => "\
+An internal error in the compiler, the program reached a state the compiler
+doesn't expect. This is a bug in the compiler rather than in the program, please
+report it along with the code which produces it.",
+            ErrorCode::E0037 => "\
+Generic functions were instantiated with too many different types, usually
+because a generic function calls itself with an ever larger type.
+
+    T grow<T>(T x) { return grow<T*>(&x); }
+
+Each call instantiates the function again, so the instantiations never end.",
+            ErrorCode::E0038 => "\
+A character which can't appear in a program, outside of a comment or a string.
+
+    u8 price = 5$;
+
+Identifiers are made of ASCII letters, digits and '_', and any whitespace
+(including tabs and Unicode spaces) separates tokens.",
+            ErrorCode::E0039 => "\
+A function needs more registers at once than the code generator has, which can
+happen with -g avrasm where every live value is kept in a register.
+
+    u32 a = 1;
+    u32 b = 2;
+    u32 c = a + b;
+
+Use narrower types, or split the work so fewer values are live at the same
+time.",
+            ErrorCode::E0040 => "\
+A value is wider than the code generator supports, which with -g avrasm is
+anything wider than 32 bits, as every value is kept in registers.
+
+    u64 total = 0;
+
+Use a type of up to 32 bits, or compile with -g llvm.",
+            ErrorCode::W0001 => "\
+An implicit conversion may change the value, such as converting a signed value
+to an unsigned type or a wider integer to a narrower one (implicit-narrowing).
+
+    void main(i16 x)
+    {
+        u8 y = x;
+    }
+
+Cast the value explicitly if the conversion is intended.",
+            ErrorCode::W0002 => "\
+A constant doesn't fit in the type (or the bits) it is stored in, and is
+truncated (overflowing-literal).
+
+    u8 x = 300;
+
+Use a wider type or a smaller constant.",
+            ErrorCode::W0003 => "\
+Code is never reached, because it follows a return, break or continue, a loop
+which never ends, a call to a function which never returns, or a condition given
+to assume which is always false (unreachable-code).
+
+    u8 sign(i8 x)
+    {
+        return x < 0;
+        putchar(45);
+    }
+
+Remove the code, or use unreachable() to mark code which is never reached.",
+            ErrorCode::W0004 => "\
+A variable is declared but its value is never read (unused-variable).
+
+    void main()
+    {
+        u8 count = 0;
+        count = 1;
+    }
+
+Remove the variable, or start its name with an underscore if it is unused on
+purpose.",
+            ErrorCode::W0005 => "\
+A function or a method is never called (unused-function).
+
+    u8 twice(u8 x) { return x * 2; }
+
+    void main() {}
+
+Remove the function, or start its name with an underscore if it is unused on
+purpose. main and arith_panic are never reported, and a function called only by
+an unused function counts as used.",
+            ErrorCode::W0006 => "\
+A declaration has the same name as a variable, an argument or a global declared
+before it, which can no longer be used by that name (shadowing). This lint is
+off unless it is turned on with -W shadowing.
+
+    u8 count = 0;
+
+    void main(u8 x)
+    {
+        u8 count = x;
+    }
+
+Rename one of the declarations.",
+            ErrorCode::W0007 => "\
+A comparison of an integer with a constant always gives the same result,
+because the constant is out of the range of the type of the integer
+(constant-comparison).
+
+    void main(u8 x)
+    {
+        if (x < 0) {}
+    }
+
+Check the type of the integer, or the constant.",
+            ErrorCode::W0008 => "\
+A function which returns a value may reach its end without returning one, in
+which case the value it gives is undefined (missing-return).
+
+    u8 sign(i8 x)
+    {
+        if (x < 0) { return 1; }
+    }
+
+Return a value on every path through the function. Paths are followed through the
+code before it is optimized, so only branches on constants are known to go one
+way.",
+            ErrorCode::W0009 => "\
+A variable may be read on a path through the function which doesn't run its
+declaration first (use-before-assign). A variable can be used after the block
+it is declared in, but only has a value if the block ran.
+
+    u8 pick(u8 c)
+    {
+        if (c)
+        {
+            u8 x = 1;
+        }
+
+        return x;
+    }
+
+Declare the variable in a block which encloses every use of it."
+        }
+    }
+}
@@ -1,4 +1,10 @@
 use std::fmt;
+use std::io::IsTerminal;
+use std::collections::HashSet;
+
+use super::{Options, ErrorCode, Lint, LintLevels};
+use crate::diagnostics::{Label, Palette, render_snippets, render_json};
+use crate::tokenizer::Span;
 
 /// Error severity
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,7 +20,11 @@ pub enum Severity
 pub struct Error
 {
     message: String,
-    severity: Severity
+    severity: Severity,
+    code: Option<ErrorCode>,
+    lint: Option<Lint>,
+    labels: Vec<Label>,
+    notes: Vec<String>
 }
 
 impl Error
@@ -25,7 +35,11 @@ impl Error
         Self
         {
             message: String::from(msg),
-            severity: Severity::Warning
+            severity: Severity::Warning,
+            code: None,
+            lint: None,
+            labels: vec![],
+            notes: vec![]
         }
     }
 
@@ -35,7 +49,11 @@ impl Error
         Self
         {
             message: String::from(msg),
-            severity: Severity::Error
+            severity: Severity::Error,
+            code: None,
+            lint: None,
+            labels: vec![],
+            notes: vec![]
         }
     }
 
@@ -45,67 +63,248 @@ impl Error
         Self
         {
             message: String::from(msg),
-            severity: Severity::FatalError
+            severity: Severity::FatalError,
+            code: None,
+            lint: None,
+            labels: vec![],
+            notes: vec![]
         }
     }
+
+    /// Give the error the code of its kind of diagnostic
+    pub fn with_code(mut self, code: ErrorCode) -> Self
+    {
+        self.code = Some(code);
+        self
+    }
+
+    /// Mark the warning as given by a lint, which also gives it the code of the lint
+    pub fn with_lint(mut self, lint: Lint) -> Self
+    {
+        self.code = Some(lint.code());
+        self.lint = Some(lint);
+        self
+    }
+
+    /// Attach a label to the source where the problem is
+    pub fn with_label(mut self, span: Span, message: &str) -> Self
+    {
+        self.labels.push(Label::primary(span, message));
+        self
+    }
+
+    /// Attach a label to the source where the problem is, unless the error already points at the source
+    pub fn with_fallback_label(self, span: &Option<Span>, message: &str) -> Self
+    {
+        match span
+        {
+            Some(span) if self.labels.is_empty() => self.with_label(span.clone(), message),
+            _ => self
+        }
+    }
+
+    /// Attach a label to source related to the problem
+    pub fn with_secondary_label(mut self, span: Span, message: &str) -> Self
+    {
+        self.labels.push(Label::secondary(span, message));
+        self
+    }
+
+    /// Attach a note, shown after the source
+    pub fn with_note(mut self, note: &str) -> Self
+    {
+        self.notes.push(String::from(note));
+        self
+    }
+}
+
+impl Error
+{
+    /// Get the name of the severity of the error, along with the color it is shown in
+    fn severity_name(&self) -> (&'static str, &'static str)
+    {
+        match self.severity
+        {
+            Severity::Warning => ("warning", "\x1b[1m\x1b[33m"),
+            Severity::Error => ("error", "\x1b[1m\x1b[31m"),
+            Severity::FatalError => ("fatal error", "\x1b[1m\x1b[31m")
+        }
+    }
+
+    /// Render the error as text, colored with escape codes if requested
+    pub fn render(&self, colored: bool) -> String
+    {
+        let (name, color) = self.severity_name();
+        let palette = Palette::new(color, colored);
+
+        let code = self.code.map(|code| format!("[{}]", code.name())).unwrap_or_default();
+
+        // The source the error is about is shown under the message
+        format!("compiler: {}{}{}{}: {}{}", palette.severity, name, code, palette.reset, self.message,
+                render_snippets(&self.labels, &self.notes, &palette))
+    }
+
+    /// Render the error as a JSON object
+    pub fn render_json(&self) -> String
+    {
+        render_json(self.severity_name().0, self.code.map(|code| code.name()).as_deref(), &self.message, &self.labels, &self.notes)
+    }
 }
 
 impl fmt::Display for Error
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-        // Render an error with the given colorings
-        write!(f, "compiler: ")?;
-        match self.severity
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// How errors are shown, either as text (which may be colored) or as JSON objects
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorFormat
+{
+    pub json: bool,
+    pub color: bool
+}
+
+impl ErrorFormat
+{
+    /// Get the error format from the options, text is colored by default only when shown on a terminal
+    pub fn from_options(opts: &Options) -> Result<Self, Error>
+    {
+        let json = match opts.map.get("--error-format").and_then(|values| values.last()).map(|value| value.as_str())
         {
-            Severity::Warning =>
-            {
-                write!(f, "\x1b[1m\x1b[33mwarning\x1b[0m")?;
-            },
-            Severity::Error =>
-            {
-                write!(f, "\x1b[1m\x1b[31merror\x1b[0m")?;
-            },
-            Severity::FatalError =>
-            {
-                write!(f, "\x1b[1m\x1b[31mfatal error\x1b[0m")?;
-            }
+            None | Some("human") => false,
+            Some("json") => true,
+            Some(value) => Err(Error::fatal_error(&format!("Bad error format '{}', expected human or json", value)))?
+        };
+
+        let color = match opts.map.get("--color").and_then(|values| values.last()).map(|value| value.as_str())
+        {
+            None | Some("auto") => std::io::stderr().is_terminal(),
+            Some("always") => true,
+            Some("never") => false,
+            Some(value) => Err(Error::fatal_error(&format!("Bad color choice '{}', expected auto, always or never", value)))?
+        };
+
+        Ok(Self
+        {
+            json,
+            color
+        })
+    }
+
+    /// Render an error in the format
+    pub fn render(&self, error: &Error) -> String
+    {
+        if self.json
+        {
+            error.render_json()
+        }
+        else
+        {
+            error.render(self.color)
         }
+    }
+}
 
-        write!(f, ": {}", self.message)
+impl Default for ErrorFormat
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            json: false,
+            color: std::io::stderr().is_terminal()
+        }
     }
 }
 
 /// Wrapper for an error recorder
 pub struct ErrorRecorder
 {
-    recorded_errors: Vec<Error>
+    recorded_errors: Vec<Error>,
+    format: ErrorFormat,
+    lints: LintLevels,
+    shown: HashSet<String>,
+    error_count: usize,
+    warning_count: usize
 }
 
 impl ErrorRecorder
 {
-    /// Generate a new error recorder
-    pub fn new() -> Self
+    /// Generate a new error recorder, which shows errors in the given format and warnings from the lints which are on
+    pub fn new(format: ErrorFormat, lints: LintLevels) -> Self
     {
         Self
         {
-            recorded_errors: vec![]
+            recorded_errors: vec![],
+            format,
+            lints,
+            shown: HashSet::new(),
+            error_count: 0,
+            warning_count: 0
         }
     }
 
-    /// Report a new error
-    pub fn report_error(&mut self, error: Error) -> Result<(), Error>
+    /// Generate a new error recorder from the error format and lint levels given in the options
+    pub fn from_options(opts: &Options) -> Result<Self, Error>
+    {
+        Ok(Self::new(ErrorFormat::from_options(opts)?, LintLevels::from_options(opts)?))
+    }
+
+    /// Report a new error, warnings from lints which are off are dropped and with -Werror any other warning becomes an
+    /// error
+    pub fn report_error(&mut self, mut error: Error) -> Result<(), Error>
     {
         if error.severity == Severity::FatalError
         {
-            Err(error)
+            return Err(error);
         }
-        else
+
+        if error.severity == Severity::Warning
         {
-            eprintln!("{}", error);
-            self.recorded_errors.push(error);
-            Ok(())
+            if let Some(lint) = error.lint
+            {
+                if !self.lints.is_enabled(lint)
+                {
+                    return Ok(());
+                }
+
+                error = if self.lints.warnings_as_errors
+                {
+                    error.with_note(&format!("'{}' is turned into an error by -Werror", lint.name()))
+                }
+                else
+                {
+                    error.with_note(&format!("'{}' can be turned off with -A {}", lint.name(), lint.name()))
+                };
+            }
+
+            if self.lints.warnings_as_errors
+            {
+                error.severity = Severity::Error;
+            }
         }
+
+        let rendered = self.format.render(&error);
+
+        // Each instantiation of a generic function gives the same warnings again
+        if !self.shown.insert(rendered.clone())
+        {
+            return Ok(());
+        }
+
+        eprintln!("{}", rendered);
+
+        match error.severity
+        {
+            Severity::Warning => self.warning_count += 1,
+            _ => self.error_count += 1
+        }
+
+        self.recorded_errors.push(error);
+        Ok(())
     }
 
     /// Unwrap a return value while reporting an error
@@ -122,12 +321,46 @@ impl ErrorRecorder
         }
     }
 
+    /// Get the number of errors reported, other than fatal errors
+    pub fn error_count(&self) -> usize
+    {
+        self.error_count
+    }
+
+    /// Get the number of warnings reported
+    pub fn warning_count(&self) -> usize
+    {
+        self.warning_count
+    }
+
+    /// Summarize the number of errors and warnings reported, as in "1 error and 2 warnings"
+    pub fn summary(&self) -> String
+    {
+        let count = |count: usize, name: &str| format!("{} {}{}", count, name, if count == 1 {""} else {"s"});
+
+        match (self.error_count, self.warning_count)
+        {
+            (0, warnings) => count(warnings, "warning"),
+            (errors, 0) => count(errors, "error"),
+            (errors, warnings) => format!("{} and {}", count(errors, "error"), count(warnings, "warning"))
+        }
+    }
+
+    /// Display the number of warnings reported, if there were any (tools reading JSON can count them themselves)
+    pub fn display_summary(&self)
+    {
+        if self.warning_count > 0 && !self.format.json
+        {
+            eprintln!("compiler: {} generated", self.summary());
+        }
+    }
+
     /// Display any recorded errors
     pub fn dump(&self)
     {
         for error in &self.recorded_errors
         {
-            eprintln!("{}", error);
+            eprintln!("{}", self.format.render(error));
         }
     }
 }
\ No newline at end of file
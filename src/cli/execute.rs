@@ -3,7 +3,7 @@ use super::{Options, Error, ErrorRecorder};
 /// Execute the compiler
 pub fn execute(opts: &Options) -> Result<(), Error>
 {
-    let mut recorder: ErrorRecorder = ErrorRecorder::new();
+    let mut recorder: ErrorRecorder = ErrorRecorder::from_options(opts)?;
     let mut input_files: Vec<crate::io::InputFile> = Vec::new();
 
     // Open input files
@@ -23,11 +23,17 @@ pub fn execute(opts: &Options) -> Result<(), Error>
         recorder.report_error(Error::fatal_error("No input files"))?;
     }
 
-    // Loop over input files and compile them
-    for input_file in input_files
+    // Compile the input files (and the modules they import) together
+    let result = crate::compile::compile(input_files, opts, &mut recorder);
+    recorder.wrap_return(result)?;
+
+    // Compilation carries on past errors to show as many as it can, but still fails once they are all shown
+    if recorder.error_count() > 0
     {
-        recorder.wrap_return(crate::compile::compile(input_file, opts))?;
+        Err(Error::fatal_error(&format!("Aborting due to {}", recorder.summary())))?
     }
 
+    recorder.display_summary();
+
     Ok(())
 }
\ No newline at end of file
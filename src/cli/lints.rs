@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use super::{Options, Error, ErrorCode};
+
+/// Named check for code which is allowed but likely to be a mistake, reported as a warning unless it is turned off
+/// with -A (some are off until turned on with -W)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint
+{
+    ImplicitNarrowing,
+    OverflowingLiteral,
+    UnreachableCode,
+    UnusedVariable,
+    UnusedFunction,
+    Shadowing,
+    ConstantComparison,
+    MissingReturn,
+    UseBeforeAssign
+}
+
+/// Every lint, in the order of their codes
+static LINTS: &[Lint] = &[
+    Lint::ImplicitNarrowing, Lint::OverflowingLiteral, Lint::UnreachableCode, Lint::UnusedVariable, Lint::UnusedFunction,
+    Lint::Shadowing, Lint::ConstantComparison, Lint::MissingReturn, Lint::UseBeforeAssign
+    ];
+
+impl Lint
+{
+    /// Get the lint with the given name, as in unused-variable
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        LINTS.iter().find(|lint| lint.name() == name).copied()
+    }
+
+    /// Get the name the lint is turned on and off by
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Lint::ImplicitNarrowing => "implicit-narrowing",
+            Lint::OverflowingLiteral => "overflowing-literal",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnusedFunction => "unused-function",
+            Lint::Shadowing => "shadowing",
+            Lint::ConstantComparison => "constant-comparison",
+            Lint::MissingReturn => "missing-return",
+            Lint::UseBeforeAssign => "use-before-assign"
+        }
+    }
+
+    /// Get the code of the warnings the lint gives
+    pub fn code(&self) -> ErrorCode
+    {
+        match self
+        {
+            Lint::ImplicitNarrowing => ErrorCode::W0001,
+            Lint::OverflowingLiteral => ErrorCode::W0002,
+            Lint::UnreachableCode => ErrorCode::W0003,
+            Lint::UnusedVariable => ErrorCode::W0004,
+            Lint::UnusedFunction => ErrorCode::W0005,
+            Lint::Shadowing => ErrorCode::W0006,
+            Lint::ConstantComparison => ErrorCode::W0007,
+            Lint::MissingReturn => ErrorCode::W0008,
+            Lint::UseBeforeAssign => ErrorCode::W0009
+        }
+    }
+
+    /// Is the lint on when it isn't given with -W or -A
+    pub fn is_default(&self) -> bool
+    {
+        *self != Lint::Shadowing
+    }
+
+    /// Get the names of every lint, separated by commas
+    pub fn names() -> String
+    {
+        LINTS.iter().map(|lint| lint.name()).collect::<Vec<&str>>().join(", ")
+    }
+}
+
+/// Which lints are on, and whether their warnings are errors
+#[derive(Debug, Clone)]
+pub struct LintLevels
+{
+    enabled: HashSet<Lint>,
+    pub warnings_as_errors: bool
+}
+
+impl LintLevels
+{
+    /// Get the lint levels from the options, lints are turned on with -W and off with -A, where 'all' names every lint
+    /// and a lint given by name takes precedence over 'all' (and -A over -W). -Werror turns every warning into an
+    /// error
+    pub fn from_options(opts: &Options) -> Result<Self, Error>
+    {
+        let mut levels = Self::default();
+
+        let warned = opts.map.get("-W").cloned().unwrap_or_default();
+        let allowed = opts.map.get("-A").cloned().unwrap_or_default();
+
+        for name in warned.iter().chain(allowed.iter())
+        {
+            if name != "all" && Lint::from_name(name).is_none()
+            {
+                Err(Error::fatal_error(&format!("Unknown lint '{}', expected all or one of {}", name, Lint::names())))?
+            }
+        }
+
+        if warned.iter().any(|name| name == "all")
+        {
+            levels.enabled.extend(LINTS.iter().copied());
+        }
+
+        if allowed.iter().any(|name| name == "all")
+        {
+            levels.enabled.clear();
+        }
+
+        levels.enabled.extend(warned.iter().filter_map(|name| Lint::from_name(name)));
+
+        for lint in allowed.iter().filter_map(|name| Lint::from_name(name))
+        {
+            levels.enabled.remove(&lint);
+        }
+
+        levels.warnings_as_errors = opts.has_short_flag("Werror");
+
+        Ok(levels)
+    }
+
+    /// Is the lint on
+    pub fn is_enabled(&self, lint: Lint) -> bool
+    {
+        self.enabled.contains(&lint)
+    }
+}
+
+impl Default for LintLevels
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            enabled: LINTS.iter().copied().filter(|lint| lint.is_default()).collect(),
+            warnings_as_errors: false
+        }
+    }
+}
@@ -2,9 +2,13 @@ mod pages;
 mod options;
 mod errors;
 mod execute;
+mod codes;
+mod lints;
 
 pub use options::*;
 pub use errors::*;
+pub use codes::*;
+pub use lints::*;
 
 /// Run the application with the given options
 pub fn run(opts: &Options) -> Result<(), Error>
@@ -21,6 +25,15 @@ pub fn run(opts: &Options) -> Result<(), Error>
         pages::display_version();
         Ok(())
     }
+    // Describe a diagnostic code in full
+    else if opts.has_long_flag("explain")
+    {
+        match opts.map.get("--explain").and_then(|codes| codes.last())
+        {
+            Some(code) => pages::display_explanation(code),
+            None => Err(Error::fatal_error("Expected a code after '--explain', as in --explain E0012"))
+        }
+    }
     else
     {
         execute::execute(opts)
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 /// Flags which accept arguments
-static ACCEPT_ARGUMENTS: &[&str] = &["-o", "--out", "-g", "-O", "--llvm-target", "--llvm-layout"];
+static ACCEPT_ARGUMENTS: &[&str] = &["-o", "--out", "-g", "-O", "-D", "-I", "--llvm-target", "--llvm-layout", "--error-format", "--color", "--explain", "-W", "-A"];
 
 /// Struct containing information regarding the command line arguments passed
 /// to the application
@@ -27,7 +27,6 @@ impl Options
 
         mut_opts.remove(0);
 
-        let mut current_arguments: Vec<String> = vec![];
         let mut current_key: String = String::new();
 
         for opt in mut_opts
@@ -37,13 +36,6 @@ impl Options
             // If the argument starts with a '-' it must be a flag
             if opt.starts_with("-")
             {
-                // Add the last key and arguments to the saved hashmap
-                if current_key.len() > 0
-                {
-                    map.insert(current_key, current_arguments);
-                    current_arguments = Vec::new();
-                }
-
                 // If the current argument accepts arguments then the current key will be used
                 if ACCEPT_ARGUMENTS.contains(&opt.as_str())
                 {
@@ -55,8 +47,16 @@ impl Options
                 }
             }
 
+            // Interpret a long flag given its argument after an equals sign, as in --color=never
+            if opt.starts_with("--") && opt.contains('=')
+            {
+                let (flag, value) = opt.split_once('=').unwrap();
+
+                map.entry(String::from(flag)).or_default().push(String::from(value));
+                long_flags.push(String::from(&flag[2..]));
+            }
             // Interpret a long flag
-            if opt.starts_with("--")
+            else if opt.starts_with("--")
             {
 
                 // Remove the "--"
@@ -83,20 +83,15 @@ impl Options
                 {
                     raw_vals.push(mut_opt);
                 }
-                // Or use it as an argument to the current key
+                // Or use it as the argument to the current key (flags which are repeated collect every argument)
                 else
                 {
-                    current_arguments.push(mut_opt);
+                    map.entry(current_key).or_default().push(mut_opt);
+                    current_key = String::new();
                 }
             }
         }
 
-        // Make sure the last argument isn't dropped
-        if current_key.len() > 0
-        {
-            map.insert(current_key, current_arguments);
-        }
-
         Options
         {
             short_flags,
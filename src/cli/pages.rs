@@ -1,9 +1,19 @@
+use super::{Error, ErrorCode, Lint};
+
 /// Display the help page
 pub fn display_help()
 {
     println!("Usage: compiler [options] file...");
     println!("Options:");
+    println!("     --bounds-check            Trap on out of range slice and array accesses");
+    println!("     --checked-arith           Trap on arithmetic overflow, division by zero and oversized shifts");
+    println!("     --color       [WHEN]      Color errors: auto (when shown on a terminal), always or never");
+    println!(" -A                [LINT]      Turn off a lint, or all of them with 'all'");
+    println!(" -D                [NAME=VAL]  Define a macro for the preprocessor (VAL defaults to 1)");
+    println!("     --error-format [FMT]      Show errors as human readable text (the default) or as json");
+    println!("     --explain     [CODE]      Describe the diagnostic with the given code, as in E0012");
     println!("     --help                    Display this page");
+    println!(" -I                [DIR]       Add a directory to search for included files");
     println!(" -g                [MODE]      Set the code gen mode to use");
     println!("     --llvm-layout [LAYOUT]    Sets the target data layout for LLVM");
     println!("     --llvm-target [TARGET]    Sets the target triple for LLVM");
@@ -12,9 +22,27 @@ pub fn display_help()
     println!(" -O                [VAL]       Set the optimization level (defaults to 2)");
     println!("     --stdout                  Display the output on stdout");
     println!(" -T  --tree                    Display the parse tree");
+    println!(" -W                [LINT]      Turn on a lint, or all of them with 'all'");
+    println!(" -Werror                       Treat warnings as errors");
     println!("\nAllowable Codegen Modes:");
     println!("   ir");
     println!("   llvm");
+    println!("\nLints:");
+    println!("   {}", Lint::names());
+}
+
+/// Display the long form description of a diagnostic code
+pub fn display_explanation(name: &str) -> Result<(), Error>
+{
+    match ErrorCode::from_name(name)
+    {
+        Some(code) =>
+        {
+            println!("{}", code.explanation());
+            Ok(())
+        },
+        None => Err(Error::fatal_error(&format!("No diagnostic has the code '{}'", name)))
+    }
 }
 
 /// Display the version page
@@ -1,8 +1,11 @@
-use crate::cli::Error;
-use crate::irgen::{Function, OpCode, Value, Symbol};
+use crate::cli::{Error, ErrorCode};
+use crate::irgen::{Function, OpCode, Value, Symbol, Literal, Instruction, NonPtrType, get_value_type, MAX_INTEGER_BITS};
+use crate::tokenizer::{FileLocation, Span};
 
 use super::{generate_comment, generate_label, generate_command, get_label, get_size_datatype};
 
+use super::super::CHECKED_PANIC_HOOK;
+
 use std::collections::HashMap;
 
 /// A wrapper for giving a context to code generation for an avrasm function
@@ -11,22 +14,37 @@ pub struct FunctionGenerationContext
     free_registers: Vec<usize>,
     function: Function,
     symbol_map: HashMap<String, usize>,
+    symbol_sizes: HashMap<String, usize>,
+    release_after: HashMap<usize, Vec<String>>,
     temp_reg: usize,
-    last_temp_assignment: String
+    last_temp_assignment: String,
+    file_names: Vec<String>,
+    next_check: usize,
+    next_loop: usize,
+    next_compare: usize
 }
 
 impl FunctionGenerationContext
 {
-    /// Generate a new FunctionGeneratorContext for the given IR function
-    pub fn new(function: Function) -> Self
+    /// Generate a new FunctionGeneratorContext for the given IR function (file_names are the files reported by
+    /// checked operations)
+    pub fn new(function: Function, file_names: Vec<String>) -> Self
     {
+        let release_after = find_last_uses(&function);
+
         Self
         {
             function,
-            free_registers: vec![25, 23, 22, 21, 20, 19, 18, 17],
+            free_registers: vec![25, 24, 23, 22, 21, 20, 19, 18, 17],
             symbol_map: HashMap::new(),
+            symbol_sizes: HashMap::new(),
+            release_after,
             temp_reg: 16,
-            last_temp_assignment: String::new()
+            last_temp_assignment: String::new(),
+            file_names,
+            next_check: 0,
+            next_loop: 0,
+            next_compare: 0
         }
     }
 
@@ -35,7 +53,7 @@ impl FunctionGenerationContext
     {
         if self.free_registers.len() == 0
         {
-            Err(Error::error("No more registers available"))
+            Err(Error::error("No more registers available").with_code(ErrorCode::E0039))
         }
         else
         {
@@ -45,35 +63,38 @@ impl FunctionGenerationContext
 
     /// Request a new 16 bit register
     pub fn get_u16_reg(&mut self) -> Result<usize, Error>
+    {
+        self.get_wide_reg(2)
+    }
+
+    /// Request a run of registers for a value of the given number of bytes, the lowest byte goes in the lowest
+    /// register
+    pub fn get_wide_reg(&mut self, size: usize) -> Result<usize, Error>
     {
         if self.free_registers.len() == 0
         {
-            Err(Error::error("No more registers available"))
+            Err(Error::error("No more registers available").with_code(ErrorCode::E0039))
         }
         else
         {
             for i in 0..self.free_registers.len()
             {
-                // The register must be even
+                // The register must be even (so the low bytes can be moved with movw)
                 if self.free_registers[i] % 2 == 0
                 {
-                    // And have register n + 1 also be available
-                    if self.free_registers.contains(&(self.free_registers[i] + 1))
-                    {
-                        // Remove register n
-                        let v = self.free_registers[i];
-                        self.free_registers.remove(i);
+                    let v = self.free_registers[i];
 
-                        // Remove register n + 1
-                        let j = self.free_registers.iter().position(|&x| x == v + 1).unwrap();
-                        self.free_registers.remove(j);
+                    // And have registers n + 1 onwards also be available
+                    if (v..v + size).all(|reg| self.free_registers.contains(&reg))
+                    {
+                        self.free_registers.retain(|reg| !(v..v + size).contains(reg));
 
                         return Ok(v)
                     }
                 }
             }
 
-            Err(Error::error("No more registers available"))
+            Err(Error::error("No more registers available").with_code(ErrorCode::E0039))
         }
     }
 
@@ -87,8 +108,11 @@ impl FunctionGenerationContext
         }
         else
         {
+            let size = get_size_datatype(symb.datatype);
+            self.symbol_sizes.insert(symb.title.clone(), size);
+
             // Get an 8 bit register if the datatype is 8 bits
-            if get_size_datatype(symb.datatype) == 1
+            if size == 1
             {
                 let reg = self.get_u8_reg()?;
 
@@ -96,21 +120,55 @@ impl FunctionGenerationContext
 
                 Ok(reg)
             }
-            // Get an 16 bit register if the datatype is 16 bits
-            else if get_size_datatype(symb.datatype) == 2
+            // Get a run of registers for anything wider
+            else
             {
-                let reg = self.get_u16_reg()?;
+                let reg = self.get_wide_reg(size)?;
 
                 self.symbol_map.insert(symb.title.clone(), reg.clone());
 
                 Ok(reg)
             }
-            // Otherwise, panic because there should never be a size other that these two so far
-            else
+        }
+    }
+
+    /// Give back the registers of a symbol which isn't used again, so later symbols can use them
+    pub fn release_register(&mut self, title: &str)
+    {
+        if let (Some(reg), Some(size)) = (self.symbol_map.remove(title), self.symbol_sizes.remove(title))
+        {
+            self.free_registers.extend(reg..reg + size);
+
+            // The lowest registers are handed out first
+            self.free_registers.sort_by(|a, b| b.cmp(a));
+        }
+    }
+
+    /// Give the registers of the first operand of an instruction to its destination if the operand isn't used again,
+    /// so the operation is worked out in place rather than needing registers for both, returning whether it was
+    pub fn reuse_register(&mut self, index: usize, inst: &Instruction) -> bool
+    {
+        if let (Value::Symbol(dest), Value::Symbol(symb)) = (&inst.arguments[0], &inst.arguments[1])
+        {
+            let last_use = self.release_after.get(&index).is_some_and(|titles| titles.contains(&symb.title));
+            let used_once = inst.arguments.iter().filter(|arg| matches!(arg, Value::Symbol(other) if other.title == symb.title)).count() == 1;
+
+            // Moving into or out of a reference stores or loads through it, anything else copies the registers
+            let values = inst.opcode != OpCode::Mov || (!dest.datatype.is_ref && !symb.datatype.is_ref);
+
+            if last_use && used_once && values && !self.symbol_map.contains_key(&dest.title) && get_size_datatype(dest.datatype) == get_size_datatype(symb.datatype)
             {
-                panic!()
+                if let (Some(reg), Some(size)) = (self.symbol_map.remove(&symb.title), self.symbol_sizes.remove(&symb.title))
+                {
+                    self.symbol_map.insert(dest.title.clone(), reg);
+                    self.symbol_sizes.insert(dest.title.clone(), size);
+
+                    return true;
+                }
             }
         }
+
+        false
     }
 
     /// Add a move instruction between two values (the force_move flag forces a move, ignoring references)
@@ -118,7 +176,7 @@ impl FunctionGenerationContext
     {
         match value
         {
-            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value"))},
+            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
             Value::Literal(lit) =>
             {
                 // Moving a literal into a symbol
@@ -127,13 +185,12 @@ impl FunctionGenerationContext
                     // Moving a literal into a symbol
                     if !symb.datatype.is_ref || force_move
                     {
-                        // Load the low byte into the first register
-                        let mut result = generate_command(&format!("ldi r{}, {}", self.get_register(symb)?, lit.value & 0xFF))?;
+                        let mut result = String::new();
 
-                        // Load the high byte if needed
-                        if get_size_datatype(lit.datatype) == 2
+                        // Load each byte into its register, starting with the low byte
+                        for byte in 0..get_size_datatype(symb.datatype)
                         {
-                            result += &generate_command(&format!("ldi r{}, {}", self.get_register(symb)? + 1, (lit.value & 0xFF00) >> 8))?;
+                            result += &generate_command(&format!("ldi r{}, {}", self.get_register(symb)? + byte, (lit.value >> (8 * byte)) & 0xFF))?;
                         }
 
                         Ok(result)
@@ -146,7 +203,7 @@ impl FunctionGenerationContext
                         let reg = self.get_register(symb)?;
                         
                         // Load the reference into the X index register
-                        result += &generate_command(&format!("movw r26, {}", reg))?;
+                        result += &generate_command(&format!("movw r26, r{}", reg))?;
 
                         let new_temp = format!("{}", lit.value & 0xFF);
                         if self.last_temp_assignment != new_temp
@@ -158,10 +215,10 @@ impl FunctionGenerationContext
                         // Store the low byte
                         result += &generate_command("st X, r16")?;
 
-                        // If needed store the high byte
-                        if get_size_datatype(lit.datatype) == 2
+                        // If needed store the higher bytes
+                        for byte in 1..get_size_datatype(lit.datatype)
                         {
-                            let new_temp = format!("{}", (lit.value & 0xFF00) >> 8);
+                            let new_temp = format!("{}", (lit.value >> (8 * byte)) & 0xFF);
                             if self.last_temp_assignment != new_temp
                             {
                                 self.last_temp_assignment = new_temp;
@@ -186,22 +243,22 @@ impl FunctionGenerationContext
                     else
                     {
                         let mut result = String::new();
+                        let size = get_size_datatype(lit.datatype);
 
                         // If the target is within the space which allows in and out commands, make use of those commands
-                        if target_lit.value < 0x60 && target_lit.value >= 0x20
+                        // (writing the high byte first, as a 16 bit register takes its value when the low byte is written)
+                        if target_lit.value >= 0x20 && target_lit.value + size as i128 <= 0x60
                         {
-                            let new_temp = format!("{}", lit.value & 0xFF);
-                            if self.last_temp_assignment != new_temp
+                            for byte in (0..size).rev()
                             {
-                                self.last_temp_assignment = format!("{}", lit.value & 0xFF);
-                                result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
-                            }
-
-                            result += &generate_command(&format!("out {}, r16", target_lit.value - 0x20))?;
+                                let new_temp = format!("{}", (lit.value >> (8 * byte)) & 0xFF);
+                                if self.last_temp_assignment != new_temp
+                                {
+                                    self.last_temp_assignment = new_temp;
+                                    result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
+                                }
 
-                            if get_size_datatype(lit.datatype) == 2
-                            {
-                                return Err(Error::error("Cannot assign 16 bit value to an 8 bit register"))
+                                result += &generate_command(&format!("out {}, r16", target_lit.value - 0x20 + byte as i128))?;
                             }
                         }
                         // Otherwise the st command will need to be used
@@ -221,13 +278,13 @@ impl FunctionGenerationContext
                             // Store the low byte
                             result += &generate_command("st X, r16")?;
 
-                            // If needed store the high byte
-                            if get_size_datatype(lit.datatype) == 2
+                            // If needed store the higher bytes
+                            for byte in 1..get_size_datatype(lit.datatype)
                             {
-                                let new_temp = format!("{}", (lit.value & 0xFF00) >> 8);
+                                let new_temp = format!("{}", (lit.value >> (8 * byte)) & 0xFF);
                                 if self.last_temp_assignment != new_temp
                                 {
-                                    self.last_temp_assignment = format!("{}", (lit.value & 0xFF00) >> 8);
+                                    self.last_temp_assignment = new_temp;
                                     result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
                                 }
 
@@ -256,16 +313,47 @@ impl FunctionGenerationContext
                     {
                         let mut result = String::new();
 
-                        // If the datasize is 8 bits, use the mov command
-                        if get_size_datatype(src_symb.datatype) == 1
+                        let dest_reg = self.get_register(symb)?;
+                        let dest_size = get_size_datatype(symb.datatype);
+
+                        // A cast only moves the bytes both types have
+                        let size = get_size_datatype(src_symb.datatype).min(dest_size);
+
+                        // If the datasize is 16 bits (and both registers are even), use the movw command
+                        if size == 2 && dest_reg % 2 == 0 && src_reg % 2 == 0
+                        {
+                            result += &generate_command(&format!("movw r{}, r{}", dest_reg, src_reg))?;
+                        }
+
+                        // Otherwise values are moved a byte at a time
+                        else
                         {
-                            result += &generate_command(&format!("mov r{}, r{}", self.get_register(symb)?, src_reg))?;
+                            for byte in 0..size
+                            {
+                                result += &generate_command(&format!("mov r{}, r{}", dest_reg + byte, src_reg + byte))?;
+                            }
                         }
 
-                        // If the datasize is 16 bits, use the movw command
-                        else if get_size_datatype(src_symb.datatype) == 2
+                        // Casting to a wider type extends the value through the temporary register
+                        if dest_size > size
                         {
-                            result += &generate_command(&format!("movw r{}, r{}", self.get_register(symb)?, src_reg))?;
+                            if src_symb.datatype.is_signed()
+                            {
+                                result += &generate_command(&format!("mov r16, r{}", src_reg + size - 1))?;
+                                result += &generate_command("lsl r16")?;
+                                result += &generate_command("sbc r16, r16")?;
+                                self.last_temp_assignment = String::new();
+                            }
+                            else if self.last_temp_assignment != "0"
+                            {
+                                self.last_temp_assignment = String::from("0");
+                                result += &generate_command("ldi r16, 0")?;
+                            }
+
+                            for byte in size..dest_size
+                            {
+                                result += &generate_command(&format!("mov r{}, r16", dest_reg + byte))?;
+                            }
                         }
 
                         Ok(result)
@@ -278,15 +366,15 @@ impl FunctionGenerationContext
                         let reg = self.get_register(symb)?;
                         
                         // Load the destination into the X register
-                        result += &generate_command(&format!("movw r26, {}", reg))?;
+                        result += &generate_command(&format!("movw r26, r{}", reg))?;
 
                         // Write the low byte
                         result += &generate_command(&format!("st X, r{}", src_reg))?;
 
-                        // Write the high byte if needed
-                        if get_size_datatype(src_symb.datatype) == 2
+                        // Write the higher bytes if needed
+                        for byte in 1..get_size_datatype(src_symb.datatype)
                         {
-                            result += &generate_command(&format!("st +X, r{}", src_reg + 1))?;
+                            result += &generate_command(&format!("st +X, r{}", src_reg + byte))?;
                         }
 
                         Ok(result)
@@ -304,15 +392,15 @@ impl FunctionGenerationContext
                     else
                     {
                         let mut result = String::new();
+                        let size = get_size_datatype(src_symb.datatype);
 
-                        // If the target is in the domain usable by  the out and in commands, use those commands
-                        if target_lit.value < 0x60 && target_lit.value >= 0x20
+                        // If the target is in the domain usable by the out and in commands, use those commands (writing the
+                        // high byte first, as a 16 bit register takes its value when the low byte is written)
+                        if target_lit.value >= 0x20 && target_lit.value + size as i128 <= 0x60
                         {
-                            result += &generate_command(&format!("out {}, r{}", target_lit.value - 0x20, src_reg))?;
-
-                            if get_size_datatype(src_symb.datatype) == 2
+                            for byte in (0..size).rev()
                             {
-                                return Err(Error::error("Cannot assign 16 bit value to an 8 bit register"))
+                                result += &generate_command(&format!("out {}, r{}", target_lit.value - 0x20 + byte as i128, src_reg + byte))?;
                             }
                         }
                         else
@@ -324,10 +412,10 @@ impl FunctionGenerationContext
                             // Store the low byte
                             result += &generate_command(&format!("st X, r{}", src_reg))?;
 
-                            // Store the high byte if needed
-                            if get_size_datatype(src_symb.datatype) == 2
+                            // Store the higher bytes if needed
+                            for byte in 1..get_size_datatype(src_symb.datatype)
                             {
-                                result += &generate_command(&format!("st +X, r{}", src_reg + 1))?;
+                                result += &generate_command(&format!("st +X, r{}", src_reg + byte))?;
                             }
                         }
 
@@ -343,344 +431,1746 @@ impl FunctionGenerationContext
         }
     }
 
-    /// Add a dereference instruction (effectively like a move, but out of a reference)
-    pub fn dereference_instruction(&mut self, target: &Value, value: &Value) -> Result<String, Error>
+    /// Load a pointer into the Z register (for program memory) or the X register (for data memory), returning the
+    /// index of the low register
+    pub fn load_pointer(&mut self, ptr: &Value, is_progmem: bool) -> Result<(String, usize), Error>
     {
-        match value
+        let mut result = String::new();
+        let ptr_reg = if is_progmem {30} else {26};
+
+        match ptr
         {
-            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value"))},
+            Value::Label(_) => {return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
             Value::Literal(lit) =>
             {
-                // Derefencing a literal into a register
-                if let Value::Symbol(symb) = target
-                {
-                    // If the literal is within the range allowable by the out and in commands use the in command
-                    if lit.value < 0x60 && lit.value >= 0x20
-                    {
-                        let mut result = String::new();
-                        let reg = self.get_register(symb)?;
-
-                        if get_size_datatype(symb.datatype) == 1
-                        {
-                            result += &generate_command(&format!("in r{}, {}", reg, lit.value - 0x20))?;
-                        }
-                        else if get_size_datatype(symb.datatype) == 2
-                        {
-                            result += &generate_command(&format!("in r{}, {}", reg + 1, lit.value + 1 - 0x20))?;
-                        }
-
-                        Ok(result)
-                    }
-                    else
-                    {
-                        let mut result = String::new();
-                        let reg = self.get_register(symb)?;
-
-                        // Load the literal into the X register
-                        result += &generate_command(&format!("ldi r26, {}", lit.value & 0xFF))?;
-                        result += &generate_command(&format!("ldi r27, {}", (lit.value & 0xFF00) >> 8))?;
-
-                        // Load the low byte
-                        result += &generate_command(&format!("ld r{}, X", reg))?;
-
-                        // Load the high byte if needed
-                        if get_size_datatype(symb.datatype) == 2
-                        {
-                            result += &generate_command(&format!("ld r{}, +X", reg + 1))?;
-                        }
-
-                        Ok(result)
-                    }
-                }
-                // Can't derefernce a literal into a literal, therefore panic
-                else
-                {
-                    panic!()
-                }
+                result += &generate_command(&format!("ldi r{}, {}", ptr_reg, lit.value & 0xFF))?;
+                result += &generate_command(&format!("ldi r{}, {}", ptr_reg + 1, (lit.value & 0xFF00) >> 8))?;
             },
-            Value::Symbol(src_symb) =>
+            Value::Symbol(symb) =>
             {
-                // Derefernce a symbol into a register
-                if let Value::Symbol(symb) = target
-                {
-                    let mut result = String::new();
-                    let reg = self.get_register(symb)?;
-                    let src_reg = self.get_register(src_symb)?;
-
-                    // Load the source into the X register
-                    result += &generate_command(&format!("movw r26, r{}", src_reg))?;
+                result += &generate_command(&format!("movw r{}, r{}", ptr_reg, self.get_register(symb)?))?;
+            }
+        }
 
-                    // Load the low byte
-                    result += &generate_command(&format!("ld r{}, X", reg))?;
+        Ok((result, ptr_reg))
+    }
 
-                    // Load the high byte if needed
-                    if get_size_datatype(symb.datatype) == 2
-                    {
-                        result += &generate_command(&format!("ld r{}, +X", reg + 1))?;
-                    }
+    /// Load a value of the given size through the X or Z register (program memory must be read with lpm)
+    pub fn load_indirect(&mut self, reg: usize, size: usize, is_progmem: bool) -> Result<String, Error>
+    {
+        let mut result = String::new();
+        let (command, ptr) = if is_progmem {("lpm", "Z")} else {("ld", "X")};
 
-                    Ok(result)
-                }
-                // Can't derefernce a symbol into a literal, therefore panic
-                else
-                {
-                    panic!()
-                }
-            }
+        for i in 0..size
+        {
+            // Post increment the pointer for every byte but the last
+            result += &generate_command(&format!("{} r{}, {}{}", command, reg + i, ptr, if i + 1 < size {"+"} else {""}))?;
         }
+
+        Ok(result)
     }
 
-    /// Add an add instruction
-    pub fn add_instruction(&mut self, dest: &Value, v0: &Value, v1: &Value) -> Result<String, Error>
+    /// Add a dereference instruction out of program memory
+    pub fn progmem_dereference_instruction(&mut self, target: &Value, value: &Value) -> Result<String, Error>
     {
-        // Get the destination register
-        let dest_reg = if let Value::Symbol(symb) = dest
+        if let Value::Symbol(symb) = target
         {
-            self.get_register(&symb)?
+            let (mut result, _) = self.load_pointer(value, true)?;
+            let reg = self.get_register(symb)?;
+
+            result += &self.load_indirect(reg, get_size_datatype(symb.datatype), true)?;
+
+            Ok(result)
         }
+        // Can't dereference into a literal
         else
         {
-            return Err(Error::error("Unable to assign to anything but a symbol"));
+            Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036))
+        }
+    }
+
+    /// Add a subtraction of two pointers (dest = (ptr0 - ptr1) / size of an element), the difference in bytes is
+    /// divided by shifting for a power of two size, and otherwise by also multiplying by the inverse of the odd part of
+    /// the size (the difference is always a multiple of the size, so the division is exact)
+    pub fn pointer_difference_instruction(&mut self, dest: &Symbol, ptr0: &Value, ptr1: &Value) -> Result<String, Error>
+    {
+        let size = match get_value_type(ptr0)
+        {
+            Some(datatype) => get_size_datatype(datatype.dereferenced()),
+            None => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
         };
 
-        match v0
+        let dest_reg = self.get_register(dest)?;
+        let mut result = String::new();
+
+        // Work out the difference in bytes in the low two bytes of the destination
+        match ptr0
         {
-            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value"))},
-            Value::Literal(_) =>
+            Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036)),
+            Value::Literal(lit) =>
             {
-                // This should have been cleaned up by the IR gen
-                if let Value::Literal(_) = v1
-                {
-                    Err(Error::error("Add command invoked with two literals"))
-                }
-                // If having a literal as the first argument can be solved by reversing the order of the operands, do so
-                else
-                {
-                    self.add_instruction(dest, v1, v0)
-                }
+                result += &generate_command(&format!("ldi r{}, {}", dest_reg, lit.value & 0xFF))?;
+                result += &generate_command(&format!("ldi r{}, {}", dest_reg + 1, (lit.value & 0xFF00) >> 8))?;
             },
-            Value::Symbol(symb0) =>
-            {
-                // If it is an '+=' then it can be a simple add (hopefully)
-                if dest == v0
-                {
-                    let mut result = String::new();
-
-                    // Symbol0 = Symbol0 + Symbol1
-                    if let Value::Symbol(symb1) = v1
-                    {
-                        result += &generate_command(&format!("add r{}, r{}", dest_reg, self.get_register(&symb1)?))?;
-                        if get_size_datatype(symb0.datatype) == 2
-                        {
-                            result += &generate_command(&format!("adc r{}, r{}", dest_reg + 1, self.get_register(&symb1)? + 1))?;
-                        }
-                    }
-                    // Symbol0 = Symbol0 + Lit
-                    else if let Value::Literal(lit1) = v1
-                    {
-                        // If the literal is 1, use the increment command instead
-                        if lit1.value == 1
-                        {
-                            result += &generate_command(&format!("inc r{}", dest_reg))?;
-                        }
-                        else
-                        {
-                            let new_temp = format!("{}", lit1.value & 0xFF);
-                            if self.last_temp_assignment != new_temp
-                            {
-                                self.last_temp_assignment = new_temp;
-                                result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
-                            }
+            Value::Symbol(symb) => result += &generate_command(&format!("movw r{}, r{}", dest_reg, self.get_register(symb)?))?
+        }
 
-                            result += &generate_command(&format!("add r{}, r16", dest_reg))?;
-                        }
-                        
-                        // Use the adc command for 16 bit operands
-                        if get_size_datatype(symb0.datatype) == 2
-                        {
-                            let new_temp = format!("{}", (lit1.value & 0xFF00) >> 8);
-                            if self.last_temp_assignment != new_temp
-                            {
-                                self.last_temp_assignment = new_temp;
-                                result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
-                            }
+        match ptr1
+        {
+            Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036)),
+            Value::Literal(lit) =>
+            {
+                result += &generate_command(&format!("subi r{}, {}", dest_reg, lit.value & 0xFF))?;
+                result += &generate_command(&format!("sbci r{}, {}", dest_reg + 1, (lit.value & 0xFF00) >> 8))?;
+            },
+            Value::Symbol(symb) =>
+            {
+                let reg = self.get_register(symb)?;
 
-                            result += &generate_command(&format!("adc r{}, r16", dest_reg + 1))?;
-                        }
-                    }
-                    else
-                    {
-                        return Err(Error::error("Unable to read value from label"));
-                    }
+                result += &generate_command(&format!("sub r{}, r{}", dest_reg, reg))?;
+                result += &generate_command(&format!("sbc r{}, r{}", dest_reg + 1, reg + 1))?;
+            }
+        }
 
-                    Ok(result)
-                }
-                // A standard + (Not yet implemented)
-                else
-                {
-                    unimplemented!()
-                }
-            },
+        // Divide by the power of two part of the size
+        for _ in 0..size.trailing_zeros()
+        {
+            result += &generate_command(&format!("asr r{}", dest_reg + 1))?;
+            result += &generate_command(&format!("ror r{}", dest_reg))?;
         }
-    }
 
-    // Add a branch operation
-    pub fn add_branch(&mut self, inst: &str, v0: &Value, v1: &Value, l0: &Value, l1: &Value) -> Result<String, Error>
-    {
-        let label0 = if let Value::Label(s) = l0 {s} else {return Err(Error::error("Expected a label"));};
-        let label1 = if let Value::Label(s) = l1 {s} else {return Err(Error::error("Expected a label"));};
+        // Divide by the odd part of the size, by multiplying by its inverse modulo 2^16 (shifting and adding with the X
+        // register holding the shifted difference)
+        let odd = (size >> size.trailing_zeros()) as u32;
 
-        if let Value::Symbol(symb0) = v0
+        if odd > 1
         {
-            let reg0 = self.get_register(&symb0)?;
+            let mut inverse = odd;
 
-            // Handle 8 bit operands
-            if get_size_datatype(symb0.datatype) == 1
+            for _ in 0..4
             {
-                let mut result = String::new();
+                inverse = inverse.wrapping_mul(2u32.wrapping_sub(odd.wrapping_mul(inverse))) & 0xFFFF;
+            }
 
-                // Compare a symbol to a symbol
-                if let Value::Symbol(symb1) = v1
-                {
-                    let reg1 = self.get_register(&symb1)?;
+            result += &generate_command(&format!("movw r26, r{}", dest_reg))?;
+            result += &generate_command(&format!("clr r{}", dest_reg))?;
+            result += &generate_command(&format!("clr r{}", dest_reg + 1))?;
 
-                    result += &generate_command(&format!("cp r{}, r{}", reg0, reg1))?;
-                }
-                // Compare a symbol to a literal
-                else if let Value::Literal(lit1) = v1
+            for bit in 0..16
+            {
+                if inverse & (1 << bit) != 0
                 {
-                    result += &generate_command(&format!("cpi r{}, {}", reg0, lit1.value))?;
+                    result += &generate_command(&format!("add r{}, r26", dest_reg))?;
+                    result += &generate_command(&format!("adc r{}, r27", dest_reg + 1))?;
                 }
-                else
+
+                if inverse >> (bit + 1) != 0
                 {
-                    unimplemented!()
+                    result += &generate_command("lsl r26")?;
+                    result += &generate_command("rol r27")?;
                 }
+            }
+        }
 
-                // Perform the branch and add an 'else' jmp
-                result += &generate_command(&format!("{} {}", inst, get_label(&self.function, label0)?))?;
-                result += &generate_command(&format!("jmp {}", get_label(&self.function, label1)?))?;
+        // Sign extend into any higher bytes
+        let dest_size = get_size_datatype(dest.datatype);
 
-                Ok(result)
-            }
-            // Handle operands of 16 bits (not yet implemented)
-            else if get_size_datatype(symb0.datatype) == 2
-            {
-                unimplemented!()
-            }
-            // No support for operands larger than 16 bits
-            else
+        if dest_size > 2
+        {
+            result += &generate_command(&format!("mov r16, r{}", dest_reg + 1))?;
+            result += &generate_command("lsl r16")?;
+            result += &generate_command("sbc r16, r16")?;
+            self.last_temp_assignment = String::new();
+
+            for byte in 2..dest_size
             {
-                panic!()
+                result += &generate_command(&format!("mov r{}, r16", dest_reg + byte))?;
             }
         }
-        // Not yet implemented having the first argument be a literal
-        else
-        {
-            unimplemented!()
-        }
+
+        Ok(result)
     }
 
-    /// Render an IR function in AVR Assembly
-    pub fn render_function(&mut self) -> Result<String, Error>
+    /// Add an array access instruction (dest = ptr[index])
+    pub fn array_instruction(&mut self, dest: &Value, ptr: &Value, index: &Value) -> Result<String, Error>
     {
-        let mut result = String::new();
+        let is_progmem = match get_value_type(ptr)
+        {
+            Some(datatype) => datatype.is_progmem_ptr(),
+            None => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        };
 
-        // Add the comment at the top of the function
-        result += &generate_comment(&format!("Function {}", self.function.render_signature()))?;
+        let symb = if let Value::Symbol(symb) = dest
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
 
-        // Add the label marking the start of the function
-        result += &generate_label(&format!("f{}", self.function.name))?;
+        let size = get_size_datatype(symb.datatype);
+        let (mut result, ptr_reg) = self.load_pointer(ptr, is_progmem)?;
 
-        // Iterate over each instruction (in order)
-        for i in 0..self.function.instructions.len()
+        match index
         {
-            let inst = self.function.instructions.get(&i).unwrap().clone();
-
-            // If there are labels available for a given instruction, write those in
-            if let Some(labels) = self.function.labels.get(&i)
+            Value::Label(_) => {return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            // Add a constant offset by subtracting its negation
+            Value::Literal(lit) =>
             {
-                for label in labels
+                let offset = -(lit.value * size as i128);
+
+                if offset != 0
                 {
-                    result += &generate_label(&get_label(&self.function, label)?)?;
+                    result += &generate_command(&format!("subi r{}, {}", ptr_reg, offset & 0xFF))?;
+                    result += &generate_command(&format!("sbci r{}, {}", ptr_reg + 1, (offset & 0xFF00) >> 8))?;
                 }
-            }
-            
-            match inst.opcode
+            },
+            // Add the index once per byte of the element
+            Value::Symbol(index_symb) =>
             {
-                OpCode::Nop => {},
-                OpCode::Jmp =>
+                let index_reg = self.get_register(index_symb)?;
+                let wide_index = get_size_datatype(index_symb.datatype) == 2;
+
+                if !wide_index && self.last_temp_assignment != "0"
+                {
+                    self.last_temp_assignment = String::from("0");
+                    result += &generate_command("ldi r16, 0")?;
+                }
+
+                for _ in 0..size
+                {
+                    result += &generate_command(&format!("add r{}, r{}", ptr_reg, index_reg))?;
+                    result += &generate_command(&format!("adc r{}, r{}", ptr_reg + 1, if wide_index {index_reg + 1} else {16}))?;
+                }
+            }
+        }
+
+        let reg = self.get_register(&symb)?;
+        result += &self.load_indirect(reg, size, is_progmem)?;
+
+        Ok(result)
+    }
+
+    /// Add a pointer offset instruction (dest = ptr +/- index), scaling the index by the size of the element
+    pub fn pointer_offset_instruction(&mut self, dest: &Value, ptr: &Value, index: &Value, subtract: bool) -> Result<String, Error>
+    {
+        let ptr_type = match get_value_type(ptr)
+        {
+            Some(datatype) => datatype,
+            None => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        };
+
+        let dest_reg = if let Value::Symbol(symb) = dest
+        {
+            // The difference between two pointers is an integer
+            if symb.datatype.num_ptr == 0
+            {
+                return self.pointer_difference_instruction(symb, ptr, index);
+            }
+
+            self.get_register(symb)?
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        let size = get_size_datatype(ptr_type.dereferenced());
+        let mut result = String::new();
+
+        // Move the pointer into the destination
+        match ptr
+        {
+            Value::Label(_) => {return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            Value::Literal(lit) =>
+            {
+                result += &generate_command(&format!("ldi r{}, {}", dest_reg, lit.value & 0xFF))?;
+                result += &generate_command(&format!("ldi r{}, {}", dest_reg + 1, (lit.value & 0xFF00) >> 8))?;
+            },
+            Value::Symbol(symb) =>
+            {
+                let ptr_reg = self.get_register(symb)?;
+
+                if ptr_reg != dest_reg
+                {
+                    result += &generate_command(&format!("movw r{}, r{}", dest_reg, ptr_reg))?;
+                }
+            }
+        }
+
+        match index
+        {
+            Value::Label(_) => {return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            // Add a constant offset by subtracting its negation
+            Value::Literal(lit) =>
+            {
+                let offset = if subtract {lit.value * size as i128} else {-(lit.value * size as i128)};
+
+                if offset != 0
                 {
-                    // Because the label is within the function, we will assume it is just a relative jump
+                    result += &generate_command(&format!("subi r{}, {}", dest_reg, offset & 0xFF))?;
+                    result += &generate_command(&format!("sbci r{}, {}", dest_reg + 1, (offset & 0xFF00) >> 8))?;
+                }
+            },
+            // Add or subtract the index once per byte of the element
+            Value::Symbol(index_symb) =>
+            {
+                let index_reg = self.get_register(index_symb)?;
+                let wide_index = get_size_datatype(index_symb.datatype) >= 2;
 
-                    if let Value::Label(label) = &inst.arguments[0]
+                // Extend a byte index into the temporary register
+                if !wide_index
+                {
+                    if index_symb.datatype.is_signed()
                     {
-                        result += &generate_command(&format!("jmp {}", get_label(&self.function, label)?))?;
+                        result += &generate_command(&format!("mov r16, r{}", index_reg))?;
+                        result += &generate_command("lsl r16")?;
+                        result += &generate_command("sbc r16, r16")?;
+                        self.last_temp_assignment = String::new();
                     }
-                },
+                    else if self.last_temp_assignment != "0"
+                    {
+                        self.last_temp_assignment = String::from("0");
+                        result += &generate_command("ldi r16, 0")?;
+                    }
+                }
 
-                // Mov Alloc and Cast are all wrappers for moves
-                OpCode::Mov | OpCode::Alloc | OpCode::Cast =>
+                let (low, high) = if subtract {("sub", "sbc")} else {("add", "adc")};
+
+                for _ in 0..size
                 {
-                    result += self.move_instruction(&inst.arguments[0], &inst.arguments[1], false)?.as_str();
-                },
+                    result += &generate_command(&format!("{} r{}, r{}", low, dest_reg, index_reg))?;
+                    result += &generate_command(&format!("{} r{}, r{}", high, dest_reg + 1, if wide_index {index_reg + 1} else {16}))?;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Add a sign or zero extension instruction
+    pub fn extend_instruction(&mut self, dest: &Value, value: &Value, signed: bool) -> Result<String, Error>
+    {
+        let symb = if let Value::Symbol(symb) = dest
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        match value
+        {
+            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            // Constants can be loaded directly at the new size
+            Value::Literal(lit) =>
+            {
+                let mut extended = *lit;
+                extended.datatype = symb.datatype;
+
+                self.move_instruction(dest, &Value::Literal(extended), false)
+            },
+            Value::Symbol(src_symb) =>
+            {
+                let mut result = String::new();
+
+                let dest_reg = self.get_register(&symb)?;
+                let src_reg = self.get_register(src_symb)?;
+
+                let dest_size = get_size_datatype(symb.datatype);
+                let src_size = get_size_datatype(src_symb.datatype);
 
-                // Dereference
-                OpCode::Deref =>
+                // Copy the low bytes
+                if dest_reg != src_reg
                 {
-                    result += self.dereference_instruction(&inst.arguments[0], &inst.arguments[1])?.as_str();
-                },
+                    for i in 0..src_size
+                    {
+                        result += &generate_command(&format!("mov r{}, r{}", dest_reg + i, src_reg + i))?;
+                    }
+                }
 
-                // Add
-                OpCode::Add =>
+                // Fill the first upper byte with copies of the sign bit, or with zeros
+                let fill_reg = dest_reg + src_size;
+
+                if signed
                 {
-                    result += self.add_instruction(&inst.arguments[0], &inst.arguments[1], &inst.arguments[2])?.as_str();
-                },
+                    result += &generate_command(&format!("mov r{}, r{}", fill_reg, src_reg + src_size - 1))?;
+                    result += &generate_command(&format!("lsl r{}", fill_reg))?;
+                    result += &generate_command(&format!("sbc r{}, r{}", fill_reg, fill_reg))?;
+                }
+                else
+                {
+                    result += &generate_command(&format!("clr r{}", fill_reg))?;
+                }
+
+                // Then copy it into the remaining bytes
+                for i in src_size + 1..dest_size
+                {
+                    result += &generate_command(&format!("mov r{}, r{}", dest_reg + i, fill_reg))?;
+                }
+
+                Ok(result)
+            }
+        }
+    }
+
+    /// Add a global address instruction
+    pub fn global_instruction(&mut self, dest: &Value, name: &Value) -> Result<String, Error>
+    {
+        let name = if let Value::Label(name) = name {name} else {return Err(Error::error("Expected a label").with_code(ErrorCode::E0036));};
+
+        if let Value::Symbol(symb) = dest
+        {
+            let reg = self.get_register(symb)?;
+
+            let mut result = generate_command(&format!("ldi r{}, lo8(g{})", reg, name))?;
+            result += &generate_command(&format!("ldi r{}, hi8(g{})", reg + 1, name))?;
+
+            Ok(result)
+        }
+        else
+        {
+            Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036))
+        }
+    }
+
+    /// Add an operation on a single bit of the integer behind a pointer, the bits of the lower I/O registers are set,
+    /// cleared and tested directly with sbi, cbi and sbis
+    pub fn bit_instruction(&mut self, inst: &Instruction) -> Result<String, Error>
+    {
+        let (ptr, bit) = if inst.opcode == OpCode::BitTst {(&inst.arguments[1], &inst.arguments[2])} else {(&inst.arguments[0], &inst.arguments[1])};
+
+        let (byte, bit) = match bit
+        {
+            Value::Literal(lit) => (lit.value / 8, lit.value % 8),
+            _ => return Err(Error::error("Only constant bits can be set, cleared or tested for avrasm").with_code(ErrorCode::E0035))
+        };
+
+        let dest_reg = match &inst.arguments[0]
+        {
+            Value::Symbol(symb) if inst.opcode == OpCode::BitTst => self.get_register(symb)?,
+            _ => 0
+        };
+
+        let mut result = String::new();
 
-                // All of the branches
+        // Data addresses 0x20 to 0x3F are the I/O registers 0 to 31, which are the only ones with single bit instructions
+        let io_register = match ptr
+        {
+            Value::Literal(lit) if (0x20..0x40).contains(&(lit.value + byte)) => Some(lit.value + byte - 0x20),
+            _ => None
+        };
 
-                // Branch Equal
-                OpCode::Beq =>
+        if let Some(io_register) = io_register
+        {
+            match inst.opcode
+            {
+                OpCode::BitSet => result += &generate_command(&format!("sbi {}, {}", io_register, bit))?,
+                OpCode::BitClr => result += &generate_command(&format!("cbi {}, {}", io_register, bit))?,
+                _ =>
                 {
-                    result += self.add_branch("breq", &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
-                },
+                    // Assume the bit is set, clearing the result unless the bit is set
+                    result += &generate_command(&format!("ldi r{}, 1", dest_reg))?;
+                    result += &generate_command(&format!("sbis {}, {}", io_register, bit))?;
+                    result += &generate_command(&format!("ldi r{}, 0", dest_reg))?;
+                }
+            }
+
+            return Ok(result);
+        }
+
+        // Otherwise the byte holding the bit is loaded into the temporary register through X
+        let (load, ptr_reg) = self.load_pointer(ptr, false)?;
+        result += &load;
+
+        if byte > 0
+        {
+            result += &generate_command(&format!("adiw r{}, {}", ptr_reg, byte))?;
+        }
+
+        result += &generate_command("ld r16, X")?;
+        self.last_temp_assignment = String::new();
+
+        match inst.opcode
+        {
+            OpCode::BitSet =>
+            {
+                result += &generate_command(&format!("ori r16, {}", 1 << bit))?;
+                result += &generate_command("st X, r16")?;
+            },
+            OpCode::BitClr =>
+            {
+                result += &generate_command(&format!("andi r16, {}", !(1 << bit) & 0xFF))?;
+                result += &generate_command("st X, r16")?;
+            },
+            _ =>
+            {
+                result += &generate_command(&format!("ldi r{}, 1", dest_reg))?;
+                result += &generate_command(&format!("sbrs r16, {}", bit))?;
+                result += &generate_command(&format!("ldi r{}, 0", dest_reg))?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the label of the start of a new loop, and the label of its end
+    pub fn loop_labels(&mut self) -> (String, String)
+    {
+        let label = format!("{}loop{}", self.function.name, self.next_loop);
+        self.next_loop += 1;
+
+        (label.clone(), format!("{}end", label))
+    }
+
+    /// Add a rotate of a run of registers by a single bit, the bit shifted out of one end is carried in T to the
+    /// other end
+    pub fn rotate_once(reg: usize, size: usize, left: bool) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        if left
+        {
+            result += &generate_command(&format!("bst r{}, 7", reg + size - 1))?;
+            result += &generate_command(&format!("lsl r{}", reg))?;
+
+            for byte in 1..size
+            {
+                result += &generate_command(&format!("rol r{}", reg + byte))?;
+            }
+
+            result += &generate_command(&format!("bld r{}, 0", reg))?;
+        }
+        else
+        {
+            result += &generate_command(&format!("bst r{}, 0", reg))?;
+            result += &generate_command(&format!("lsr r{}", reg + size - 1))?;
+
+            for byte in (0..size - 1).rev()
+            {
+                result += &generate_command(&format!("ror r{}", reg + byte))?;
+            }
+
+            result += &generate_command(&format!("bld r{}, 7", reg + size - 1))?;
+        }
+
+        Ok(result)
+    }
+
+    /// Add an intrinsic operation on an integer of a whole number of bytes, constant arguments are evaluated at
+    /// compile time and the counts are accumulated in the temporary register
+    pub fn intrinsic_instruction(&mut self, inst: &Instruction) -> Result<String, Error>
+    {
+        let symb = if let Value::Symbol(symb) = &inst.arguments[0]
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        let bits = symb.datatype.bit_width().unwrap();
+        let size = bits / 8;
+
+        if bits % 8 != 0
+        {
+            return Err(Error::error(&format!("Intrinsics on '{}' are not supported for avrasm, only on whole bytes", symb.datatype)).with_code(ErrorCode::E0035));
+        }
+
+        let amount = inst.arguments.get(2).cloned();
+
+        if let Value::Literal(lit) = &inst.arguments[1]
+        {
+            let constant_amount = match &amount
+            {
+                None => Some(0),
+                Some(Value::Literal(amount)) => Some(amount.value),
+                _ => None
+            };
+
+            if let Some(value) = constant_amount.and_then(|amount| symb.datatype.evaluate_intrinsic(inst.opcode, lit.value, amount))
+            {
+                return self.move_instruction(&inst.arguments[0], &Value::Literal(Literal::new(value, symb.datatype)), false);
+            }
+        }
+
+        let reg = self.get_register(&symb)?;
+        let mut result = String::new();
+
+        self.last_temp_assignment = String::new();
 
-                // Branch Not Equal
-                OpCode::Bne =>
+        match inst.opcode
+        {
+            OpCode::Rotl | OpCode::Rotr =>
+            {
+                let left = inst.opcode == OpCode::Rotl;
+
+                match amount.unwrap()
                 {
-                    result += self.add_branch("brne", &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
-                },
+                    Value::Literal(lit) =>
+                    {
+                        result += &self.move_instruction(&inst.arguments[0], &inst.arguments[1], false)?;
+
+                        // The amount wraps around at the width, and the rotate goes the shorter way around
+                        let count = ((lit.value as u128 & (u128::MAX >> (MAX_INTEGER_BITS - bits))) % bits as u128) as usize;
+                        let (left, count) = if count > bits / 2 {(!left, bits - count)} else {(left, count)};
+
+                        if bits == 8 && count == 4
+                        {
+                            result += &generate_command(&format!("swap r{}", reg))?;
+                        }
+                        else
+                        {
+                            for _ in 0..count
+                            {
+                                result += &FunctionGenerationContext::rotate_once(reg, size, left)?;
+                            }
+                        }
+                    },
+                    Value::Symbol(amount_symb) =>
+                    {
+                        // The amount is wrapped by masking it, so the width must be a power of two
+                        if !bits.is_power_of_two()
+                        {
+                            return Err(Error::error(&format!("Rotates of '{}' by a variable amount are not supported for avrasm", symb.datatype)).with_code(ErrorCode::E0035));
+                        }
 
-                // Branch Less Than
-                OpCode::Blt =>
+                        let (start, end) = self.loop_labels();
+
+                        // The amount is counted down in the temporary register
+                        result += &generate_command(&format!("mov r16, r{}", self.get_register(&amount_symb)?))?;
+                        result += &generate_command(&format!("andi r16, {}", bits - 1))?;
+                        result += &self.move_instruction(&inst.arguments[0], &inst.arguments[1], false)?;
+                        result += &generate_command("tst r16")?;
+                        result += &generate_command(&format!("breq {}", end))?;
+                        result += &generate_label(&start)?;
+                        result += &FunctionGenerationContext::rotate_once(reg, size, left)?;
+                        result += &generate_command("dec r16")?;
+                        result += &generate_command(&format!("brne {}", start))?;
+                        result += &generate_label(&end)?;
+                    },
+                    Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+                }
+            },
+            OpCode::Bswap =>
+            {
+                let src_reg = match &inst.arguments[1]
                 {
-                    result += self.add_branch("brlo", &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
-                },
+                    Value::Symbol(src_symb) => self.get_register(src_symb)?,
+                    _ => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+                };
 
-                // Branch Less Than or Equal To
-                OpCode::Ble =>
+                // Bytes are swapped in place through the temporary register, or moved straight into their new places
+                if src_reg == reg
                 {
-                    // a <= b == !(b < a)
-                    // Reverse Arguments and Branches
-                    result += self.add_branch("brlo", &inst.arguments[1], &inst.arguments[0], &inst.arguments[3], &inst.arguments[2])?.as_str();
-                },
-                OpCode::Bgt =>
+                    for byte in 0..size / 2
+                    {
+                        result += &generate_command(&format!("mov r16, r{}", reg + byte))?;
+                        result += &generate_command(&format!("mov r{}, r{}", reg + byte, reg + size - 1 - byte))?;
+                        result += &generate_command(&format!("mov r{}, r16", reg + size - 1 - byte))?;
+                    }
+                }
+                else
                 {
-                    // a > b == b < a
-                    // Reverse Arguments
-                    result += self.add_branch("brlo", &inst.arguments[1], &inst.arguments[0], &inst.arguments[2], &inst.arguments[3])?.as_str();
-                },
-                OpCode::Bge =>
+                    for byte in 0..size
+                    {
+                        result += &generate_command(&format!("mov r{}, r{}", reg + byte, src_reg + size - 1 - byte))?;
+                    }
+                }
+            },
+            _ =>
+            {
+                let src_reg = match &inst.arguments[1]
                 {
-                    // a >= b == !(a < b)
-                    // Reverse Branches
-                    result += self.add_branch("brlo", &inst.arguments[0], &inst.arguments[1], &inst.arguments[3], &inst.arguments[2])?.as_str();
-                },
-                _ => {panic!("Not yet implemented conversion for\n{}", inst)
+                    Value::Symbol(src_symb) => self.get_register(src_symb)?,
+                    _ => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+                };
+
+                result += &generate_command("clr r16")?;
+
+                if inst.opcode == OpCode::Popcnt
+                {
+                    // Count every bit which is set
+                    for byte in 0..size
+                    {
+                        for bit in 0..8
+                        {
+                            result += &generate_command(&format!("sbrc r{}, {}", src_reg + byte, bit))?;
+                            result += &generate_command("inc r16")?;
+                        }
+                    }
+                }
+                else
+                {
+                    // Count the clear bits from one end, up to the first bit which is set
+                    let (_, end) = self.loop_labels();
+
+                    let mut order: Vec<usize> = (0..bits).collect();
+
+                    if inst.opcode == OpCode::Clz
+                    {
+                        order.reverse();
+                    }
+
+                    for bit in order
+                    {
+                        result += &generate_command(&format!("sbrc r{}, {}", src_reg + bit / 8, bit % 8))?;
+                        result += &generate_command(&format!("rjmp {}", end))?;
+                        result += &generate_command("inc r16")?;
+                    }
+
+                    result += &generate_label(&end)?;
+                }
+
+                result += &generate_command(&format!("mov r{}, r16", reg))?;
+
+                for byte in 1..size
+                {
+                    result += &generate_command(&format!("clr r{}", reg + byte))?;
                 }
             }
         }
 
         Ok(result)
     }
+
+    /// Add a copy or fill of a number of bytes in memory, X points to the destination, Z to the source and Y counts
+    /// the bytes (Y is saved, as it is the frame pointer of code compiled by avr-gcc)
+    pub fn memory_instruction(&mut self, inst: &Instruction) -> Result<String, Error>
+    {
+        // Nothing is copied or set for a count of zero
+        if let Value::Literal(lit) = &inst.arguments[2]
+        {
+            if lit.value == 0
+            {
+                return Ok(String::new());
+            }
+        }
+
+        let mut result = String::new();
+        let (start, end) = self.loop_labels();
+
+        result += &generate_command("push r28")?;
+        result += &generate_command("push r29")?;
+
+        match &inst.arguments[2]
+        {
+            Value::Literal(lit) =>
+            {
+                result += &generate_command(&format!("ldi r28, {}", lit.value & 0xFF))?;
+                result += &generate_command(&format!("ldi r29, {}", (lit.value & 0xFF00) >> 8))?;
+            },
+            Value::Symbol(symb) =>
+            {
+                result += &generate_command(&format!("movw r28, r{}", self.get_register(symb)?))?;
+                result += &generate_command("sbiw r28, 0")?;
+                result += &generate_command(&format!("breq {}", end))?;
+            },
+            Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        }
+
+        result += &self.load_pointer(&inst.arguments[0], false)?.0;
+
+        // A copy reads each byte through Z, a fill stores the same byte every time
+        if inst.opcode == OpCode::Memcpy
+        {
+            result += &self.load_pointer(&inst.arguments[1], true)?.0;
+        }
+        else
+        {
+            match &inst.arguments[1]
+            {
+                Value::Literal(lit) => result += &generate_command(&format!("ldi r16, {}", lit.value & 0xFF))?,
+                Value::Symbol(symb) => result += &generate_command(&format!("mov r16, r{}", self.get_register(symb)?))?,
+                Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+            }
+        }
+
+        self.last_temp_assignment = String::new();
+
+        result += &generate_label(&start)?;
+
+        if inst.opcode == OpCode::Memcpy
+        {
+            result += &generate_command("ld r16, Z+")?;
+        }
+
+        result += &generate_command("st X+, r16")?;
+        result += &generate_command("sbiw r28, 1")?;
+        result += &generate_command(&format!("brne {}", start))?;
+        result += &generate_label(&end)?;
+
+        result += &generate_command("pop r29")?;
+        result += &generate_command("pop r28")?;
+
+        Ok(result)
+    }
+
+    /// Add a dereference instruction (effectively like a move, but out of a reference)
+    pub fn dereference_instruction(&mut self, target: &Value, value: &Value) -> Result<String, Error>
+    {
+        // Program memory can only be read using the lpm instruction
+        if let Some(datatype) = get_value_type(value)
+        {
+            if datatype.is_progmem_ptr()
+            {
+                return self.progmem_dereference_instruction(target, value);
+            }
+        }
+
+        match value
+        {
+            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            Value::Literal(lit) =>
+            {
+                // Derefencing a literal into a register
+                if let Value::Symbol(symb) = target
+                {
+                    let size = get_size_datatype(symb.datatype);
+
+                    // If every byte is within the range allowable by the out and in commands use the in command
+                    if lit.value >= 0x20 && lit.value + size as i128 <= 0x60
+                    {
+                        let mut result = String::new();
+                        let reg = self.get_register(symb)?;
+
+                        // Read the low byte first, 16 bit I/O registers latch the high byte on that read
+                        for byte in 0..size
+                        {
+                            result += &generate_command(&format!("in r{}, {}", reg + byte, lit.value + byte as i128 - 0x20))?;
+                        }
+
+                        Ok(result)
+                    }
+                    else
+                    {
+                        let mut result = String::new();
+                        let reg = self.get_register(symb)?;
+
+                        // Load the literal into the X register
+                        result += &generate_command(&format!("ldi r26, {}", lit.value & 0xFF))?;
+                        result += &generate_command(&format!("ldi r27, {}", (lit.value & 0xFF00) >> 8))?;
+
+                        // Load the low byte
+                        result += &generate_command(&format!("ld r{}, X", reg))?;
+
+                        // Load the higher bytes if needed
+                        for byte in 1..get_size_datatype(symb.datatype)
+                        {
+                            result += &generate_command(&format!("ld r{}, +X", reg + byte))?;
+                        }
+
+                        Ok(result)
+                    }
+                }
+                // Can't derefernce a literal into a literal, therefore panic
+                else
+                {
+                    panic!()
+                }
+            },
+            Value::Symbol(src_symb) =>
+            {
+                // Derefernce a symbol into a register
+                if let Value::Symbol(symb) = target
+                {
+                    let mut result = String::new();
+                    let reg = self.get_register(symb)?;
+                    let src_reg = self.get_register(src_symb)?;
+
+                    // Load the source into the X register
+                    result += &generate_command(&format!("movw r26, r{}", src_reg))?;
+
+                    // Load the low byte
+                    result += &generate_command(&format!("ld r{}, X", reg))?;
+
+                    // Load the higher bytes if needed
+                    for byte in 1..get_size_datatype(symb.datatype)
+                    {
+                        result += &generate_command(&format!("ld r{}, +X", reg + byte))?;
+                    }
+
+                    Ok(result)
+                }
+                // Can't derefernce a symbol into a literal, therefore panic
+                else
+                {
+                    panic!()
+                }
+            }
+        }
+    }
+
+    /// Add an add instruction (checked additions must set the carry flag, so can't make use of the inc command)
+    pub fn add_instruction(&mut self, dest: &Value, v0: &Value, v1: &Value, checked: bool) -> Result<String, Error>
+    {
+        // Get the destination register
+        let dest_reg = if let Value::Symbol(symb) = dest
+        {
+            self.get_register(&symb)?
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        match v0
+        {
+            Value::Label(_) => {Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))},
+            Value::Literal(_) =>
+            {
+                // This should have been cleaned up by the IR gen
+                if let Value::Literal(_) = v1
+                {
+                    Err(Error::error("Add command invoked with two literals").with_code(ErrorCode::E0036))
+                }
+                // If having a literal as the first argument can be solved by reversing the order of the operands, do so
+                else
+                {
+                    self.add_instruction(dest, v1, v0, checked)
+                }
+            },
+            Value::Symbol(symb0) =>
+            {
+                // If it is an '+=' then it can be a simple add (hopefully)
+                if dest == v0
+                {
+                    let mut result = String::new();
+
+                    // Symbol0 = Symbol0 + Symbol1, carrying from each byte into the next
+                    if let Value::Symbol(symb1) = v1
+                    {
+                        let src_reg = self.get_register(&symb1)?;
+
+                        for byte in 0..get_size_datatype(symb0.datatype)
+                        {
+                            result += &generate_command(&format!("{} r{}, r{}", if byte == 0 {"add"} else {"adc"}, dest_reg + byte, src_reg + byte))?;
+                        }
+                    }
+                    // Symbol0 = Symbol0 + Lit
+                    else if let Value::Literal(lit1) = v1
+                    {
+                        let size = get_size_datatype(symb0.datatype);
+
+                        // If the literal is 1, use the increment command instead (which doesn't set the carry flag, so
+                        // only for single bytes)
+                        if lit1.value == 1 && !checked && size == 1
+                        {
+                            result += &generate_command(&format!("inc r{}", dest_reg))?;
+                        }
+                        else
+                        {
+                            for byte in 0..size
+                            {
+                                let new_temp = format!("{}", (lit1.value >> (8 * byte)) & 0xFF);
+                                if self.last_temp_assignment != new_temp
+                                {
+                                    self.last_temp_assignment = new_temp;
+                                    result += &generate_command(&format!("ldi r16, {}", self.last_temp_assignment))?;
+                                }
+
+                                result += &generate_command(&format!("{} r{}, r16", if byte == 0 {"add"} else {"adc"}, dest_reg + byte))?;
+                            }
+                        }
+                    }
+                    else
+                    {
+                        return Err(Error::error("Unable to read value from label").with_code(ErrorCode::E0036));
+                    }
+
+                    Ok(result)
+                }
+                // The destination is the second operand, so the operands are swapped
+                else if dest == v1
+                {
+                    self.add_instruction(dest, v1, v0, checked)
+                }
+                // Otherwise the first operand is moved into the destination, which is then added to
+                else
+                {
+                    let mut result = self.move_instruction(dest, v0, true)?;
+                    result += &self.add_instruction(dest, dest, v1, checked)?;
+
+                    Ok(result)
+                }
+            },
+        }
+    }
+
+    /// Get the register holding a byte of an operand, a byte of a literal is loaded into the temporary register
+    /// (which doesn't change the flags)
+    pub fn operand_register(&mut self, value: &Value, byte: usize, result: &mut String) -> Result<usize, Error>
+    {
+        match value
+        {
+            Value::Symbol(symb) => Ok(self.get_register(symb)? + byte),
+            Value::Literal(lit) =>
+            {
+                let new_temp = format!("{}", (lit.value >> (8 * byte)) & 0xFF);
+                if self.last_temp_assignment != new_temp
+                {
+                    self.last_temp_assignment = new_temp;
+                    *result += &generate_command(&format!("ldi r{}, {}", self.temp_reg, self.last_temp_assignment))?;
+                }
+
+                Ok(self.temp_reg)
+            },
+            Value::Label(_) => Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        }
+    }
+
+    /// Add a subtraction or a bitwise operation, worked out a byte at a time in the destination (a subtraction
+    /// carries the borrow from each byte into the next)
+    pub fn binary_instruction(&mut self, opcode: OpCode, dest: &Value, v0: &Value, v1: &Value) -> Result<String, Error>
+    {
+        let symb = if let Value::Symbol(symb) = dest
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        let size = get_size_datatype(symb.datatype);
+
+        // The destination is the second operand, so the operands are swapped
+        if dest == v1 && dest != v0
+        {
+            if opcode != OpCode::Sub
+            {
+                return self.binary_instruction(opcode, dest, v1, v0);
+            }
+
+            // a - b == -(b - a), and -x == !x + 1 (one is added by subtracting 0xFF...)
+            let mut result = self.binary_instruction(opcode, dest, v1, v0)?;
+            let reg = self.get_register(&symb)?;
+
+            for byte in 0..size
+            {
+                result += &generate_command(&format!("com r{}", reg + byte))?;
+            }
+
+            for byte in 0..size
+            {
+                result += &generate_command(&format!("{} r{}, 255", if byte == 0 {"subi"} else {"sbci"}, reg + byte))?;
+            }
+
+            return Ok(result);
+        }
+
+        let mut result = String::new();
+
+        if dest != v0
+        {
+            result += &self.move_instruction(dest, v0, true)?;
+        }
+
+        let reg = self.get_register(&symb)?;
+
+        for byte in 0..size
+        {
+            let command = match (opcode, v1)
+            {
+                (OpCode::Sub, Value::Literal(lit)) => format!("{} r{}, {}", if byte == 0 {"subi"} else {"sbci"}, reg + byte, (lit.value >> (8 * byte)) & 0xFF),
+                (OpCode::And, Value::Literal(lit)) => format!("andi r{}, {}", reg + byte, (lit.value >> (8 * byte)) & 0xFF),
+                (OpCode::Or, Value::Literal(lit)) => format!("ori r{}, {}", reg + byte, (lit.value >> (8 * byte)) & 0xFF),
+                _ =>
+                {
+                    let src = self.operand_register(v1, byte, &mut result)?;
+
+                    let name = match opcode
+                    {
+                        OpCode::Sub => if byte == 0 {"sub"} else {"sbc"},
+                        OpCode::And => "and",
+                        OpCode::Or => "or",
+                        _ => "eor"
+                    };
+
+                    format!("{} r{}, r{}", name, reg + byte, src)
+                }
+            };
+
+            result += &generate_command(&command)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Add a shift of a run of registers by a single bit, a signed right shift keeps the sign bit
+    pub fn shift_once(reg: usize, size: usize, left: bool, signed: bool) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        if left
+        {
+            result += &generate_command(&format!("lsl r{}", reg))?;
+
+            for byte in 1..size
+            {
+                result += &generate_command(&format!("rol r{}", reg + byte))?;
+            }
+        }
+        else
+        {
+            result += &generate_command(&format!("{} r{}", if signed {"asr"} else {"lsr"}, reg + size - 1))?;
+
+            for byte in (0..size - 1).rev()
+            {
+                result += &generate_command(&format!("ror r{}", reg + byte))?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Add a shift, a constant amount moves whole bytes and then shifts a bit at a time, while a variable amount is
+    /// counted down in the temporary register
+    pub fn shift_instruction(&mut self, opcode: OpCode, dest: &Value, v0: &Value, v1: &Value) -> Result<String, Error>
+    {
+        let symb = if let Value::Symbol(symb) = dest
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        let bits = symb.datatype.bit_width().unwrap();
+        let size = get_size_datatype(symb.datatype);
+        let left = opcode == OpCode::Shl;
+        let signed = !left && symb.datatype.is_signed();
+
+        // The unused bits above the width would be shifted down into the value
+        if !left && bits % 8 != 0
+        {
+            return Err(Error::error(&format!("Right shifts of '{}' are not supported for avrasm, only of whole bytes", symb.datatype)).with_code(ErrorCode::E0035));
+        }
+
+        let mut result = String::new();
+
+        match v1
+        {
+            Value::Literal(lit) =>
+            {
+                if dest != v0
+                {
+                    result += &self.move_instruction(dest, v0, true)?;
+                }
+
+                let reg = self.get_register(&symb)?;
+
+                // Shifting by the width or more clears the value, or fills it with its sign
+                let count = lit.value.clamp(0, bits as i128) as usize;
+                let whole = count / 8;
+
+                if whole > 0
+                {
+                    if signed
+                    {
+                        result += &generate_command(&format!("mov r16, r{}", reg + size - 1))?;
+                        result += &generate_command("lsl r16")?;
+                        result += &generate_command("sbc r16, r16")?;
+                        self.last_temp_assignment = String::new();
+                    }
+
+                    if left
+                    {
+                        for byte in (0..size).rev()
+                        {
+                            result += &generate_command(&if byte >= whole {format!("mov r{}, r{}", reg + byte, reg + byte - whole)} else {format!("clr r{}", reg + byte)})?;
+                        }
+                    }
+                    else
+                    {
+                        for byte in 0..size
+                        {
+                            result += &generate_command(&if byte + whole < size {format!("mov r{}, r{}", reg + byte, reg + byte + whole)}
+                                else if signed {format!("mov r{}, r16", reg + byte)} else {format!("clr r{}", reg + byte)})?;
+                        }
+                    }
+                }
+
+                for _ in 0..count % 8
+                {
+                    result += &FunctionGenerationContext::shift_once(reg, size, left, signed)?;
+                }
+            },
+            Value::Symbol(amount_symb) =>
+            {
+                let (start, end) = self.loop_labels();
+
+                // The amount is read before the destination is written, as the destination may be the amount
+                result += &generate_command(&format!("mov r16, r{}", self.get_register(amount_symb)?))?;
+                self.last_temp_assignment = String::new();
+
+                if dest != v0
+                {
+                    result += &self.move_instruction(dest, v0, true)?;
+                }
+
+                let reg = self.get_register(&symb)?;
+
+                result += &generate_command("tst r16")?;
+                result += &generate_command(&format!("breq {}", end))?;
+                result += &generate_label(&start)?;
+                result += &FunctionGenerationContext::shift_once(reg, size, left, signed)?;
+                result += &generate_command("dec r16")?;
+                result += &generate_command(&format!("brne {}", start))?;
+                result += &generate_label(&end)?;
+            },
+            Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        }
+
+        Ok(result)
+    }
+
+    /// Get the branch taken when one value is less than another, which depends on whether they are signed
+    pub fn less_branch(v0: &Value, v1: &Value) -> &'static str
+    {
+        let datatype = [v0, v1].iter().find_map(|value| if let Value::Symbol(symb) = value {Some(symb.datatype)} else {None});
+
+        if datatype.is_some_and(|datatype| datatype.is_signed()) {"brlt"} else {"brlo"}
+    }
+
+    /// Add a comparison of two values, a byte at a time carrying from each byte into the next
+    pub fn compare_values(&mut self, v0: &Value, v1: &Value) -> Result<String, Error>
+    {
+        let size = match [v0, v1].iter().filter_map(|value| if let Value::Symbol(symb) = value {Some(get_size_datatype(symb.datatype))} else {None}).max()
+        {
+            Some(size) => size,
+            None => return Err(Error::error("Comparison of two literals").with_code(ErrorCode::E0036))
+        };
+
+        let mut result = String::new();
+
+        for byte in 0..size
+        {
+            // The first byte can be compared to a literal directly
+            if let (0, Value::Symbol(symb0), Value::Literal(lit1)) = (byte, v0, v1)
+            {
+                result += &generate_command(&format!("cpi r{}, {}", self.get_register(symb0)?, lit1.value & 0xFF))?;
+                continue;
+            }
+
+            let reg0 = self.operand_register(v0, byte, &mut result)?;
+            let reg1 = self.operand_register(v1, byte, &mut result)?;
+
+            result += &generate_command(&format!("{} r{}, r{}", if byte == 0 {"cp"} else {"cpc"}, reg0, reg1))?;
+        }
+
+        Ok(result)
+    }
+
+    /// Add a comparison giving 1 if it holds and 0 otherwise
+    pub fn compare_instruction(&mut self, opcode: OpCode, dest: &Value, v0: &Value, v1: &Value) -> Result<String, Error>
+    {
+        let symb = if let Value::Symbol(symb) = dest
+        {
+            symb.clone()
+        }
+        else
+        {
+            return Err(Error::error("Unable to assign to anything but a symbol").with_code(ErrorCode::E0036));
+        };
+
+        let less = FunctionGenerationContext::less_branch(v0, v1);
+        let greater_equal = if less == "brlt" {"brge"} else {"brsh"};
+
+        // a > b == b < a, and a <= b == b >= a
+        let (left, right, branch) = match opcode
+        {
+            OpCode::Ceq => (v0, v1, "breq"),
+            OpCode::Cne => (v0, v1, "brne"),
+            OpCode::Clt => (v0, v1, less),
+            OpCode::Cge => (v0, v1, greater_equal),
+            OpCode::Cgt => (v1, v0, less),
+            _ => (v1, v0, greater_equal)
+        };
+
+        let mut result = self.compare_values(left, right)?;
+
+        let reg = self.get_register(&symb)?;
+        let label = format!("{}compare{}", self.function.name, self.next_compare);
+        self.next_compare += 1;
+
+        // Loading a register doesn't change the flags, so the result is set to one, and then to zero unless the
+        // branch is taken
+        result += &generate_command(&format!("ldi r{}, 1", reg))?;
+        result += &generate_command(&format!("{} {}", branch, label))?;
+        result += &generate_command(&format!("ldi r{}, 0", reg))?;
+        result += &generate_label(&label)?;
+
+        for byte in 1..get_size_datatype(symb.datatype)
+        {
+            result += &generate_command(&format!("ldi r{}, 0", reg + byte))?;
+        }
+
+        Ok(result)
+    }
+
+    /// Add a check after an operation, calling the panic hook if the operation overflowed
+    pub fn overflow_check(&mut self, is_signed: bool, location: &FileLocation) -> Result<String, Error>
+    {
+        // Signed overflow sets the overflow flag, unsigned overflow sets the carry flag
+        self.panic_unless(if is_signed {"brvc"} else {"brcc"}, location)
+    }
+
+    /// Add a call to the panic hook, skipped by the given branch
+    pub fn panic_unless(&mut self, branch: &str, location: &FileLocation) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        let label = format!("{}check{}", self.function.name, self.next_check);
+        self.next_check += 1;
+
+        result += &generate_command(&format!("{} {}", branch, label))?;
+        result += &self.panic_call(location)?;
+        result += &generate_label(&label)?;
+
+        Ok(result)
+    }
+
+    /// Add a call to the panic hook (the hook takes the file name in r24:r25, the line in r22:r23 and the column in
+    /// r20:r21)
+    pub fn panic_call(&mut self, location: &FileLocation) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        let file_index = self.file_names.iter().position(|name| name == location.name()).unwrap();
+
+        result += &generate_command(&format!("ldi r24, lo8(file{})", file_index))?;
+        result += &generate_command(&format!("ldi r25, hi8(file{})", file_index))?;
+        result += &generate_command(&format!("ldi r22, {}", location.row & 0xFF))?;
+        result += &generate_command(&format!("ldi r23, {}", (location.row & 0xFF00) >> 8))?;
+        result += &generate_command(&format!("ldi r20, {}", location.col & 0xFF))?;
+        result += &generate_command(&format!("ldi r21, {}", (location.col & 0xFF00) >> 8))?;
+        result += &generate_command(&format!("call f{}", CHECKED_PANIC_HOOK))?;
+
+        Ok(result)
+    }
+
+    /// Add a bounds check, calling the panic hook unless v0 < v1 (for Clt) or v0 <= v1 (for Cle) as unsigned values
+    pub fn bounds_check(&mut self, opcode: OpCode, v0: &Value, v1: &Value, location: &FileLocation) -> Result<String, Error>
+    {
+        // A check between two constants which is left after optimization always fails
+        if let (Value::Literal(_), Value::Literal(_)) = (v0, v1)
+        {
+            return self.panic_call(location);
+        }
+
+        // a <= b == !(b < a), so the operands are compared the other way around
+        let (left, right, branch) = if opcode == OpCode::Clt {(v0, v1, "brlo")} else {(v1, v0, "brsh")};
+
+        let size = [left, right].iter()
+            .filter_map(|value| if let Value::Symbol(symb) = value {Some(get_size_datatype(symb.datatype))} else {None})
+            .max()
+            .unwrap();
+
+        let mut result = String::new();
+
+        // Compare a byte at a time, literals are loaded into the temporary register (which doesn't change the flags)
+        for byte in 0..size
+        {
+            let mut registers = vec![];
+
+            for value in [left, right]
+            {
+                match value
+                {
+                    Value::Symbol(symb) => registers.push(self.get_register(symb)? + byte),
+                    Value::Literal(lit) =>
+                    {
+                        self.last_temp_assignment = format!("{}", (lit.value >> (8 * byte)) & 0xFF);
+                        result += &generate_command(&format!("ldi r{}, {}", self.temp_reg, self.last_temp_assignment))?;
+                        registers.push(self.temp_reg);
+                    },
+                    Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+                }
+            }
+
+            result += &generate_command(&format!("{} r{}, r{}", if byte == 0 {"cp"} else {"cpc"}, registers[0], registers[1]))?;
+        }
+
+        result += &self.panic_unless(branch, location)?;
+
+        Ok(result)
+    }
+
+    // Add a branch operation
+    pub fn add_branch(&mut self, inst: &str, v0: &Value, v1: &Value, l0: &Value, l1: &Value) -> Result<String, Error>
+    {
+        let label0 = if let Value::Label(s) = l0 {s} else {return Err(Error::error("Expected a label").with_code(ErrorCode::E0036));};
+        let label1 = if let Value::Label(s) = l1 {s} else {return Err(Error::error("Expected a label").with_code(ErrorCode::E0036));};
+
+        let mut result = self.compare_values(v0, v1)?;
+
+        // Perform the branch and add an 'else' jmp
+        result += &generate_command(&format!("{} {}", inst, get_label(&self.function, label0)?))?;
+        result += &generate_command(&format!("jmp {}", get_label(&self.function, label1)?))?;
+
+        Ok(result)
+    }
+
+    /// Render an IR function in AVR Assembly
+    pub fn render_function(&mut self) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        // Add the comment at the top of the function
+        result += &generate_comment(&format!("Function {}", self.function.render_signature()))?;
+
+        // Add the label marking the start of the function
+        result += &generate_label(&format!("f{}", self.function.name))?;
+
+        // Every variable is kept in registers, and there are only enough of them for operations on values of up to 32
+        // bits
+        for i in 0..self.function.instructions.len()
+        {
+            let inst = self.function.instructions.get(&i).unwrap();
+
+            for datatype in inst.arguments.iter().filter_map(|value| if let Value::Symbol(symb) = value {Some(symb.datatype)} else {None})
+            {
+                if datatype.num_ptr == 0 && !datatype.is_ref && datatype.bit_width().is_some_and(|bits| bits > 32)
+                {
+                    return Err(Error::error(&format!("'{}' is too wide for avrasm, in function '{}'", datatype, self.function.name))
+                        .with_code(ErrorCode::E0040).with_fallback_label(&inst.span, "used here")
+                        .with_note("values of up to 32 bits are supported with -g avrasm, use a narrower type or -g llvm"));
+                }
+            }
+        }
+
+        // Only additions and bounds checks are checked at runtime, anything else asked to be checked is rejected
+        // rather than silently compiled unchecked
+        for i in 0..self.function.instructions.len()
+        {
+            let inst = self.function.instructions.get(&i).unwrap();
+
+            if let Some(location) = &inst.checked
+            {
+                if !matches!(inst.opcode, OpCode::Add | OpCode::Clt | OpCode::Cle)
+                {
+                    return Err(Error::error(&format!("Checked '{}' is not supported for avrasm", format!("{:?}", inst.opcode).to_lowercase()))
+                        .with_code(ErrorCode::E0035).with_label(Span::new(location.clone(), location.clone()), "checked here")
+                        .with_note("only additions are checked for overflow with -g avrasm, compile without --checked-arith or use -g llvm"));
+                }
+            }
+        }
+
+        // Iterate over each instruction (in order)
+        for i in 0..self.function.instructions.len()
+        {
+            let inst = self.function.instructions.get(&i).unwrap().clone();
+
+            // If there are labels available for a given instruction, write those in
+            if let Some(labels) = self.function.labels.get(&i)
+            {
+                // The temporary register may hold something else when the label is jumped to
+                self.last_temp_assignment = String::new();
+
+                for label in labels
+                {
+                    result += &generate_label(&get_label(&self.function, label)?)?;
+                }
+            }
+            
+            result += &self.render_instruction(i, &inst).map_err(|error| error.with_fallback_label(&inst.span, "while generating code for this"))?;
+
+            // Registers of symbols which aren't used again are free for the rest of the function
+            if let Some(titles) = self.release_after.remove(&i)
+            {
+                for title in titles
+                {
+                    self.release_register(&title);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Render a single IR instruction in AVR Assembly (i is the index of the instruction)
+    pub fn render_instruction(&mut self, i: usize, inst: &Instruction) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        match inst.opcode
+        {
+            OpCode::Nop => {},
+            OpCode::Jmp =>
+            {
+                // Because the label is within the function, we will assume it is just a relative jump
+
+                if let Value::Label(label) = &inst.arguments[0]
+                {
+                    result += &generate_command(&format!("jmp {}", get_label(&self.function, label)?))?;
+                }
+            },
+
+            // Mov Alloc and Cast are all wrappers for moves (Alloc and Cast set a reference, rather than storing
+            // through it)
+            OpCode::Mov | OpCode::Alloc | OpCode::Cast =>
+            {
+                // Nothing is moved if the destination takes over the registers of the value
+                if !self.reuse_register(i, inst)
+                {
+                    result += self.move_instruction(&inst.arguments[0], &inst.arguments[1], inst.opcode != OpCode::Mov)?.as_str();
+                }
+            },
+
+            // Sign and Zero Extension
+            OpCode::Sext | OpCode::Zext =>
+            {
+                result += self.extend_instruction(&inst.arguments[0], &inst.arguments[1], inst.opcode == OpCode::Sext)?.as_str();
+            },
+
+            // Dereference
+            OpCode::Deref =>
+            {
+                result += self.dereference_instruction(&inst.arguments[0], &inst.arguments[1])?.as_str();
+            },
+
+            // Array Access
+            OpCode::Array =>
+            {
+                result += self.array_instruction(&inst.arguments[0], &inst.arguments[1], &inst.arguments[2])?.as_str();
+            },
+
+            // Global Address
+            OpCode::Global =>
+            {
+                result += self.global_instruction(&inst.arguments[0], &inst.arguments[1])?.as_str();
+            },
+
+            // Pointer Offset
+            OpCode::Add | OpCode::Sub if get_value_type(&inst.arguments[1]).is_some_and(|t| t.num_ptr > 0) =>
+            {
+                result += self.pointer_offset_instruction(&inst.arguments[0], &inst.arguments[1], &inst.arguments[2], inst.opcode == OpCode::Sub)?.as_str();
+            },
+
+            // Add (the first operand is the destination if the destination took over its registers)
+            OpCode::Add =>
+            {
+                let v0 = if self.reuse_register(i, inst) {&inst.arguments[0]} else {&inst.arguments[1]};
+                result += self.add_instruction(&inst.arguments[0], v0, &inst.arguments[2], inst.checked.is_some())?.as_str();
+
+                if let Some(location) = &inst.checked
+                {
+                    let is_signed = get_value_type(&inst.arguments[0]).unwrap().is_signed();
+                    result += self.overflow_check(is_signed, location)?.as_str();
+                }
+            },
+
+            // Subtraction and Bitwise Operations
+            OpCode::Sub | OpCode::And | OpCode::Or | OpCode::Xor =>
+            {
+                let v0 = if self.reuse_register(i, inst) {&inst.arguments[0]} else {&inst.arguments[1]};
+                result += self.binary_instruction(inst.opcode, &inst.arguments[0], v0, &inst.arguments[2])?.as_str();
+            },
+
+            // Shifts
+            OpCode::Shl | OpCode::Shr =>
+            {
+                let v0 = if self.reuse_register(i, inst) {&inst.arguments[0]} else {&inst.arguments[1]};
+                result += self.shift_instruction(inst.opcode, &inst.arguments[0], v0, &inst.arguments[2])?.as_str();
+            },
+
+            // Bounds Checks
+            OpCode::Clt | OpCode::Cle if inst.checked.is_some() =>
+            {
+                result += self.bounds_check(inst.opcode, &inst.arguments[1], &inst.arguments[2], inst.checked.as_ref().unwrap())?.as_str();
+            },
+
+            // Comparisons
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cle | OpCode::Cgt | OpCode::Cge =>
+            {
+                let v0 = if self.reuse_register(i, inst) {&inst.arguments[0]} else {&inst.arguments[1]};
+                result += self.compare_instruction(inst.opcode, &inst.arguments[0], v0, &inst.arguments[2])?.as_str();
+            },
+
+            // Bit Operations
+            OpCode::BitSet | OpCode::BitClr | OpCode::BitTst =>
+            {
+                result += self.bit_instruction(inst)?.as_str();
+            },
+
+            // Intrinsics
+            OpCode::Rotl | OpCode::Rotr | OpCode::Popcnt | OpCode::Clz | OpCode::Ctz | OpCode::Bswap =>
+            {
+                result += self.intrinsic_instruction(inst)?.as_str();
+            },
+
+            // Memory Operations
+            OpCode::Memcpy | OpCode::Memset =>
+            {
+                result += self.memory_instruction(inst)?.as_str();
+            },
+
+            // Assumptions only guide the optimizer
+            OpCode::Unreachable | OpCode::Assume => {},
+
+            // All of the branches
+
+            // Branch Equal
+            OpCode::Beq =>
+            {
+                result += self.add_branch("breq", &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
+            },
+
+            // Branch Not Equal
+            OpCode::Bne =>
+            {
+                result += self.add_branch("brne", &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
+            },
+
+            // Branch Less Than
+            OpCode::Blt =>
+            {
+                let less = FunctionGenerationContext::less_branch(&inst.arguments[0], &inst.arguments[1]);
+                result += self.add_branch(less, &inst.arguments[0], &inst.arguments[1], &inst.arguments[2], &inst.arguments[3])?.as_str();
+            },
+
+            // Branch Less Than or Equal To
+            OpCode::Ble =>
+            {
+                // a <= b == !(b < a)
+                // Reverse Arguments and Branches
+                let less = FunctionGenerationContext::less_branch(&inst.arguments[0], &inst.arguments[1]);
+                result += self.add_branch(less, &inst.arguments[1], &inst.arguments[0], &inst.arguments[3], &inst.arguments[2])?.as_str();
+            },
+            OpCode::Bgt =>
+            {
+                // a > b == b < a
+                // Reverse Arguments
+                let less = FunctionGenerationContext::less_branch(&inst.arguments[0], &inst.arguments[1]);
+                result += self.add_branch(less, &inst.arguments[1], &inst.arguments[0], &inst.arguments[2], &inst.arguments[3])?.as_str();
+            },
+            OpCode::Bge =>
+            {
+                // a >= b == !(a < b)
+                // Reverse Branches
+                let less = FunctionGenerationContext::less_branch(&inst.arguments[0], &inst.arguments[1]);
+                result += self.add_branch(less, &inst.arguments[0], &inst.arguments[1], &inst.arguments[3], &inst.arguments[2])?.as_str();
+            },
+            // Return
+            OpCode::Ret =>
+            {
+                result += self.return_instruction(&inst.arguments[0])?.as_str();
+            },
+            _ =>
+            {
+                let operation = match inst.opcode
+                {
+                    OpCode::Push | OpCode::Call => String::from("Function calls are"),
+                    _ => format!("'{}' is", format!("{:?}", inst.opcode).to_lowercase())
+                };
+
+                return Err(Error::error(&format!("{} not supported for avrasm", operation)).with_code(ErrorCode::E0035)
+                    .with_note("compile with -g llvm instead"));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Add a return instruction, the value is given back in the registers avr-gcc uses, ending at r25
+    pub fn return_instruction(&mut self, value: &Value) -> Result<String, Error>
+    {
+        let mut result = String::new();
+
+        let size = match get_value_type(value)
+        {
+            Some(datatype) if datatype.raw_type == NonPtrType::Void && datatype.num_ptr == 0 => 0,
+            Some(datatype) => get_size_datatype(datatype),
+            None => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+        };
+
+        let first = 26 - size.next_multiple_of(2);
+
+        // The value may overlap the registers it is returned in, so the high bytes are moved first
+        for byte in (0..size).rev()
+        {
+            match value
+            {
+                Value::Literal(lit) => result += &generate_command(&format!("ldi r{}, {}", first + byte, (lit.value >> (8 * byte)) & 0xFF))?,
+                Value::Symbol(symb) =>
+                {
+                    let reg = self.get_register(symb)? + byte;
+
+                    if reg != first + byte
+                    {
+                        result += &generate_command(&format!("mov r{}, r{}", first + byte, reg))?;
+                    }
+                },
+                Value::Label(_) => return Err(Error::fatal_error("Cannot use label as a value").with_code(ErrorCode::E0036))
+            }
+        }
+
+        result += &generate_command("ret")?;
+
+        Ok(result)
+    }
+}
+
+/// Find the instruction after which each symbol is no longer used, by the index of the instruction. A symbol used
+/// inside a loop lives until the jump back to the start of the loop, as it may be used again in the next iteration
+fn find_last_uses(function: &Function) -> HashMap<usize, Vec<String>>
+{
+    let mut ranges: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut loops = vec![];
+
+    let positions: HashMap<&String, usize> = function.labels.iter().flat_map(|(i, labels)| labels.iter().map(move |label| (label, *i))).collect();
+
+    for i in 0..function.instructions.len()
+    {
+        for arg in &function.instructions.get(&i).unwrap().arguments
+        {
+            match arg
+            {
+                Value::Symbol(symb) => ranges.entry(symb.title.clone()).or_insert((i, i)).1 = i,
+                // A jump to an earlier instruction closes a loop
+                Value::Label(label) => match positions.get(label)
+                {
+                    Some(start) if *start <= i => loops.push((*start, i)),
+                    _ => {}
+                },
+                Value::Literal(_) => {}
+            }
+        }
+    }
+
+    // Widening a range to cover one loop may make it overlap another
+    let mut changed = true;
+
+    while changed
+    {
+        changed = false;
+
+        for (first, last) in ranges.values_mut()
+        {
+            for (start, end) in &loops
+            {
+                if *first <= *end && *last >= *start && (*first > *start || *last < *end)
+                {
+                    *first = (*first).min(*start);
+                    *last = (*last).max(*end);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut result: HashMap<usize, Vec<String>> = HashMap::new();
+
+    for (title, (_, last)) in ranges
+    {
+        result.entry(last).or_default().push(title);
+    }
+
+    result
 }
 
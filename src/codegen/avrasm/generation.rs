@@ -1,34 +1,96 @@
 use crate::cli::Error;
-use crate::irgen::{Function};
+use crate::irgen::{Function, Global};
 
-use super::FunctionGenerationContext;
+use super::{FunctionGenerationContext, generate_command, generate_label, get_size_datatype};
+use super::super::checked_file_names;
 
 /// Wrapper for the AVR Assembly Code Generator
 #[derive(Debug, Clone)]
 pub struct AvrAsmGenerator
 {
-    functions: Vec<Function>
+    functions: Vec<Function>,
+    globals: Vec<Global>
 }
 
 impl AvrAsmGenerator
 {
-    /// Generate a new AvrAsmGenerator from a vector of IR functions
-    pub fn new(functions: Vec<Function>) -> Self
+    /// Generate a new AvrAsmGenerator from a vector of IR functions and globals
+    pub fn new(functions: Vec<Function>, globals: Vec<Global>) -> Self
     {
         Self
         {
-            functions
+            functions,
+            globals
         }
     }
 
+    /// Render a global into the data or program memory section
+    fn render_global(global: &Global) -> Result<String, Error>
+    {
+        let size = get_size_datatype(global.datatype);
+
+        let directive = match size
+        {
+            1 => ".byte",
+            2 => ".word",
+            4 => ".long",
+            8 => ".quad",
+            16 => ".octa",
+            _ => ".byte"
+        };
+
+        let mask = if size >= 16 {-1} else {(1i128 << (size * 8)) - 1};
+
+        let values: Vec<String> = match directive
+        {
+            // Values of any other size are written a byte at a time, lowest byte first
+            ".byte" => (0..global.element_count()).flat_map(|i| (0..size).map(move |byte| format!("{}", (global.value_at(i) >> (8 * byte)) & 0xFF))).collect(),
+            _ => (0..global.element_count()).map(|i| format!("{}", global.value_at(i) & mask)).collect()
+        };
+
+        let mut result = String::new();
+
+        result += &generate_command(if global.is_progmem {".section .progmem.data"} else {".section .data"})?;
+        result += &generate_label(&format!("g{}", global.name))?;
+        result += &generate_command(&format!("{} {}", directive, values.join(", ")))?;
+
+        Ok(result)
+    }
+
     /// Render each function in turn
     pub fn render(self) -> Result<String, Error>
     {
         let mut result = String::new();
 
+        if !self.globals.is_empty()
+        {
+            for global in &self.globals
+            {
+                result += &AvrAsmGenerator::render_global(global)?;
+            }
+
+            result += &generate_command(".section .text")?;
+        }
+
+        // File names reported by checked operations
+        let file_names = checked_file_names(&self.functions);
+
+        if !file_names.is_empty()
+        {
+            result += &generate_command(".section .data")?;
+
+            for (i, name) in file_names.iter().enumerate()
+            {
+                result += &generate_label(&format!("file{}", i))?;
+                result += &generate_command(&format!(".asciz {:?}", name))?;
+            }
+
+            result += &generate_command(".section .text")?;
+        }
+
         for function in self.functions
         {
-            let mut context = FunctionGenerationContext::new(function);
+            let mut context = FunctionGenerationContext::new(function, file_names.clone());
             result += &format!("{}", context.render_function()?);
         }
 
@@ -1,7 +1,7 @@
 use crate::cli::Error;
 
 use crate::irgen::Function;
-use crate::irgen::{DataType, NonPtrType};
+use crate::irgen::DataType;
 
 /// Generate a comment in avrasm
 pub fn generate_comment(data: &str) -> Result<String, Error>
@@ -27,16 +27,16 @@ pub fn get_label(func: &Function, label: &String) -> Result<String, Error>
     Ok(format!("{}{}", func.name, label))
 }
 
-/// Get the size of a datatype
+/// Get the size of a datatype (a reference holds the address it refers to)
 pub fn get_size_datatype(t: DataType) -> usize
 {
-    if t.num_ptr == 0
+    if t.num_ptr == 0 && !t.is_ref
     {
-        match t.raw_type
+        // Integers take up as many bytes (and so registers) as their width needs, so a u24 takes up 3 bytes
+        match DataType::new(t.raw_type, 0, false).bit_width()
         {
-            NonPtrType::I8 | NonPtrType::U8 => 1,
-            NonPtrType::I16 | NonPtrType::U16 => 2,
-            _ => unimplemented!()
+            Some(bits) => bits.div_ceil(8),
+            None => unimplemented!()
         }
     }
     else
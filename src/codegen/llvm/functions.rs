@@ -1,8 +1,10 @@
 use crate::cli::Error;
+use crate::tokenizer::FileLocation;
 
-use crate::irgen::{Function, DataType, NonPtrType, Symbol, Value, OpCode, get_value_type};
+use crate::irgen::{Function, DataType, NonPtrType, Symbol, Value, OpCode, Instruction, get_value_type, correct_type_references};
 
-use super::{convert_to_llvm, bytes_size_of};
+use super::{convert_to_llvm_in, bytes_size_of, bits_size_of};
+use super::super::CHECKED_PANIC_HOOK;
 
 use std::collections::HashMap;
 
@@ -49,12 +51,17 @@ pub struct FunctionGenerationContext
     next_temp: usize,
     result: String,
     current_arguments: String,
+    program_space: bool,
+    file_names: Vec<String>,
+    next_check: usize,
+    pub declarations: Vec<String>
 }
 
 impl FunctionGenerationContext
 {
-    /// Generate a new function generation context object
-    pub fn new(func: Function) -> Self
+    /// Generate a new function generation context object (program_space is set if the target keeps
+    /// program memory in a separate address space, file_names are the files reported by checked operations)
+    pub fn new(func: Function, program_space: bool, file_names: Vec<String>) -> Self
     {
         Self
         {
@@ -62,10 +69,369 @@ impl FunctionGenerationContext
             values: HashMap::new(),
             next_temp: 0,
             result: String::new(),
-            current_arguments: String::new()
+            current_arguments: String::new(),
+            program_space,
+            file_names,
+            next_check: 0,
+            declarations: vec![]
         }
     }
 
+    /// Record a declaration required at the top level of the module
+    pub fn declare(&mut self, declaration: String)
+    {
+        if !self.declarations.contains(&declaration)
+        {
+            self.declarations.push(declaration);
+        }
+    }
+
+    /// Render an integer value as a 64 bit offset (the sign of the original type is respected)
+    pub fn render_offset(&mut self, val: &Value) -> String
+    {
+        let datatype = get_value_type(val).unwrap();
+
+        match val
+        {
+            Value::Literal(literal) => format!("{}", literal.value),
+            _ if datatype.bit_width() == Some(64) => self.render_value(val, false),
+            // Wider indexes are truncated, as no offset can be larger than 64 bits
+            _ if datatype.bit_width().is_some_and(|bits| bits > 64) =>
+            {
+                let current = self.render_value(val, true);
+                let temp = self.get_next_temp();
+
+                self.insert_command(&format!("{} = trunc {} to i64", temp, current));
+
+                temp
+            },
+            _ =>
+            {
+                let current = self.render_value(val, true);
+                let temp = self.get_next_temp();
+
+                self.insert_command(&format!("{} = {} {} to i64", temp, if datatype.is_signed() {"sext"} else {"zext"}, current));
+
+                temp
+            }
+        }
+    }
+
+    /// Add pointer arithmetic (offsets and differences are measured in elements rather than bytes)
+    pub fn add_pointer_arithmetic(&mut self, inst: &Instruction)
+    {
+        let dest_type = get_value_type(&inst.arguments[0]).unwrap();
+        let ptr_type = get_value_type(&inst.arguments[1]).unwrap();
+        let element_type = self.llvm_type(&ptr_type.dereferenced());
+
+        let ptr = self.render_value(&inst.arguments[1], true);
+        let result = self.get_next_temp();
+
+        // Difference between two pointers
+        if dest_type.num_ptr == 0
+        {
+            let other = self.render_value(&inst.arguments[2], true);
+
+            let address0 = self.get_next_temp();
+            let address1 = self.get_next_temp();
+            let bytes = self.get_next_temp();
+
+            self.insert_command(&format!("{} = ptrtoint {} to i64", address0, ptr));
+            self.insert_command(&format!("{} = ptrtoint {} to i64", address1, other));
+            self.insert_command(&format!("{} = sub i64 {}, {}", bytes, address0, address1));
+            self.insert_command(&format!("{} = sdiv exact i64 {}, {}", result, bytes, bytes_size_of(&ptr_type.dereferenced())));
+        }
+        // Offset of a pointer
+        else
+        {
+            let mut offset = self.render_offset(&inst.arguments[2]);
+
+            if inst.opcode == OpCode::Sub
+            {
+                let negated = self.get_next_temp();
+                self.insert_command(&format!("{} = sub i64 0, {}", negated, offset));
+                offset = negated;
+            }
+
+            self.insert_command(&format!("{} = getelementptr {}, {}, i64 {}", result, element_type, ptr, offset));
+        }
+
+        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&dest_type), result));
+    }
+
+    /// Add a checked arithmetic operation, which calls the panic hook if the operation overflows, divides by zero or
+    /// shifts by more than the width of the type
+    pub fn add_checked_arithmetic(&mut self, inst: &Instruction)
+    {
+        let location = inst.checked.clone().unwrap();
+
+        let datatype = get_value_type(&inst.arguments[0]).unwrap();
+        let llvm_type = self.llvm_type(&datatype);
+        let is_signed = datatype.is_signed();
+        let bits = datatype.bit_width().unwrap_or(64);
+
+        let val0 = self.render_value(&inst.arguments[1], false);
+        let val1 = self.render_value(&inst.arguments[2], false);
+
+        let result = self.get_next_temp();
+        let failed = self.get_next_temp();
+
+        let operation = match inst.opcode
+        {
+            OpCode::Add | OpCode::Sub | OpCode::Mul =>
+            {
+                // The overflow intrinsics give both the result and whether the operation overflowed
+                let intrinsic = format!("llvm.{}{}.with.overflow.{}",
+                    if is_signed {"s"} else {"u"},
+                    format!("{:?}", inst.opcode).to_lowercase(),
+                    llvm_type);
+
+                self.declare(format!("declare {{{0}, i1}} @{1}({0}, {0})", llvm_type, intrinsic));
+
+                let pair = self.get_next_temp();
+                self.insert_command(&format!("{} = call {{{1}, i1}} @{2}({1} {3}, {1} {4})", pair, llvm_type, intrinsic, val0, val1));
+                self.insert_command(&format!("{} = extractvalue {{{}, i1}} {}, 0", result, llvm_type, pair));
+                self.insert_command(&format!("{} = extractvalue {{{}, i1}} {}, 1", failed, llvm_type, pair));
+
+                None
+            },
+            OpCode::Div | OpCode::Mod =>
+            {
+                let zero = self.get_next_temp();
+                self.insert_command(&format!("{} = icmp eq {} {}, 0", zero, llvm_type, val1));
+
+                // Signed division of the minimum value by -1 overflows
+                if is_signed
+                {
+                    let is_min = self.get_next_temp();
+                    let is_negative_one = self.get_next_temp();
+                    let overflow = self.get_next_temp();
+
+                    self.insert_command(&format!("{} = icmp eq {} {}, {}", is_min, llvm_type, val0, -1i128 << (bits - 1)));
+                    self.insert_command(&format!("{} = icmp eq {} {}, -1", is_negative_one, llvm_type, val1));
+                    self.insert_command(&format!("{} = and i1 {}, {}", overflow, is_min, is_negative_one));
+                    self.insert_command(&format!("{} = or i1 {}, {}", failed, zero, overflow));
+                }
+                else
+                {
+                    self.insert_command(&format!("{} = or i1 {}, false", failed, zero));
+                }
+
+                Some(match (inst.opcode, is_signed)
+                {
+                    (OpCode::Div, true) => "sdiv",
+                    (OpCode::Div, false) => "udiv",
+                    (_, true) => "srem",
+                    (_, false) => "urem"
+                })
+            },
+            _ =>
+            {
+                // Shifting by the width of the type or more is undefined
+                self.insert_command(&format!("{} = icmp uge {} {}, {}", failed, llvm_type, val1, bits));
+
+                Some(match (inst.opcode, is_signed)
+                {
+                    (OpCode::Shl, _) => "shl",
+                    (_, true) => "ashr",
+                    (_, false) => "lshr"
+                })
+            }
+        };
+
+        self.add_panic_branch(&failed, &location);
+
+        // Operations which are undefined on failure are only performed once the check has passed
+        if let Some(command) = operation
+        {
+            self.insert_command(&format!("{} = {} {} {}, {}", result, command, llvm_type, val0, val1));
+        }
+
+        self.add_move(&inst.arguments[0], format!("{} {}", llvm_type, result));
+    }
+
+    /// Add an operation on a single bit of the integer behind a pointer, the integer is accessed with volatile loads
+    /// and stores as it is usually a peripheral register
+    pub fn add_bit_operation(&mut self, inst: &Instruction)
+    {
+        let (ptr_value, bit_value) = if inst.opcode == OpCode::BitTst {(&inst.arguments[1], &inst.arguments[2])} else {(&inst.arguments[0], &inst.arguments[1])};
+
+        let datatype = get_value_type(ptr_value).unwrap().dereferenced();
+        let llvm_type = self.llvm_type(&datatype);
+        let bits = bits_size_of(&datatype);
+
+        let ptr = self.render_value(ptr_value, true);
+
+        // The bit is given as a byte, so it is converted to the type of the integer to be shifted
+        let bit = match bit_value
+        {
+            Value::Literal(literal) => format!("{}", literal.value),
+            _ =>
+            {
+                let byte = self.render_value(bit_value, false);
+
+                if bits == 8
+                {
+                    byte
+                }
+                else
+                {
+                    let temp = self.get_next_temp();
+                    self.insert_command(&format!("{} = {} i8 {} to {}", temp, if bits < 8 {"trunc"} else {"zext"}, byte, llvm_type));
+                    temp
+                }
+            }
+        };
+
+        let current = self.get_next_temp();
+        let mask = self.get_next_temp();
+
+        self.insert_command(&format!("{} = load volatile {}, {}, align {}", current, llvm_type, ptr, bytes_size_of(&datatype)));
+        self.insert_command(&format!("{} = shl {} 1, {}", mask, llvm_type, bit));
+
+        match inst.opcode
+        {
+            OpCode::BitTst =>
+            {
+                let masked = self.get_next_temp();
+                let is_set = self.get_next_temp();
+                let result = self.get_next_temp();
+
+                let dest_type = self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap());
+
+                self.insert_command(&format!("{} = and {} {}, {}", masked, llvm_type, current, mask));
+                self.insert_command(&format!("{} = icmp ne {} {}, 0", is_set, llvm_type, masked));
+                self.insert_command(&format!("{} = zext i1 {} to {}", result, is_set, dest_type));
+
+                self.add_move(&inst.arguments[0], format!("{} {}", dest_type, result));
+            },
+            _ =>
+            {
+                let next = self.get_next_temp();
+
+                if inst.opcode == OpCode::BitSet
+                {
+                    self.insert_command(&format!("{} = or {} {}, {}", next, llvm_type, current, mask));
+                }
+                else
+                {
+                    let inverted = self.get_next_temp();
+                    self.insert_command(&format!("{} = xor {} {}, -1", inverted, llvm_type, mask));
+                    self.insert_command(&format!("{} = and {} {}, {}", next, llvm_type, current, inverted));
+                }
+
+                self.insert_command(&format!("store volatile {} {}, {}, align {}", llvm_type, next, ptr, bytes_size_of(&datatype)));
+            }
+        }
+    }
+
+    /// Add an intrinsic operation on an integer, which maps onto the matching LLVM intrinsic (a rotate is a funnel
+    /// shift of the integer with itself)
+    pub fn add_intrinsic(&mut self, inst: &Instruction)
+    {
+        let datatype = get_value_type(&inst.arguments[0]).unwrap();
+        let llvm_type = self.llvm_type(&datatype);
+
+        let value = self.render_value(&inst.arguments[1], true);
+
+        // The bytes of a single byte are already in order
+        if inst.opcode == OpCode::Bswap && bits_size_of(&datatype) == 8
+        {
+            self.add_move(&inst.arguments[0], value);
+            return;
+        }
+
+        let (name, arguments) = match inst.opcode
+        {
+            OpCode::Rotl | OpCode::Rotr =>
+            {
+                let amount = self.render_value(&inst.arguments[2], true);
+                (if inst.opcode == OpCode::Rotl {"fshl"} else {"fshr"}, format!("{0}, {0}, {1}", value, amount))
+            },
+            OpCode::Popcnt => ("ctpop", value),
+            // Counting the zeros of zero gives the width of the integer, rather than an undefined result
+            OpCode::Clz => ("ctlz", format!("{}, i1 false", value)),
+            OpCode::Ctz => ("cttz", format!("{}, i1 false", value)),
+            _ => ("bswap", value)
+        };
+
+        let parameters = match inst.opcode
+        {
+            OpCode::Rotl | OpCode::Rotr => format!("{0}, {0}, {0}", llvm_type),
+            OpCode::Clz | OpCode::Ctz => format!("{}, i1", llvm_type),
+            _ => llvm_type.clone()
+        };
+
+        self.declare(format!("declare {0} @llvm.{1}.{0}({2})", llvm_type, name, parameters));
+
+        let result = self.get_next_temp();
+        self.insert_command(&format!("{} = call {} @llvm.{}.{}({})", result, llvm_type, name, llvm_type, arguments));
+
+        self.add_move(&inst.arguments[0], format!("{} {}", llvm_type, result));
+    }
+
+    /// Add a copy or fill of a number of bytes in memory, through the memcpy and memset intrinsics
+    pub fn add_memory_operation(&mut self, inst: &Instruction)
+    {
+        let dest = self.render_byte_pointer(&inst.arguments[0]);
+        let count = self.render_value(&inst.arguments[2], true);
+
+        if inst.opcode == OpCode::Memcpy
+        {
+            let src = self.render_byte_pointer(&inst.arguments[1]);
+
+            self.declare(String::from("declare void @llvm.memcpy.p0i8.p0i8.i16(i8*, i8*, i16, i1)"));
+            self.insert_command(&format!("call void @llvm.memcpy.p0i8.p0i8.i16(i8* {}, i8* {}, {}, i1 false)", dest, src, count));
+        }
+        else
+        {
+            let value = self.render_value(&inst.arguments[1], true);
+
+            self.declare(String::from("declare void @llvm.memset.p0i8.i16(i8*, i8, i16, i1)"));
+            self.insert_command(&format!("call void @llvm.memset.p0i8.i16(i8* {}, {}, {}, i1 false)", dest, value, count));
+        }
+    }
+
+    /// Render a pointer as a pointer to bytes, as taken by the memory intrinsics
+    pub fn render_byte_pointer(&mut self, val: &Value) -> String
+    {
+        let ptr = self.render_value(val, true);
+        let temp = self.get_next_temp();
+
+        self.insert_command(&format!("{} = bitcast {} to i8*", temp, ptr));
+
+        temp
+    }
+
+    /// Branch to a call of the panic hook if a check has failed, reporting the location of the check (the hook is not
+    /// expected to return)
+    pub fn add_panic_branch(&mut self, failed: &str, location: &FileLocation)
+    {
+        let label_failed = format!("check{}", self.next_check);
+        let label_passed = format!("check{}.ok", self.next_check);
+        self.next_check += 1;
+
+        self.insert_command(&format!("br i1 {}, label %{}, label %{}", failed, label_failed, label_passed));
+
+        let file_index = self.file_names.iter().position(|name| name == location.name()).unwrap();
+        let file_length = self.file_names[file_index].len() + 1;
+
+        self.declare(format!("declare void @{}(i8*, i16, i16)", CHECKED_PANIC_HOOK));
+
+        self.result += &format!("\n  {}:\n", label_failed);
+        self.insert_command(&format!("call void @{}(i8* getelementptr inbounds ([{1} x i8], [{1} x i8]* @.file{2}, i64 0, i64 0), i16 {3}, i16 {4})",
+                            CHECKED_PANIC_HOOK, file_length, file_index, location.row, location.col));
+        self.insert_command("unreachable");
+
+        self.result += &format!("\n  {}:\n", label_passed);
+    }
+
+    /// Convert a type to the llvm representation for the current target
+    pub fn llvm_type(&self, datatype: &DataType) -> String
+    {
+        convert_to_llvm_in(datatype, self.program_space)
+    }
+
     /// Insert a new command
     pub fn insert_command(&mut self, cmd: &str)
     {
@@ -103,7 +469,7 @@ impl FunctionGenerationContext
         self.insert_command(
             &format!("{} = alloca {}, align {}", 
                             ptr, 
-                            convert_to_llvm(&dt), 
+                            self.llvm_type(&dt), 
                             bytes_size_of(&dt)));
     }
 
@@ -120,7 +486,7 @@ impl FunctionGenerationContext
 
         if include_type
         {
-            format!("{} {}",convert_to_llvm(&pdt), ptr)
+            format!("{} {}",self.llvm_type(&pdt), ptr)
         }
         else
         {
@@ -144,8 +510,8 @@ impl FunctionGenerationContext
 
         self.insert_command(&format!("{} = load {}, {} {}, align {}", 
                                         reg, 
-                                        convert_to_llvm(&dt),
-                                        convert_to_llvm(&pdt),
+                                        self.llvm_type(&dt),
+                                        self.llvm_type(&pdt),
                                         ptr,
                                         bytes_size_of(&var.datatype)));
 
@@ -153,11 +519,11 @@ impl FunctionGenerationContext
         {
             if !(dt.raw_type == NonPtrType::Void && dt.num_ptr == 0)
             {
-                format!("{} {}", convert_to_llvm(&dt), reg)
+                format!("{} {}", self.llvm_type(&dt), reg)
             }
             else
             {
-                format!("{}", convert_to_llvm(&dt))
+                format!("{}", self.llvm_type(&dt))
             }
         }
         else
@@ -184,7 +550,7 @@ impl FunctionGenerationContext
                     {
                         if include_type
                         {
-                            format!("{} {}", convert_to_llvm(&literal.datatype), literal.value)
+                            format!("{} {}", self.llvm_type(&literal.datatype), literal.value)
                         }
                         else
                         {
@@ -195,11 +561,11 @@ impl FunctionGenerationContext
                     {
                         if include_type
                         {
-                            format!("{0} inttoptr (i64 {1} to {0})", convert_to_llvm(&literal.datatype), literal.value)
+                            format!("{0} inttoptr (i64 {1} to {0})", self.llvm_type(&literal.datatype), literal.value)
                         }
                         else
                         {
-                            format!("inttoptr (i64 {1} to {0})", convert_to_llvm(&literal.datatype), literal.value)
+                            format!("inttoptr (i64 {1} to {0})", self.llvm_type(&literal.datatype), literal.value)
                         }
                     }
                 }
@@ -207,7 +573,7 @@ impl FunctionGenerationContext
                 {
                     if include_type
                     {
-                        format!("{}", convert_to_llvm(&literal.datatype))
+                        format!("{}", self.llvm_type(&literal.datatype))
                     }
                     else
                     {
@@ -286,7 +652,7 @@ impl FunctionGenerationContext
         self.result = String::new();
 
         // Function return type and name
-        self.result += &format!("define {} @{}", convert_to_llvm(&func.return_type), func.name);
+        self.result += &format!("define {} @{}", self.llvm_type(&func.return_type), func.name);
 
         // Arguments
         self.result += "(";
@@ -295,7 +661,7 @@ impl FunctionGenerationContext
 
         for (i, (name, datatype)) in func.arguments.iter().enumerate()
         {
-            let s = format!("{} %{}", convert_to_llvm(datatype), name);
+            let s = format!("{} %{}", self.llvm_type(datatype), name);
             self.result += &s;
 
             if i < func.arguments.len() - 1
@@ -306,7 +672,15 @@ impl FunctionGenerationContext
             argument_names.push((name.clone(), s));
         }
 
-        self.result += ")\n";
+        self.result += ")";
+
+        // Functions which never return let calls to them end a block
+        if func.is_noreturn
+        {
+            self.result += " noreturn";
+        }
+
+        self.result += "\n";
 
         // Body
 
@@ -363,6 +737,32 @@ impl FunctionGenerationContext
                         let val = self.render_value(&inst.arguments[1], true);
                         self.add_move(&inst.arguments[0], val);
                     },
+                    // Checked Arithmetic
+                    OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div | OpCode::Mod | OpCode::Shl | OpCode::Shr if inst.checked.is_some() =>
+                    {
+                        self.add_checked_arithmetic(inst);
+                    },
+                    // Cast into a reference (sets the address it refers to)
+                    OpCode::Cast if get_value_type(&inst.arguments[0]).unwrap().is_ref =>
+                    {
+                        let dest_type = get_value_type(&inst.arguments[0]).unwrap();
+                        let src_type = get_value_type(&inst.arguments[1]).unwrap();
+
+                        let mut current = self.render_value(&inst.arguments[1], false);
+                        let current_type = self.llvm_type(&src_type);
+                        let next_type = self.llvm_type(&dest_type);
+
+                        if current_type != next_type
+                        {
+                            let next = self.get_next_temp();
+                            self.insert_command(&format!("{} = {} {} {} to {}", next, if src_type.num_ptr == 0 {"inttoptr"} else {"bitcast"},
+                                                current_type, current, next_type));
+                            current = next;
+                        }
+
+                        let ptr = self.render_pointer(&inst.arguments[0]);
+                        self.insert_command(&format!("store {} {}, {}", next_type, current, ptr));
+                    },
                     // Cast
                     OpCode::Cast =>
                     {
@@ -371,14 +771,14 @@ impl FunctionGenerationContext
                         let src_type = get_value_type(&inst.arguments[1]).unwrap();
 
                         // Get the sizes of the types
-                        let dest_size = bytes_size_of(&dest_type);
-                        let src_size = bytes_size_of(&src_type);
+                        let dest_size = bits_size_of(&dest_type);
+                        let src_size = bits_size_of(&src_type);
 
                         let mut current = self.render_value(&inst.arguments[1], false);
 
-                        let mut current_type = convert_to_llvm(&src_type);
+                        let mut current_type = self.llvm_type(&src_type);
 
-                        if src_type.num_ptr > 0
+                        if src_type.num_ptr > 0 && self.llvm_type(&dest_type) != current_type
                         {
                             let next = self.get_next_temp();
                             self.insert_command(&format!("{} = ptrtoint {} {} to i64", next, current_type, current));
@@ -386,25 +786,25 @@ impl FunctionGenerationContext
                             current_type = String::from("i64");
                         }
 
-                        if convert_to_llvm(&dest_type) != convert_to_llvm(&src_type)
+                        if self.llvm_type(&dest_type) != self.llvm_type(&src_type)
                         {
                             // If the destination is smaller, truncation is necessary
-                            if dest_size < src_size && current_type != if dest_type.num_ptr == 0 {convert_to_llvm(&dest_type)} else {String::from("i64")}
+                            if dest_size < src_size && current_type != if dest_type.num_ptr == 0 {self.llvm_type(&dest_type)} else {String::from("i64")}
                             {
                                 let next = self.get_next_temp();
-                                let next_type = if dest_type.num_ptr == 0 {convert_to_llvm(&dest_type)} else {String::from("i64")};
+                                let next_type = if dest_type.num_ptr == 0 {self.llvm_type(&dest_type)} else {String::from("i64")};
                                 self.insert_command(&format!("{} = trunc {} {} to {}", next, current_type, current, next_type));
                                 
                                 current = next;
                                 current_type = next_type;
                             }
                             // If the destination is larger, extension is necessary
-                            else if dest_size > src_size && current_type != if dest_type.num_ptr == 0 {convert_to_llvm(&dest_type)} else {String::from("i64")}
+                            else if dest_size > src_size && current_type != if dest_type.num_ptr == 0 {self.llvm_type(&dest_type)} else {String::from("i64")}
                             {
                                 let next = self.get_next_temp();
-                                let next_type = if dest_type.num_ptr == 0 {convert_to_llvm(&dest_type)} else {String::from("i64")};
+                                let next_type = if dest_type.num_ptr == 0 {self.llvm_type(&dest_type)} else {String::from("i64")};
                                 self.insert_command(&format!("{} = {} {} {} to {}", 
-                                    next, if dest_type.is_signed() {"sext"} else {"zext"},
+                                    next, if src_type.is_signed() {"sext"} else {"zext"},
                                     current_type, current, next_type));
 
                                 current = next;
@@ -417,23 +817,36 @@ impl FunctionGenerationContext
                                 if src_type.num_ptr == 0
                                 {
                                     let next = self.get_next_temp();
-                                    self.insert_command(&format!("{} = inttoptr {} {} to {}", next, current_type, current, convert_to_llvm(&dest_type)));
+                                    self.insert_command(&format!("{} = inttoptr {} {} to {}", next, current_type, current, self.llvm_type(&dest_type)));
                                     current = next;
-                                    current_type = convert_to_llvm(&dest_type);
+                                    current_type = self.llvm_type(&dest_type);
                                 }
                                 // The source is a pointer
                                 else
                                 {
                                     let next = self.get_next_temp();
-                                    self.insert_command(&format!("{} = bitcast {} {} to {}", next, current_type, current, convert_to_llvm(&dest_type)));
+                                    self.insert_command(&format!("{} = bitcast {} {} to {}", next, current_type, current, self.llvm_type(&dest_type)));
                                     current = next;
-                                    current_type = convert_to_llvm(&dest_type);
+                                    current_type = self.llvm_type(&dest_type);
                                 }
                             }
                         }
 
                         self.add_move(&inst.arguments[0], format!("{} {}", current_type, current));
                     },
+                    // Sign and Zero Extension
+                    OpCode::Sext | OpCode::Zext =>
+                    {
+                        let dest_type = correct_type_references(get_value_type(&inst.arguments[0]).unwrap());
+
+                        let val = self.render_value(&inst.arguments[1], true);
+                        let next = self.get_next_temp();
+
+                        self.insert_command(&format!("{} = {} {} to {}", next, if inst.opcode == OpCode::Sext {"sext"} else {"zext"},
+                                            val, self.llvm_type(&dest_type)));
+
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&dest_type), next));
+                    },
                     // Dereference Command
                     OpCode::Deref =>
                     {
@@ -446,11 +859,11 @@ impl FunctionGenerationContext
 
                             self.insert_command(&format!("{} = load {}, {}, align {}", 
                                             reg, 
-                                            convert_to_llvm(&dt),
+                                            self.llvm_type(&dt),
                                             val,
                                             bytes_size_of(&var.datatype)));
 
-                            self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&dt), reg));
+                            self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&dt), reg));
                         };
                     },
                     // Dereference Command
@@ -463,7 +876,7 @@ impl FunctionGenerationContext
                                 let ptr_dt = self.values.get(&var0.title).unwrap().get_pointer_datatype();
                                 let ptr = self.values.get(&var1.title).unwrap().ptr.clone();
 
-                                self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&ptr_dt), ptr));
+                                self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&ptr_dt), ptr));
                             }
                         };
                     },
@@ -489,8 +902,17 @@ impl FunctionGenerationContext
                         );
 
                         self.add_compare(command, temp.clone(), &inst.arguments[1], &inst.arguments[2]);
-                        self.insert_command(&format!("{} = zext i1 {} to {}", &temp2, &temp, convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap())));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp2));
+
+                        // A checked comparison traps if it does not hold
+                        if let Some(location) = &inst.checked
+                        {
+                            let failed = self.get_next_temp();
+                            self.insert_command(&format!("{} = xor i1 {}, true", failed, temp));
+                            self.add_panic_branch(&failed, location);
+                        }
+
+                        self.insert_command(&format!("{} = zext i1 {} to {}", &temp2, &temp, self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap())));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp2));
                     },
                     // Branch Commands
                     OpCode::Bne | OpCode::Beq | OpCode::Bge | OpCode::Bgt | OpCode::Ble | OpCode::Blt =>
@@ -518,6 +940,11 @@ impl FunctionGenerationContext
                         self.add_compare(command, temp.clone(), &inst.arguments[0], &inst.arguments[1]);
                         self.insert_command(&format!("br i1 {}, {}, {}", &temp, label_true, label_false));
                     },
+                    // Pointer Arithmetic
+                    OpCode::Add | OpCode::Sub if get_value_type(&inst.arguments[1]).unwrap().num_ptr > 0 =>
+                    {
+                        self.add_pointer_arithmetic(inst);
+                    },
                     // Add Command
                     OpCode::Add =>
                     {
@@ -527,7 +954,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = add {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Sub Command
                     OpCode::Sub =>
@@ -538,7 +965,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = sub {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Mul Command
                     OpCode::Mul =>
@@ -549,7 +976,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = mul {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Div Command
                     OpCode::Div =>
@@ -559,8 +986,8 @@ impl FunctionGenerationContext
                         let val0 = self.render_value(&inst.arguments[1], true);
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
-                        self.insert_command(&format!("{} = div {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.insert_command(&format!("{} = {} {}, {}", temp, if get_value_type(&inst.arguments[1]).unwrap().is_signed() {"sdiv"} else {"udiv"}, val0, val1));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // And Command
                     OpCode::And =>
@@ -571,7 +998,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = and {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Or Command
                     OpCode::Or =>
@@ -582,7 +1009,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = or {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Xor Command
                     OpCode::Xor =>
@@ -593,7 +1020,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = xor {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Mod Command
                     OpCode::Mod =>
@@ -604,7 +1031,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = {} {}, {}", temp, if get_value_type(&inst.arguments[1]).unwrap().is_signed() {"srem"} else {"urem"}, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Shl Command
                     OpCode::Shl =>
@@ -615,7 +1042,7 @@ impl FunctionGenerationContext
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
                         self.insert_command(&format!("{} = shl {}, {}", temp, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Shr Command
                     OpCode::Shr =>
@@ -625,8 +1052,8 @@ impl FunctionGenerationContext
                         let val0 = self.render_value(&inst.arguments[1], true);
                         let val1 =  self.render_value(&inst.arguments[2], false);
 
-                        self.insert_command(&format!("{} = {} {}, {}", temp, if get_value_type(&inst.arguments[1]).unwrap().is_signed() {"lshr"} else {"ashr"}, val0, val1));
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.insert_command(&format!("{} = {} {}, {}", temp, if get_value_type(&inst.arguments[1]).unwrap().is_signed() {"ashr"} else {"lshr"}, val0, val1));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp));
                     },
                     // Array Command
                     OpCode::Array =>
@@ -635,17 +1062,42 @@ impl FunctionGenerationContext
                         let temp2 = self.get_next_temp();
 
                         let val0 = self.render_value(&inst.arguments[1], true);
-                        let val1 =  self.render_value(&inst.arguments[2], true);
+                        let val1 = self.render_offset(&inst.arguments[2]);
 
-                        let val_type = convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap());
-                        let ptr_type = convert_to_llvm(&get_value_type(&inst.arguments[1]).unwrap());
+                        let val_type = self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap());
+                        let ptr_type = self.llvm_type(&get_value_type(&inst.arguments[1]).unwrap());
 
-                        self.insert_command(&format!("{} = getelementptr {}, {}, {}", temp, val_type, val0, val1));
+                        // Indexes are extended according to their own sign, rather than always being sign extended
+                        self.insert_command(&format!("{} = getelementptr {}, {}, i64 {}", temp, val_type, val0, val1));
 
                         self.insert_command(&format!("{} = load {}, {} {}, align {}", temp2, val_type, ptr_type, temp,
                                             bytes_size_of(&get_value_type(&inst.arguments[0]).unwrap())));
 
-                        self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp2));
+                        self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&get_value_type(&inst.arguments[0]).unwrap()), temp2));
+                    },
+                    // Global Address Command
+                    OpCode::Global =>
+                    {
+                        if let Value::Label(name) = &inst.arguments[1]
+                        {
+                            let global = self.func.globals.values().find(|global| global.name == *name).unwrap().clone();
+                            let address_type = self.llvm_type(&global.address_type());
+
+                            // Arrays are referred to by a pointer to their first element
+                            let address = if let Some(length) = global.length
+                            {
+                                let array_type = format!("[{} x {}]", length, self.llvm_type(&global.datatype));
+                                let space = if self.program_space && global.is_progmem {" addrspace(1)"} else {""};
+
+                                format!("getelementptr inbounds ({0}, {0}{1}* @{2}, i64 0, i64 0)", array_type, space, name)
+                            }
+                            else
+                            {
+                                format!("@{}", name)
+                            };
+
+                            self.add_move(&inst.arguments[0], format!("{} {}", address_type, address));
+                        }
                     },
                     // Push Command
                     OpCode::Push =>
@@ -665,16 +1117,63 @@ impl FunctionGenerationContext
                                 self.current_arguments.pop();
                             }
 
-                            self.insert_command(&format!("{} = call {} @{}({})",
-                                                    temp, 
-                                                    convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()),
-                                                    func_label,
-                                                    self.current_arguments));
+                            let datatype = get_value_type(&inst.arguments[0]).unwrap();
+
+                            // Calls to void functions give no value
+                            if datatype.raw_type == NonPtrType::Void && datatype.num_ptr == 0
+                            {
+                                self.insert_command(&format!("call void @{}({})", func_label, self.current_arguments));
+                            }
+                            else
+                            {
+                                self.insert_command(&format!("{} = call {} @{}({})",
+                                                        temp, 
+                                                        self.llvm_type(&datatype),
+                                                        func_label,
+                                                        self.current_arguments));
+
+                                self.add_move(&inst.arguments[0], format!("{} {}", self.llvm_type(&datatype), temp));
+                            }
 
                             self.current_arguments = String::new();
+                        }
+                    },
+                    // Bit Operations
+                    OpCode::BitSet | OpCode::BitClr | OpCode::BitTst =>
+                    {
+                        self.add_bit_operation(inst);
+                    },
+                    // Intrinsics
+                    OpCode::Rotl | OpCode::Rotr | OpCode::Popcnt | OpCode::Clz | OpCode::Ctz | OpCode::Bswap =>
+                    {
+                        self.add_intrinsic(inst);
+                    },
+                    OpCode::Memcpy | OpCode::Memset =>
+                    {
+                        self.add_memory_operation(inst);
+                    },
+                    // Assumptions
+                    OpCode::Unreachable =>
+                    {
+                        self.insert_command("unreachable");
+                    },
+                    OpCode::Assume =>
+                    {
+                        let condition = self.render_value(&inst.arguments[0], true);
+                        let is_true = self.get_next_temp();
 
-                            self.add_move(&inst.arguments[0], format!("{} {}", convert_to_llvm(&get_value_type(&inst.arguments[0]).unwrap()), temp));
+                        self.declare(String::from("declare void @llvm.assume(i1)"));
+
+                        if get_value_type(&inst.arguments[0]).unwrap().num_ptr > 0
+                        {
+                            self.insert_command(&format!("{} = icmp ne {}, null", is_true, condition));
+                        }
+                        else
+                        {
+                            self.insert_command(&format!("{} = icmp ne {}, 0", is_true, condition));
                         }
+
+                        self.insert_command(&format!("call void @llvm.assume(i1 {})", is_true));
                     },
                     // Unconditional Jump
                     OpCode::Jmp =>
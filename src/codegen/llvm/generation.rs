@@ -1,26 +1,54 @@
 use crate::cli::Error;
-use crate::irgen::{Function};
+use crate::irgen::{Function, Global};
 
-use super::FunctionGenerationContext;
+use super::{FunctionGenerationContext, convert_to_llvm, bytes_size_of, llvm_string_constant};
+use super::super::{CHECKED_PANIC_HOOK, checked_file_names};
 
 /// Wrapper for the LLVM IR Code Generator
 #[derive(Debug, Clone)]
 pub struct LLVMGenerator
 {
-    functions: Vec<Function>
+    functions: Vec<Function>,
+    globals: Vec<Global>
 }
 
 impl LLVMGenerator
 {
-    /// Generate a new AvrAsmGenerator from a vector of IR functions
-    pub fn new(functions: Vec<Function>) -> Self
+    /// Generate a new AvrAsmGenerator from a vector of IR functions and globals
+    pub fn new(functions: Vec<Function>, globals: Vec<Global>) -> Self
     {
         Self
         {
-            functions
+            functions,
+            globals
         }
     }
 
+    /// Render a global (globals in program memory are placed in address space 1 if requested)
+    fn render_global(global: &Global, program_space: bool) -> String
+    {
+        let element_type = convert_to_llvm(&global.datatype);
+
+        let (datatype, value) = match global.length
+        {
+            Some(length) =>
+            {
+                let values: Vec<String> = (0..length).map(|i| format!("{} {}", element_type, global.value_at(i))).collect();
+
+                (format!("[{} x {}]", length, element_type), format!("[{}]", values.join(", ")))
+            },
+            None => (element_type.clone(), format!("{}", global.value_at(0)))
+        };
+
+        format!("@{} = {}{} {} {}, align {}\n",
+            global.name,
+            if program_space && global.is_progmem {"addrspace(1) "} else {""},
+            if global.is_progmem {"constant"} else {"global"},
+            datatype,
+            value,
+            bytes_size_of(&global.datatype))
+    }
+
     /// Render each function in turn
     pub fn render(self, target: Option<&str>, datalayout: Option<&str>) -> Result<String, Error>
     {
@@ -36,10 +64,53 @@ impl LLVMGenerator
             result += &format!("target triple = \"{}\"\n", target_str);
         }
 
+        // AVR keeps program memory in a separate address space
+        let program_space = match target
+        {
+            Some(target_str) => target_str.starts_with("avr"),
+            None => false
+        };
+
+        for global in &self.globals
+        {
+            result += &LLVMGenerator::render_global(global, program_space);
+        }
+
+        // File names reported by checked operations
+        let file_names = checked_file_names(&self.functions);
+
+        for (i, name) in file_names.iter().enumerate()
+        {
+            let (length, data) = llvm_string_constant(name);
+            result += &format!("@.file{} = private unnamed_addr constant [{} x i8] {}, align 1\n", i, length, data);
+        }
+
+        // The panic hook may be defined by the program itself
+        let defines_hook = self.functions.iter().any(|function| function.name == CHECKED_PANIC_HOOK);
+
+        let mut declarations: Vec<String> = vec![];
+
         for function in self.functions
         {
-            let mut context = FunctionGenerationContext::new(function);
+            let mut context = FunctionGenerationContext::new(function, program_space, file_names.clone());
             result += &format!("{}", context.render_function()?);
+
+            for declaration in context.declarations
+            {
+                if !declarations.contains(&declaration)
+                {
+                    declarations.push(declaration);
+                }
+            }
+        }
+
+        // Declare anything used but not defined
+        for declaration in declarations
+        {
+            if !(defines_hook && declaration.contains(&format!("@{}(", CHECKED_PANIC_HOOK)))
+            {
+                result += &format!("{}\n", declaration);
+            }
         }
 
         Ok(result)
@@ -3,7 +3,24 @@ use crate::irgen::{DataType, NonPtrType};
 /// Convert a type to a string in the format llvm uses (no u32 or u64, just i32, i64 etc.)
 pub fn convert_to_llvm(datatype: &DataType) -> String
 {
-    format!("{}{}", datatype, if datatype.is_ref {"*"} else {""}).replace("u", "i").replace("&", "")
+    convert_to_llvm_in(datatype, false)
+}
+
+/// Convert a type to a string in the format llvm uses, placing pointers into program memory in
+/// address space 1 if the target has a separate program address space
+pub fn convert_to_llvm_in(datatype: &DataType, program_space: bool) -> String
+{
+    let raw = format!("{}", DataType::new(datatype.raw_type, 0, false)).replace("u", "i");
+    let ptrs = "*".repeat(datatype.num_ptr + if datatype.is_ref {1} else {0});
+
+    if program_space && datatype.is_progmem && !ptrs.is_empty()
+    {
+        format!("{} addrspace(1){}", raw, ptrs)
+    }
+    else
+    {
+        format!("{}{}", raw, ptrs)
+    }
 }
 
 /// Gets the number of bytes in a type
@@ -17,13 +34,44 @@ pub fn bytes_size_of(datatype: &DataType) -> usize
     {
         match datatype.raw_type
         {
-            NonPtrType::I8 | NonPtrType::U8 => 1,
-            NonPtrType::I16 | NonPtrType::U16 => 2,
-            NonPtrType::I32 | NonPtrType::U32 => 4,
-            NonPtrType::I64 | NonPtrType::U64 => 8,
             NonPtrType::Void => 0,
             NonPtrType::Bool => 1,
             NonPtrType::Unknown => {panic!()}
+            // Integers take up a power of two number of bytes, so an i24 takes up 4 bytes
+            _ => bits_size_of(datatype).div_ceil(8).next_power_of_two()
         }
     }
-}
\ No newline at end of file
+}
+
+/// Gets the number of bits in a type, integers may have a width which is not a whole number of bytes
+pub fn bits_size_of(datatype: &DataType) -> usize
+{
+    match DataType::new(datatype.raw_type, 0, false).bit_width()
+    {
+        Some(bits) if datatype.num_ptr == 0 => bits,
+        _ => bytes_size_of(datatype) * 8
+    }
+}
+
+/// Render a string as a null terminated llvm character array, returning the length and the constant
+pub fn llvm_string_constant(data: &str) -> (usize, String)
+{
+    let mut result = String::from("c\"");
+
+    for byte in data.bytes()
+    {
+        // Quotes, backslashes and non printable characters must be escaped
+        if byte == b'"' || byte == b'\\' || !(0x20..0x7F).contains(&byte)
+        {
+            result += &format!("\\{:02X}", byte);
+        }
+        else
+        {
+            result.push(byte as char);
+        }
+    }
+
+    result += "\\00\"";
+
+    (data.len() + 1, result)
+}
@@ -3,7 +3,32 @@ mod llvm;
 
 use crate::cli::{Error, Options};
 
-use crate::irgen::Function;
+use crate::irgen::{Function, Global};
+
+/// Function called when a checked operation fails, as `void arith_panic(u8* file, u16 line, u16 column)`
+pub static CHECKED_PANIC_HOOK: &str = "arith_panic";
+
+/// Get the names of all files reported by checked operations (in order of first use)
+pub fn checked_file_names(functions: &[Function]) -> Vec<String>
+{
+    let mut result: Vec<String> = vec![];
+
+    for func in functions
+    {
+        for i in 0..func.instructions.len()
+        {
+            if let Some(location) = func.instructions.get(&i).and_then(|inst| inst.checked.clone())
+            {
+                if !result.iter().any(|name| name == location.name())
+                {
+                    result.push(String::from(location.name()));
+                }
+            }
+        }
+    }
+
+    result
+}
 
 /// Code Generation Mode
 /// What language the output will be in
@@ -29,6 +54,17 @@ impl CodegenMode
             _ => CodegenMode::Unknown
         }
     }
+
+    /// Get the width of a pointer in the output, in bits (the difference between two pointers is a signed integer
+    /// of the same width)
+    pub fn pointer_bits(&self) -> usize
+    {
+        match self
+        {
+            CodegenMode::AvrAssembly => 16,
+            _ => 64
+        }
+    }
 }
 
 /// Wrapper which compiles the individually translated functions
@@ -37,18 +73,20 @@ pub struct CodeGenerator
 {
     mode: CodegenMode,
     functions: Vec<Function>,
+    globals: Vec<Global>,
     options: Options
 }
 
 impl CodeGenerator
 {
     /// Generate a new CodeGenerator object
-    pub fn new(mode: CodegenMode, functions: Vec<Function>, options: Options) -> Self
+    pub fn new(mode: CodegenMode, functions: Vec<Function>, globals: Vec<Global>, options: Options) -> Self
     {
         Self
         {
             mode,
             functions,
+            globals,
             options
         }
     }
@@ -63,6 +101,17 @@ impl CodeGenerator
             CodegenMode::Unknown => {return Err(Error::fatal_error("Unknown Codegen Mode"));},
             CodegenMode::IntermediateRepresentation =>
             {
+                // Render each global
+                for global in &self.globals
+                {
+                    result += &format!("{}\n", global);
+                }
+
+                if !self.globals.is_empty()
+                {
+                    result += "\n";
+                }
+
                 // Render each function of intermediate representation
                 for func in &self.functions
                 {
@@ -72,7 +121,7 @@ impl CodeGenerator
             CodegenMode::AvrAssembly =>
             {
                 // Invoke the renderer for the AvrAsm code generator
-                result = format!("{}", avrasm::AvrAsmGenerator::new(self.functions.clone()).render()?)
+                result = format!("{}", avrasm::AvrAsmGenerator::new(self.functions.clone(), self.globals.clone()).render()?)
             },
             CodegenMode::LLVM =>
             {
@@ -97,7 +146,7 @@ impl CodeGenerator
                 };
 
                 // Invoke the renderer for the LLVM code generaor
-                result = format!("{}", llvm::LLVMGenerator::new(self.functions.clone()).render(target, layout)?)
+                result = format!("{}", llvm::LLVMGenerator::new(self.functions.clone(), self.globals.clone()).render(target, layout)?)
             }
         }
 
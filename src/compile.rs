@@ -1,36 +1,37 @@
 use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 use super::io::InputFile;
-use super::cli::{Error, ErrorRecorder, Options};
-use super::tokenizer::tokenize;
+use super::cli::{Error, ErrorCode, ErrorRecorder, Lint, Options};
+use super::module::{Module, load_modules};
+use super::preprocess::Preprocessor;
 use super::irgen;
-use super::codegen::{CodeGenerator, CodegenMode};
+use super::codegen::{CodeGenerator, CodegenMode, CHECKED_PANIC_HOOK};
 
-use super::parser::{ParseTreeNode, display_parse_tree};
+use super::parser::{Library, Item, FunctionDecl, display_syntax_tree};
+use super::tokenizer::Span;
 
-/// Compile the given input file
-pub fn compile(input: InputFile, options: &Options) -> Result<(), Error>
-{
-    let mut recorder: ErrorRecorder = ErrorRecorder::new();
-    let data = input.data;
-    let filename = input.filename;
-
-    // Tokenization
-    let tokens = tokenize(data, filename);
-
-    // Parsing
-    let node = recorder.wrap_return(super::parser::parse(tokens))?;
+/// Maximum number of instantiations of generic functions, which stops a generic function which instantiates itself
+/// with ever larger types
+static MAX_INSTANTIATIONS: usize = 4096;
 
-    if node.is_none()
-    {
-        Err(Error::fatal_error("No Parse Tree Returned"))?
-    }
+/// Compile the given input files, along with any modules they import, into a single output, reporting any errors
+/// compilation can carry on past (and warnings) to the recorder
+pub fn compile(inputs: Vec<InputFile>, options: &Options, recorder: &mut ErrorRecorder) -> Result<(), Error>
+{
+    // Preprocessing, Tokenization and Parsing
+    let preprocessor = Preprocessor::from_options(options)?;
+    let modules = load_modules(inputs, &preprocessor, recorder)?;
 
     if options.has_long_flag("tree") || options.has_short_flag("T")
     {
-        println!("Parse Tree:");
+        for module in &modules
+        {
+            println!("Parse Tree ({}):", module.filename);
 
-        display_parse_tree(node.clone().unwrap(), String::new(), false);
+            display_syntax_tree(&Library{items: module.items.clone()});
+        }
     }
 
     // Determine Optimization Level
@@ -48,36 +49,178 @@ pub fn compile(input: InputFile, options: &Options) -> Result<(), Error>
         }
     }
 
-    // Convert parse tree to IR
+    // Globals are collected first so they can be used from any function
+    let mut globals = vec![];
+    let mut module_globals = vec![];
+    let mut module_functions = vec![];
+    let mut module_generics = vec![];
+
+    // Linked names of items along with the names they were defined with, to point out both definitions of an item
+    // defined twice
+    let mut linked_globals = HashMap::new();
+    let mut linked_functions = HashMap::new();
+
+    // Calls are typed by the signature of the function they call, by its linked name
+    let mut signatures = HashMap::new();
+
+    for (index, module) in modules.iter().enumerate()
+    {
+        // Shared with the functions evaluating initializers, so the globals before each one aren't copied for it
+        let mut global_table = Rc::new(HashMap::new());
+        let mut function_table = HashMap::new();
+        let mut generic_table = HashMap::new();
+
+        for item in &module.items
+        {
+            match item
+            {
+                Item::Global(declaration) =>
+                {
+                    let mut global = irgen::Global::from_declaration(declaration, &global_table)?;
+                    let name = global.name.clone();
+
+                    global.name = module.linked_name(&name);
+
+                    if let Some(previous) = linked_globals.insert(global.name.clone(), item.name_span())
+                    {
+                        Err(definition_error(ErrorCode::E0008, format!("Global '{}' defined more than once", name), item.name_span(), previous))?
+                    }
+
+                    Rc::make_mut(&mut global_table).insert(name, global.clone());
+                    globals.push(global);
+                },
+                Item::Function(function) =>
+                {
+                    let name = function.name.name.clone();
+
+                    if irgen::Builtin::from_name(&name).is_some()
+                    {
+                        Err(definition_error(ErrorCode::E0009, format!("Function '{}' has the same name as a builtin", name), item.name_span(), None))?
+                    }
+
+                    if let Some(previous) = linked_functions.insert(module.linked_name(&name), item.name_span())
+                    {
+                        Err(definition_error(ErrorCode::E0008, format!("Function '{}' defined more than once", name), item.name_span(), previous))?
+                    }
+
+                    // Generic functions are only compiled once they are used
+                    if function.is_generic()
+                    {
+                        generic_table.insert(name.clone(), irgen::GenericFunction::from_declaration(function, module.linked_name(&name), index));
+                    }
+                    else
+                    {
+                        signatures.insert(module.linked_name(&name), irgen::Signature::from_declaration(function)?);
+                        function_table.insert(name.clone(), module.linked_name(&name));
+                    }
+                },
+                // Methods are found by their type and name, and linked under a name qualified by their type
+                Item::Impl(block) =>
+                {
+                    for method in irgen::methods_from_impl(block)?
+                    {
+                        if let Some(previous) = linked_functions.insert(module.linked_name(&method.name), name_span(&method.node))
+                        {
+                            Err(definition_error(ErrorCode::E0008, format!("Method '{}' defined more than once", method.key), name_span(&method.node), previous))?
+                        }
+
+                        signatures.insert(module.linked_name(&method.name), irgen::Signature::from_declaration(&method.node)?);
+                        function_table.insert(method.key, module.linked_name(&method.name));
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        module_globals.push(Rc::unwrap_or_clone(global_table));
+        module_functions.push(function_table);
+        module_generics.push(generic_table);
+    }
+
+    let mut module_items = vec![];
+    let signatures = Rc::new(signatures);
+
+    for index in 0..modules.len()
+    {
+        let (global_table, function_table, generic_table) = visible_items(&modules, index, &module_globals, &module_functions, &module_generics)?;
+
+        module_items.push((Rc::new(global_table), Rc::new(function_table), Rc::new(generic_table), signatures.clone()));
+    }
+
+    // Convert the syntax tree to IR
     let mut functions = vec![];
+    let mut instantiations = vec![];
 
-    match node.unwrap()
+    for (index, module) in modules.iter().enumerate()
     {
-        ParseTreeNode::Library(children) =>
+        for item in &module.items
         {
-            for child in children
+            if let Item::Function(declaration) = item
             {
-                let mut function = irgen::Function::from_parse_tree_node(child)?;
+                if declaration.is_generic()
+                {
+                    continue;
+                }
 
-                function = irgen::correct_types(function);
+                let name = module.linked_name(&declaration.name.name);
 
-                function = irgen::optimize_function(function, optimization_level, !options.has_long_flag("nocomp"));
+                let mut function = lower_function(declaration, name, &module_items[index], options, optimization_level, recorder)?;
 
+                instantiations.append(&mut function.instantiations);
                 functions.push(function);
             }
-        },
-        _ => {}
+            else if let Item::Impl(block) = item
+            {
+                for method in irgen::methods_from_impl(block)?
+                {
+                    let mut function = lower_function(&method.node, module.linked_name(&method.name), &module_items[index], options, optimization_level, recorder)?;
+
+                    instantiations.append(&mut function.instantiations);
+                    functions.push(function);
+                }
+            }
+        }
     }
 
-    // Code Generation
-    let mut codegen_mode = CodegenMode::IntermediateRepresentation;
+    // Every instantiation of a generic function is compiled as a separate function (which may use further
+    // instantiations)
+    let mut instantiated = HashSet::new();
+    let mut next = 0;
+
+    while next < instantiations.len()
+    {
+        let instantiation = instantiations[next].clone();
+        next += 1;
+
+        if !instantiated.insert(instantiation.name())
+        {
+            continue;
+        }
+
+        if instantiated.len() > MAX_INSTANTIATIONS
+        {
+            irgen::compiler_error_loc(ErrorCode::E0037, format!("Too many instantiations of generic functions, '{}' may instantiate itself without end",
+                                              instantiation.generic.name), &instantiation.pos)?
+        }
 
-    if let Some(name) = options.map.get("-g")
+        let items = &module_items[instantiation.generic.module];
+
+        let mut function = lower_function(&instantiation.declaration(), instantiation.name(), items, options, optimization_level, recorder)?;
+
+        instantiations.append(&mut function.instantiations);
+        functions.push(function);
+    }
+
+    warn_unused_functions(&modules, &functions, &instantiations, recorder)?;
+
+    // Nothing is generated once an error has been reported, compilation is aborted once it returns
+    if recorder.error_count() > 0
     {
-        codegen_mode = CodegenMode::from_mode(&name[0]);
+        return Ok(());
     }
 
-    let output = CodeGenerator::new(codegen_mode, functions, options.clone()).render()?;
+    // Code Generation
+    let output = CodeGenerator::new(codegen_mode(options), functions, globals, options.clone()).render()?;
 
     // Display Output to stdout
     if options.has_long_flag("stdout")
@@ -111,4 +254,219 @@ pub fn compile(input: InputFile, options: &Options) -> Result<(), Error>
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Get the code generator selected with -g, IR is generated by default
+fn codegen_mode(options: &Options) -> CodegenMode
+{
+    match options.map.get("-g")
+    {
+        Some(name) => CodegenMode::from_mode(&name[0]),
+        None => CodegenMode::IntermediateRepresentation
+    }
+}
+
+/// Lower a function to IR under the given name, with access to the given globals, functions and generic functions
+fn lower_function(node: &FunctionDecl, name: String, items: &ModuleItems, options: &Options, optimization_level: usize, recorder: &mut ErrorRecorder) -> Result<irgen::Function, Error>
+{
+    let (global_table, function_table, generic_table, signatures) = items;
+
+    let span = name_span(node);
+
+    let lowering = irgen::LoweringOptions
+    {
+        checked_arith: options.has_long_flag("checked-arith"),
+        bounds_check: options.has_long_flag("bounds-check"),
+        pointer_bits: codegen_mode(options).pointer_bits()
+    };
+
+    let mut function = irgen::Function::from_declaration(node, global_table.clone(), function_table.clone(), generic_table.clone(),
+                                                     signatures.clone(), lowering)?;
+
+    function.name = name;
+
+    function = irgen::infer_types(function);
+    function.check_flow(&span);
+
+    for warning in function.warnings.drain(..)
+    {
+        recorder.report_error(warning)?;
+    }
+
+    let function = irgen::optimize_function(function, optimization_level, !options.has_long_flag("nocomp"));
+
+    // Constant conditions have been folded by now, so only paths which really reach the end are left
+    if function.is_noreturn && function.may_return()
+    {
+        Err(definition_error(ErrorCode::E0011, format!("Function '{}' is marked #[noreturn], but may return", function.name), span, None)
+            .with_note("every path through the function must end in a loop or in a call to a function which never returns"))?
+    }
+
+    Ok(function)
+}
+
+/// Warn about functions and methods which are never called from any function (main, the hook called by checked
+/// arithmetic and names starting with an underscore are never reported), generic functions are used once they are
+/// instantiated
+fn warn_unused_functions(modules: &[Module], functions: &[irgen::Function], instantiations: &[irgen::Instantiation], recorder: &mut ErrorRecorder) -> Result<(), Error>
+{
+    let called: HashSet<&String> = functions.iter().flat_map(|function| function.calls.iter()).collect();
+    let instantiated: HashSet<&String> = instantiations.iter().map(|instantiation| &instantiation.generic.name).collect();
+
+    for module in modules
+    {
+        for item in &module.items
+        {
+            let unused = match item
+            {
+                Item::Function(function) =>
+                {
+                    let name = function.name.name.clone();
+                    let linked_name = module.linked_name(&name);
+
+                    let used = if function.is_generic() {instantiated.contains(&linked_name)} else {called.contains(&linked_name)};
+
+                    if used || name == "main" || linked_name == CHECKED_PANIC_HOOK {vec![]} else {vec![(format!("Function '{}'", name), name, function.clone())]}
+                },
+                Item::Impl(block) =>
+                {
+                    let mut unused = vec![];
+
+                    for method in irgen::methods_from_impl(block)?
+                    {
+                        if !called.contains(&module.linked_name(&method.name))
+                        {
+                            let name = String::from(method.key.rsplit('.').next().unwrap());
+
+                            unused.push((format!("Method '{}'", method.key), name, method.node));
+                        }
+                    }
+
+                    unused
+                },
+                _ => vec![]
+            };
+
+            for (description, name, node) in unused
+            {
+                if !name.starts_with('_')
+                {
+                    recorder.report_error(irgen::compiler_warning_loc(Lint::UnusedFunction, format!("{} is never called", description), &name_span(&node)))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the span of the name of a function
+fn name_span(function: &FunctionDecl) -> Option<Span>
+{
+    Some(function.name.span.clone())
+}
+
+/// Generate an error about the definition of an item, labelling its name along with any earlier definition
+fn definition_error(code: ErrorCode, text: String, span: Option<Span>, previous: Option<Span>) -> Error
+{
+    let mut error = Error::error(&format!("Compilation Error: {}", text)).with_code(code);
+
+    if let Some(span) = span
+    {
+        error = error.with_label(span, "");
+    }
+
+    if let Some(previous) = previous
+    {
+        error = error.with_secondary_label(previous, "first defined here");
+    }
+
+    error
+}
+
+/// Globals, functions and generic functions visible from a module, by the names they are referred to with
+type VisibleItems = (HashMap<String, irgen::Global>, HashMap<String, String>, HashMap<String, irgen::GenericFunction>);
+
+/// Visible items of a module, shared between all of the functions of the module, along with the signatures of all
+/// functions by their linked names
+type ModuleItems = (Rc<HashMap<String, irgen::Global>>, Rc<HashMap<String, String>>, Rc<HashMap<String, irgen::GenericFunction>>,
+                    Rc<HashMap<String, irgen::Signature>>);
+
+/// Get the globals and functions visible from a module, which are its own items, the items of the modules it imports
+/// (qualified by the name of the module, other than methods) and any items brought into scope with `use`
+fn visible_items(modules: &[Module], index: usize, module_globals: &[HashMap<String, irgen::Global>], module_functions: &[HashMap<String, String>],
+                 module_generics: &[HashMap<String, irgen::GenericFunction>]) -> Result<VisibleItems, Error>
+{
+    let module = &modules[index];
+
+    let mut globals = module_globals[index].clone();
+    let mut functions = module_functions[index].clone();
+    let mut generics = module_generics[index].clone();
+
+    for (alias, imported) in &module.imports
+    {
+        for (name, global) in &module_globals[*imported]
+        {
+            globals.insert(format!("{}::{}", alias, name), global.clone());
+        }
+
+        for (name, linked) in &module_functions[*imported]
+        {
+            // Methods are called through their receiver, so they are used without the name of the module
+            if irgen::is_method_key(name)
+            {
+                if functions.get(name).is_some_and(|other| other != linked)
+                {
+                    Err(Error::error(&format!("Compilation Error: Method '{}' defined in more than one module", name)).with_code(ErrorCode::E0008))?
+                }
+
+                functions.insert(name.clone(), linked.clone());
+                continue;
+            }
+
+            functions.insert(format!("{}::{}", alias, name), linked.clone());
+        }
+
+        for (name, generic) in &module_generics[*imported]
+        {
+            generics.insert(format!("{}::{}", alias, name), generic.clone());
+        }
+    }
+
+    for item in &module.items
+    {
+        if let Item::Use(path) = item
+        {
+            let (alias, name) = path.name.rsplit_once("::").unwrap();
+
+            if !module.imports.iter().any(|(other, _)| other == alias)
+            {
+                Err(Error::error(&format!("Compilation Error: Module '{}' is not imported", alias)).with_code(ErrorCode::E0010).with_label(path.span.clone(), "not imported"))?
+            }
+
+            if globals.contains_key(name) || functions.contains_key(name) || generics.contains_key(name)
+            {
+                Err(Error::error(&format!("Compilation Error: Cannot use '{}', the name '{}' is already defined", path.name, name)).with_code(ErrorCode::E0008).with_label(path.span.clone(), "already defined"))?
+            }
+
+            if let Some(global) = globals.get(&path.name).cloned()
+            {
+                globals.insert(String::from(name), global);
+            }
+            else if let Some(linked) = functions.get(&path.name).cloned()
+            {
+                functions.insert(String::from(name), linked);
+            }
+            else if let Some(generic) = generics.get(&path.name).cloned()
+            {
+                generics.insert(String::from(name), generic);
+            }
+            else
+            {
+                Err(Error::error(&format!("Compilation Error: Module '{}' has no item '{}'", alias, name)).with_code(ErrorCode::E0010).with_label(path.span.clone(), "not found"))?
+            }
+        }
+    }
+
+    Ok((globals, functions, generics))
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use super::tokenizer::Span;
+
+/// Color of the line numbers and bars framing the source, and of secondary labels
+static FRAME_COLOR: &str = "\x1b[1m\x1b[34m";
+static RESET: &str = "\x1b[0m";
+
+/// Escape codes coloring a diagnostic, which are all empty when color is turned off
+#[derive(Debug, Clone, Copy)]
+pub struct Palette
+{
+    pub severity: &'static str,
+    pub frame: &'static str,
+    pub reset: &'static str
+}
+
+impl Palette
+{
+    /// Generate a new palette, marking the severity of the diagnostic (and its primary labels) with the given color
+    pub fn new(severity: &'static str, colored: bool) -> Self
+    {
+        if colored
+        {
+            Self
+            {
+                severity,
+                frame: FRAME_COLOR,
+                reset: RESET
+            }
+        }
+        else
+        {
+            Self
+            {
+                severity: "",
+                frame: "",
+                reset: ""
+            }
+        }
+    }
+}
+
+/// Message attached to a span of the source, a primary label marks the problem and a secondary label marks related
+/// code (such as an earlier definition)
+#[derive(Debug, Clone)]
+pub struct Label
+{
+    pub span: Span,
+    pub message: String,
+    pub primary: bool
+}
+
+impl Label
+{
+    /// Generate a new primary label
+    pub fn primary(span: Span, message: &str) -> Self
+    {
+        Self
+        {
+            span,
+            message: String::from(message),
+            primary: true
+        }
+    }
+
+    /// Generate a new secondary label
+    pub fn secondary(span: Span, message: &str) -> Self
+    {
+        Self
+        {
+            span,
+            message: String::from(message),
+            primary: false
+        }
+    }
+}
+
+/// Render the source lines under the labels of a diagnostic, each underlined with carets (or dashes for a secondary
+/// label) and followed by its message, then the notes of the diagnostic. Lines are read from the files the labels
+/// are in, if a file can't be read only the location of the label is given
+///
+///  --> main.pc:4:12
+///   |
+/// 4 |     return t;
+///   |            ^ not found
+///   |
+///   = note: ...
+pub fn render_snippets(labels: &[Label], notes: &[String], palette: &Palette) -> String
+{
+    // Labels are shown in the order they come in the source, keeping the files in the order they are first labelled
+    let mut labels = labels.to_vec();
+    let files: Vec<String> = labels.iter().map(|label| String::from(label.span.start.name())).collect();
+
+    labels.sort_by_key(|label| (files.iter().position(|file| file == label.span.start.name()), label.span.start.row, label.span.start.col));
+
+    let width = labels.iter().map(|label| label.span.start.row.to_string().len()).max().unwrap_or(1);
+    let gutter = " ".repeat(width);
+
+    let mut sources: HashMap<String, Option<String>> = HashMap::new();
+    let mut result = String::new();
+    let mut file: Option<String> = None;
+
+    for label in &labels
+    {
+        let start = &label.span.start;
+
+        // The file is given again whenever the labels move to another file
+        if file.as_deref() != Some(start.name())
+        {
+            let arrow = if file.is_none() {"-->"} else {":::"};
+
+            // The location given is that of the problem, if it is in the file
+            let location = labels.iter().find(|other| other.primary && other.span.start.name() == start.name()).map_or(start, |other| &other.span.start);
+
+            result += &format!("\n{}{}{}{} {}:{}:{}", gutter, palette.frame, arrow, palette.reset, start.name(), location.row, location.col);
+            result += &format!("\n{} {}|{}", gutter, palette.frame, palette.reset);
+
+            file = Some(String::from(start.name()));
+        }
+
+        let line = match read_source(&mut sources, start.name()).and_then(|source| source.split('\n').nth(start.row.saturating_sub(1)))
+        {
+            Some(line) => line.trim_end_matches('\r'),
+            None => continue
+        };
+
+        // Tabs are kept under the underline, so it lines up however wide they are shown
+        let padding: String = line.chars().take(start.col.saturating_sub(1)).map(|c| if c == '\t' {'\t'} else {' '}).collect();
+
+        // A span over several lines is underlined to the end of its first line
+        let end = if label.span.end.row == start.row {label.span.end.col} else {line.chars().count() + 1};
+        let length = end.saturating_sub(start.col).max(1);
+
+        let (mark, mark_color) = if label.primary {("^", palette.severity)} else {("-", palette.frame)};
+
+        result += &format!("\n{}{:>width$} |{} {}", palette.frame, start.row, palette.reset, line, width = width);
+        result += &format!("\n{} {}|{} {}{}{}{}{}", gutter, palette.frame, palette.reset, padding, mark_color, mark.repeat(length),
+                           if label.message.is_empty() {String::new()} else {format!(" {}", label.message)}, palette.reset);
+    }
+
+    if !notes.is_empty() && !labels.is_empty()
+    {
+        result += &format!("\n{} {}|{}", gutter, palette.frame, palette.reset);
+    }
+
+    for note in notes
+    {
+        result += &format!("\n{} {}={} note: {}", gutter, palette.frame, palette.reset, note);
+    }
+
+    result
+}
+
+/// Get the source of a file, which is read the first time it is needed (None if it can't be read)
+fn read_source<'a>(sources: &'a mut HashMap<String, Option<String>>, name: &str) -> Option<&'a String>
+{
+    sources.entry(String::from(name)).or_insert_with(|| std::fs::read_to_string(name).ok()).as_ref()
+}
+
+/// Render a diagnostic as a single line JSON object, for editors and other tools. Labels are given with the file,
+/// line and column they start and end at, along with the range of bytes they cover when the file can be read
+///
+/// {"severity": "error", "code": null, "message": "...", "file": "main.pc", "line": 4, "column": 12, "end_line": 4,
+///  "end_column": 13, "labels": [{"file": "main.pc", "line": 4, ..., "byte_start": 40, "byte_end": 41, "primary": true,
+///  "message": "not found"}], "notes": []}
+pub fn render_json(severity: &str, code: Option<&str>, message: &str, labels: &[Label], notes: &[String]) -> String
+{
+    let mut sources: HashMap<String, Option<String>> = HashMap::new();
+    let mut rendered_labels = vec![];
+
+    for label in labels
+    {
+        let mut fields = span_fields(&label.span);
+
+        if let Some((start, end)) = read_source(&mut sources, label.span.start.name()).and_then(|source| label.span.byte_range(source))
+        {
+            fields.push(format!("\"byte_start\": {}, \"byte_end\": {}", start, end));
+        }
+
+        fields.push(format!("\"primary\": {}", label.primary));
+        fields.push(format!("\"message\": {}", json_string(&label.message)));
+
+        rendered_labels.push(format!("{{{}}}", fields.join(", ")));
+    }
+
+    let mut fields = vec![
+        format!("\"severity\": {}", json_string(severity)),
+        format!("\"code\": {}", code.map(json_string).unwrap_or(String::from("null"))),
+        format!("\"message\": {}", json_string(message))
+        ];
+
+    // The location of the diagnostic is where its first primary label is
+    match labels.iter().find(|label| label.primary)
+    {
+        Some(label) => fields.extend(span_fields(&label.span)),
+        None => fields.push(String::from("\"file\": null"))
+    }
+
+    fields.push(format!("\"labels\": [{}]", rendered_labels.join(", ")));
+    fields.push(format!("\"notes\": [{}]", notes.iter().map(|note| json_string(note)).collect::<Vec<String>>().join(", ")));
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Get the JSON fields giving the file, lines and columns of a span
+fn span_fields(span: &Span) -> Vec<String>
+{
+    vec![
+        format!("\"file\": {}", json_string(span.start.name())),
+        format!("\"line\": {}", span.start.row),
+        format!("\"column\": {}", span.start.col),
+        format!("\"end_line\": {}", span.end.row),
+        format!("\"end_column\": {}", span.end.col)
+        ]
+}
+
+/// Quote and escape a string for JSON
+fn json_string(text: &str) -> String
+{
+    let mut result = String::from("\"");
+
+    for c in text.chars()
+    {
+        match c
+        {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            '\r' => result += "\\r",
+            '\t' => result += "\\t",
+            c if (c as u32) < 0x20 => result += &format!("\\u{:04x}", c as u32),
+            c => result.push(c)
+        }
+    }
+
+    result.push('"');
+    result
+}
@@ -0,0 +1,41 @@
+use super::compiler_error_loc;
+
+use crate::cli::{Error, ErrorCode};
+use crate::parser::FunctionDecl;
+
+/// Attribute of a function, written before the function as #[name]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attribute
+{
+    NoReturn
+}
+
+impl Attribute
+{
+    /// Get the attribute with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name
+        {
+            "noreturn" => Some(Attribute::NoReturn),
+            _ => None
+        }
+    }
+}
+
+/// Get the attributes of a function
+pub fn function_attributes(function: &FunctionDecl) -> Result<Vec<Attribute>, Error>
+{
+    let mut attributes = vec![];
+
+    for name in &function.attributes
+    {
+        match Attribute::from_name(&name.name)
+        {
+            Some(attribute) => attributes.push(attribute),
+            None => compiler_error_loc(ErrorCode::E0016, format!("Unknown attribute '{}'", name.name), &Some(name.span.clone()))?
+        }
+    }
+
+    Ok(attributes)
+}
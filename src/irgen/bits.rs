@@ -0,0 +1,108 @@
+use super::{Expression, Function, Value, Literal, Symbol, Instruction, OpCode, DataType};
+
+use super::{get_value_type, correct_type_references, compiler_error_loc, compiler_warning_loc};
+
+use crate::cli::{Error, ErrorCode, Lint};
+
+use std::cell::RefCell;
+
+impl Expression
+{
+    /// Check that a range of bits lies within an integer of the given type, giving the mask of a field of that many bits
+    fn bit_range_mask(&self, datatype: DataType, start: usize, end: usize) -> Result<i128, Error>
+    {
+        let bits = match datatype.bit_width()
+        {
+            Some(bits) => bits,
+            None => compiler_error_loc(ErrorCode::E0029, format!("Cannot take a range of the bits of '{}', only integers have bits", datatype), &self.location())?
+        };
+
+        if end > bits
+        {
+            compiler_error_loc(ErrorCode::E0029, format!("Bit range {}..{} is out of range for '{}'", start, end, datatype), &self.location())?;
+        }
+
+        Ok(datatype.wrap_value(1i128.checked_shl((end - start) as u32).unwrap_or(0).wrapping_sub(1)))
+    }
+
+    /// Add an operation on two values of the same type, giving the result in a new register
+    fn bitwise_operation(opcode: OpCode, val0: Value, val1: Value, datatype: DataType, func: &RefCell<&mut Function>) -> Value
+    {
+        let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+        func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+            value.clone(),
+            val0,
+            val1
+            ]));
+
+        value
+    }
+
+    /// Render a read of a range of bits, which are shifted down to the lowest bits of the result
+    pub fn render_bit_range(&mut self, start: usize, end: usize, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        self.children[0].render(func)?;
+        let mut value = self.children[0].value(func)?;
+
+        let datatype = correct_type_references(get_value_type(&value).unwrap());
+        let mask = self.bit_range_mask(datatype, start, end)?;
+
+        if start > 0
+        {
+            value = Expression::bitwise_operation(OpCode::Shr, value, Value::Literal(Literal::new(start as i128, datatype)), datatype, func);
+        }
+
+        self.value = Some(Expression::bitwise_operation(OpCode::And, value, Value::Literal(Literal::new(mask, datatype)), datatype, func));
+
+        Ok(())
+    }
+
+    /// Render an assignment to a range of bits, which reads the whole integer, replaces the bits in the range and
+    /// writes it back (the value of the assignment is the value of the field)
+    pub fn render_bit_range_assignment(&mut self, start: usize, end: usize, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        self.children[0].render(func)?;
+        self.children[1].render(func)?;
+
+        let target = self.children[0].value(func)?;
+        let value = self.children[1].value(func)?;
+
+        let datatype = correct_type_references(get_value_type(&target).unwrap());
+        let mask = self.bit_range_mask(datatype, start, end)?;
+
+        if let Value::Literal(lit) = &value
+        {
+            if lit.value & !mask != 0
+            {
+                func.borrow_mut().warnings.push(compiler_warning_loc(Lint::OverflowingLiteral, format!("Constant {} does not fit in bits {}..{}", lit.value, start, end), &self.location()));
+            }
+        }
+
+        let value = Expression::implicit_conversion(value, datatype, func, &self.location(), false);
+        let field = Expression::bitwise_operation(OpCode::And, value, Value::Literal(Literal::new(mask, datatype)), datatype, func);
+
+        // The current value is read through the reference for memory, or directly for a variable
+        let current = if self.children[0].is_reference()
+        {
+            Expression::load_reference(&target, func)
+        }
+        else
+        {
+            target.clone()
+        };
+
+        let cleared = Expression::bitwise_operation(OpCode::And, current, Value::Literal(Literal::new(datatype.wrap_value(!(mask << start)), datatype)), datatype, func);
+        let shifted = Expression::bitwise_operation(OpCode::Shl, field.clone(), Value::Literal(Literal::new(start as i128, datatype)), datatype, func);
+        let combined = Expression::bitwise_operation(OpCode::Or, cleared, shifted, datatype, func);
+
+        func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+            target,
+            combined
+            ]));
+
+        self.value = Some(field);
+
+        Ok(())
+    }
+}
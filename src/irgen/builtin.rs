@@ -0,0 +1,341 @@
+use super::{Expression, ExpressionType, Function, Value, Literal, Symbol, Instruction, OpCode, DataType, NonPtrType};
+
+use super::{get_value_type, correct_type_references, compiler_error_loc, compiler_warning_loc};
+
+use crate::cli::{Error, ErrorCode, Lint};
+
+use std::cell::RefCell;
+
+/// Functions provided by the compiler, which are lowered to instructions rather than calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin
+{
+    BitSet,
+    BitClear,
+    BitTest,
+    RotateLeft,
+    RotateRight,
+    Popcount,
+    Clz,
+    Ctz,
+    Bswap,
+    Memcpy,
+    Memset,
+    Unreachable,
+    Assume
+}
+
+impl Builtin
+{
+    /// Get the builtin with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name
+        {
+            "bit_set" => Some(Builtin::BitSet),
+            "bit_clear" => Some(Builtin::BitClear),
+            "bit_test" => Some(Builtin::BitTest),
+            "rotate_left" => Some(Builtin::RotateLeft),
+            "rotate_right" => Some(Builtin::RotateRight),
+            "popcount" => Some(Builtin::Popcount),
+            "clz" => Some(Builtin::Clz),
+            "ctz" => Some(Builtin::Ctz),
+            "bswap" => Some(Builtin::Bswap),
+            "memcpy" => Some(Builtin::Memcpy),
+            "memset" => Some(Builtin::Memset),
+            "unreachable" => Some(Builtin::Unreachable),
+            "assume" => Some(Builtin::Assume),
+            _ => None
+        }
+    }
+
+    /// Get the name the builtin is called by
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Builtin::BitSet => "bit_set",
+            Builtin::BitClear => "bit_clear",
+            Builtin::BitTest => "bit_test",
+            Builtin::RotateLeft => "rotate_left",
+            Builtin::RotateRight => "rotate_right",
+            Builtin::Popcount => "popcount",
+            Builtin::Clz => "clz",
+            Builtin::Ctz => "ctz",
+            Builtin::Bswap => "bswap",
+            Builtin::Memcpy => "memcpy",
+            Builtin::Memset => "memset",
+            Builtin::Unreachable => "unreachable",
+            Builtin::Assume => "assume"
+        }
+    }
+
+    /// Get the number of arguments the builtin takes
+    pub fn argument_count(&self) -> usize
+    {
+        match self
+        {
+            Builtin::Unreachable => 0,
+            Builtin::Popcount | Builtin::Clz | Builtin::Ctz | Builtin::Bswap | Builtin::Assume => 1,
+            Builtin::BitSet | Builtin::BitClear | Builtin::BitTest | Builtin::RotateLeft | Builtin::RotateRight => 2,
+            Builtin::Memcpy | Builtin::Memset => 3
+        }
+    }
+
+    /// Get the opcode the builtin is lowered to
+    pub fn opcode(&self) -> OpCode
+    {
+        match self
+        {
+            Builtin::BitSet => OpCode::BitSet,
+            Builtin::BitClear => OpCode::BitClr,
+            Builtin::BitTest => OpCode::BitTst,
+            Builtin::RotateLeft => OpCode::Rotl,
+            Builtin::RotateRight => OpCode::Rotr,
+            Builtin::Popcount => OpCode::Popcnt,
+            Builtin::Clz => OpCode::Clz,
+            Builtin::Ctz => OpCode::Ctz,
+            Builtin::Bswap => OpCode::Bswap,
+            Builtin::Memcpy => OpCode::Memcpy,
+            Builtin::Memset => OpCode::Memset,
+            Builtin::Unreachable => OpCode::Unreachable,
+            Builtin::Assume => OpCode::Assume
+        }
+    }
+}
+
+impl Expression
+{
+    /// Get the constant address the expression casts to a pointer, if it is one (so that fixed addresses such as those
+    /// of peripheral registers are known to the code generator)
+    fn constant_pointer(&self) -> Option<Literal>
+    {
+        match &self.mode
+        {
+            ExpressionType::Cast(datatype) if datatype.num_ptr > 0 && !datatype.is_slice =>
+            {
+                self.children[0].evaluate_constant().ok().map(|lit| Literal::new(lit.value, *datatype))
+            },
+            _ => None
+        }
+    }
+
+    /// Render a call to a builtin
+    pub fn render_builtin(&mut self, builtin: Builtin, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        if self.children.len() != builtin.argument_count()
+        {
+            compiler_error_loc(ErrorCode::E0017, format!("Builtin {} expects {} arguments, got {}", builtin.name(), builtin.argument_count(), self.children.len()), &self.location())?;
+        }
+
+        match builtin
+        {
+            Builtin::BitSet | Builtin::BitClear | Builtin::BitTest => self.render_bit_builtin(builtin, func),
+            Builtin::Memcpy | Builtin::Memset => self.render_memory_builtin(builtin, func),
+            Builtin::Unreachable | Builtin::Assume => self.render_assumption_builtin(builtin, func),
+            _ => self.render_integer_builtin(builtin, func)
+        }
+    }
+
+    /// Get the type of the integer given to an intrinsic, which must not be a pointer or a slice
+    fn integer_argument_type(&self, builtin: Builtin, value: &Value) -> Result<DataType, Error>
+    {
+        let datatype = correct_type_references(get_value_type(value).unwrap());
+
+        if datatype.raw_type != NonPtrType::Unknown && datatype.bit_width().is_none()
+        {
+            compiler_error_loc(ErrorCode::E0028, format!("Builtin {} expects an integer, got '{}'", builtin.name(), datatype), &self.location())?;
+        }
+
+        // The bytes of a single byte are already in order, other integers must be made of pairs of bytes
+        if builtin == Builtin::Bswap && datatype.bit_width().is_some_and(|bits| bits != 8 && bits % 16 != 0)
+        {
+            compiler_error_loc(ErrorCode::E0028, format!("Builtin bswap expects an integer of one byte or an even number of bytes, got '{}'", datatype), &self.location())?;
+        }
+
+        Ok(datatype)
+    }
+
+    /// Render an intrinsic on an integer, the result has the type of the integer (the amount of a rotate is converted
+    /// to that type)
+    fn render_integer_builtin(&mut self, builtin: Builtin, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let mut arguments = vec![];
+
+        for child in &mut self.children
+        {
+            child.render(func)?;
+            arguments.push(child.value(func)?);
+        }
+
+        let mut datatype = self.integer_argument_type(builtin, &arguments[0])?;
+
+        if arguments.len() > 1
+        {
+            let amount_type = self.integer_argument_type(builtin, &arguments[1])?;
+
+            // A literal value is rotated at the width of the amount
+            if datatype.raw_type == NonPtrType::Unknown
+            {
+                datatype = amount_type;
+                arguments[0] = Expression::implicit_conversion(arguments[0].clone(), datatype, func, &self.location(), false);
+            }
+
+            arguments[1] = Expression::implicit_conversion(arguments[1].clone(), datatype, func, &self.location(), false);
+        }
+
+        let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+        func.borrow_mut().add_instruction(Instruction::new(builtin.opcode(), vec![value.clone()].into_iter().chain(arguments).collect()));
+
+        self.value = Some(value);
+
+        Ok(())
+    }
+
+    /// Render a builtin which copies or sets a number of bytes in memory, the count is converted to the type of the
+    /// length of a slice
+    fn render_memory_builtin(&mut self, builtin: Builtin, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let mut arguments = vec![];
+
+        for child in &mut self.children
+        {
+            child.render(func)?;
+            arguments.push(child.value(func)?);
+        }
+
+        // Memory is copied from a pointer, or set to a byte
+        let pointers = if builtin == Builtin::Memcpy {2} else {1};
+
+        for argument in &arguments[..pointers]
+        {
+            let datatype = correct_type_references(get_value_type(argument).unwrap());
+
+            if datatype.num_ptr == 0 || datatype.is_slice || datatype.is_progmem
+            {
+                compiler_error_loc(ErrorCode::E0028, format!("Builtin {} expects pointers into memory, got '{}'", builtin.name(), datatype), &self.location())?;
+            }
+        }
+
+        if builtin == Builtin::Memset
+        {
+            arguments[1] = Expression::implicit_conversion(arguments[1].clone(), DataType::new(NonPtrType::U8, 0, false), func, &self.location(), true);
+        }
+
+        arguments[2] = Expression::implicit_conversion(arguments[2].clone(), DataType::slice_length(), func, &self.location(), true);
+
+        func.borrow_mut().add_instruction(Instruction::new(builtin.opcode(), arguments));
+
+        self.value = None;
+
+        Ok(())
+    }
+
+    /// Render a builtin which tells the optimizer something it can't prove, either that the code is never reached or
+    /// that a condition always holds (a constant condition is checked at compile time)
+    fn render_assumption_builtin(&mut self, builtin: Builtin, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        self.value = None;
+
+        if builtin == Builtin::Unreachable
+        {
+            func.borrow_mut().add_instruction(Instruction::new(OpCode::Unreachable, vec![]));
+
+            return Ok(());
+        }
+
+        self.children[0].render(func)?;
+        let condition = self.children[0].value(func)?;
+
+        let datatype = correct_type_references(get_value_type(&condition).unwrap());
+
+        if datatype.raw_type != NonPtrType::Unknown && datatype.bit_width().is_none() && datatype.num_ptr == 0
+        {
+            compiler_error_loc(ErrorCode::E0028, format!("Builtin assume expects a condition, got '{}'", datatype), &self.location())?;
+        }
+
+        match condition
+        {
+            Value::Literal(lit) if lit.value != 0 => {},
+            Value::Literal(_) =>
+            {
+                func.borrow_mut().warnings.push(compiler_warning_loc(Lint::UnreachableCode, String::from("Assumed condition is always false, the code is never reached"), &self.location()));
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Unreachable, vec![]));
+            },
+            condition => func.borrow_mut().add_instruction(Instruction::new(OpCode::Assume, vec![condition]))
+        }
+
+        Ok(())
+    }
+
+    /// Render a builtin which sets, clears or tests a single bit of the integer behind a pointer
+    fn render_bit_builtin(&mut self, builtin: Builtin, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let ptr = match self.children[0].constant_pointer()
+        {
+            Some(address) => Value::Literal(address),
+            None =>
+            {
+                self.children[0].render(func)?;
+                self.children[0].value(func)?
+            }
+        };
+
+        let datatype = correct_type_references(get_value_type(&ptr).unwrap());
+
+        if datatype.num_ptr != 1 || datatype.is_slice || datatype.is_progmem || datatype.dereferenced().bit_width().is_none()
+        {
+            compiler_error_loc(ErrorCode::E0028, format!("Builtin {} expects a pointer to an integer in memory, got '{}'", builtin.name(), datatype), &self.location())?;
+        }
+
+        let element_type = datatype.dereferenced();
+
+        self.children[1].render(func)?;
+        let bit = self.children[1].value(func)?;
+
+        // The bit is given as a byte, constant bits must be within the integer
+        let bit_type = DataType::new(NonPtrType::U8, 0, false);
+
+        let bit = match bit
+        {
+            Value::Literal(lit) =>
+            {
+                if lit.value < 0 || lit.value >= element_type.bit_width().unwrap() as i128
+                {
+                    compiler_error_loc(ErrorCode::E0029, format!("Bit {} is out of range for '{}'", lit.value, element_type), &self.location())?;
+                }
+
+                Value::Literal(Literal::new(lit.value, bit_type))
+            },
+            value => Expression::implicit_conversion(value, bit_type, func, &self.location(), false)
+        };
+
+        match builtin
+        {
+            Builtin::BitTest =>
+            {
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::U8, 0, false)));
+
+                func.borrow_mut().add_instruction(Instruction::new(builtin.opcode(), vec![
+                    value.clone(),
+                    ptr,
+                    bit
+                    ]));
+
+                self.value = Some(value);
+            },
+            _ =>
+            {
+                func.borrow_mut().add_instruction(Instruction::new(builtin.opcode(), vec![
+                    ptr,
+                    bit
+                    ]));
+
+                self.value = None;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use super::{Expression, ExpressionType, Value, Literal, OpCode, DataType, NonPtrType};
+
+use super::{compiler_error_loc};
+
+use crate::cli::{Error, ErrorCode};
+
+use std::cmp::Ordering;
+
+impl Expression
+{
+    /// Evaluate an expression at compile time, giving a literal with the type of the expression (integer literals
+    /// which have not been combined with a typed value have an unknown type and are evaluated exactly)
+    pub fn evaluate_constant(&self) -> Result<Literal, Error>
+    {
+        match &self.mode
+        {
+            ExpressionType::IntegerLiteral =>
+            {
+                match &self.value
+                {
+                    Some(Value::Literal(lit)) => Ok(*lit),
+                    _ => compiler_error_loc(ErrorCode::E0032, String::from("Expected an integer literal"), &self.location())
+                }
+            },
+            ExpressionType::Cast(datatype) =>
+            {
+                let lit = self.children[0].evaluate_constant()?;
+
+                if datatype.bit_width().is_none()
+                {
+                    compiler_error_loc(ErrorCode::E0030, format!("Cannot cast a constant to '{}'", datatype), &self.location())?;
+                }
+
+                Ok(Literal::new(datatype.wrap_value(lit.value), *datatype))
+            },
+            ExpressionType::UnaryMinus =>
+            {
+                let lit = self.children[0].evaluate_constant()?;
+
+                self.constant_result(lit.value.checked_neg(), lit.datatype)
+            },
+            ExpressionType::BitwiseNot =>
+            {
+                let lit = self.children[0].evaluate_constant()?;
+
+                self.constant_result(Some(!lit.value), lit.datatype)
+            },
+            ExpressionType::LogicalNot =>
+            {
+                let lit = self.children[0].evaluate_constant()?;
+
+                Ok(Literal::new((lit.value == 0) as i128, DataType::new(NonPtrType::Unknown, 0, false)))
+            },
+            ExpressionType::LogicalAnd | ExpressionType::LogicalOr =>
+            {
+                // The second operand is only evaluated if the first doesn't decide the result, as at runtime
+                let lit0 = self.children[0].evaluate_constant()?;
+                let decided = (lit0.value != 0) == (self.mode == ExpressionType::LogicalOr);
+
+                let value = if decided
+                {
+                    lit0.value != 0
+                }
+                else
+                {
+                    self.children[1].evaluate_constant()?.value != 0
+                };
+
+                Ok(Literal::new(value as i128, DataType::new(NonPtrType::Unknown, 0, false)))
+            },
+            ExpressionType::Ternary =>
+            {
+                let condition = self.children[0].evaluate_constant()?;
+                let (taken, untaken) = if condition.value != 0 {(1, 2)} else {(2, 1)};
+                let lit = self.children[taken].evaluate_constant()?;
+
+                // Both branches are converted to their common type, as they would be at runtime, but the branch which
+                // is not taken is never evaluated, so errors in it are ignored (and it then doesn't affect the type)
+                let datatype = match self.children[untaken].evaluate_constant()
+                {
+                    Ok(other) => lit.datatype.common_type(&other.datatype),
+                    Err(_) => lit.datatype
+                };
+
+                Ok(Literal::new(datatype.wrap_value(lit.value), datatype))
+            },
+            ExpressionType::BinaryExpression(opcode) =>
+            {
+                let lit0 = self.children[0].evaluate_constant()?;
+                let lit1 = self.children[1].evaluate_constant()?;
+
+                self.evaluate_binary(*opcode, lit0, lit1)
+            },
+            ExpressionType::Identifier =>
+            {
+                let name = match &self.value
+                {
+                    Some(Value::Symbol(symb)) => symb.title.clone(),
+                    _ => String::new()
+                };
+
+                compiler_error_loc(ErrorCode::E0030, format!("'{}' is not a constant, expected a constant expression", name), &self.location())
+            },
+            ExpressionType::Global(_) =>
+            {
+                let name = match &self.value
+                {
+                    Some(Value::Label(label)) => label.clone(),
+                    _ => String::new()
+                };
+
+                compiler_error_loc(ErrorCode::E0030, format!("Global '{}' is not a constant, expected a constant expression", name), &self.location())
+            },
+            // A global which is read is the only child of a dereference
+            ExpressionType::UnaryOperation(OpCode::Deref, _) if matches!(self.children[0].mode, ExpressionType::Global(_)) =>
+            {
+                self.children[0].evaluate_constant()
+            },
+            ExpressionType::FunctionCall | ExpressionType::GenericCall(_) =>
+            {
+                compiler_error_loc(ErrorCode::E0030, String::from("Function calls are not allowed in a constant expression"), &self.location())
+            },
+            // Intrinsics on integers are evaluated at the width of their first typed argument (i32 for untyped
+            // arguments, as at runtime)
+            ExpressionType::Builtin(builtin) =>
+            {
+                let mut values = vec![];
+
+                for child in &self.children
+                {
+                    values.push(child.evaluate_constant()?);
+                }
+
+                let datatype = values.iter().map(|lit| lit.datatype).find(|datatype| datatype.raw_type != NonPtrType::Unknown)
+                    .unwrap_or(DataType::new(NonPtrType::I32, 0, false));
+                let amount = values.get(1).map(|lit| lit.value).unwrap_or(0);
+
+                match values.first().and_then(|lit| datatype.evaluate_intrinsic(builtin.opcode(), lit.value, amount))
+                {
+                    Some(value) if values.len() == builtin.argument_count() => Ok(Literal::new(value, datatype)),
+                    _ => compiler_error_loc(ErrorCode::E0030, format!("Builtin {} is not allowed in a constant expression", builtin.name()), &self.location())
+                }
+            },
+            ExpressionType::AssignmentExpression(_) | ExpressionType::PreExpression(_) | ExpressionType::PostExpression(_) =>
+            {
+                compiler_error_loc(ErrorCode::E0030, String::from("Assignments are not allowed in a constant expression"), &self.location())
+            },
+            _ =>
+            {
+                compiler_error_loc(ErrorCode::E0030, String::from("Expected a constant expression"), &self.location())
+            }
+        }
+    }
+
+    /// Evaluate a binary operation on two constants, with the same conversions and width as the operation would have
+    /// at runtime
+    fn evaluate_binary(&self, opcode: OpCode, lit0: Literal, lit1: Literal) -> Result<Literal, Error>
+    {
+        // Shifts keep the type of the value being shifted
+        let datatype = match opcode
+        {
+            OpCode::Shl | OpCode::Shr => lit0.datatype,
+            _ => lit0.datatype.common_type(&lit1.datatype)
+        };
+
+        if let Some(datatype) = [lit0.datatype, lit1.datatype].iter().find(|datatype| datatype.raw_type != NonPtrType::Unknown && datatype.bit_width().is_none())
+        {
+            compiler_error_loc(ErrorCode::E0030, format!("Constant expressions must be integers, got '{}'", datatype), &self.location())?;
+        }
+
+        let value0 = datatype.wrap_value(lit0.value);
+        let value1 = if opcode == OpCode::Shl || opcode == OpCode::Shr {lit1.value} else {datatype.wrap_value(lit1.value)};
+
+        // Typed values wrap around, as they would at runtime
+        let typed = datatype.bit_width().is_some();
+
+        let value = match opcode
+        {
+            OpCode::Add | OpCode::Sub | OpCode::Mul => datatype.evaluate_wrapping(opcode, value0, value1),
+            OpCode::Div | OpCode::Mod =>
+            {
+                if value1 == 0
+                {
+                    compiler_error_loc(ErrorCode::E0031, String::from("Division by zero in a constant expression"), &self.location())?;
+                }
+
+                datatype.evaluate_wrapping(opcode, value0, value1)
+            },
+            OpCode::Shl | OpCode::Shr =>
+            {
+                let bits = datatype.bit_width().unwrap_or(127) as i128;
+
+                if value1 < 0 || value1 >= bits
+                {
+                    compiler_error_loc(ErrorCode::E0031, format!("Shift by {} is out of range in a constant expression", value1), &self.location())?;
+                }
+
+                if opcode == OpCode::Shl
+                {
+                    // Bits shifted out of an untyped constant would be lost
+                    if typed {Some(value0.wrapping_shl(value1 as u32))} else {value0.checked_mul(1i128 << value1)}
+                }
+                else if typed
+                {
+                    datatype.evaluate_wrapping(opcode, value0, value1)
+                }
+                else
+                {
+                    Some(value0 >> value1)
+                }
+            },
+            OpCode::And => Some(value0 & value1),
+            OpCode::Or => Some(value0 | value1),
+            OpCode::Xor => Some(value0 ^ value1),
+            // Comparisons produce either zero or one
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cle | OpCode::Cgt | OpCode::Cge =>
+            {
+                let ordering = datatype.compare_values(value0, value1);
+
+                let result = match opcode
+                {
+                    OpCode::Ceq => ordering == Ordering::Equal,
+                    OpCode::Cne => ordering != Ordering::Equal,
+                    OpCode::Clt => ordering == Ordering::Less,
+                    OpCode::Cle => ordering != Ordering::Greater,
+                    OpCode::Cgt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less
+                };
+
+                return Ok(Literal::new(result as i128, DataType::new(NonPtrType::Unknown, 0, false)));
+            },
+            _ => compiler_error_loc(ErrorCode::E0030, format!("Operation '{:?}' is not allowed in a constant expression", opcode), &self.location())?
+        };
+
+        self.constant_result(value, datatype)
+    }
+
+    /// Get the result of a constant operation, wrapping it to the width of its type (an untyped constant which does
+    /// not fit in 128 bits is an error)
+    fn constant_result(&self, value: Option<i128>, datatype: DataType) -> Result<Literal, Error>
+    {
+        match value
+        {
+            Some(v) => Ok(Literal::new(datatype.wrap_value(v), datatype)),
+            None => compiler_error_loc(ErrorCode::E0031, String::from("Constant expression overflows"), &self.location())
+        }
+    }
+}
@@ -1,29 +1,28 @@
-use crate::cli::Error;
-use crate::parser::ParseTreeNode;
-use crate::tokenizer::FileLocation;
+use crate::cli::{Error, ErrorCode, Lint};
+use crate::tokenizer::Span;
 
-/// Display a compiler error (from IR code gen)
-pub fn compiler_error<T>(text: String) -> Result<T, Error>
+/// Generate a compiler warning from a lint with the location if the location is known
+pub fn compiler_warning_loc(lint: Lint, text: String, loc: &Option<Span>) -> Error
 {
-    Err(Error::error(&format!("Compilation Error: {}", text)))
+    match loc
+    {
+        None => Error::warning(&format!("Compilation Warning: {}", text)).with_lint(lint),
+        Some(span) => Error::warning(&format!("Compilation Warning: {}", text)).with_lint(lint).with_label(span.clone(), "")
+    }
 }
 
-/// Expected, got style error for IR code gen
-pub fn expected_got_error<T>(expected: &str, got: ParseTreeNode) -> Result<T, Error>
+/// Display the location if the location is known
+pub fn compiler_error_loc<T>(code: ErrorCode, text: String, loc: &Option<Span>) -> Result<T, Error>
 {
-    let raw_got_str = format!("{:?}", got);
-    let got_str = raw_got_str.split("(").nth(0).unwrap();
-
-    compiler_error(format!("Expected {}, got {}", expected, got_str))
+    compiler_error_label(code, text, loc, "")
 }
 
-/// Display the location if the location is known
-pub fn compiler_error_loc<T>(text: String, loc: &Option<FileLocation>) -> Result<T, Error>
+/// Display the location if the location is known, labelled with a message
+pub fn compiler_error_label<T>(code: ErrorCode, text: String, loc: &Option<Span>, label: &str) -> Result<T, Error>
 {
     match loc
     {
-        None => Err(Error::error(&format!("Compilation Error: {}", text))),
-        Some(l) => Err(Error::error(&format!("Compilation Error: {}", format!("{} at {}", text, l))))
+        None => Err(Error::error(&format!("Compilation Error: {}", text)).with_code(code)),
+        Some(span) => Err(Error::error(&format!("Compilation Error: {}", text)).with_code(code).with_label(span.clone(), label))
     }
-    
 }
\ No newline at end of file
@@ -1,14 +1,14 @@
-use super::{Function, Value, Literal, Symbol, Instruction, OpCode, attempt_mutate_type, has_unknown_type, get_value_type, correct_type_references, type_from_parse_tree};
+use super::{Function, Value, Literal, Symbol, Instruction, OpCode, Instantiation, arguments_from_ast, attempt_mutate_type, has_unknown_type, get_value_type, correct_type_references, type_from_ast, check_address_space};
+use super::{slice_length, method_key, Builtin, Signature};
 
-use crate::cli::Error;
+use crate::cli::{Error, ErrorCode, Lint};
 
-use super::{expected_got_error, compiler_error_loc};
+use super::{compiler_error_loc, compiler_warning_loc};
 use super::{DataType, NonPtrType};
 
-use crate::parser::ParseTreeNode;
-use crate::parser::ExpressionType as ExpressionTypeP;
+use crate::parser::{Expr, UnaryOp, BinaryOp};
 
-use crate::tokenizer::{Token, FileLocation};
+use crate::tokenizer::Span;
 
 use std::cell::RefCell;
 
@@ -19,8 +19,16 @@ pub enum ExpressionType
     LogicalAnd,
     LogicalOr,
     FunctionCall,
+    Builtin(Builtin),
+    GenericCall(Vec<DataType>),
+    MethodCall(String),
     LogicalNot,
     ArrayAccess,
+    ArrayAccessLeft,
+    Subslice(bool),
+    SliceLength,
+    BitRange(usize, usize),
+    BitRangeAssignment(usize, usize),
     BitwiseNot,
     Ternary,
     UnaryOperation(OpCode, isize),
@@ -30,6 +38,7 @@ pub enum ExpressionType
     UnaryMinus,
     IntegerLiteral,
     Identifier,
+    Global(DataType),
     PreExpression(OpCode),
     PostExpression(OpCode),
     BinaryExpression(OpCode),
@@ -40,10 +49,10 @@ pub enum ExpressionType
 #[derive(Debug, Clone)]
 pub struct Expression
 {
-    mode: ExpressionType,
-    value: Option<Value>,
-    children: Vec<Expression>,
-    pos: Option<FileLocation>
+    pub mode: ExpressionType,
+    pub value: Option<Value>,
+    pub children: Vec<Expression>,
+    pub pos: Option<Span>
 }
 
 impl Expression
@@ -60,289 +69,827 @@ impl Expression
         }
     }
 
-    /// Generate a new expression object which references a token
-    pub fn new_with_token(mode: ExpressionType, value: Option<Value>, children: Vec<Expression>, token: &Token) -> Self
+    /// Generate a new expression object covering a span of the source
+    pub fn new_with_span(mode: ExpressionType, value: Option<Value>, children: Vec<Expression>, span: &Span) -> Self
     {
         Self
         {
             mode,
             value,
-            children: children,
-            pos: Some(token.location.clone())
+            children,
+            pos: Some(span.clone())
         }
     }
 
+    /// Generate a new expression from an expression of the syntax tree, which covers all of the source of the
+    /// expression
+    pub fn from_ast(expr: &Expr, func: &RefCell<&mut Function>) -> Result<Self, Error>
+    {
+        let mut result = Expression::from_expr(expr, func)?;
+        result.pos = Some(expr.span());
 
-    pub fn from_parse_tree_node(node: ParseTreeNode, func: &RefCell<&mut Function>) -> Result<Self, Error>
+        Ok(result)
+    }
+
+    /// Generate the target of an assignment, a dereferenced pointer is written through rather than read
+    fn target_from_ast(expr: &Expr, func: &RefCell<&mut Function>) -> Result<Self, Error>
     {
-        match &node
+        match expr
         {
-            ParseTreeNode::IntegerLiteral(token) =>
+            Expr::Unary {op: UnaryOp::Dereference, operand, span} =>
             {
-                Ok(Expression::new_with_token(ExpressionType::IntegerLiteral, 
-                    Some(Value::Literal(Literal::new(i128::from_str_radix(token.data.as_str(), 10).unwrap(),
-                        DataType::new(NonPtrType::Unknown, 0, false)))), vec![], token))
+                let child0 = Expression::from_ast(operand, func)?;
+
+                Ok(Expression::new_with_span(ExpressionType::DereferenceLeft, None, vec![child0], span))
             },
-            ParseTreeNode::Identifier(token) =>
+            _ => Expression::from_ast(expr, func)
+        }
+    }
+
+    /// Generate a range of bits of an integer, the bounds of the range must be known at compile time
+    fn bit_range_from_ast(child0: Expression, start: &Expr, end: &Expr, func: &RefCell<&mut Function>) -> Result<Self, Error>
+    {
+        let start = Expression::from_ast(start, func)?.evaluate_constant()?.value;
+        let end = Expression::from_ast(end, func)?.evaluate_constant()?.value;
+
+        if start < 0 || end <= start
+        {
+            compiler_error_loc(ErrorCode::E0029, format!("Invalid bit range {}..{}, the range must contain at least one bit", start, end), &child0.location())?;
+        }
+
+        Ok(Expression::new(ExpressionType::BitRange(start as usize, end.min(u16::MAX as i128) as usize), None, vec![child0]))
+    }
+
+    /// Get the instruction for a binary operator, the logical operators aren't a single instruction as they only
+    /// evaluate their second operand when it is needed
+    fn binary_opcode(op: BinaryOp) -> Option<OpCode>
+    {
+        match op
+        {
+            BinaryOp::Add => Some(OpCode::Add),
+            BinaryOp::Subtract => Some(OpCode::Sub),
+            BinaryOp::Multiply => Some(OpCode::Mul),
+            BinaryOp::Divide => Some(OpCode::Div),
+            BinaryOp::Modulus => Some(OpCode::Mod),
+            BinaryOp::ShiftLeft => Some(OpCode::Shl),
+            BinaryOp::ShiftRight => Some(OpCode::Shr),
+            BinaryOp::LessThan => Some(OpCode::Clt),
+            BinaryOp::LessThanOrEqual => Some(OpCode::Cle),
+            BinaryOp::GreaterThan => Some(OpCode::Cgt),
+            BinaryOp::GreaterThanOrEqual => Some(OpCode::Cge),
+            BinaryOp::Equal => Some(OpCode::Ceq),
+            BinaryOp::NotEqual => Some(OpCode::Cne),
+            BinaryOp::BitwiseAnd => Some(OpCode::And),
+            BinaryOp::BitwiseOr => Some(OpCode::Or),
+            BinaryOp::BitwiseXor => Some(OpCode::Xor),
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr => None
+        }
+    }
+
+    /// Generate a new expression from an expression of the syntax tree
+    fn from_expr(expr: &Expr, func: &RefCell<&mut Function>) -> Result<Self, Error>
+    {
+        match expr
+        {
+            Expr::Integer(text, span) =>
             {
-                let val = Value::Symbol(match func.borrow_mut().symbol_table.get(&token.data)
+                let value = match text.parse::<i128>()
                 {
-                    Some(v) => v.clone(),
-                    None => {compiler_error_loc(format!("Symbol {} not found in symbol table", token.data), &Some(token.location.clone()))?;unreachable!()}
-                });
+                    Ok(v) => v,
+                    Err(_) => compiler_error_loc(ErrorCode::E0032, format!("Integer literal '{}' is too large", text), &Some(span.clone()))?
+                };
 
-                Ok(Expression::new(ExpressionType::Identifier,
-                    Some(val), vec![]))
+                Ok(Expression::new_with_span(ExpressionType::IntegerLiteral, 
+                    Some(Value::Literal(Literal::new(value, DataType::new(NonPtrType::Unknown, 0, false)))), vec![], span))
             },
-            ParseTreeNode::Expression(expr_type, children) =>
+            Expr::Path(ident) =>
             {
-                match expr_type
+                // Local symbols shadow globals
+                let symbol = func.borrow_mut().symbol_table.get(&ident.name).cloned();
+                let global = func.borrow().globals.get(&ident.name).cloned();
+
+                if let Some(v) = symbol
                 {
-                    // The Unary Plus Does basically Nothing
-                    ExpressionTypeP::UnaryPlus => {Expression::from_parse_tree_node(children[0].clone(), func)},
-                    ExpressionTypeP::UnaryMinus => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                    Ok(Expression::new_with_span(ExpressionType::Identifier,
+                        Some(Value::Symbol(v)), vec![], &ident.span))
+                }
+                else if let Some(g) = global
+                {
+                    let address = Expression::new_with_span(ExpressionType::Global(g.address_type()),
+                        Some(Value::Label(g.name.clone())), vec![], &ident.span);
 
-                        Ok(Expression::new(ExpressionType::UnaryMinus, None, vec![child0]))
-                    },
-                    ExpressionTypeP::BitwiseNot => 
+                    // Arrays are used as a pointer to their first element, any other global is read through its address
+                    if g.is_array()
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-
-                        Ok(Expression::new(ExpressionType::UnaryMinus, None, vec![child0]))
-                    },
-                    ExpressionTypeP::LogicalNot => 
+                        Ok(address)
+                    }
+                    else
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                        Ok(Expression::new_with_span(ExpressionType::UnaryOperation(OpCode::Deref, -1), None, vec![address], &ident.span))
+                    }
+                }
+                else
+                {
+                    compiler_error_loc(ErrorCode::E0012, format!("Symbol {} not found in symbol table", ident.name), &Some(ident.span.clone()))
+                }
+            },
+            Expr::Unary {op, operand, ..} =>
+            {
+                let child0 = Expression::from_ast(operand, func)?;
 
-                        Ok(Expression::new(ExpressionType::LogicalNot, None, vec![child0]))
-                    },
-                    ExpressionTypeP::PreIncrement => 
+                let mode = match op
+                {
+                    UnaryOp::Minus => ExpressionType::UnaryMinus,
+                    UnaryOp::BitwiseNot => ExpressionType::BitwiseNot,
+                    UnaryOp::LogicalNot => ExpressionType::LogicalNot,
+                    UnaryOp::PreIncrement => ExpressionType::PreExpression(OpCode::Add),
+                    UnaryOp::PreDecrement => ExpressionType::PreExpression(OpCode::Sub),
+                    UnaryOp::PostIncrement => ExpressionType::PostExpression(OpCode::Add),
+                    UnaryOp::PostDecrement => ExpressionType::PostExpression(OpCode::Sub),
+                    UnaryOp::Dereference => ExpressionType::UnaryOperation(OpCode::Deref, -1),
+                    UnaryOp::Reference => match child0.global_address()
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-
-                        Ok(Expression::new(ExpressionType::PreExpression(OpCode::Add), None, vec![child0]))
+                        // The reference of a global is just its address
+                        Some(address) => return Ok(address),
+                        None => ExpressionType::UnaryOperation(OpCode::Ref, 1)
                     },
-                    ExpressionTypeP::PreDecrement => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                    // The Unary Plus Does basically Nothing
+                    UnaryOp::Plus => return Ok(child0)
+                };
 
-                        Ok(Expression::new(ExpressionType::PreExpression(OpCode::Sub), None, vec![child0]))
-                    },
-                    ExpressionTypeP::PostIncrement => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                // Incrementing and decrementing writes the result back
+                let child0 = match mode
+                {
+                    ExpressionType::PreExpression(_) | ExpressionType::PostExpression(_) => child0.into_left()?,
+                    _ => child0
+                };
 
-                        Ok(Expression::new(ExpressionType::PostExpression(OpCode::Add), None, vec![child0]))
-                    },
-                    ExpressionTypeP::PostDecrement => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                Ok(Expression::new(mode, None, vec![child0]))
+            },
+            Expr::Binary {op, lhs, rhs, ..} =>
+            {
+                let child0 = Expression::from_ast(lhs, func)?;
+                let child1 = Expression::from_ast(rhs, func)?;
 
-                        Ok(Expression::new(ExpressionType::PostExpression(OpCode::Sub), None, vec![child0]))
-                    },
-                    ExpressionTypeP::Add | ExpressionTypeP::Subtract | ExpressionTypeP::Multiply | ExpressionTypeP::Divide |
-                    ExpressionTypeP::Modulus | ExpressionTypeP::ShiftLeft | ExpressionTypeP::ShiftRight | ExpressionTypeP::LessThan |
-                    ExpressionTypeP::LessThanOrEqual | ExpressionTypeP::GreaterThan | ExpressionTypeP::GreaterThanOrEqual |
-                    ExpressionTypeP::Equal | ExpressionTypeP::NotEqual | ExpressionTypeP::BitwiseAnd | ExpressionTypeP::BitwiseOr |
-                    ExpressionTypeP::BitwiseXor => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
-
-                        Ok(Expression::new(ExpressionType::BinaryExpression(
-                            match expr_type
-                            {
-                                ExpressionTypeP::Add => OpCode::Add,
-                                ExpressionTypeP::Subtract => OpCode::Sub,
-                                ExpressionTypeP::Multiply => OpCode::Mul,
-                                ExpressionTypeP::Divide => OpCode::Div,
-                                ExpressionTypeP::Modulus => OpCode::Mod,
-                                ExpressionTypeP::ShiftLeft => OpCode::Shl,
-                                ExpressionTypeP::ShiftRight => OpCode::Shr,
-                                ExpressionTypeP::LessThan => OpCode::Clt,
-                                ExpressionTypeP::LessThanOrEqual => OpCode::Cle,
-                                ExpressionTypeP::GreaterThan => OpCode::Cgt,
-                                ExpressionTypeP::GreaterThanOrEqual => OpCode::Cge,
-                                ExpressionTypeP::Equal => OpCode::Ceq,
-                                ExpressionTypeP::NotEqual => OpCode::Cne,
-                                ExpressionTypeP::BitwiseAnd => OpCode::And,
-                                ExpressionTypeP::BitwiseOr => OpCode::Or,
-                                ExpressionTypeP::BitwiseXor => OpCode::Xor,
-                                _ => {unreachable!();}
-                            }
-                        ), None, vec![child0, child1]))
-                    },
-                    ExpressionTypeP::ArrayAccess =>
+                let mode = match Expression::binary_opcode(*op)
+                {
+                    Some(opcode) => ExpressionType::BinaryExpression(opcode),
+                    None if *op == BinaryOp::LogicalAnd => ExpressionType::LogicalAnd,
+                    None => ExpressionType::LogicalOr
+                };
+
+                Ok(Expression::new(mode, None, vec![child0, child1]))
+            },
+            Expr::Index {base, index, ..} =>
+            {
+                let child0 = Expression::from_ast(base, func)?;
+                let child1 = Expression::from_ast(index, func)?;
+
+                Ok(Expression::new(ExpressionType::ArrayAccess, None, vec![child0, child1]))
+            },
+            Expr::Slice {base, start, end, ..} =>
+            {
+                let child0 = Expression::from_ast(base, func)?;
+
+                // A range without a start begins at the first element
+                let child1 = match start
+                {
+                    None => Expression::new(ExpressionType::IntegerLiteral,
+                        Some(Value::Literal(Literal::new(0, DataType::new(NonPtrType::Unknown, 0, false)))), vec![]),
+                    Some(start) => Expression::from_ast(start, func)?
+                };
+
+                let mut new_children = vec![child0, child1];
+
+                // A range without an end ends at the length
+                if let Some(end) = end
+                {
+                    new_children.push(Expression::from_ast(end, func)?);
+                }
+
+                Ok(Expression::new(ExpressionType::Subslice(new_children.len() == 3), None, new_children))
+            },
+            Expr::BitRange {base, start, end, ..} =>
+            {
+                let child0 = Expression::from_ast(base, func)?;
+
+                Expression::bit_range_from_ast(child0, start, end, func)
+            },
+            Expr::Member {base, member, ..} =>
+            {
+                let child0 = Expression::from_ast(base, func)?;
+
+                if member.name != "len"
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Unknown member '{}', only the 'len' of a slice or array can be accessed", member.name), &Some(member.span.clone()))?;
+                }
+
+                Ok(Expression::new_with_span(ExpressionType::SliceLength, None, vec![child0], &member.span))
+            },
+            Expr::Assign {op: Some(op), target, value, ..} =>
+            {
+                let child0 = Expression::target_from_ast(target, func)?.into_left()?;
+                let child1 = Expression::from_ast(value, func)?;
+
+                let opcode = match Expression::binary_opcode(*op)
+                {
+                    Some(opcode) => opcode,
+                    None => compiler_error_loc(ErrorCode::E0036, format!("No compound assignment for '{:?}'", op), &child0.location())?
+                };
+
+                Ok(Expression::new(ExpressionType::AssignmentExpression(Some(opcode)), None, vec![child0, child1]))
+            },
+            Expr::Assign {op: None, target, value, ..} =>
+            {
+                // The integer a range of bits is taken from is written to, so it may be a dereferenced pointer
+                let child0 = match target.as_ref()
+                {
+                    Expr::BitRange {base, start, end, span} =>
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
+                        let child0 = Expression::target_from_ast(base, func)?;
 
-                        Ok(Expression::new(ExpressionType::ArrayAccess, None, vec![child0, child1]))
+                        let mut range = Expression::bit_range_from_ast(child0, start, end, func)?;
+                        range.pos = Some(span.clone());
+                        range
                     },
-                    ExpressionTypeP::AddAssign =>
+                    _ => Expression::target_from_ast(target, func)?
+                };
+                let child1 = Expression::from_ast(value, func)?;
+
+                // Assigning to a range of bits replaces just those bits of the integer
+                if let ExpressionType::BitRange(start, end) = child0.mode
+                {
+                    let target = child0.children[0].clone().into_left()?;
+
+                    return Ok(Expression::new(ExpressionType::BitRangeAssignment(start, end), None, vec![
+                        target,
+                        child1
+                    ]));
+                }
+
+                let child0 = child0.into_left()?;
+
+                Ok(Expression::new(ExpressionType::AssignmentExpression(None), None, vec![
+                    child0,
+                    child1
+                ]))
+            },
+            Expr::Comma {lhs, rhs, ..} =>
+            {
+                let child0 = Expression::from_ast(lhs, func)?;
+                let child1 = Expression::from_ast(rhs, func)?;
+
+                Ok(Expression::new(ExpressionType::Comma, None, vec![
+                    child0,
+                    child1
+                ]))
+            },
+            Expr::Cast {operand, datatype, ..} =>
+            {
+                let child0 = Expression::from_ast(operand, func)?;
+                let datatype = type_from_ast(datatype)?;
+
+                Ok(Expression::new(ExpressionType::Cast(datatype), None, vec![
+                    child0
+                ]))
+            },
+            Expr::Ternary {cond, then, otherwise, ..} =>
+            {
+                let child0 = Expression::from_ast(cond, func)?;
+                let child1 = Expression::from_ast(then, func)?;
+                let child2 = Expression::from_ast(otherwise, func)?;
+
+                Ok(Expression::new(ExpressionType::Ternary, None, vec![
+                    child0,
+                    child1,
+                    child2
+                ]))
+            },
+            Expr::Call {callee, type_args, args, ..} =>
+            {
+                let mut new_children = vec![];
+
+                for arg in args
+                {
+                    new_children.push(Expression::from_ast(arg, func)?);
+                }
+
+                // Generic functions are instantiated once the types of the arguments are known
+                if func.borrow().generics.contains_key(&callee.name)
+                {
+                    let mut types = vec![];
+
+                    for datatype in type_args.iter().flatten()
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
-
-                        Ok(Expression::new(ExpressionType::AssignmentExpression(Some(
-                            match expr_type
-                            {
-                                ExpressionTypeP::AddAssign => OpCode::Add,
-                                ExpressionTypeP::SubtractAssign => OpCode::Sub,
-                                ExpressionTypeP::MultiplyAssign => OpCode::Mul,
-                                ExpressionTypeP::DivideAssign => OpCode::Div,
-                                ExpressionTypeP::ModulusAssign => OpCode::Mod,
-                                ExpressionTypeP::ShiftLeftAssign => OpCode::Shl,
-                                ExpressionTypeP::ShiftRightAssign => OpCode::Shr,
-                                ExpressionTypeP::BitwiseAndAssign => OpCode::And,
-                                ExpressionTypeP::BitwiseOrAssign => OpCode::Or,
-                                ExpressionTypeP::BitwiseXorAssign => OpCode::Xor,
-                                _ => {unreachable!();}
-                            })
-                        ), None, vec![child0, child1]))
+                        types.push(type_from_ast(datatype)?);
                     }
-                    ExpressionTypeP::Assignment => 
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
 
-                        Ok(Expression::new(ExpressionType::AssignmentExpression(None), None, vec![
-                            child0,
-                            child1
-                        ]))
-                    },
-                    ExpressionTypeP::Comma =>
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
+                    return Ok(Expression::new_with_span(ExpressionType::GenericCall(types), Some(Value::Label(callee.name.clone())), new_children, &callee.span));
+                }
+                else if type_args.is_some()
+                {
+                    compiler_error_loc(ErrorCode::E0018, format!("Function {} is not generic, but was given type arguments", callee.name), &Some(callee.span.clone()))?;
+                }
 
-                        Ok(Expression::new(ExpressionType::Comma, None, vec![
-                            child0,
-                            child1
-                        ]))
-                    },
-                    ExpressionTypeP::Cast =>
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let datatype = type_from_parse_tree(children[1].clone())?;
+                // Builtins are lowered to instructions once the types of their arguments are known
+                if let Some(builtin) = Builtin::from_name(&callee.name)
+                {
+                    return Ok(Expression::new_with_span(ExpressionType::Builtin(builtin), None, new_children, &callee.span));
+                }
 
-                        Ok(Expression::new(ExpressionType::Cast(datatype), None, vec![
-                            child0
-                        ]))
-                    },
-                    ExpressionTypeP::Ternary =>
+                // Functions from other modules are called by the name they are linked under, anything else which isn't
+                // known is left to be linked in
+                let linked_name = func.borrow().functions.get(&callee.name).cloned();
+
+                let func_name = match linked_name
+                {
+                    Some(name) => name,
+                    None if callee.name.contains("::") =>
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
-                        let child2 = Expression::from_parse_tree_node(children[2].clone(), func)?;
-
-                        Ok(Expression::new(ExpressionType::Ternary, None, vec![
-                            child0,
-                            child1,
-                            child2
-                        ]))
+                        compiler_error_loc(ErrorCode::E0013, format!("Function {} not found", callee.name), &Some(callee.span.clone()))?
                     },
-                    ExpressionTypeP::Reference =>
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                    None => callee.name.clone()
+                };
 
-                        Ok(Expression::new(ExpressionType::UnaryOperation(OpCode::Ref, 1), None, vec![
-                            child0
-                        ]))
-                    },
-                    ExpressionTypeP::Dereference =>
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                Ok(Expression::new_with_span(ExpressionType::FunctionCall, Some(Value::Label(func_name)), new_children, &callee.span))
+            },
+            Expr::MethodCall {receiver, method, args, ..} =>
+            {
+                // The receiver is the first argument, the method is found once its type is known
+                let mut new_children = vec![Expression::from_ast(receiver, func)?];
 
-                        Ok(Expression::new(ExpressionType::UnaryOperation(OpCode::Deref, -1), None, vec![
-                            child0
-                        ]))
-                    },
-                    ExpressionTypeP::DereferenceLeft =>
-                    {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
+                for arg in args
+                {
+                    new_children.push(Expression::from_ast(arg, func)?);
+                }
 
-                        Ok(Expression::new(ExpressionType::DereferenceLeft, None, vec![
-                            child0
-                        ]))
-                    },
-                    ExpressionTypeP::FunctionCall =>
-                    {
-                        let func_name = match &children[0]
-                        {
-                            ParseTreeNode::Identifier(token) => token.data.clone(),
-                            _ => {panic!("")}
-                        };
+                Ok(Expression::new_with_span(ExpressionType::MethodCall(method.name.clone()), None, new_children, &method.span))
+            }
+        }
+    }
 
-                        let mut new_children = vec![];
+    /// Get the location of the expression, falling back on the location of its first child
+    pub fn location(&self) -> Option<Span>
+    {
+        match &self.pos
+        {
+            Some(pos) => Some(pos.clone()),
+            None => self.children.iter().find_map(|child| child.location())
+        }
+    }
 
-                        for child in &children[1..children.len()]
-                        {
-                            new_children.push(Expression::from_parse_tree_node(child.clone(), func)?);
-                        }
+    /// If the expression reads a global, get the expression for the address of that global
+    fn global_address(&self) -> Option<Expression>
+    {
+        if self.mode == ExpressionType::UnaryOperation(OpCode::Deref, -1)
+        {
+            if let ExpressionType::Global(_) = self.children[0].mode
+            {
+                return Some(self.children[0].clone());
+            }
+        }
 
-                        Ok(Expression::new(ExpressionType::FunctionCall, Some(Value::Label(func_name)), new_children))
-                    },
-                    ExpressionTypeP::LogicalAnd =>
+        None
+    }
+
+    /// Convert an expression which reads a global or an element into one which writes to it
+    fn into_left(self) -> Result<Self, Error>
+    {
+        if self.mode == ExpressionType::ArrayAccess
+        {
+            return Ok(Expression
+            {
+                mode: ExpressionType::ArrayAccessLeft,
+                ..self
+            });
+        }
+        else if self.mode == ExpressionType::SliceLength
+        {
+            compiler_error_loc(ErrorCode::E0023, String::from("Cannot assign to the length of a slice, take a range of it instead"), &self.location())?;
+        }
+        else if let ExpressionType::BitRange(start, end) = self.mode
+        {
+            compiler_error_loc(ErrorCode::E0029, format!("Bits {}..{} can only be assigned to with '='", start, end), &self.location())?;
+        }
+
+        Ok(match self.global_address()
+        {
+            Some(address) => Expression
+            {
+                mode: ExpressionType::DereferenceLeft,
+                value: None,
+                children: vec![address],
+                pos: self.pos
+            },
+            None => self
+        })
+    }
+
+    /// Does the expression refer to memory through a reference
+    pub fn is_reference(&self) -> bool
+    {
+        self.mode == ExpressionType::DereferenceLeft || self.mode == ExpressionType::ArrayAccessLeft
+    }
+
+    /// Push an argument to a function call, slices are followed by their length. Constants are given the type of the
+    /// parameter they are passed as and narrower integers are widened to it, when the function is known
+    fn push_argument(value: Value, parameter: Option<DataType>, func: &RefCell<&mut Function>, pos: &Option<Span>)
+    {
+        let value = match parameter
+        {
+            Some(datatype) if (has_unknown_type(&value) || get_value_type(&value).unwrap().widens_to(&datatype)) && !datatype.is_slice =>
+            {
+                Expression::implicit_conversion(value, datatype, func, pos, true)
+            },
+            _ => value
+        };
+
+        let is_slice = get_value_type(&value).unwrap().is_slice;
+
+        func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![value.clone()]));
+
+        if is_slice
+        {
+            func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![slice_length(&value)]));
+        }
+    }
+
+    /// Get the register holding the result of a call, of the return type of the function if it is known (functions
+    /// which aren't known are left to be linked in, and return an i32 as in C). The call is recorded, to find
+    /// functions which are never called
+    fn call_result(linked_name: &str, func: &RefCell<&mut Function>) -> Value
+    {
+        func.borrow_mut().calls.insert(String::from(linked_name));
+
+
+        let datatype = match func.borrow().signatures.get(linked_name)
+        {
+            Some(signature) => signature.return_type,
+            None => DataType::new(NonPtrType::I32, 0, false)
+        };
+
+        Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype))
+    }
+
+    /// Get the types of the parameters of a function, by its linked name (none are known for functions which are
+    /// linked in)
+    fn parameter_types(linked_name: &str, func: &RefCell<&mut Function>) -> Vec<DataType>
+    {
+        func.borrow().signatures.get(linked_name).map(|signature| signature.arguments.clone()).unwrap_or_default()
+    }
+
+    /// End the code after a call to a function which never returns, so it is known that nothing follows the call
+    fn end_if_noreturn(linked_name: &str, func: &RefCell<&mut Function>)
+    {
+        if func.borrow().signatures.get(linked_name).is_some_and(|signature| signature.noreturn)
+        {
+            func.borrow_mut().add_instruction(Instruction::new(OpCode::Unreachable, vec![]));
+        }
+    }
+
+    /// Ensure a value is not a slice, for operations which only apply to integers and pointers
+    fn check_not_slice(value: &Value, operation: &str, pos: &Option<Span>) -> Result<(), Error>
+    {
+        let datatype = correct_type_references(get_value_type(value).unwrap());
+
+        if datatype.is_slice
+        {
+            compiler_error_loc(ErrorCode::E0022, format!("Cannot {} the slice '{}', index it or take a range of it instead", operation, datatype), pos)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load the value behind a reference into a new register
+    pub fn load_reference(reference: &Value, func: &RefCell<&mut Function>) -> Value
+    {
+        let datatype = correct_type_references(get_value_type(reference).unwrap());
+        let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+        func.borrow_mut().add_instruction(Instruction::new(OpCode::Deref, vec![
+            value.clone(),
+            reference.clone()
+            ]));
+
+        value
+    }
+
+    /// Get the literal used to increment or decrement a value (pointers are moved by one element)
+    fn increment_literal(datatype: &DataType) -> Value
+    {
+        if datatype.num_ptr > 0
+        {
+            Value::Literal(Literal::new(1, DataType::new(NonPtrType::I64, 0, false)))
+        }
+        else
+        {
+            Value::Literal(Literal::new(1, *datatype))
+        }
+    }
+
+    /// Get the resulting type of an operation involving pointers, giving integer offsets an explicit type (None if
+    /// neither operand is a pointer)
+    fn pointer_arithmetic(opcode: OpCode, val0: &mut Value, val1: &mut Value, difference: DataType, pos: &Option<Span>) -> Result<Option<DataType>, Error>
+    {
+        let type0 = correct_type_references(get_value_type(val0).unwrap());
+        let type1 = correct_type_references(get_value_type(val1).unwrap());
+
+        if type0.is_slice || type1.is_slice
+        {
+            compiler_error_loc(ErrorCode::E0022, format!("Invalid operands '{}' and '{}', slices can only be indexed or have a range taken of them", type0, type1), pos)?;
+        }
+
+        if type0.num_ptr == 0 && type1.num_ptr == 0
+        {
+            return Ok(None);
+        }
+
+        match opcode
+        {
+            // Comparisons between pointers, or between a pointer and a constant address
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cgt | OpCode::Cle | OpCode::Cge =>
+            {
+                if type0.num_ptr > 0 && type1.num_ptr > 0
+                {
+                    if type0 != type1
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
+                        compiler_error_loc(ErrorCode::E0022, format!("Mismatched pointer types, cannot compare '{}' with '{}' without a cast", type0, type1), pos)?;
+                    }
+                }
+                else if let Value::Literal(_) = val1
+                {
+                    *val1 = attempt_mutate_type(val1.clone(), type0);
+                }
+                else if let Value::Literal(_) = val0
+                {
+                    *val0 = attempt_mutate_type(val0.clone(), type1);
+                }
+                else
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot compare '{}' with '{}' without a cast", type0, type1), pos)?;
+                }
 
-                        Ok(Expression::new(ExpressionType::LogicalAnd, None, vec![
-                            child0,
-                            child1
-                        ]))
-                    },
-                    ExpressionTypeP::LogicalOr =>
+                Ok(Some(DataType::new(NonPtrType::U8, 0, false)))
+            },
+            OpCode::Add | OpCode::Sub if type0.num_ptr > 0 =>
+            {
+                if type0.dereferenced().raw_type == NonPtrType::Void && type0.num_ptr == 1
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot perform arithmetic on '{}'", type0), pos)?;
+                }
+
+                // The difference between two pointers is the number of elements between them
+                if type1.num_ptr > 0
+                {
+                    if opcode == OpCode::Add
                     {
-                        let child0 = Expression::from_parse_tree_node(children[0].clone(), func)?;
-                        let child1 = Expression::from_parse_tree_node(children[1].clone(), func)?;
+                        compiler_error_loc(ErrorCode::E0022, format!("Cannot add '{}' to '{}'", type1, type0), pos)?;
+                    }
+                    else if type0 != type1
+                    {
+                        compiler_error_loc(ErrorCode::E0022, format!("Mismatched pointer types, cannot subtract '{}' from '{}' without a cast", type1, type0), pos)?;
+                    }
 
-                        Ok(Expression::new(ExpressionType::LogicalOr, None, vec![
-                            child0,
-                            child1
-                        ]))
-                    },
-                    default => {panic!("{:?}", default);}
+                    return Ok(Some(difference));
                 }
+
+                *val1 = attempt_mutate_type(val1.clone(), DataType::new(NonPtrType::I64, 0, false));
+
+                Ok(Some(type0))
             },
-            default =>
+            _ =>
             {
-                expected_got_error("an expression", default.clone())
+                compiler_error_loc(ErrorCode::E0022, format!("Invalid operands '{}' and '{}' for pointer arithmetic", type0, type1), pos)
             }
         }
     }
 
-    /// Render an expression
-    pub fn render(&mut self, func: &RefCell<&mut Function>) -> Result<(), Error>
+    /// Get the result of comparing an integer with a constant out of the range of its type, which is the same whatever
+    /// the value of the integer is (along with the type of the integer)
+    fn constant_comparison(opcode: OpCode, val0: &Value, val1: &Value) -> Option<(bool, DataType)>
+    {
+        // The comparison is looked at with the constant second
+        let (opcode, value, constant) = match (val0, val1)
+        {
+            (value, Value::Literal(lit)) if !has_unknown_type(value) && has_unknown_type(val1) => (opcode, value, lit.value),
+            (Value::Literal(lit), value) if has_unknown_type(val0) && !has_unknown_type(value) =>
+            {
+                let swapped = match opcode
+                {
+                    OpCode::Clt => OpCode::Cgt,
+                    OpCode::Cgt => OpCode::Clt,
+                    OpCode::Cle => OpCode::Cge,
+                    OpCode::Cge => OpCode::Cle,
+                    other => other
+                };
+
+                (swapped, value, lit.value)
+            },
+            _ => return None
+        };
+
+        let datatype = correct_type_references(get_value_type(value).unwrap());
+        let (min, max) = datatype.value_range()?;
+
+        let result = match opcode
+        {
+            OpCode::Ceq if constant < min || constant > max => false,
+            OpCode::Cne if constant < min || constant > max => true,
+            OpCode::Clt if max < constant || min >= constant => max < constant,
+            OpCode::Cle if max <= constant || min > constant => max <= constant,
+            OpCode::Cgt if min > constant || max <= constant => min > constant,
+            OpCode::Cge if min >= constant || max < constant => min >= constant,
+            _ => return None
+        };
+
+        Some((result, datatype))
+    }
+
+    /// Get the type of an operand of an arithmetic operator after it is promoted, literals have the type they have on
+    /// their own (values worked out from literals alone are left with an unknown type)
+    fn operand_type(value: &Value) -> DataType
+    {
+        match value
+        {
+            Value::Literal(lit) if lit.datatype.raw_type == NonPtrType::Unknown => DataType::literal(lit.value),
+            _ => correct_type_references(get_value_type(value).unwrap()).promoted()
+        }
+    }
+
+    /// Convert a value to the given type, sign or zero extending integers as needed (narrowing conversions truncate
+    /// the value, with a warning if requested)
+    pub fn implicit_conversion(value: Value, target: DataType, func: &RefCell<&mut Function>, pos: &Option<Span>, warn: bool) -> Value
+    {
+        let target = correct_type_references(target);
+        let source = correct_type_references(get_value_type(&value).unwrap());
+
+        if source.raw_type == NonPtrType::Unknown
+        {
+            if let Value::Literal(lit) = &value
+            {
+                if warn && !target.can_represent(lit.value)
+                {
+                    func.borrow_mut().warnings.push(compiler_warning_loc(Lint::OverflowingLiteral, format!("Constant {} does not fit in '{}'", lit.value, target), pos));
+                }
+            }
+            // A value worked out from literals alone is moved into the new type, which it is then inferred to have
+            else if target.raw_type != NonPtrType::Unknown
+            {
+                let result = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), target));
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                    result.clone(),
+                    value
+                    ]));
+
+                return result;
+            }
+
+            return attempt_mutate_type(value, target);
+        }
+
+        let (source_bits, target_bits) = match (source.bit_width(), target.bit_width())
+        {
+            (Some(source_bits), Some(target_bits)) if source != target => (source_bits, target_bits),
+            _ => return value
+        };
+
+        // Constants which fit in the new type can simply be given the new type
+        if let Value::Literal(lit) = &value
+        {
+            if target.can_represent(lit.value)
+            {
+                return Value::Literal(Literal::new(lit.value, target));
+            }
+        }
+
+        if warn && target_bits < source_bits
+        {
+            func.borrow_mut().warnings.push(compiler_warning_loc(Lint::ImplicitNarrowing, format!("Implicit conversion from '{}' to '{}' may change the value", source, target), pos));
+        }
+
+        // Widening is done by extending according to the signedness of the source
+        let opcode = if target_bits <= source_bits {OpCode::Cast} else if source.is_signed() {OpCode::Sext} else {OpCode::Zext};
+
+        let result = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), target));
+
+        func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+            result.clone(),
+            value
+            ]));
+
+        result
+    }
+
+    /// Give a symbol declared with let the type of its value, which is only known once the value has been rendered
+    fn resolve_symbol(&mut self, func: &RefCell<&mut Function>)
+    {
+        let declared = match &self.value
+        {
+            Some(Value::Symbol(symbol)) if self.mode == ExpressionType::Identifier && symbol.datatype.raw_type == NonPtrType::Unknown =>
+            {
+                func.borrow().symbol_table.get(&symbol.title).cloned()
+            },
+            _ => None
+        };
+
+        if let Some(symbol) = declared
+        {
+            self.value = Some(Value::Symbol(symbol));
+        }
+    }
+
+    /// Render an expression, marking the instructions it adds with its span
+    pub fn render(&mut self, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let outer = func.borrow().current_span.clone();
+
+        if self.pos.is_some()
+        {
+            func.borrow_mut().current_span = self.pos.clone();
+        }
+
+        let result = self.render_instructions(func);
+        func.borrow_mut().current_span = outer;
+
+        result
+    }
+
+    /// Render the instructions of an expression
+    fn render_instructions(&mut self, func: &RefCell<&mut Function>) -> Result<(), Error>
     {
+        // The types of the operands are checked before they are rendered
+        self.resolve_symbol(func);
+
+        for child in &mut self.children
+        {
+            child.resolve_symbol(func);
+        }
+
         match self.mode.clone()
         {
             ExpressionType::IntegerLiteral | ExpressionType::Identifier=> {},
+            ExpressionType::Global(datatype) =>
+            {
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Global, vec![
+                    value.clone(),
+                    self.value.clone().unwrap()
+                    ]));
+
+                self.value = Some(value);
+            },
             ExpressionType::UnaryMinus =>
             {
                 self.children[0].render(func)?;
                 let val0 = self.children[0].value(func)?;
 
-                let datatype = get_value_type(&val0).unwrap();
+                // A literal stays a literal, so it can still take the type it is used as
+                if let Value::Literal(lit) = &val0
+                {
+                    if lit.datatype.raw_type == NonPtrType::Unknown
+                    {
+                        let value = lit.value.wrapping_neg();
+                        self.value = Some(Value::Literal(Literal::new(value, lit.datatype)));
+
+                        return Ok(());
+                    }
+                }
+
+                // The operand is promoted first
+                let datatype = correct_type_references(get_value_type(&val0).unwrap()).promoted();
+                let val0 = Expression::implicit_conversion(val0, datatype, func, &self.location(), false);
 
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(datatype.clone())));
                 self.value = Some(value.clone());
 
-                func.borrow_mut().add_instruction(Instruction::new(OpCode::Sub, vec![
+                func.borrow_mut().add_arithmetic_instruction(OpCode::Sub, vec![
                     value,
                     Value::Literal(Literal::new(0, datatype)),
                     val0,
-                    ]));
+                    ], self.location())?;
             },
             ExpressionType::BitwiseNot =>
             {
                 self.children[0].render(func)?;
                 let val0 = self.children[0].value(func)?;
 
-                let datatype = get_value_type(&val0).unwrap();
+                // A literal stays a literal, so it can still take the type it is used as
+                if let Value::Literal(lit) = &val0
+                {
+                    if lit.datatype.raw_type == NonPtrType::Unknown
+                    {
+                        let value = !lit.value;
+                        self.value = Some(Value::Literal(Literal::new(value, lit.datatype)));
+
+                        return Ok(());
+                    }
+                }
+
+                // The operand is promoted first
+                let datatype = correct_type_references(get_value_type(&val0).unwrap()).promoted();
+                let val0 = Expression::implicit_conversion(val0, datatype, func, &self.location(), false);
 
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(datatype.clone())));
                 self.value = Some(value.clone());
@@ -361,24 +908,40 @@ impl Expression
                 let mut val0 = self.children[0].value(func)?;
                 let mut val1 = self.children[1].value(func)?;
 
-                let datatype = if !has_unknown_type(&val0) && has_unknown_type(&val1) // First is known
+                // Pointers are always the first operand of an addition
+                if opcode == OpCode::Add && get_value_type(&val1).unwrap().num_ptr > 0 && get_value_type(&val0).unwrap().num_ptr == 0
                 {
-                    get_value_type(&val0).unwrap()
+                    std::mem::swap(&mut val0, &mut val1);
                 }
-                else if has_unknown_type(&val0) && !has_unknown_type(&val1) // Second is known
+
+                if let Some((result, datatype)) = Expression::constant_comparison(opcode, &val0, &val1)
                 {
-                    get_value_type(&val1).unwrap()
+                    let (min, max) = datatype.value_range().unwrap();
+
+                    func.borrow_mut().warnings.push(compiler_warning_loc(Lint::ConstantComparison, format!("Comparison is always {}, '{}' only holds values from {} to {}",
+                                                                                                          result, datatype, min, max), &self.location()));
                 }
-                else if !has_unknown_type(&val0) && !has_unknown_type(&val1) // Both Known
+
+                let difference = func.borrow().pointer_difference_type();
+
+                let datatype = if let Some(pointer_type) = Expression::pointer_arithmetic(opcode, &mut val0, &mut val1, difference, &self.location())?
                 {
-                    if get_value_type(&val0).unwrap() == get_value_type(&val1).unwrap()
-                    {
-                        get_value_type(&val0).unwrap()
-                    }
-                    else
-                    {
-                        DataType::new(NonPtrType::Unknown, 0, false)
-                    }
+                    pointer_type
+                }
+                // Operands are promoted, then shifts keep the type of the value being shifted and anything else uses the
+                // common type of both (values worked out from literals alone take the type of the other operand)
+                else if !has_unknown_type(&val0) || !has_unknown_type(&val1)
+                {
+                    let type0 = Expression::operand_type(&val0);
+                    let type1 = Expression::operand_type(&val1);
+
+                    let is_shift = opcode == OpCode::Shl || opcode == OpCode::Shr;
+                    let datatype = if is_shift && type0.raw_type != NonPtrType::Unknown {type0} else {type0.common_type(&type1)};
+
+                    val0 = Expression::implicit_conversion(val0, datatype, func, &self.location(), false);
+                    val1 = Expression::implicit_conversion(val1, datatype, func, &self.location(), false);
+
+                    datatype
                 }
                 else
                 {
@@ -391,24 +954,17 @@ impl Expression
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(datatype)));
                 self.value = Some(value.clone());
 
-                func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+                func.borrow_mut().add_arithmetic_instruction(opcode, vec![
                     value,
                     val0,
                     val1,
-                    ]));
+                    ], self.location())?;
             },
             ExpressionType::ArrayAccess =>
             {
-                self.children[0].render(func)?;
-                self.children[1].render(func)?;
-
-                let val0 = self.children[0].value(func)?;
-                let mut val1 = self.children[1].value(func)?;
-
-                val1 = attempt_mutate_type(val1, DataType::new(NonPtrType::U64, 0, false));
+                let (val0, val1, datatype) = self.render_element(func)?;
 
-                let mut dt = get_value_type(&val0).unwrap();
-                dt.num_ptr -= 1;
+                let dt = datatype.dereferenced();
                 
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(dt)));
                 self.value = Some(value.clone());
@@ -419,6 +975,82 @@ impl Expression
                     val1,
                     ]));
             },
+            ExpressionType::ArrayAccessLeft =>
+            {
+                let (val0, val1, mut datatype) = self.render_element(func)?;
+
+                // Program memory is read only
+                if datatype.is_progmem_ptr()
+                {
+                    compiler_error_loc(ErrorCode::E0024, format!("Cannot write through '{}', program memory is read only", datatype), &self.location())?;
+                }
+
+                // The address of the element is found, which is then referred to like a dereference
+                datatype.is_slice = false;
+
+                let address = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Add, vec![
+                    address.clone(),
+                    val0,
+                    val1
+                    ]));
+
+                datatype.is_ref = true;
+                datatype.num_ptr -= 1;
+
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype));
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Cast, vec![
+                    value.clone(),
+                    address
+                    ]));
+
+                self.value = Some(value);
+            },
+            ExpressionType::Subslice(has_end) =>
+            {
+                self.render_subslice(has_end, func)?;
+            },
+            ExpressionType::SliceLength =>
+            {
+                let (_, length) = self.children[0].render_slice(func)?;
+
+                self.value = Some(length);
+            },
+            ExpressionType::BitRange(start, end) =>
+            {
+                self.render_bit_range(start, end, func)?;
+            },
+            ExpressionType::BitRangeAssignment(start, end) =>
+            {
+                self.render_bit_range_assignment(start, end, func)?;
+            },
+            ExpressionType::Builtin(builtin) =>
+            {
+                self.render_builtin(builtin, func)?;
+            },
+            // Assigning a slice copies both its pointer and its length
+            ExpressionType::AssignmentExpression(None) if self.children[0].is_slice() =>
+            {
+                self.children[0].render(func)?;
+                let target = self.children[0].value(func)?;
+
+                let (ptr, length) = self.children[1].render_slice(func)?;
+
+                Expression::check_slice_type(get_value_type(&target).unwrap(), &ptr, &self.location())?;
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                    target.clone(),
+                    ptr
+                    ]));
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                    slice_length(&target),
+                    length
+                    ]));
+
+                self.value = Some(target);
+            },
             ExpressionType::AssignmentExpression(operation) =>
             {
                 self.children[0].render(func)?;
@@ -427,7 +1059,39 @@ impl Expression
                 let mut val0 = self.children[0].value(func)?;
                 let mut val1 = self.children[1].value(func)?;
 
-                let datatype = if !has_unknown_type(&val0) && has_unknown_type(&val1) // First is known
+                let difference = func.borrow().pointer_difference_type();
+
+                // Compound assignment to a pointer moves it by a number of elements
+                let pointer_type = match operation
+                {
+                    Some(opcode) => Expression::pointer_arithmetic(opcode, &mut val0, &mut val1, difference, &self.location())?,
+                    None => None
+                };
+
+                if let Some(result_type) = pointer_type
+                {
+                    let target_type = correct_type_references(get_value_type(&val0).unwrap());
+
+                    if result_type != target_type
+                    {
+                        compiler_error_loc(ErrorCode::E0021, format!("Cannot assign '{}' to '{}'", result_type, target_type), &self.location())?;
+                    }
+                }
+
+                // The value is converted to the type of the target (the count of a shift may be narrowed freely)
+                if pointer_type.is_none() && !has_unknown_type(&val0) && !has_unknown_type(&val1)
+                {
+                    let target_type = get_value_type(&val0).unwrap();
+                    let is_shift = operation == Some(OpCode::Shl) || operation == Some(OpCode::Shr);
+
+                    val1 = Expression::implicit_conversion(val1, target_type, func, &self.location(), !is_shift);
+                }
+
+                let datatype = if let Some(pointer_type) = pointer_type
+                {
+                    pointer_type
+                }
+                else if !has_unknown_type(&val0) && has_unknown_type(&val1) // First is known
                 {
                     get_value_type(&val0).unwrap()
                 }
@@ -437,7 +1101,7 @@ impl Expression
                 }
                 else if !has_unknown_type(&val0) && !has_unknown_type(&val1) // Both Known
                 {
-                    if get_value_type(&val0).unwrap() == get_value_type(&val1).unwrap()
+                    if correct_type_references(get_value_type(&val0).unwrap()) == correct_type_references(get_value_type(&val1).unwrap())
                     {
                         get_value_type(&val0).unwrap()
                     }
@@ -454,18 +1118,41 @@ impl Expression
                 val0 = attempt_mutate_type(val0, datatype.clone());
                 val1 = attempt_mutate_type(val1, datatype.clone());
 
+                check_address_space(&get_value_type(&val0).unwrap(), &get_value_type(&val1).unwrap(), &self.location())?;
+
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(datatype)));
                 self.value = Some(value.clone());
 
                 match operation
                 {
+                    // Operations on memory behind a reference must read, modify and then write back the value
+                    Some(opcode) if self.children[0].is_reference() =>
+                    {
+                        let current = Expression::load_reference(&val0, func);
+
+                        func.borrow_mut().add_arithmetic_instruction(opcode, vec![
+                            current.clone(),
+                            current.clone(),
+                            val1,
+                            ], self.location())?;
+
+                        func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                            val0,
+                            current.clone()
+                            ]));
+
+                        func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                            value,
+                            current
+                            ]));
+                    },
                     Some(opcode) =>
                     {
-                        func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+                        func.borrow_mut().add_arithmetic_instruction(opcode, vec![
                             val0.clone(),
                             val0.clone(),
                             val1,
-                            ]));
+                            ], self.location())?;
                         
                         func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
                             value.clone(),
@@ -487,6 +1174,26 @@ impl Expression
                 }
 
                 
+            },
+            ExpressionType::PreExpression(opcode) if self.children[0].is_reference() =>
+            {
+                self.children[0].render(func)?;
+
+                let val0 = self.children[0].value(func)?;
+                let current = Expression::load_reference(&val0, func);
+
+                func.borrow_mut().add_arithmetic_instruction(opcode, vec![
+                    current.clone(),
+                    current.clone(),
+                    Expression::increment_literal(&get_value_type(&current).unwrap()),
+                    ], self.location())?;
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                    val0,
+                    current.clone()
+                    ]));
+
+                self.value = Some(current)
             },
             ExpressionType::PreExpression(opcode) =>
             {
@@ -494,20 +1201,45 @@ impl Expression
 
                 let val0 = self.children[0].value(func)?;
 
-                func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+                Expression::check_not_slice(&val0, "increment or decrement", &self.location())?;
+
+                func.borrow_mut().add_arithmetic_instruction(opcode, vec![
                     val0.clone(),
                     val0.clone(),
-                    Value::Literal(Literal::new(1, get_value_type(&val0).unwrap())),
-                    ]));
+                    Expression::increment_literal(&get_value_type(&val0).unwrap()),
+                    ], self.location())?;
 
                 self.value = Some(val0)
             },
+            ExpressionType::PostExpression(opcode) if self.children[0].is_reference() =>
+            {
+                self.children[0].render(func)?;
+
+                let val0 = self.children[0].value(func)?;
+                let previous = Expression::load_reference(&val0, func);
+                let next = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), get_value_type(&previous).unwrap()));
+
+                func.borrow_mut().add_arithmetic_instruction(opcode, vec![
+                    next.clone(),
+                    previous.clone(),
+                    Expression::increment_literal(&get_value_type(&previous).unwrap()),
+                    ], self.location())?;
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
+                    val0,
+                    next
+                    ]));
+
+                self.value = Some(previous)
+            },
             ExpressionType::PostExpression(opcode) =>
             {
                 self.children[0].render(func)?;
 
                 let val0 = self.children[0].value(func)?;
 
+                Expression::check_not_slice(&val0, "increment or decrement", &self.location())?;
+
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), correct_type_references(get_value_type(&val0).unwrap())));
 
                 func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
@@ -515,11 +1247,11 @@ impl Expression
                     val0.clone(),
                     ]));
 
-                func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
+                func.borrow_mut().add_arithmetic_instruction(opcode, vec![
                     val0.clone(),
                     val0.clone(),
-                    Value::Literal(Literal::new(1, get_value_type(&val0).unwrap())),
-                    ]));
+                    Expression::increment_literal(&get_value_type(&val0).unwrap()),
+                    ], self.location())?;
 
                 self.value = Some(value)
             },
@@ -537,6 +1269,18 @@ impl Expression
 
                 let corrected_type = correct_type_references(datatype.clone());
 
+                // The length of a slice can't be made up by a cast
+                if corrected_type.is_slice
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Cannot cast to the slice '{}', take a range of a pointer instead", corrected_type), &self.location())?;
+                }
+
+                // Pointers into program memory can't be converted into pointers into RAM or vice versa
+                if let Some(source_type) = get_value_type(&val0)
+                {
+                    check_address_space(&corrected_type, &source_type, &self.location())?;
+                }
+
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), corrected_type.clone()));
 
                 val0 = attempt_mutate_type(val0, corrected_type);
@@ -571,6 +1315,8 @@ impl Expression
 
                 self.children[1].render(func)?;
 
+                Expression::check_not_slice(&self.children[1].value(func)?, "choose between", &self.location())?;
+
                 func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
                     value.clone(),
                     self.children[1].value(func)?,
@@ -584,6 +1330,8 @@ impl Expression
 
                 self.children[2].render(func)?;
 
+                Expression::check_not_slice(&self.children[2].value(func)?, "choose between", &self.location())?;
+
                 func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![
                     value,
                     self.children[2].value(func)?,
@@ -598,7 +1346,8 @@ impl Expression
                 let clause = func.borrow_mut().get_label();
                 let exit = func.borrow_mut().get_label();
 
-                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::Unknown, 0, false)));
+                // The result is 0 or 1 as an i32 (as in C)
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::I32, 0, false)));
                 self.value = Some(value.clone());
 
                 self.children[0].render(func)?;
@@ -638,9 +1387,17 @@ impl Expression
 
                 let val0 = self.children[0].value(func)?;
 
+                Expression::check_not_slice(&val0, if opcode == OpCode::Deref {"dereference"} else {"take the address of"}, &self.location())?;
+
                 let mut datatype = get_value_type(&val0).unwrap();
                 datatype.num_ptr = (datatype.num_ptr as isize + delta) as usize;
 
+                // Values loaded out of program memory are ordinary values
+                if datatype.num_ptr == 0
+                {
+                    datatype.is_progmem = false;
+                }
+
                 let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), datatype.clone()));
 
                 func.borrow_mut().add_instruction(Instruction::new(opcode, vec![
@@ -656,6 +1413,13 @@ impl Expression
                 let mut val0 = self.children[0].value(func)?;
 
                 let mut datatype = get_value_type(&val0).unwrap().clone();
+
+                // Program memory is read only
+                if datatype.is_progmem_ptr()
+                {
+                    compiler_error_loc(ErrorCode::E0024, format!("Cannot write through '{}', program memory is read only", datatype), &self.location())?;
+                }
+
                 datatype.is_ref = true;
                 datatype.num_ptr -= 1;
 
@@ -672,16 +1436,24 @@ impl Expression
             },
             ExpressionType::FunctionCall =>
             {
-                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::Unknown, 0, false)));
+                let value = match &self.value
+                {
+                    Some(Value::Label(name)) => Expression::call_result(name, func),
+                    _ => unreachable!()
+                };
+
+                let parameters = match &self.value
+                {
+                    Some(Value::Label(name)) => Expression::parameter_types(name, func),
+                    _ => unreachable!()
+                };
 
-                let l = self.children.len();
-                for arg in &mut self.children[0..l]
+                for (index, arg) in self.children.iter_mut().enumerate()
                 {
                     arg.render(func)?;
 
                     // Push an argument
-                    func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![
-                        arg.value(func)?]));
+                    Expression::push_argument(arg.value(func)?, parameters.get(index).copied(), func, &arg.location());
                 }
 
                 // Call the function
@@ -689,16 +1461,157 @@ impl Expression
                     value.clone(),
                     self.value.clone().unwrap()]));
 
+                if let Some(Value::Label(name)) = &self.value
+                {
+                    Expression::end_if_noreturn(name, func);
+                }
+
                 self.value = Some(value.clone());
 
             },
+            ExpressionType::MethodCall(name) =>
+            {
+                self.children[0].render(func)?;
+                let receiver = self.children[0].value(func)?;
+
+                // Methods are found by the type of the receiver
+                let datatype = correct_type_references(get_value_type(&receiver).unwrap());
+                let linked_name = func.borrow().functions.get(&method_key(&datatype, &name)).cloned();
+
+                let linked_name = match linked_name
+                {
+                    Some(linked_name) => linked_name,
+                    None => compiler_error_loc(ErrorCode::E0014, format!("No method '{}' found for '{}'", name, datatype), &self.pos)?
+                };
+
+                let value = Expression::call_result(&linked_name, func);
+
+                let parameters = Expression::parameter_types(&linked_name, func);
+
+                // The receiver is passed as the first argument
+                Expression::push_argument(receiver, parameters.first().copied(), func, &self.children[0].location());
+
+                for (index, arg) in self.children.iter_mut().enumerate().skip(1)
+                {
+                    arg.render(func)?;
+
+                    Expression::push_argument(arg.value(func)?, parameters.get(index).copied(), func, &arg.location());
+                }
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Call, vec![
+                    value.clone(),
+                    Value::Label(linked_name.clone())]));
+
+                Expression::end_if_noreturn(&linked_name, func);
+
+                self.value = Some(value);
+            },
+            ExpressionType::GenericCall(explicit_types) =>
+            {
+                let name = match &self.value
+                {
+                    Some(Value::Label(name)) => name.clone(),
+                    _ => unreachable!()
+                };
+
+                let generic = func.borrow().generics.get(&name).cloned().unwrap();
+
+                let mut values = vec![];
+                let mut lengths = vec![];
+
+                for arg in &mut self.children
+                {
+                    lengths.push(arg.array_length(func));
+
+                    arg.render(func)?;
+                    values.push(arg.value(func)?);
+                }
+
+                // Type arguments which aren't given are inferred from the arguments
+                let types = if explicit_types.is_empty()
+                {
+                    let argument_types: Vec<Option<DataType>> = values.iter()
+                        .map(|value| get_value_type(value).map(correct_type_references).filter(|datatype| datatype.raw_type != NonPtrType::Unknown))
+                        .collect();
+
+                    generic.infer_types(&argument_types, &self.pos)?
+                }
+                else
+                {
+                    explicit_types.clone()
+                };
+
+                if types.len() != generic.parameters.len()
+                {
+                    compiler_error_loc(ErrorCode::E0018, format!("Function {} expects {} type arguments, got {}", name, generic.parameters.len(), types.len()), &self.pos)?;
+                }
+
+                let instantiation = Instantiation
+                {
+                    generic,
+                    types,
+                    pos: self.pos.clone()
+                };
+
+                // The signature of the instantiation is known, so the arguments are converted to the types it expects
+                let declaration = instantiation.declaration();
+                let signature = Signature::from_declaration(&declaration)?;
+                let arguments = arguments_from_ast(&declaration.params)?;
+
+                if arguments.len() != values.len()
+                {
+                    compiler_error_loc(ErrorCode::E0017, format!("Function {} expects {} arguments, got {}", name, arguments.len(), values.len()), &self.pos)?;
+                }
+
+                for ((value, length), (_, datatype)) in values.into_iter().zip(lengths).zip(arguments)
+                {
+                    // Slices are passed along with their length, global arrays are passed as a slice of all of their elements
+                    if datatype.is_slice
+                    {
+                        let length = match length
+                        {
+                            _ if get_value_type(&value).unwrap().is_slice => slice_length(&value),
+                            Some(length) => length,
+                            None => compiler_error_loc(ErrorCode::E0021, format!("Expected a slice, got '{}'", get_value_type(&value).unwrap()), &self.pos)?
+                        };
+
+                        Expression::check_slice_type(datatype, &value, &self.pos)?;
+
+                        func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![value]));
+                        func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![length]));
+
+                        continue;
+                    }
+
+                    let value = Expression::implicit_conversion(value, datatype, func, &self.pos, true);
+
+                    func.borrow_mut().add_instruction(Instruction::new(OpCode::Push, vec![value]));
+                }
+
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), signature.return_type));
+
+                func.borrow_mut().add_instruction(Instruction::new(OpCode::Call, vec![
+                    value.clone(),
+                    Value::Label(instantiation.name())]));
+
+                // Every instantiation of a generic function has the attributes of the generic function
+                if signature.noreturn
+                {
+                    func.borrow_mut().add_instruction(Instruction::new(OpCode::Unreachable, vec![]));
+                }
+
+                func.borrow_mut().instantiations.push(instantiation);
+
+                self.value = Some(value);
+            },
             ExpressionType::LogicalAnd =>
             {
                 let body = func.borrow_mut().get_label();
                 let clause = func.borrow_mut().get_label();
                 let exit = func.borrow_mut().get_label();
 
-                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::Unknown, 0, false)));
+                // The result is 0 or 1 as an i32 (as in C)
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::I32, 0, false)));
                 self.value = Some(value.clone());
 
                 self.children[0].render(func)?;
@@ -740,7 +1653,8 @@ impl Expression
                 let clause = func.borrow_mut().get_label();
                 let exit = func.borrow_mut().get_label();
 
-                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::Unknown, 0, false)));
+                // The result is 0 or 1 as an i32 (as in C)
+                let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::I32, 0, false)));
                 self.value = Some(value.clone());
 
                 self.children[0].render(func)?;
@@ -784,6 +1698,10 @@ impl Expression
     /// Get the return value from an expression
     pub fn value(&self, _func: &RefCell<&mut Function>) -> Result<Value, Error>
     {
-        Ok(self.value.clone().unwrap())
+        match &self.value
+        {
+            Some(value) => Ok(value.clone()),
+            None => compiler_error_loc(ErrorCode::E0025, String::from("Expression does not have a value"), &self.location())
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use super::{Function, OpCode, Value, NonPtrType, compiler_warning_loc};
+
+use crate::cli::Lint;
+use crate::tokenizer::Span;
+
+use std::collections::HashSet;
+
+impl Function
+{
+    /// Check the paths through the function before it is optimized, warning about variables which may be read before
+    /// they are assigned and about paths which reach the end of a function returning a value without returning one
+    /// (pos is the name of the function)
+    pub fn check_flow(&mut self, pos: &Option<Span>)
+    {
+        let return_name = match &self.return_value
+        {
+            Value::Symbol(symbol) => symbol.title.clone(),
+            _ => String::new()
+        };
+
+        // Arguments are assigned before the function starts, and the result only has to be assigned if there is one
+        let mut tracked: HashSet<String> = self.declarations.keys().filter(|name| !self.arguments.iter().any(|(argument, _)| argument == *name)).cloned().collect();
+
+        if (self.return_type.raw_type != NonPtrType::Void || self.return_type.num_ptr > 0) && !self.is_noreturn
+        {
+            tracked.insert(return_name.clone());
+        }
+
+        let assigned = self.get_assigned_before(&tracked);
+        let mut reported = HashSet::new();
+
+        for (index, state) in assigned.iter().enumerate()
+        {
+            let state = match state
+            {
+                Some(state) => state,
+                None => continue
+            };
+
+            for name in self.get_flow_reads(index)
+            {
+                if !tracked.contains(&name) || state.contains(&name) || !reported.insert(name.clone())
+                {
+                    continue;
+                }
+
+                // The result is only read by the return at the end of the function
+                let warning = if name == return_name
+                {
+                    compiler_warning_loc(Lint::MissingReturn, format!("Function '{}' may reach its end without returning a value", self.name), pos)
+                }
+                else
+                {
+                    compiler_warning_loc(Lint::UseBeforeAssign, format!("Variable '{}' may be read before it is assigned", name), &self.declarations[&name])
+                        .with_note(&format!("on some paths through the function '{}' is read without this declaration running first, declare it in a block which encloses every use of it", name))
+                };
+
+                self.warnings.push(warning);
+            }
+        }
+    }
+
+    /// Get the tracked symbols which are assigned on every path from the start of the function to each instruction
+    /// (None for instructions which are never reached)
+    fn get_assigned_before(&self, tracked: &HashSet<String>) -> Vec<Option<HashSet<String>>>
+    {
+        let mut states: Vec<Option<HashSet<String>>> = vec![None; self.instructions.len()];
+
+        if states.is_empty()
+        {
+            return states;
+        }
+
+        states[0] = Some(HashSet::new());
+
+        let mut work = vec![0];
+
+        // Paths meet with the symbols assigned along all of them, until nothing changes
+        while let Some(index) = work.pop()
+        {
+            let mut state = states[index].clone().unwrap();
+
+            state.extend(self.get_flow_writes(index).into_iter().filter(|name| tracked.contains(name)));
+
+            for next in self.get_flow_branches(index)
+            {
+                let merged = match &states[next]
+                {
+                    Some(current) => current.intersection(&state).cloned().collect(),
+                    None => state.clone()
+                };
+
+                if states[next].as_ref() != Some(&merged)
+                {
+                    states[next] = Some(merged);
+                    work.push(next);
+                }
+            }
+        }
+
+        states
+    }
+
+    /// Get the possible branches from an instruction, where a branch on two constants only takes the way it goes
+    fn get_flow_branches(&self, index: usize) -> Vec<usize>
+    {
+        let inst = self.instructions.get(&index).unwrap();
+
+        let taken = match (inst.opcode, inst.arguments.first(), inst.arguments.get(1))
+        {
+            (OpCode::Beq | OpCode::Bne | OpCode::Blt | OpCode::Bgt | OpCode::Ble | OpCode::Bge, Some(Value::Literal(lit0)), Some(Value::Literal(lit1))) =>
+            {
+                let (value0, value1) = (lit0.value, lit1.value);
+
+                let holds = match inst.opcode
+                {
+                    OpCode::Beq => value0 == value1,
+                    OpCode::Bne => value0 != value1,
+                    OpCode::Blt => value0 < value1,
+                    OpCode::Bgt => value0 > value1,
+                    OpCode::Ble => value0 <= value1,
+                    _ => value0 >= value1
+                };
+
+                inst.arguments.get(if holds {2} else {3})
+            },
+            _ => None
+        };
+
+        match taken
+        {
+            Some(Value::Label(label)) => vec![self.labels_reverse[label]],
+            _ => self.get_next_branches(index)
+        }
+    }
+
+    /// Get the names of the symbols an instruction assigns, taking the address of a symbol counts as assigning it as
+    /// it may be assigned through the pointer
+    fn get_flow_writes(&self, index: usize) -> Vec<String>
+    {
+        let inst = self.instructions.get(&index).unwrap();
+
+        match (inst.opcode, inst.arguments.first(), inst.arguments.get(1))
+        {
+            (OpCode::Ref, _, Some(Value::Symbol(symbol))) => vec![symbol.title.clone()],
+            (opcode, Some(Value::Symbol(symbol)), _) if writes_first_argument(opcode) && (!symbol.datatype.is_ref || opcode == OpCode::Cast) =>
+            {
+                vec![symbol.title.clone()]
+            },
+            _ => vec![]
+        }
+    }
+
+    /// Get the names of the symbols an instruction reads
+    fn get_flow_reads(&self, index: usize) -> Vec<String>
+    {
+        let inst = self.instructions.get(&index).unwrap();
+
+        let read = match inst.opcode
+        {
+            OpCode::Ref => &inst.arguments[..0],
+            opcode if !writes_first_argument(opcode) => &inst.arguments[..],
+            _ => match inst.arguments.first()
+            {
+                // Writing through a reference reads the pointer
+                Some(Value::Symbol(symbol)) if symbol.datatype.is_ref && inst.opcode != OpCode::Cast => &inst.arguments[..],
+                _ => &inst.arguments[inst.arguments.len().min(1)..]
+            }
+        };
+
+        read.iter().filter_map(|value| match value
+        {
+            Value::Symbol(symbol) => Some(symbol.title.clone()),
+            _ => None
+        }).collect()
+    }
+}
+
+/// Does an instruction with the opcode write its first argument, rather than only reading its arguments
+fn writes_first_argument(opcode: OpCode) -> bool
+{
+    !matches!(opcode, OpCode::Beq | OpCode::Bge | OpCode::Bgt | OpCode::Ble | OpCode::Blt | OpCode::Bne | OpCode::Push | OpCode::Ret | OpCode::Jmp |
+              OpCode::Nop | OpCode::Unreachable | OpCode::BitSet | OpCode::BitClr | OpCode::Memcpy | OpCode::Memset | OpCode::Assume)
+}
@@ -0,0 +1,172 @@
+use super::DataType;
+
+use super::compiler_error_loc;
+
+use crate::cli::{Error, ErrorCode};
+use crate::parser::{FunctionDecl, Param, TypeExpr};
+use crate::tokenizer::Span;
+
+/// Generic function, which is compiled separately for every list of types it is used with
+#[derive(Debug, Clone)]
+pub struct GenericFunction
+{
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub node: FunctionDecl,
+    pub module: usize
+}
+
+/// Use of a generic function with a list of types
+#[derive(Debug, Clone)]
+pub struct Instantiation
+{
+    pub generic: GenericFunction,
+    pub types: Vec<DataType>,
+    pub pos: Option<Span>
+}
+
+impl GenericFunction
+{
+    /// Generate a new generic function from its declaration, linked under the given name and defined in the given
+    /// module
+    pub fn from_declaration(function: &FunctionDecl, name: String, module: usize) -> Self
+    {
+        Self
+        {
+            name,
+            parameters: function.type_params.iter().map(|parameter| parameter.name.clone()).collect(),
+            node: function.clone(),
+            module
+        }
+    }
+
+    /// Infer the type arguments of a call from the types of the values passed to the function (values of an unknown
+    /// type, such as integer literals, do not take part). A type parameter used by several arguments is given the
+    /// common type of all of them
+    pub fn infer_types(&self, argument_types: &[Option<DataType>], pos: &Option<Span>) -> Result<Vec<DataType>, Error>
+    {
+        let mut types: Vec<Option<DataType>> = vec![None; self.parameters.len()];
+
+        for (argument, argument_type) in self.node.params.iter().zip(argument_types)
+        {
+            let (index, num_ptr) = match (self.parameter_use(argument), argument_type)
+            {
+                (Some(used), Some(_)) => used,
+                _ => continue
+            };
+
+            let argument_type = argument_type.unwrap();
+
+            // The pointers written in the argument type are not part of the type parameter
+            if argument_type.num_ptr < num_ptr
+            {
+                continue;
+            }
+
+            // A slice given for a pointer or slice argument is taken apart like a pointer
+            let mut datatype = argument_type;
+            datatype.num_ptr -= num_ptr;
+            datatype.is_progmem = datatype.is_progmem && datatype.num_ptr > 0;
+            datatype.is_slice = datatype.is_slice && num_ptr == 0;
+
+            types[index] = Some(match types[index]
+            {
+                Some(current) => current.common_type(&datatype),
+                None => datatype
+            });
+        }
+
+        let mut result = vec![];
+
+        for (parameter, datatype) in self.parameters.iter().zip(types)
+        {
+            match datatype
+            {
+                Some(datatype) => result.push(datatype),
+                None => compiler_error_loc(ErrorCode::E0019, format!("Cannot infer the type parameter '{}' of '{}', give it explicitly as {}<...>",
+                                                   parameter, self.name, self.name), pos)?
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the type parameter an argument is declared with, along with the number of pointers applied to it
+    fn parameter_use(&self, argument: &Param) -> Option<(usize, usize)>
+    {
+        let datatype = &argument.datatype;
+        let index = self.parameters.iter().position(|parameter| *parameter == datatype.name.name)?;
+
+        Some((index, datatype.pointers + datatype.slice as usize))
+    }
+}
+
+impl Instantiation
+{
+    /// Get the name the instantiation is linked under, the name of the generic function followed by the types
+    /// for example max.u8, swap.i16p or sum.u8s (for a slice)
+    pub fn name(&self) -> String
+    {
+        let mut result = self.generic.name.clone();
+
+        for datatype in &self.types
+        {
+            result += &format!(".{}", mangled_type_name(datatype));
+        }
+
+        result
+    }
+
+    /// Get the declaration of the function with the types substituted for the type parameters
+    pub fn declaration(&self) -> FunctionDecl
+    {
+        let mut function = self.generic.node.clone();
+
+        // The type parameters are left out, but the attributes are kept
+        function.type_params.clear();
+        function.visit_types_mut(&mut |datatype| self.substitute(datatype));
+
+        function
+    }
+
+    /// Substitute the type for a type parameter written as a type, the pointers of the type come before any written
+    /// after the type parameter
+    fn substitute(&self, datatype: &mut TypeExpr)
+    {
+        let substitute = match self.generic.parameters.iter().position(|parameter| *parameter == datatype.name.name)
+        {
+            Some(index) => self.types[index],
+            None => return
+        };
+
+        let mut raw = substitute;
+        raw.num_ptr = 0;
+        raw.is_progmem = false;
+        raw.is_slice = false;
+
+        // A slice written after the type parameter is a slice of the type, and a slice given for the type parameter is
+        // only kept if nothing is written after it (the pointer of the slice is counted along with the others)
+        let pointers = substitute.num_ptr + datatype.pointers + datatype.slice as usize;
+        let slice = datatype.slice || (datatype.pointers == 0 && substitute.is_slice);
+
+        datatype.progmem |= substitute.is_progmem;
+        datatype.name.name = format!("{}", raw);
+        datatype.pointers = pointers - slice as usize;
+        datatype.slice = slice;
+    }
+}
+
+/// Get the name of a type as it is used in linked names, for example u8, i16p or progmem_u8s (pointers are written as
+/// a 'p' and slices as an 's')
+pub fn mangled_type_name(datatype: &DataType) -> String
+{
+    let mut raw = *datatype;
+    raw.num_ptr = 0;
+    raw.is_ref = false;
+    raw.is_progmem = false;
+    raw.is_slice = false;
+
+    let num_ptr = if datatype.is_slice {datatype.num_ptr - 1} else {datatype.num_ptr};
+
+    format!("{}{}{}{}", if datatype.is_progmem {"progmem_"} else {""}, raw, "p".repeat(num_ptr), if datatype.is_slice {"s"} else {""})
+}
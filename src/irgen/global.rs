@@ -0,0 +1,198 @@
+use std::fmt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{DataType, NonPtrType, Literal, Expression, Function};
+
+use super::{compiler_error_loc, type_from_ast};
+
+use crate::parser::{GlobalDecl, ArrayLength, Initializer, Expr};
+
+use crate::tokenizer::Span;
+
+use crate::cli::{Error, ErrorCode};
+
+/// Global variable or table, stored outside of any function
+#[derive(Debug, Clone)]
+pub struct Global
+{
+    pub name: String,
+    pub datatype: DataType,
+    pub length: Option<usize>,
+    pub values: Vec<i128>,
+    pub is_progmem: bool
+}
+
+impl Global
+{
+    /// Generate a new global object from its declaration (globals defined before it are given so they can be
+    /// reported in initializers)
+    pub fn from_declaration(declaration: &GlobalDecl, globals: &Rc<HashMap<String, Global>>) -> Result<Self, Error>
+    {
+        let declared_type = type_from_ast(&declaration.datatype)?;
+        let name = declaration.name.name.clone();
+        let pos = Some(declaration.name.span.clone());
+
+        // Globals hold integer data, the progmem qualifier applies to the storage of the global itself
+        if declared_type.num_ptr > 0 || declared_type.raw_type == NonPtrType::Void
+        {
+            compiler_error_loc(ErrorCode::E0033, format!("Global '{}' must have an integer type, got '{}'", name, declared_type), &pos)?;
+        }
+
+        let datatype = DataType::new(declared_type.raw_type, 0, false);
+
+        // Get the initial values
+        let values = match &declaration.value
+        {
+            None => vec![],
+            Some(Initializer::List(items, _)) =>
+            {
+                let mut values = vec![];
+
+                for item in items
+                {
+                    values.push(initializer_from_ast(item, datatype, globals)?);
+                }
+
+                values
+            },
+            Some(Initializer::Value(value)) => vec![initializer_from_ast(value, datatype, globals)?]
+        };
+
+        let is_list = matches!(declaration.value, Some(Initializer::List(_, _)));
+
+        // Get the length of the array (if the global is an array)
+        let length = match &declaration.length
+        {
+            None if is_list => compiler_error_loc(ErrorCode::E0033, format!("Initializer list given for non-array global '{}'", name), &pos)?,
+            None => None,
+            Some(ArrayLength::Inferred) if is_list => Some(values.len()),
+            Some(ArrayLength::Inferred) => compiler_error_loc(ErrorCode::E0033, format!("Array global '{}' without a length requires an initializer list", name), &pos)?,
+            Some(ArrayLength::Given(length)) =>
+            {
+                let length = constant_from_ast(length, globals)?.0.value;
+
+                if length <= 0
+                {
+                    compiler_error_loc(ErrorCode::E0033, format!("Array global '{}' must have a positive length, got {}", name, length), &pos)?;
+                }
+
+                Some(length as usize)
+            }
+        };
+
+        if let Some(l) = length
+        {
+            if values.len() > l
+            {
+                compiler_error_loc(ErrorCode::E0033, format!("Too many initializers for array global '{}' of length {}", name, l), &pos)?;
+            }
+        }
+
+        // Data in program memory can't be written to at runtime, so it must be initialized
+        if declared_type.is_progmem && values.is_empty()
+        {
+            compiler_error_loc(ErrorCode::E0033, format!("Program memory global '{}' must be initialized", name), &pos)?;
+        }
+
+        Ok(Self
+        {
+            name,
+            datatype,
+            length,
+            values,
+            is_progmem: declared_type.is_progmem
+        })
+    }
+
+    /// Is the global an array
+    pub fn is_array(&self) -> bool
+    {
+        self.length.is_some()
+    }
+
+    /// Get the type of a pointer to the global
+    pub fn address_type(&self) -> DataType
+    {
+        if self.is_progmem
+        {
+            DataType::new_progmem(self.datatype.raw_type, 1)
+        }
+        else
+        {
+            DataType::new(self.datatype.raw_type, 1, false)
+        }
+    }
+
+    /// Get the number of elements stored in the global
+    pub fn element_count(&self) -> usize
+    {
+        self.length.unwrap_or(1)
+    }
+
+    /// Get the value stored at the given element (uninitialized elements are zero)
+    pub fn value_at(&self, index: usize) -> i128
+    {
+        if index < self.values.len()
+        {
+            self.values[index]
+        }
+        else
+        {
+            0
+        }
+    }
+}
+
+/// Evaluate a constant expression, along with the location of the expression
+fn constant_from_ast(expr: &Expr, globals: &Rc<HashMap<String, Global>>) -> Result<(Literal, Option<Span>), Error>
+{
+    // Constant expressions can't refer to any symbols, so they are built within an empty function
+    let mut function = Function::new();
+    function.globals = globals.clone();
+
+    let func = RefCell::new(&mut function);
+    let expr = Expression::from_ast(expr, &func)?;
+
+    Ok((expr.evaluate_constant()?, expr.location()))
+}
+
+/// Evaluate the initial value of a global, which must fit in the type of the global as either a signed or an
+/// unsigned value
+fn initializer_from_ast(expr: &Expr, datatype: DataType, globals: &Rc<HashMap<String, Global>>) -> Result<i128, Error>
+{
+    let (lit, pos) = constant_from_ast(expr, globals)?;
+
+    if let Some(bits) = datatype.bit_width()
+    {
+        if !DataType::integer(bits, true).unwrap().can_represent(lit.value) && !DataType::integer(bits, false).unwrap().can_represent(lit.value)
+        {
+            compiler_error_loc(ErrorCode::E0034, format!("Initializer {} does not fit in '{}'", lit.value, datatype), &pos)?;
+        }
+    }
+
+    Ok(datatype.wrap_value(lit.value))
+}
+
+impl fmt::Display for Global
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "global {}{} {}", if self.is_progmem {"progmem "} else {""}, self.datatype, self.name)?;
+
+        if let Some(length) = self.length
+        {
+            write!(f, "[{}]", length)?;
+        }
+
+        write!(f, " = {{")?;
+
+        for i in 0..self.element_count()
+        {
+            write!(f, "{}{}", self.value_at(i), if i + 1 < self.element_count() {", "} else {""})?;
+        }
+
+        write!(f, "}}")
+    }
+}
@@ -1,16 +1,19 @@
 use std::fmt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::cell::RefCell;
+use std::rc::Rc;
 
-use super::{DataType, NonPtrType};
+use super::{DataType, NonPtrType, Global, GenericFunction, Instantiation, Signature, Attribute, function_attributes};
 
-use crate::parser::ParseTreeNode;
+use crate::parser::FunctionDecl;
 
-use super::{expected_got_error};
+use super::compiler_error_loc;
 
-use super::{Statement, get_value_type, identifier_from_parse_tree, type_from_parse_tree, arguments_from_parse_tree};
+use super::{Statement, get_value_type, type_from_ast, arguments_from_ast, check_progmem_pointer, expand_slice_arguments};
 
-use crate::cli::Error;
+use crate::cli::{Error, ErrorCode};
+
+use crate::tokenizer::{FileLocation, Span};
 
 /// Intermediate Representation OpCode
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -44,11 +47,27 @@ pub enum OpCode
     Or,
     Xor,
     Cast,
+    Sext, // Sign Extend
+    Zext, // Zero Extend
     Deref,
     Ref,
     Array,
     Push,
-    Call
+    Call,
+    Global, // Address of a Global
+    BitSet, // Set a Bit of the Integer behind a Pointer
+    BitClr, // Clear a Bit of the Integer behind a Pointer
+    BitTst, // Test a Bit of the Integer behind a Pointer
+    Rotl, // Rotate Left
+    Rotr, // Rotate Right
+    Popcnt, // Count the Set Bits
+    Clz, // Count the Leading Zeros
+    Ctz, // Count the Trailing Zeros
+    Bswap, // Swap the Order of the Bytes
+    Memcpy, // Copy a Number of Bytes from one Pointer to Another
+    Memset, // Set a Number of Bytes behind a Pointer
+    Unreachable, // Marks Code which is Never Reached
+    Assume // Assume a Value is not Zero
 }
 
 /// Symbol with type
@@ -136,7 +155,9 @@ impl fmt::Display for Value
 pub struct Instruction
 {
     pub opcode: OpCode,
-    pub arguments: Vec<Value>
+    pub arguments: Vec<Value>,
+    pub checked: Option<FileLocation>, // Location reported if a checked operation fails
+    pub span: Option<Span> // Source the instruction was lowered from, for errors from the code generator
 }
 
 impl Instruction
@@ -147,7 +168,21 @@ impl Instruction
         Self
         {
             opcode,
-            arguments
+            arguments,
+            checked: None,
+            span: None
+        }
+    }
+
+    /// Generate a new instruction which is checked at runtime, reporting the given location on failure
+    pub fn new_checked(opcode: OpCode, arguments: Vec<Value>, location: FileLocation) -> Self
+    {
+        Self
+        {
+            opcode,
+            arguments,
+            checked: Some(location),
+            span: None
         }
     }
 }
@@ -163,10 +198,27 @@ impl fmt::Display for Instruction
             write!(f, "{:<15}", format!("{}", arg))?;
         }
 
+        if self.checked.is_some()
+        {
+            write!(f, "checked")?;
+        }
+
         Ok(())
     }
 }
 
+/// The options a function is lowered to IR with
+#[derive(Debug, Clone, Copy)]
+pub struct LoweringOptions
+{
+    /// Arithmetic is checked for overflow at runtime
+    pub checked_arith: bool,
+    /// Accesses to slices and arrays are checked to be in range
+    pub bounds_check: bool,
+    /// The width of a pointer, which is also the width of the difference between two pointers
+    pub pointer_bits: usize
+}
+
 /// Function implementation in Intermediate representation
 #[derive(Debug, Clone)]
 pub struct Function
@@ -181,6 +233,20 @@ pub struct Function
     pub name: String,
     pub arguments: Vec<(String, DataType)>,
 
+    pub globals: Rc<HashMap<String, Global>>,
+    pub functions: Rc<HashMap<String, String>>,
+    pub generics: Rc<HashMap<String, GenericFunction>>,
+    pub signatures: Rc<HashMap<String, Signature>>,
+    pub instantiations: Vec<Instantiation>,
+    pub checked_arith: bool,
+    pub bounds_check: bool,
+    pub pointer_bits: usize,
+    pub is_noreturn: bool,
+    pub warnings: Vec<Error>,
+    pub calls: HashSet<String>,
+    pub declarations: HashMap<String, Option<Span>>,
+    pub current_span: Option<Span>,
+
     next_label: usize,
     next_register: usize,
     next_index: usize,
@@ -208,6 +274,20 @@ impl Function
             name: String::from("[UNKNOWN]"),
             arguments: vec![],
 
+            globals: Rc::new(HashMap::new()),
+            functions: Rc::new(HashMap::new()),
+            generics: Rc::new(HashMap::new()),
+            signatures: Rc::new(HashMap::new()),
+            instantiations: vec![],
+            checked_arith: false,
+            bounds_check: false,
+            pointer_bits: 64,
+            is_noreturn: false,
+            warnings: vec![],
+            calls: HashSet::new(),
+            declarations: HashMap::new(),
+            current_span: None,
+
             next_label: 0,
             next_register: 1,
             next_index: 0,
@@ -238,45 +318,71 @@ impl Function
         self.labels_reverse = new_reverse;
     }
 
-    /// Generate a new function object from a parse tree node
-    pub fn from_parse_tree_node(node: ParseTreeNode) -> Result<Self, Error>
+    /// Generate a new function object from a function declaration, with access to the given globals, functions and
+    /// generic functions, and the signatures of the functions by their linked names, lowered with the given options
+    pub fn from_declaration(function: &FunctionDecl, globals: Rc<HashMap<String, Global>>, functions: Rc<HashMap<String, String>>,
+                            generics: Rc<HashMap<String, GenericFunction>>, signatures: Rc<HashMap<String, Signature>>,
+                            lowering: LoweringOptions) -> Result<Self, Error>
     {
-        match node
+        let attributes = function_attributes(function)?;
+
+        let mut result = Self::new();
+        result.globals = globals;
+        result.functions = functions;
+        result.generics = generics;
+        result.signatures = signatures;
+        result.checked_arith = lowering.checked_arith;
+        result.bounds_check = lowering.bounds_check;
+        result.pointer_bits = lowering.pointer_bits;
+        result.is_noreturn = attributes.contains(&Attribute::NoReturn);
+
+        // Get the function signature
+        let name = function.name.name.clone();
+        let return_type = type_from_ast(&function.ret)?;
+        let arguments = arguments_from_ast(&function.params)?;
+
+        // Only pointers can refer to program memory
+        let pos = Some(function.name.span.clone());
+
+        check_progmem_pointer(&return_type, &pos)?;
+
+        // The length of a slice can't be returned along with its pointer
+        if return_type.is_slice
         {
-            ParseTreeNode::Function(children) =>
-            {
-                let mut result = Self::new();
+            compiler_error_loc(ErrorCode::E0026, format!("Function {} cannot return the slice '{}'", name, return_type), &pos)?;
+        }
 
-                // Get the function signature
-                let name = identifier_from_parse_tree(children[1].clone())?;
-                let return_type = type_from_parse_tree(children[0].clone())?;
-                let arguments = arguments_from_parse_tree(children[2].clone())?;
+        for (_, datatype) in &arguments
+        {
+            check_progmem_pointer(datatype, &pos)?;
+        }
 
-                result.set_function_signature(return_type, name, arguments);
+        result.set_function_signature(return_type, name, expand_slice_arguments(arguments.clone()));
 
-                // Generate the code for the function
-                let refcell = RefCell::new(&mut result);
+        // Generate the code for the function
+        let refcell = RefCell::new(&mut result);
 
-                refcell.borrow_mut().return_value = Value::Symbol(Symbol::new(String::from("R0"), return_type.clone()));
+        refcell.borrow_mut().return_value = Value::Symbol(Symbol::new(String::from("R0"), return_type.clone()));
 
-                let statement = Statement::from_parse_tree_node(children[3].clone(), &refcell)?;
+        let statement = Statement::from_ast(&function.body, &refcell)?;
 
-                statement.render(&refcell)?;
+        statement.check_types(&refcell)?;
+        statement.lint_function(&arguments, &refcell);
+        statement.render(&refcell)?;
 
-                // Add the exit label
-                refcell.borrow_mut().place_label_here(String::from("exit"));
-                let ret_val = refcell.borrow().return_value.clone();
-                refcell.borrow_mut().add_instruction(Instruction::new(OpCode::Ret, vec![ret_val]));
+        // Add the exit label
+        refcell.borrow_mut().place_label_here(String::from("exit"));
+        let ret_val = refcell.borrow().return_value.clone();
+        refcell.borrow_mut().add_instruction(Instruction::new(OpCode::Ret, vec![ret_val]));
 
-                let finalresult = refcell.borrow_mut().clone();
-                Ok(finalresult)
+        let finalresult = refcell.borrow_mut().clone();
+        Ok(finalresult)
+    }
 
-            },
-            default =>
-            {
-                expected_got_error("Function", default)
-            }
-        }
+    /// Get the type of the difference between two pointers, a signed integer as wide as a pointer
+    pub fn pointer_difference_type(&self) -> DataType
+    {
+        DataType::integer(self.pointer_bits, true).unwrap()
     }
 
     /// Set the function signature
@@ -301,8 +407,8 @@ impl Function
 
         if inst.is_some()
         {
-            // The instruction can't be a call (the label won't exist within the function)
-            if inst.unwrap().opcode != OpCode::Call
+            // The instruction can't be a call or a global (the label won't exist within the function)
+            if inst.unwrap().opcode != OpCode::Call && inst.unwrap().opcode != OpCode::Global
             {
                 for val in &inst.unwrap().arguments
                 {
@@ -332,6 +438,14 @@ impl Function
         }
     }
 
+    /// Check if the end of the function can be reached, which it can't if every path ends in a loop or never returns
+    pub fn may_return(&self) -> bool
+    {
+        let exit = self.instructions.len() - 1;
+
+        self.get_explored_from(0).contains(&exit)
+    }
+
     /// Change an instruction at the given index to a nop
     pub fn change_to_nop(&mut self, index: usize)
     {
@@ -344,6 +458,12 @@ impl Function
     {
         let mut result = vec![];
 
+        // Nothing comes after code which is never reached
+        if self.instructions.get(&index).is_some_and(|inst| inst.opcode == OpCode::Unreachable)
+        {
+            return result;
+        }
+
         // If there are jump values, those are the possible branches
         if let Some(jump_vals) = self.get_jump_values(index)
         {
@@ -407,9 +527,44 @@ impl Function
         format!("R{}", self.next_register - 1)
     }
 
+    /// Add an arithmetic instruction, which is checked at runtime if checked arithmetic is enabled (a checked
+    /// instruction reports the location of the operation, so it must have one)
+    pub fn add_arithmetic_instruction(&mut self, opcode: OpCode, arguments: Vec<Value>, location: Option<Span>) -> Result<(), Error>
+    {
+        let is_checkable = matches!(opcode, OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div | OpCode::Mod | OpCode::Shl | OpCode::Shr);
+
+        // Only integer arithmetic is checked
+        let is_integer = arguments.iter().all(|arg| match get_value_type(arg)
+        {
+            Some(datatype) => datatype.num_ptr == 0,
+            None => false
+        });
+
+        if self.checked_arith && is_checkable && is_integer
+        {
+            match location
+            {
+                Some(span) => self.add_instruction(Instruction::new_checked(opcode, arguments, span.start)),
+                None => compiler_error_loc(ErrorCode::E0036, format!("Checked '{:?}' has no location to report", opcode), &None)?
+            }
+        }
+        else
+        {
+            self.add_instruction(Instruction::new(opcode, arguments));
+        }
+
+        Ok(())
+    }
+
     /// Add an instruction
-    pub fn add_instruction(&mut self, inst: Instruction)
+    pub fn add_instruction(&mut self, mut inst: Instruction)
     {
+        // Instructions are marked with the source being lowered
+        if inst.span.is_none()
+        {
+            inst.span = self.current_span.clone();
+        }
+
         self.instructions.insert(self.next_index, inst);
         self.next_index += 1;
     }
@@ -462,31 +617,13 @@ impl Function
     /// Get all instructions which can be reached from a given index
     pub fn get_explored_from(&self, index: usize) -> Vec<usize>
     {
-        let mut fronts= vec![index];
-        let mut explored = vec![];
-
-        // While there are still instructions to explore
-        while fronts.len() > 0
-        {
-            let mut next_fronts = vec![];
-
-            for front in fronts
-            {
-                explored.push(front);
-
-                for v in self.get_next_branches(front)
-                {
-                    if !explored.contains(&v) && !next_fronts.contains(&v)
-                    {
-                        next_fronts.push(v);
-                    }
-                }
-            }
-
-            fronts = next_fronts;
-        }
+        explore_from(index, |front| self.get_next_branches(front))
+    }
 
-        explored
+    /// Get all possible branches from every instruction
+    pub fn get_all_next_branches(&self) -> Vec<Vec<usize>>
+    {
+        (0..self.instructions.len()).map(|i| self.get_next_branches(i)).collect()
     }
 
     /// Check if an instruction has side effects
@@ -494,9 +631,10 @@ impl Function
     {
         match self.instructions.get(&index)
         {
+            // Checked operations may fail at runtime
             Some(v) =>
             {
-                v.opcode == OpCode::Call
+                v.opcode == OpCode::Call || v.checked.is_some()
             },
             None => false
         }
@@ -516,7 +654,8 @@ impl Function
                 match inst.opcode
                 {
                     // Branches are special cases where both arguments are reads
-                    OpCode::Beq | OpCode::Bge | OpCode::Bgt | OpCode::Ble | OpCode::Blt | OpCode::Bne | OpCode::Push | OpCode::Ret =>
+                    OpCode::Beq | OpCode::Bge | OpCode::Bgt | OpCode::Ble | OpCode::Blt | OpCode::Bne | OpCode::Push | OpCode::Ret |
+                    OpCode::BitSet | OpCode::BitClr | OpCode::Memcpy | OpCode::Memset | OpCode::Assume =>
                     {
                         if inst.arguments.contains(&value)
                         {
@@ -639,41 +778,55 @@ impl Function
         self.labels_reverse.remove(&label);
     }
 
-    /// Get the domain of a register
-    pub fn get_register_domain(&self, register: Value) -> Vec<usize>
+    /// Get, for each instruction, the position every instruction it can reach is explored at from it
+    pub fn get_explored_order(&self) -> Vec<Vec<Option<usize>>>
+    {
+        let mut result = vec![];
+
+        let branches = self.get_all_next_branches();
+
+        for i in 0..self.instructions.len()
+        {
+            let mut order = vec![None; self.instructions.len()];
+
+            for (position, e) in explore_from(i, |front| branches[front].iter().cloned()).into_iter().enumerate()
+            {
+                order[e] = Some(position);
+            }
+
+            result.push(order);
+        }
+
+        result
+    }
+
+    /// Get the domain of a register, given the order instructions are explored in from each instruction
+    pub fn get_register_domain(&self, register: Value, order: &[Vec<Option<usize>>]) -> Vec<usize>
     {
         let mut result = vec![];
 
         let (reads, writes) = self.get_reads_writes_for(register.clone());
-        
+
         // Go through all instructions
-        for i in 0..self.instructions.len()
+        for (i, explored) in order.iter().enumerate()
         {
             // Any write must be in the domain
             if writes.contains(&i)
             {
                 result.push(i);
-                break;
+                continue;
             }
 
-            let explored = self.get_explored_from(i);
-
             // Any instruction that can reach a read before another write
-            for e in explored
+            let first_read = reads.iter().filter_map(|r| explored[*r]).min();
+            let first_write = writes.iter().filter_map(|w| explored[*w]).min();
+
+            match (first_read, first_write)
             {
-                if reads.contains(&e)
-                {
-                    if !result.contains(&i)
-                    {
-                        result.push(i);
-                    }
-                    break;
-                }
-                else if writes.contains(&e)
-                {
-                    break;
-                }
-            } 
+                (Some(read), Some(write)) if read <= write => result.push(i),
+                (Some(_), None) => result.push(i),
+                _ => {}
+            }
         }
 
         result
@@ -723,6 +876,40 @@ impl Function
     }
 }
 
+/// Get all instructions which can be reached from a given index, in the order they are explored, given the possible
+/// branches from each instruction
+pub fn explore_from<B: IntoIterator<Item = usize>>(index: usize, next_branches: impl Fn(usize) -> B) -> Vec<usize>
+{
+    let mut fronts= vec![index];
+    let mut explored = vec![];
+
+    // Everything explored or about to be, so checking for an instruction doesn't go through the whole list
+    let mut seen = HashSet::from([index]);
+
+    // While there are still instructions to explore
+    while fronts.len() > 0
+    {
+        let mut next_fronts = vec![];
+
+        for front in fronts
+        {
+            explored.push(front);
+
+            for v in next_branches(front)
+            {
+                if seen.insert(v)
+                {
+                    next_fronts.push(v);
+                }
+            }
+        }
+
+        fronts = next_fronts;
+    }
+
+    explored
+}
+
 impl fmt::Display for Function
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
@@ -0,0 +1,280 @@
+use super::{Statement, StatementType, Expression, ExpressionType, Function, Value, Builtin, Global, Signature, DataType};
+
+use super::compiler_warning_loc;
+
+use crate::cli::{Error, Lint};
+use crate::tokenizer::Span;
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Variable declared in a scope of the function being linted
+struct Declaration
+{
+    name: String,
+    pos: Option<Span>,
+    used: bool
+}
+
+/// State of the lints which look at the statements of a function, which follow the blocks of the source rather than
+/// the symbol table
+struct Linter
+{
+    scopes: Vec<Vec<Declaration>>,
+    unused: Vec<Declaration>,
+    read_outside: HashSet<String>,
+    globals: Rc<HashMap<String, Global>>,
+    signatures: Rc<HashMap<String, Signature>>,
+    warnings: Vec<Error>
+}
+
+impl Statement
+{
+    /// Check the statements of a function for unused variables, shadowed names and unreachable code, adding any
+    /// warnings to the function
+    pub fn lint_function(&self, arguments: &[(String, DataType)], func: &RefCell<&mut Function>)
+    {
+        let mut linter = Linter
+        {
+            scopes: vec![],
+            unused: vec![],
+            read_outside: HashSet::new(),
+            globals: func.borrow().globals.clone(),
+            signatures: func.borrow().signatures.clone(),
+            warnings: vec![]
+        };
+
+        // Arguments are part of the signature, so they aren't reported when unused
+        linter.scopes.push(arguments.iter().map(|(name, _)| Declaration
+        {
+            name: name.clone(),
+            pos: None,
+            used: true
+        }).collect());
+
+        linter.statement(self);
+        linter.close_scope();
+
+        // A variable can still be read after the block it is declared in, which is checked along the paths through the
+        // function once it is rendered
+        for declaration in std::mem::take(&mut linter.unused)
+        {
+            if !linter.read_outside.contains(&declaration.name)
+            {
+                linter.warnings.push(compiler_warning_loc(Lint::UnusedVariable, format!("Unused variable '{}'", declaration.name), &declaration.pos));
+            }
+        }
+
+        func.borrow_mut().warnings.append(&mut linter.warnings);
+    }
+
+    /// Is the statement a declaration, which declares its names in the enclosing block rather than a block of its own
+    fn is_declaration(&self) -> bool
+    {
+        !self.children.is_empty() && self.children.iter().all(|child| matches!(child.mode, StatementType::InitializationStatement))
+    }
+}
+
+impl Linter
+{
+    /// Lint a statement and the statements in it
+    fn statement(&mut self, statement: &Statement)
+    {
+        match statement.mode
+        {
+            StatementType::CompoundStatement if statement.is_declaration() =>
+            {
+                for child in &statement.children
+                {
+                    self.statement(child);
+                }
+            },
+            StatementType::CompoundStatement =>
+            {
+                self.scopes.push(vec![]);
+
+                // Only the first statement after the code stops is reported
+                let mut stopped: Option<&Statement> = None;
+
+                for child in &statement.children
+                {
+                    if let Some(previous) = stopped.filter(|_| !matches!(child.mode, StatementType::Empty))
+                    {
+                        let mut warning = compiler_warning_loc(Lint::UnreachableCode, String::from("Unreachable code"), &child.pos);
+
+                        if let Some(span) = &previous.pos
+                        {
+                            warning = warning.with_secondary_label(span.clone(), "any code after this is never reached");
+                        }
+
+                        self.warnings.push(warning);
+                        stopped = None;
+                    }
+                    else if stopped.is_none() && self.diverges(child)
+                    {
+                        stopped = Some(child);
+                    }
+
+                    self.statement(child);
+                }
+
+                self.close_scope();
+            },
+            StatementType::InitializationStatement =>
+            {
+                // The statements of a block expression are only visible inside of it
+                self.scopes.push(vec![]);
+
+                for child in &statement.children
+                {
+                    self.statement(child);
+                }
+
+                if let Some(expr) = &statement.expr
+                {
+                    self.expression(expr);
+                }
+
+                self.close_scope();
+
+                if let Some((_, name)) = &statement.init_data
+                {
+                    self.declare(name, &statement.pos);
+                }
+            },
+            _ =>
+            {
+                if let Some(expr) = &statement.expr
+                {
+                    self.expression(expr);
+                }
+
+                for child in &statement.children
+                {
+                    self.statement(child);
+                }
+            }
+        }
+    }
+
+    /// Lint an expression, marking the variables it reads as used (assigning to a variable doesn't read it)
+    fn expression(&mut self, expression: &Expression)
+    {
+        match &expression.mode
+        {
+            ExpressionType::AssignmentExpression(None) if expression.children[0].mode == ExpressionType::Identifier =>
+            {
+                self.expression(&expression.children[1]);
+            },
+            ExpressionType::Identifier =>
+            {
+                if let Some(Value::Symbol(symbol)) = &expression.value
+                {
+                    let declaration = self.scopes.iter_mut().rev().flat_map(|scope| scope.iter_mut().rev()).find(|declaration| declaration.name == symbol.title);
+
+                    match declaration
+                    {
+                        Some(declaration) => declaration.used = true,
+                        None => {self.read_outside.insert(symbol.title.clone());}
+                    }
+                }
+            },
+            _ =>
+            {
+                for child in &expression.children
+                {
+                    self.expression(child);
+                }
+            }
+        }
+    }
+
+    /// Declare a variable in the innermost scope, warning if it shadows an argument, another variable or a global
+    fn declare(&mut self, name: &str, pos: &Option<Span>)
+    {
+        let previous = self.scopes.iter().flat_map(|scope| scope.iter()).rev().find(|declaration| declaration.name == name);
+
+        match previous
+        {
+            Some(previous) =>
+            {
+                let mut warning = compiler_warning_loc(Lint::Shadowing, format!("Declaration of '{}' shadows an earlier declaration", name), pos);
+
+                match &previous.pos
+                {
+                    Some(span) => warning = warning.with_secondary_label(span.clone(), "declared here"),
+                    None => warning = warning.with_note(&format!("'{}' is an argument of the function", name))
+                }
+
+                self.warnings.push(warning);
+            },
+            None if self.globals.contains_key(name) =>
+            {
+                self.warnings.push(compiler_warning_loc(Lint::Shadowing, format!("Declaration of '{}' shadows the global '{}'", name, name), pos));
+            },
+            None => {}
+        }
+
+        self.scopes.last_mut().unwrap().push(Declaration
+        {
+            name: String::from(name),
+            pos: pos.clone(),
+            used: false
+        });
+    }
+
+    /// Leave the innermost scope, keeping any variable in it which is never read in it (names starting with an
+    /// underscore are unused on purpose)
+    fn close_scope(&mut self)
+    {
+        for declaration in self.scopes.pop().unwrap_or_default()
+        {
+            if !declaration.used && !declaration.name.starts_with('_')
+            {
+                self.unused.push(declaration);
+            }
+        }
+    }
+
+    /// Does the code after the statement never run, because it always returns, breaks, continues, loops forever or
+    /// calls a function which never returns
+    fn diverges(&self, statement: &Statement) -> bool
+    {
+        match statement.mode
+        {
+            StatementType::ReturnStatement | StatementType::BreakStatement | StatementType::ContinueStatement => true,
+            StatementType::CompoundStatement => statement.children.iter().any(|child| self.diverges(child)),
+            StatementType::IfStatement => self.diverges(&statement.children[0]) && self.diverges(&statement.children[1]),
+            StatementType::LoopStatement => !Linter::breaks(&statement.children[0]),
+            StatementType::WhileStatement | StatementType::DoWhileStatement =>
+            {
+                statement.expr.as_ref().is_some_and(|expr| expr.evaluate_constant().is_ok_and(|lit| lit.value != 0)) && !Linter::breaks(&statement.children[0])
+            },
+            StatementType::ExpressionStatement => statement.expr.as_ref().is_some_and(|expr| self.never_returns(expr)),
+            StatementType::InitializationStatement | StatementType::Empty => false
+        }
+    }
+
+    /// Does the statement break out of the loop it is in (breaks in loops inside of it only leave those loops)
+    fn breaks(statement: &Statement) -> bool
+    {
+        match statement.mode
+        {
+            StatementType::BreakStatement => true,
+            StatementType::LoopStatement | StatementType::WhileStatement | StatementType::DoWhileStatement => false,
+            _ => statement.children.iter().any(Linter::breaks)
+        }
+    }
+
+    /// Is the expression a call which never returns
+    fn never_returns(&self, expression: &Expression) -> bool
+    {
+        match (&expression.mode, &expression.value)
+        {
+            (ExpressionType::Builtin(Builtin::Unreachable), _) => true,
+            (ExpressionType::FunctionCall, Some(Value::Label(name))) => self.signatures.get(name).is_some_and(|signature| signature.noreturn),
+            _ => false
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::{DataType, type_from_ast, arguments_from_ast, mangled_type_name};
+
+use super::compiler_error_loc;
+
+use crate::cli::{Error, ErrorCode};
+use crate::parser::{FunctionDecl, ImplBlock};
+
+/// Method of a type, defined in an impl block and compiled as a free function which is given the receiver as its
+/// first argument
+#[derive(Debug, Clone)]
+pub struct Method
+{
+    pub key: String,
+    pub name: String,
+    pub node: FunctionDecl
+}
+
+/// Get the name a method is found under in the table of functions, the type followed by the name of the method (this
+/// can't be confused with the name of a function, as it is not an identifier)
+pub fn method_key(datatype: &DataType, name: &str) -> String
+{
+    format!("{}.{}", datatype, name)
+}
+
+/// Check if a name in the table of functions is the key of a method
+pub fn is_method_key(name: &str) -> bool
+{
+    name.contains('.')
+}
+
+/// Get the methods of an impl block, each of which must take the receiver as its first argument named 'self'
+pub fn methods_from_impl(block: &ImplBlock) -> Result<Vec<Method>, Error>
+{
+    let datatype = type_from_ast(&block.datatype)?;
+    let mut methods = vec![];
+
+    for function in &block.methods
+    {
+        let name = &function.name.name;
+        let location = Some(function.name.span.clone());
+
+        if function.is_generic()
+        {
+            compiler_error_loc(ErrorCode::E0015, format!("Method '{}' of '{}' cannot be generic", name, datatype), &location)?;
+        }
+
+        match arguments_from_ast(&function.params)?.first()
+        {
+            Some((argument, argument_type)) if argument == "self" && *argument_type == datatype => {},
+            _ => compiler_error_loc(ErrorCode::E0015, format!("The first argument of method '{}' must be 'self' of type '{}'", name, datatype), &location)?
+        }
+
+        methods.push(Method
+        {
+            key: method_key(&datatype, name),
+            name: format!("{}.{}", mangled_type_name(&datatype), name),
+            node: function.clone()
+        });
+    }
+
+    Ok(methods)
+}
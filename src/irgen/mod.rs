@@ -5,6 +5,18 @@ mod utils;
 mod optimize;
 mod errors;
 mod types;
+mod global;
+mod constant;
+mod generic;
+mod slice;
+mod method;
+mod builtin;
+mod bits;
+mod attribute;
+mod signature;
+mod lint;
+mod flow;
+mod typecheck;
 
 pub use instruction::*;
 pub use expression::*;
@@ -12,4 +24,11 @@ pub use statement::*;
 pub use utils::*;
 pub use optimize::*;
 pub use errors::*;
-pub use types::*;
\ No newline at end of file
+pub use types::*;
+pub use global::*;
+pub use generic::*;
+pub use slice::*;
+pub use method::*;
+pub use builtin::*;
+pub use attribute::*;
+pub use signature::*;
\ No newline at end of file
@@ -1,5 +1,5 @@
 mod optimizations;
-mod typecorrect;
+mod typeinfer;
 
 pub use optimizations::*;
-pub use typecorrect::*;
+pub use typeinfer::*;
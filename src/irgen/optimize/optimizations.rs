@@ -1,5 +1,7 @@
-use crate::irgen::{Function, Value, OpCode, Literal};
-use crate::irgen::get_value_type;
+use crate::irgen::{Function, Value, OpCode, Literal, Instruction, Symbol, DataType};
+use crate::irgen::{get_value_type, explore_from};
+
+use std::collections::{HashMap, HashSet};
 
 pub fn optimize_function(f: Function, level: usize, combine: bool) -> Function
 {
@@ -32,8 +34,11 @@ pub fn optimize_function(f: Function, level: usize, combine: bool) -> Function
             func = optimization_remove_nop(func);
         }
 
-        // Level 0 Optimizations (Constant Folding, Clean Branches, Remove Unused Registers, Remove Dead Code, Remove Unused Labels, Remove Nop's)
+        // Level 0 Optimizations (Narrow Arithmetic, Constant Folding, Assumptions, Bounds Checks, Clean Branches, Remove Unused Registers, Remove Dead Code, Remove Unused Labels, Remove Nop's)
+        func = optimization_narrow_arithmetic(func);
         func = optimization_arithmatic_constants(func);
+        func = optimization_assumptions(func);
+        func = optimization_bounds_checks(func);
         func = optimization_remove_unused_registers(func);
         func = optimization_remove_nop(func);
         func = optimization_redundant_moves(func);
@@ -75,14 +80,14 @@ pub fn optimization_remove_nop(f: Function) -> Function
 {
     let mut func = f.clone();
 
-    let mut indexes_to_remove = vec![];
+    let mut indexes_to_remove = HashSet::new();
 
     // Find all nop commands
-    for (index, inst) in (&func.instructions).clone()
+    for (index, inst) in &func.instructions
     {
         if inst.opcode == OpCode::Nop
         {
-            indexes_to_remove.push(index);
+            indexes_to_remove.insert(*index);
         }
     }
 
@@ -120,10 +125,11 @@ pub fn optimization_remove_nop(f: Function) -> Function
             // Move the labels
             if let Some(labels)= func.labels.remove(&i)
             {
+                // Labels moved here from a nop may not have been recorded here yet, so they are recorded where they
+                // move to
                 for label in &labels.clone()
                 {
-                    let last = func.labels_reverse.remove(label).unwrap();
-                    func.labels_reverse.insert(label.clone(), last - amt_to_shift);
+                    func.labels_reverse.insert(label.clone(), i - amt_to_shift);
                 }
 
                 func.labels.insert(i - amt_to_shift, labels.clone());
@@ -485,6 +491,11 @@ pub fn optimization_remove_unused_registers(f: Function) -> Function
 
     let symbols = func.get_all_symbols();
 
+    // Writes are only changed to nop's, which doesn't change where instructions lead, so what each write reaches is
+    // only found once
+    let mut explored_from: HashMap<usize, HashSet<usize>> = HashMap::new();
+    let branches = func.get_all_next_branches();
+
     for symbol in symbols
     {
         // Skip the register if the datatype is a reference (the instruction will have side effects)
@@ -512,7 +523,7 @@ pub fn optimization_remove_unused_registers(f: Function) -> Function
         {
             if !func.has_side_effects(*write)
             {
-                let explored = &func.get_explored_from(*write)[1..];
+                let explored = explored_from.entry(*write).or_insert_with(|| explore_from(*write, |front| branches[front].iter().cloned())[1..].iter().cloned().collect());
                 /*
                 println!("For: {}", &func.instructions.get(write).unwrap());
 
@@ -613,10 +624,13 @@ pub fn optimization_combine_domains(f: Function) -> Function
 
     let mut to_combine = vec![];
 
+    // What each instruction reaches is the same for every register, so it is only found once
+    let order = func.get_explored_order();
+
     // Find all domains
     for symbol in symbols
     {
-        let domain = func.get_register_domain(Value::Symbol(symbol.clone()));
+        let domain = func.get_register_domain(Value::Symbol(symbol.clone()), &order);
 
         domains.push((domain.len(), Value::Symbol(symbol.clone()), domain.clone()));
     }
@@ -685,9 +699,20 @@ pub fn optimization_combine_domains(f: Function) -> Function
             continue;
         }
 
-        let root = set[0].clone();
-        for v in &set[1..set.len()]
+        // Arguments must keep their names, so they are used as the root if present
+        let root = match set.iter().find(|v| if let Value::Symbol(symb) = v {func.arguments.iter().any(|(name, _)| name == &symb.title)} else {false})
         {
+            Some(arg) => arg.clone(),
+            None => set[0].clone()
+        };
+
+        for v in &set
+        {
+            if v == &root
+            {
+                continue;
+            }
+
             for i in 0..func.instructions.len()
             {
                 // Replace all occurences of one of the later registers with the root one
@@ -716,6 +741,194 @@ pub fn optimization_combine_domains(f: Function) -> Function
     func
 }
 
+/// Work out arithmetic on promoted operands in the type its result is truncated to, so adding two u8 values and
+/// storing the sum in a u8 is done in 8 bits rather than in the i32 they are promoted to (the low bits of a sum,
+/// difference, product or bitwise operation only depend on the low bits of its operands). Comparisons of extended
+/// values compare the values before they were extended
+pub fn optimization_narrow_arithmetic(f: Function) -> Function
+{
+    let mut func = f.clone();
+
+    for i in 0..func.instructions.len()
+    {
+        let inst = func.instructions.get(&i).unwrap().clone();
+
+        match inst.opcode
+        {
+            OpCode::Cast =>
+            {
+                let target = get_value_type(&inst.arguments[0]).unwrap();
+
+                if target.bit_width().is_some() && is_narrowable(&func, &inst.arguments[1], target, i)
+                {
+                    let value = narrow_value(&mut func, &inst.arguments[1], target);
+
+                    let cast = func.instructions.get_mut(&i).unwrap();
+                    cast.opcode = OpCode::Mov;
+                    cast.arguments[1] = value;
+                }
+            },
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cle | OpCode::Cgt | OpCode::Cge if inst.checked.is_none() =>
+            {
+                if let Some(operands) = narrowed_comparison(&func, &inst, i)
+                {
+                    func.instructions.get_mut(&i).unwrap().arguments.splice(1..3, operands);
+
+                    narrow_tested_result(&mut func, i);
+                }
+            },
+            _ => ()
+        }
+    }
+
+    func
+}
+
+/// Get the value a symbol read by the given instruction was extended from, the symbol must only be written by the
+/// extension and the value must not change before it is read
+fn extension_source(func: &Function, value: &Value, user: usize) -> Option<Value>
+{
+    match value
+    {
+        Value::Symbol(symbol) if !symbol.datatype.is_ref && !func.arguments.contains(&(symbol.title.clone(), symbol.datatype)) => (),
+        _ => return None
+    }
+
+    let (_, writes) = func.get_reads_writes_for(value.clone());
+
+    if writes.len() != 1
+    {
+        return None;
+    }
+
+    let inst = func.instructions.get(&writes[0]).unwrap();
+
+    if inst.opcode != OpCode::Sext && inst.opcode != OpCode::Zext
+    {
+        return None;
+    }
+
+    match &inst.arguments[1]
+    {
+        source @ Value::Symbol(source_symbol) if !source_symbol.datatype.is_ref =>
+        {
+            let (_, source_writes) = func.get_reads_writes_for(source.clone());
+
+            if source_writes.iter().any(|write| *write > writes[0] && *write < user) {None} else {Some(source.clone())}
+        },
+        _ => None
+    }
+}
+
+/// Check if a value read by the given instruction can be worked out in a narrower type, it must be a literal, an
+/// extension of a value of that type, or an operation on such values whose result is only read once
+fn is_narrowable(func: &Function, value: &Value, target: DataType, user: usize) -> bool
+{
+    if let Value::Literal(_) = value
+    {
+        return true;
+    }
+
+    if let Some(source) = extension_source(func, value, user)
+    {
+        return get_value_type(&source) == Some(target);
+    }
+
+    let symbol = match value
+    {
+        Value::Symbol(symbol) if !symbol.datatype.is_ref => symbol,
+        _ => return false
+    };
+
+    let (reads, writes) = func.get_reads_writes_for(value.clone());
+
+    if writes.len() != 1 || func.arguments.contains(&(symbol.title.clone(), symbol.datatype))
+    {
+        return false;
+    }
+
+    let inst = func.instructions.get(&writes[0]).unwrap();
+    let uses = func.instructions.get(&user).unwrap().arguments.iter().filter(|arg| *arg == value).count();
+
+    matches!(inst.opcode, OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::And | OpCode::Or | OpCode::Xor) &&
+        inst.checked.is_none() && reads == vec![user] && uses == 1 &&
+        is_narrowable(func, &inst.arguments[1], target, writes[0]) && is_narrowable(func, &inst.arguments[2], target, writes[0])
+}
+
+/// Rewrite a value found to be narrowable so it is worked out in the narrower type, giving the narrowed value
+fn narrow_value(func: &mut Function, value: &Value, target: DataType) -> Value
+{
+    let symbol = match value
+    {
+        Value::Literal(lit) => return Value::Literal(Literal::new(target.wrap_value(lit.value), target)),
+        Value::Symbol(symbol) => symbol,
+        Value::Label(_) => return value.clone()
+    };
+
+    let (_, writes) = func.get_reads_writes_for(value.clone());
+    let inst = func.instructions.get(&writes[0]).unwrap().clone();
+
+    // Extended values are used directly, leaving the extension unused
+    if inst.opcode == OpCode::Sext || inst.opcode == OpCode::Zext
+    {
+        return inst.arguments[1].clone();
+    }
+
+    let narrowed = Value::Symbol(Symbol::new(symbol.title.clone(), target));
+    let val0 = narrow_value(func, &inst.arguments[1], target);
+    let val1 = narrow_value(func, &inst.arguments[2], target);
+
+    func.instructions.get_mut(&writes[0]).unwrap().arguments = vec![narrowed.clone(), val0, val1];
+
+    narrowed
+}
+
+/// Get the operands of a comparison of two extended values (or an extended value and a literal which fits in its
+/// type) from before they were extended
+fn narrowed_comparison(func: &Function, inst: &Instruction, index: usize) -> Option<Vec<Value>>
+{
+    let source = extension_source(func, &inst.arguments[1], index).or_else(|| extension_source(func, &inst.arguments[2], index))?;
+    let datatype = get_value_type(&source).unwrap();
+
+    inst.arguments[1..3].iter()
+        .map(|arg| match arg
+        {
+            Value::Literal(lit) if datatype.can_represent(lit.value) => Some(Value::Literal(Literal::new(lit.value, datatype))),
+            _ => extension_source(func, arg, index).filter(|source| get_value_type(source) == Some(datatype))
+        })
+        .collect()
+}
+
+/// Give the result of a comparison the type of its operands when it is only tested against 0 by a branch
+fn narrow_tested_result(func: &mut Function, index: usize)
+{
+    let inst = func.instructions.get(&index).unwrap().clone();
+    let datatype = get_value_type(&inst.arguments[1]).unwrap();
+
+    let (reads, writes) = func.get_reads_writes_for(inst.arguments[0].clone());
+
+    if writes.len() != 1 || reads.len() != 1
+    {
+        return;
+    }
+
+    let branch = func.instructions.get(&reads[0]).unwrap();
+
+    let is_test = matches!(branch.opcode, OpCode::Bne | OpCode::Beq) && branch.arguments[0] == inst.arguments[0] &&
+        matches!(&branch.arguments[1], Value::Literal(lit) if lit.value == 0);
+
+    if let (true, Value::Symbol(symbol)) = (is_test, &inst.arguments[0])
+    {
+        let narrowed = Value::Symbol(Symbol::new(symbol.title.clone(), datatype));
+
+        func.instructions.get_mut(&index).unwrap().arguments[0] = narrowed.clone();
+
+        let branch = func.instructions.get_mut(&reads[0]).unwrap();
+        branch.arguments[0] = narrowed;
+        branch.arguments[1] = Value::Literal(Literal::new(0, datatype));
+    }
+}
+
 /// Remove redundant moves
 pub fn optimization_redundant_moves(f: Function) -> Function
 {
@@ -794,34 +1007,385 @@ pub fn optimization_arithmatic_constants(f: Function) -> Function
                 {
                     match instruction.opcode
                     {
+                        // Division by zero is left to be handled at runtime
+                        OpCode::Div if lit1.value == 0 => {},
+                        // Pointer arithmetic is scaled by the size of the element, which depends on the target
+                        _ if lit0.datatype.num_ptr > 0 || lit1.datatype.num_ptr > 0 => {},
                         OpCode::Add | OpCode::Sub | OpCode::Mul | OpCode::Div =>
                         {
                             let mut new_inst = instruction.clone();
 
                             new_inst.opcode = OpCode::Mov;
-                            if let Value::Literal(mut new_arg) = new_inst.arguments[1].clone()
+                            new_inst.checked = None;
+
+                            // The operation is performed at the width of its result
+                            let datatype = match get_value_type(&instruction.arguments[0])
                             {
-                                new_arg.value = 
-                                match instruction.opcode
-                                {
-                                    OpCode::Add => lit0.value + lit1.value,
-                                    OpCode::Sub => lit0.value - lit1.value,
-                                    OpCode::Mul => lit0.value * lit1.value,
-                                    OpCode::Div => lit0.value / lit1.value,
-                                    _ => {panic!()}
-                                };
+                                Some(datatype) if datatype.bit_width().is_some() => datatype,
+                                _ => lit0.datatype
+                            };
+
+                            // A checked operation which overflows must still fail at runtime
+                            let value = if instruction.checked.is_some()
+                            {
+                                datatype.evaluate(instruction.opcode, lit0.value, lit1.value)
+                            }
+                            else
+                            {
+                                datatype.evaluate_wrapping(instruction.opcode, lit0.value, lit1.value)
+                            };
+
+                            if let Some(value) = value
+                            {
+                                let mut new_arg = lit0;
+                                new_arg.value = value;
 
                                 new_inst.arguments = vec![new_inst.arguments[0].clone(), Value::Literal(new_arg)];
 
                                 func.instructions.insert(*i, new_inst);
                             }
                         },
+                        OpCode::Rotl | OpCode::Rotr =>
+                        {
+                            let datatype = get_value_type(&instruction.arguments[0]).unwrap();
+
+                            if let Some(value) = datatype.evaluate_intrinsic(instruction.opcode, lit0.value, lit1.value)
+                            {
+                                func.instructions.insert(*i, Instruction::new(OpCode::Mov, vec![
+                                    instruction.arguments[0].clone(),
+                                    Value::Literal(Literal::new(value, datatype))
+                                    ]));
+                            }
+                        },
                         _ => {}
                     }
                 }
             }
         }
+        // Intrinsics on a single constant
+        else if let [dest, Value::Literal(lit)] = instruction.arguments.as_slice()
+        {
+            if matches!(instruction.opcode, OpCode::Popcnt | OpCode::Clz | OpCode::Ctz | OpCode::Bswap)
+            {
+                let datatype = get_value_type(dest).unwrap();
+
+                if let Some(value) = datatype.evaluate_intrinsic(instruction.opcode, lit.value, 0)
+                {
+                    func.instructions.insert(*i, Instruction::new(OpCode::Mov, vec![
+                        dest.clone(),
+                        Value::Literal(Literal::new(value, datatype))
+                        ]));
+                }
+            }
+        }
     }  
 
     func
-}
\ No newline at end of file
+}
+/// Fold compares and branches using what is known about their operands, which comes from constants, from
+/// assumptions made earlier in the block (with nothing written to their operands in between) and from code which is
+/// never reached (a branch to it always goes the other way)
+pub fn optimization_assumptions(f: Function) -> Function
+{
+    let mut func = f.clone();
+
+    // Registers which have their address taken may be written through a pointer, and references read memory which
+    // anything may write, so nothing is assumed about either
+    let referenced: Vec<Value> = func.instructions.values()
+        .filter(|inst| inst.opcode == OpCode::Ref)
+        .map(|inst| inst.arguments[1].clone())
+        .collect();
+
+    let is_stable = |value: &Value| !referenced.contains(value) && !get_value_type(value).is_some_and(|datatype| datatype.is_ref);
+
+    let mut facts: Vec<(OpCode, Value, Value)> = vec![];
+    let mut compares: Vec<(Value, (OpCode, Value, Value))> = vec![];
+    let mut constants: Vec<(Value, Value)> = vec![];
+
+    for index in 0..func.instructions.len()
+    {
+        // Anything may jump to a label, so nothing is known there
+        if func.labels.contains_key(&index)
+        {
+            facts.clear();
+            compares.clear();
+            constants.clear();
+        }
+
+        let inst = func.instructions.get(&index).unwrap().clone();
+
+        // Registers which hold a constant are compared as that constant
+        let operands: Vec<Value> = inst.arguments.iter()
+            .map(|value| constants.iter().find(|(register, _)| register == value).map_or(value.clone(), |(_, constant)| constant.clone()))
+            .collect();
+
+        match inst.opcode
+        {
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cle | OpCode::Cgt | OpCode::Cge =>
+            {
+                if let Some(known) = known_condition(&facts, inst.opcode, &operands[1], &operands[2])
+                {
+                    let datatype = get_value_type(&inst.arguments[0]).unwrap();
+
+                    func.instructions.insert(index, Instruction::new(OpCode::Mov, vec![
+                        inst.arguments[0].clone(),
+                        Value::Literal(Literal::new(known as i128, datatype))
+                        ]));
+                }
+            },
+            OpCode::Beq | OpCode::Bne | OpCode::Blt | OpCode::Ble | OpCode::Bgt | OpCode::Bge =>
+            {
+                let known = if is_unreachable_label(&func, &inst.arguments[2])
+                {
+                    Some(false)
+                }
+                else if is_unreachable_label(&func, &inst.arguments[3])
+                {
+                    Some(true)
+                }
+                else
+                {
+                    known_condition(&facts, branch_condition(inst.opcode), &operands[0], &operands[1])
+                };
+
+                if let Some(known) = known
+                {
+                    func.instructions.insert(index, Instruction::new(OpCode::Jmp, vec![
+                        inst.arguments[if known {2} else {3}].clone()
+                        ]));
+                }
+            },
+            // A jump into code which is never reached is never reached either
+            OpCode::Jmp if is_unreachable_label(&func, &inst.arguments[0]) =>
+            {
+                func.instructions.insert(index, Instruction::new(OpCode::Unreachable, vec![]));
+            },
+            OpCode::Assume =>
+            {
+                let condition = inst.arguments[0].clone();
+
+                if is_stable(&condition)
+                {
+                    facts.push((OpCode::Cne, condition.clone(), Value::Literal(Literal::new(0, get_value_type(&condition).unwrap()))));
+
+                    if let Some((_, compare)) = compares.iter().find(|(value, _)| *value == condition)
+                    {
+                        facts.push(compare.clone());
+                    }
+                }
+            },
+            _ => {}
+        }
+
+        // What was known about a register no longer holds once it is written
+        let writes = !matches!(inst.opcode, OpCode::Beq | OpCode::Bne | OpCode::Blt | OpCode::Ble | OpCode::Bgt | OpCode::Bge |
+                                            OpCode::Push | OpCode::Ret | OpCode::Assume);
+
+        if let Some(written) = inst.arguments.first().filter(|_| writes)
+        {
+            facts.retain(|(_, val0, val1)| val0 != written && val1 != written);
+            compares.retain(|(value, (_, val0, val1))| value != written && val0 != written && val1 != written);
+            constants.retain(|(register, _)| register != written);
+
+            if let (OpCode::Mov | OpCode::Cast, Some(Value::Literal(lit))) = (inst.opcode, inst.arguments.get(1))
+            {
+                if is_stable(written) && lit.datatype.num_ptr == 0
+                {
+                    constants.push((written.clone(), Value::Literal(*lit)));
+                }
+            }
+        }
+
+        // Remember which compare gave a register, in case it is assumed later
+        if matches!(inst.opcode, OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cle | OpCode::Cgt | OpCode::Cge) &&
+            inst.arguments[1..].iter().all(|arg| *arg != inst.arguments[0] && is_stable(arg))
+        {
+            compares.push((inst.arguments[0].clone(), (inst.opcode, operands[1].clone(), operands[2].clone())));
+        }
+    }
+
+    func
+}
+
+/// Check if a label is placed on code which is never reached
+fn is_unreachable_label(func: &Function, label: &Value) -> bool
+{
+    match label
+    {
+        Value::Label(label) => func.labels_reverse.get(label)
+            .and_then(|index| func.instructions.get(index))
+            .is_some_and(|inst| inst.opcode == OpCode::Unreachable),
+        _ => false
+    }
+}
+
+/// Get the compare made by a branch
+fn branch_condition(opcode: OpCode) -> OpCode
+{
+    match opcode
+    {
+        OpCode::Beq => OpCode::Ceq,
+        OpCode::Bne => OpCode::Cne,
+        OpCode::Blt => OpCode::Clt,
+        OpCode::Ble => OpCode::Cle,
+        OpCode::Bgt => OpCode::Cgt,
+        _ => OpCode::Cge
+    }
+}
+
+/// Get the compare which holds exactly when a compare does not
+fn negated_condition(opcode: OpCode) -> OpCode
+{
+    match opcode
+    {
+        OpCode::Ceq => OpCode::Cne,
+        OpCode::Cne => OpCode::Ceq,
+        OpCode::Clt => OpCode::Cge,
+        OpCode::Cge => OpCode::Clt,
+        OpCode::Cgt => OpCode::Cle,
+        _ => OpCode::Cgt
+    }
+}
+
+/// Get the compare which holds for the operands the other way around
+fn swapped_condition(opcode: OpCode) -> OpCode
+{
+    match opcode
+    {
+        OpCode::Clt => OpCode::Cgt,
+        OpCode::Cgt => OpCode::Clt,
+        OpCode::Cle => OpCode::Cge,
+        OpCode::Cge => OpCode::Cle,
+        other => other
+    }
+}
+
+/// Check if one compare holding means another compare of the same operands holds
+fn implies_condition(fact: OpCode, opcode: OpCode) -> bool
+{
+    fact == opcode || matches!((fact, opcode),
+        (OpCode::Clt, OpCode::Cle) | (OpCode::Clt, OpCode::Cne) | (OpCode::Cgt, OpCode::Cge) | (OpCode::Cgt, OpCode::Cne) |
+        (OpCode::Ceq, OpCode::Cle) | (OpCode::Ceq, OpCode::Cge))
+}
+
+/// Check if two values are the same register or the same constant
+fn same_operand(val0: &Value, val1: &Value) -> bool
+{
+    match (val0, val1)
+    {
+        (Value::Literal(lit0), Value::Literal(lit1)) => lit0.value == lit1.value,
+        (Value::Symbol(symb0), Value::Symbol(symb1)) => symb0.title == symb1.title,
+        _ => false
+    }
+}
+
+/// Get whether a compare is known to hold or not, either because both operands are constants or because of a fact
+/// about the same operands
+fn known_condition(facts: &[(OpCode, Value, Value)], opcode: OpCode, val0: &Value, val1: &Value) -> Option<bool>
+{
+    if let (Value::Literal(lit0), Value::Literal(lit1)) = (val0, val1)
+    {
+        // Pointers are compared by address, which may depend on the target
+        if lit0.datatype.num_ptr > 0 || lit1.datatype.num_ptr > 0
+        {
+            return None;
+        }
+
+        let (value0, value1) = (lit0.datatype.wrap_value(lit0.value), lit1.datatype.wrap_value(lit1.value));
+
+        return Some(match opcode
+        {
+            OpCode::Ceq => value0 == value1,
+            OpCode::Cne => value0 != value1,
+            OpCode::Clt => value0 < value1,
+            OpCode::Cle => value0 <= value1,
+            OpCode::Cgt => value0 > value1,
+            _ => value0 >= value1
+        });
+    }
+
+    for (fact, fact0, fact1) in facts
+    {
+        let fact = if same_operand(fact0, val0) && same_operand(fact1, val1)
+        {
+            *fact
+        }
+        else if same_operand(fact0, val1) && same_operand(fact1, val0)
+        {
+            swapped_condition(*fact)
+        }
+        else
+        {
+            continue;
+        };
+
+        if implies_condition(fact, opcode)
+        {
+            return Some(true);
+        }
+        else if implies_condition(fact, negated_condition(opcode))
+        {
+            return Some(false);
+        }
+    }
+
+    None
+}
+
+/// Remove bounds checks which always pass, either because both sides are constants or because the same check has
+/// already been made earlier in the block
+pub fn optimization_bounds_checks(f: Function) -> Function
+{
+    let mut func = f.clone();
+
+    for index in 0..func.instructions.len()
+    {
+        let inst = match func.instructions.get(&index)
+        {
+            Some(inst) if inst.checked.is_some() && (inst.opcode == OpCode::Clt || inst.opcode == OpCode::Cle) => inst.clone(),
+            _ => continue
+        };
+
+        // A check which always fails must be kept, so it fails at runtime (constants are compared as the unsigned type
+        // of the check, as folded casts may leave them unwrapped)
+        if let (Value::Literal(lit0), Value::Literal(lit1)) = (&inst.arguments[1], &inst.arguments[2])
+        {
+            let value0 = lit0.datatype.wrap_value(lit0.value);
+            let value1 = lit1.datatype.wrap_value(lit1.value);
+
+            if (inst.opcode == OpCode::Clt && value0 < value1) || (inst.opcode == OpCode::Cle && value0 <= value1)
+            {
+                func.change_to_nop(index);
+            }
+
+            continue;
+        }
+
+        // Look back through the block for the same check, stopping at anything which could change its operands
+        for previous in (0..index).rev()
+        {
+            if func.labels.contains_key(&(previous + 1))
+            {
+                break;
+            }
+
+            let other = func.instructions.get(&previous).unwrap();
+
+            if other.checked.is_some() && other.opcode == inst.opcode && other.arguments[1..] == inst.arguments[1..]
+            {
+                func.change_to_nop(index);
+                break;
+            }
+
+            let writes_operand = other.arguments.first().is_some_and(|arg| inst.arguments[1..].contains(arg) ||
+                get_value_type(arg).is_some_and(|datatype| datatype.is_ref));
+
+            if other.opcode == OpCode::Call || func.get_jump_values(previous).is_some() || writes_operand
+            {
+                break;
+            }
+        }
+    }
+
+    func
+}
@@ -0,0 +1,167 @@
+use crate::irgen::{Function, Instruction, Value, DataType, NonPtrType, OpCode};
+use crate::irgen::{force_mutate_type, get_value_type, correct_type_references};
+
+use std::collections::HashMap;
+
+/// Check if all of the arguments of an instruction share a single type
+fn has_uniform_arguments(opcode: OpCode) -> bool
+{
+    !matches!(opcode, OpCode::Array | OpCode::Deref | OpCode::Ref | OpCode::Global | OpCode::Sext | OpCode::Zext |
+        OpCode::BitSet | OpCode::BitClr | OpCode::BitTst | OpCode::Memcpy | OpCode::Memset)
+}
+
+/// Get the type of an argument, using the inferred type for symbols whose type is unknown
+fn argument_type(arg: &Value, inferred: &HashMap<String, DataType>) -> Option<DataType>
+{
+    match arg
+    {
+        Value::Label(_) => None,
+        Value::Symbol(symb) if symb.datatype.raw_type == NonPtrType::Unknown => inferred.get(&symb.title).copied(),
+        _ => get_value_type(arg).map(correct_type_references).filter(|datatype| datatype.raw_type != NonPtrType::Unknown)
+    }
+}
+
+/// Get the common type of all of the known arguments of an instruction
+fn instruction_type(inst: &Instruction, inferred: &HashMap<String, DataType>) -> Option<DataType>
+{
+    if !has_uniform_arguments(inst.opcode)
+    {
+        return None;
+    }
+
+    inst.arguments.iter()
+        .filter_map(|arg| argument_type(arg, inferred))
+        .reduce(|datatype, next| datatype.common_type(&next))
+}
+
+/// Propagate the known types of values to symbols of unknown type used with them, until nothing changes
+fn propagate_types(func: &Function, inferred: &mut HashMap<String, DataType>)
+{
+    loop
+    {
+        let mut changed = false;
+
+        for i in 0..func.instructions.len()
+        {
+            let inst = func.instructions.get(&i).unwrap();
+
+            if let Some(datatype) = instruction_type(inst, inferred)
+            {
+                for arg in &inst.arguments
+                {
+                    if let Value::Symbol(symb) = arg
+                    {
+                        if symb.datatype.raw_type == NonPtrType::Unknown
+                        {
+                            let new_type = match inferred.get(&symb.title)
+                            {
+                                Some(current) => current.common_type(&datatype),
+                                None => datatype
+                            };
+
+                            if inferred.insert(symb.title.clone(), new_type) != Some(new_type)
+                            {
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if !changed
+        {
+            break;
+        }
+    }
+}
+
+/// Infer the types of values whose type is unknown from the values they are used with. Types are combined with the
+/// usual arithmetic conversions, so the result does not depend on the order of the arguments. Literals which are
+/// only used with values of unknown type (as in a condition of 1 < 2) have the type they have on their own
+pub fn infer_types(f: Function) -> Function
+{
+    let mut func = f.clone();
+
+    let mut inferred: HashMap<String, DataType> = HashMap::new();
+
+    propagate_types(&func, &mut inferred);
+
+    for i in 0..func.instructions.len()
+    {
+        if instruction_type(func.instructions.get(&i).unwrap(), &inferred).is_some()
+        {
+            continue;
+        }
+
+        for arg in func.instructions.get_mut(&i).unwrap().arguments.iter_mut()
+        {
+            if let Value::Literal(lit) = arg
+            {
+                if lit.datatype.raw_type == NonPtrType::Unknown
+                {
+                    lit.datatype = DataType::literal(lit.value);
+                }
+            }
+        }
+    }
+
+    // Values worked out from those literals take their types
+    propagate_types(&func, &mut inferred);
+
+    for i in 0..func.instructions.len()
+    {
+        let datatype = instruction_type(func.instructions.get(&i).unwrap(), &inferred);
+        let inst = func.instructions.get_mut(&i).unwrap();
+
+        for arg in inst.arguments.iter_mut()
+        {
+            let new_type = match arg
+            {
+                Value::Symbol(symb) => inferred.get(&symb.title).copied(),
+                _ => datatype
+            };
+
+            if let Some(new_type) = new_type
+            {
+                *arg = force_mutate_type(arg.clone(), new_type);
+            }
+        }
+    }
+
+    // Moves between integers of different types become explicit conversions
+    for i in 0..func.instructions.len()
+    {
+        let inst = func.instructions.get_mut(&i).unwrap();
+
+        if inst.opcode != OpCode::Mov && inst.opcode != OpCode::Alloc
+        {
+            continue;
+        }
+
+        let dest_type = get_value_type(&inst.arguments[0]).unwrap();
+        let src_type = correct_type_references(get_value_type(&inst.arguments[1]).unwrap());
+
+        let (dest_bits, src_bits) = match (correct_type_references(dest_type).bit_width(), src_type.bit_width())
+        {
+            (Some(dest_bits), Some(src_bits)) if correct_type_references(dest_type) != src_type => (dest_bits, src_bits),
+            _ => continue
+        };
+
+        if let Value::Literal(lit) = &mut inst.arguments[1]
+        {
+            lit.datatype = correct_type_references(dest_type);
+        }
+        else if dest_bits > src_bits
+        {
+            inst.opcode = if src_type.is_signed() {OpCode::Sext} else {OpCode::Zext};
+        }
+        // A cast into a reference changes the address it refers to, so only values can be truncated
+        else if !dest_type.is_ref
+        {
+            inst.opcode = OpCode::Cast;
+        }
+    }
+
+    func
+}
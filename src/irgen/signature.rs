@@ -0,0 +1,28 @@
+use super::{DataType, Attribute, type_from_ast, arguments_from_ast, function_attributes};
+
+use crate::cli::Error;
+use crate::parser::FunctionDecl;
+
+/// What a call needs to know about the function it calls, the types of the arguments it takes and of the value it
+/// gives
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature
+{
+    pub return_type: DataType,
+    pub arguments: Vec<DataType>,
+    pub noreturn: bool
+}
+
+impl Signature
+{
+    /// Get the signature of a function, which must not be generic
+    pub fn from_declaration(function: &FunctionDecl) -> Result<Self, Error>
+    {
+        Ok(Signature
+        {
+            return_type: type_from_ast(&function.ret)?,
+            arguments: arguments_from_ast(&function.params)?.into_iter().map(|(_, datatype)| datatype).collect(),
+            noreturn: function_attributes(function)?.contains(&Attribute::NoReturn)
+        })
+    }
+}
@@ -0,0 +1,261 @@
+use super::{Expression, ExpressionType, Function, Value, Literal, Symbol, Instruction, OpCode, DataType, NonPtrType};
+
+use super::{get_value_type, correct_type_references, attempt_mutate_type, check_address_space, compiler_error_loc};
+
+use crate::cli::{Error, ErrorCode};
+
+use crate::tokenizer::Span;
+
+use std::cell::RefCell;
+
+/// Get the name of the symbol which holds the length of a slice
+pub fn slice_length_name(name: &str) -> String
+{
+    format!("{}.len", name)
+}
+
+/// Get the symbol which holds the length of a slice, slices are always held in symbols
+pub fn slice_length(slice: &Value) -> Value
+{
+    match slice
+    {
+        Value::Symbol(symb) => Value::Symbol(Symbol::new(slice_length_name(&symb.title), DataType::slice_length())),
+        _ => unreachable!("Slices are always held in symbols")
+    }
+}
+
+/// Expand the slices in a list of arguments, a slice is passed as the pointer to its elements followed by its length
+pub fn expand_slice_arguments(arguments: Vec<(String, DataType)>) -> Vec<(String, DataType)>
+{
+    let mut result = vec![];
+
+    for (name, datatype) in arguments
+    {
+        result.push((name.clone(), datatype));
+
+        if datatype.is_slice
+        {
+            result.push((slice_length_name(&name), DataType::slice_length()));
+        }
+    }
+
+    result
+}
+
+impl Expression
+{
+    /// Get the length of the global array the expression refers to, if it refers to one (this must be checked before
+    /// the expression is rendered, after which only the address of the array is known)
+    pub fn array_length(&self, func: &RefCell<&mut Function>) -> Option<Value>
+    {
+        match (&self.mode, &self.value)
+        {
+            (ExpressionType::Global(_), Some(Value::Label(name))) => func.borrow().globals.values()
+                .find(|global| global.name == *name)
+                .and_then(|global| global.length)
+                .map(|length| Value::Literal(Literal::new(length as i128, DataType::slice_length()))),
+            _ => None
+        }
+    }
+
+    /// Check if the expression is a slice (only known before rendering for symbols)
+    pub fn is_slice(&self) -> bool
+    {
+        matches!(&self.value, Some(value) if get_value_type(value).is_some_and(|datatype| datatype.is_slice))
+    }
+
+    /// Render an expression used as a slice, giving the pointer to its elements and its length (global arrays are
+    /// used as a slice of all of their elements)
+    pub fn render_slice(&mut self, func: &RefCell<&mut Function>) -> Result<(Value, Value), Error>
+    {
+        let length = self.array_length(func);
+
+        self.render(func)?;
+        let value = self.value(func)?;
+
+        let datatype = correct_type_references(get_value_type(&value).unwrap());
+
+        match length
+        {
+            _ if datatype.is_slice => Ok((value.clone(), slice_length(&value))),
+            Some(length) => Ok((value, length)),
+            None => compiler_error_loc(ErrorCode::E0021, format!("Expected a slice, got '{}'", datatype), &self.location())
+        }
+    }
+
+    /// Ensure the pointer of a slice can be stored in a slice of the given type
+    pub fn check_slice_type(target: DataType, value: &Value, pos: &Option<Span>) -> Result<(), Error>
+    {
+        let mut datatype = correct_type_references(get_value_type(value).unwrap());
+        datatype.is_slice = true;
+
+        check_address_space(&target, &datatype, pos)?;
+
+        if datatype != correct_type_references(target)
+        {
+            compiler_error_loc(ErrorCode::E0021, format!("Cannot convert '{}' to '{}'", datatype, correct_type_references(target)), pos)?;
+        }
+
+        Ok(())
+    }
+
+    /// Render the base and index of an array access, giving the pointer, the index and the type of the pointer. The
+    /// index is checked to be within the length of slices and global arrays if bounds checking is enabled
+    pub fn render_element(&mut self, func: &RefCell<&mut Function>) -> Result<(Value, Value, DataType), Error>
+    {
+        let length = self.children[0].array_length(func);
+
+        self.children[0].render(func)?;
+        self.children[1].render(func)?;
+
+        let ptr = self.children[0].value(func)?;
+        let index = self.children[1].value(func)?;
+
+        let datatype = correct_type_references(get_value_type(&ptr).unwrap());
+
+        if datatype.num_ptr == 0
+        {
+            compiler_error_loc(ErrorCode::E0023, format!("Cannot index into '{}'", datatype), &self.location())?;
+        }
+
+        let length = if datatype.is_slice {Some(slice_length(&ptr))} else {length};
+
+        if let Some(length) = length
+        {
+            Expression::bounds_check(OpCode::Clt, index.clone(), length, func, &self.location())?;
+        }
+
+        Ok((ptr, attempt_mutate_type(index, DataType::new(NonPtrType::U64, 0, false)), datatype))
+    }
+
+    /// Render a range of the elements of a slice, array or pointer as a new slice (a pointer has no length, so the
+    /// end of the range must be given)
+    pub fn render_subslice(&mut self, has_end: bool, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let length = self.children[0].array_length(func);
+
+        self.children[0].render(func)?;
+        let base = self.children[0].value(func)?;
+
+        let base_type = correct_type_references(get_value_type(&base).unwrap());
+
+        if base_type.num_ptr == 0
+        {
+            compiler_error_loc(ErrorCode::E0023, format!("Cannot take a slice of '{}'", base_type), &self.location())?;
+        }
+
+        let length = if base_type.is_slice {Some(slice_length(&base))} else {length};
+
+        self.children[1].render(func)?;
+        let start = self.children[1].value(func)?;
+
+        let end = if has_end
+        {
+            self.children[2].render(func)?;
+            self.children[2].value(func)?
+        }
+        else
+        {
+            match &length
+            {
+                Some(length) => length.clone(),
+                None => compiler_error_loc(ErrorCode::E0023, format!("The end of a slice of '{}' must be given, a pointer has no length", base_type), &self.location())?
+            }
+        };
+
+        // The range must not be reversed, and must end within the elements
+        Expression::bounds_check(OpCode::Cle, start.clone(), end.clone(), func, &self.location())?;
+
+        if let Some(length) = length.filter(|_| has_end)
+        {
+            Expression::bounds_check(OpCode::Cle, end.clone(), length, func, &self.location())?;
+        }
+
+        let start = Expression::implicit_conversion(start, DataType::slice_length(), func, &self.location(), true);
+        let end = Expression::implicit_conversion(end, DataType::slice_length(), func, &self.location(), true);
+
+        let mut slice_type = base_type;
+        slice_type.is_slice = true;
+
+        let value = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), slice_type));
+
+        func.borrow_mut().add_instruction(Instruction::new(OpCode::Add, vec![
+            value.clone(),
+            base,
+            start.clone()
+            ]));
+
+        func.borrow_mut().add_arithmetic_instruction(OpCode::Sub, vec![
+            slice_length(&value),
+            end,
+            start
+            ], self.location())?;
+
+        self.value = Some(value);
+
+        Ok(())
+    }
+
+    /// Check at runtime that a value compares to a limit (with Clt for an index below a length, or Cle for the end of
+    /// a range), reporting the given location if it does not. Nothing is checked unless bounds checking is enabled
+    pub fn bounds_check(opcode: OpCode, value: Value, limit: Value, func: &RefCell<&mut Function>, pos: &Option<Span>) -> Result<(), Error>
+    {
+        if !func.borrow().bounds_check
+        {
+            return Ok(());
+        }
+
+        let location = match pos
+        {
+            Some(span) => span.start.clone(),
+            None => return compiler_error_loc(ErrorCode::E0036, format!("Bounds check '{:?}' has no location to report", opcode), &None)
+        };
+
+        // Both sides are compared as unsigned integers wide enough for either of them, so negative values are out of
+        // range rather than below the limit
+        let operand_bits = |operand: &Value| match operand
+        {
+            Value::Literal(lit) if lit.datatype.raw_type == NonPtrType::Unknown =>
+            {
+                if DataType::slice_length().can_represent(lit.value) {16} else {64}
+            },
+            _ => correct_type_references(get_value_type(operand).unwrap()).bit_width().unwrap_or(64)
+        };
+
+        let bits = operand_bits(&value).max(operand_bits(&limit)).max(16);
+        let datatype = DataType::integer(bits, false).unwrap();
+
+        let value = Expression::unsigned_operand(value, datatype, func, pos);
+        let limit = Expression::unsigned_operand(limit, datatype, func, pos);
+
+        let result = Value::Symbol(Symbol::new(func.borrow_mut().get_register(), DataType::new(NonPtrType::U8, 0, false)));
+
+        func.borrow_mut().add_instruction(Instruction::new_checked(opcode, vec![
+            result,
+            value,
+            limit
+            ], location));
+
+        Ok(())
+    }
+
+    /// Convert an operand of a bounds check to the unsigned type it is compared as
+    fn unsigned_operand(value: Value, datatype: DataType, func: &RefCell<&mut Function>, pos: &Option<Span>) -> Value
+    {
+        if let Value::Literal(lit) = &value
+        {
+            return Value::Literal(Literal::new(datatype.wrap_value(lit.value), datatype));
+        }
+
+        let source = correct_type_references(get_value_type(&value).unwrap());
+
+        // Signed values are extended by their sign before they are reinterpreted as unsigned
+        let value = match DataType::integer(datatype.bit_width().unwrap(), true)
+        {
+            Some(signed) if source.is_signed() => Expression::implicit_conversion(value, signed, func, pos, false),
+            _ => value
+        };
+
+        Expression::implicit_conversion(value, datatype, func, pos, false)
+    }
+}
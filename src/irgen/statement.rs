@@ -1,13 +1,14 @@
-use super::{Function, Value, Literal, Expression, Instruction, OpCode, Symbol, attempt_mutate_type, type_from_parse_tree, identifier_from_parse_tree};
+use super::{Function, Value, Literal, Expression, Instruction, OpCode, Symbol, type_from_ast};
+use super::{get_value_type, correct_type_references, check_address_space, check_progmem_pointer, slice_length, slice_length_name};
 
-use crate::cli::Error;
+use crate::cli::{Error, ErrorCode};
 
-use super::{expected_got_error, compiler_error, compiler_error_loc};
+use super::compiler_error_loc;
 use super::{DataType, NonPtrType};
 
-use crate::parser::ParseTreeNode;
+use crate::parser::{Stmt, InitValue};
 
-use crate::tokenizer::{Token, FileLocation};
+use crate::tokenizer::Span;
 
 use std::cell::RefCell;
 
@@ -32,11 +33,11 @@ pub enum StatementType
 #[derive(Debug, Clone)]
 pub struct Statement
 {
-    mode: StatementType,
+    pub mode: StatementType,
     pub expr: Option<Expression>,
-    children: Vec<Statement>,
-    pos: Option<FileLocation>,
-    init_data: Option<(DataType, String)>
+    pub children: Vec<Statement>,
+    pub pos: Option<Span>,
+    pub init_data: Option<(DataType, String)>
 }
 
 impl Statement
@@ -54,15 +55,15 @@ impl Statement
         }
     }
 
-    /// Generate a new IR Statement with a token
-    pub fn new_with_token(mode: StatementType, token: &Token) -> Self
+    /// Generate a new IR Statement covering a span of the source
+    pub fn new_with_span(mode: StatementType, span: Span) -> Self
     {
         Self
         {
             mode,
             expr: None,
             children: vec![],
-            pos: Some(token.location.clone()),
+            pos: Some(span),
             init_data: None
         }
     }
@@ -73,184 +74,244 @@ impl Statement
         self.children.push(child);
     }
 
-    /// Generate a new IR Statement from a parse tree node
-    pub fn from_parse_tree_node(node: ParseTreeNode, func: &RefCell<&mut Function>) -> Result<Self, Error>
+    /// Generate a new IR Statement from a statement of the syntax tree, which covers all of the source of the statement
+    pub fn from_ast(stmt: &Stmt, func: &RefCell<&mut Function>) -> Result<Self, Error>
     {
-        match &node
+        let mut result = Statement::from_stmt(stmt, func)?;
+        result.pos = Some(stmt.span());
+
+        Ok(result)
+    }
+
+    /// Generate a new IR Statement from a statement of the syntax tree
+    fn from_stmt(stmt: &Stmt, func: &RefCell<&mut Function>) -> Result<Self, Error>
+    {
+        match stmt
         {
-            ParseTreeNode::Empty =>
+            Stmt::Empty(_) =>
             {
                 Ok(Statement::new(StatementType::Empty))
             },
-            ParseTreeNode::Statement(children) =>
+            Stmt::Continue(_) =>
             {
-                // If the length of the children is zero, it is a noop
-                if children.len() == 0
-                {
-                    Ok(Statement::new(StatementType::Empty))
-                }
-                else
-                {
-                    match &children[0]
-                    {
-                        ParseTreeNode::RawToken(token) =>
-                        {
-                            if token.data == "continue"
-                            {
-                                Ok(Statement::new_with_token(StatementType::ContinueStatement, &token))
-                            }
-                            else if token.data == "break"
-                            {
-                                Ok(Statement::new_with_token(StatementType::BreakStatement, &token))
-                            }
-                            else
-                            {
-                                compiler_error(format!("Expected 'continue' or 'break', got '{}'", token.data))?;
-                                unreachable!();
-                            }
-                        },
-                        ParseTreeNode::Expression(_, _) =>
-                        {
-                            let mut result = Statement::new(StatementType::ExpressionStatement);
+                Ok(Statement::new(StatementType::ContinueStatement))
+            },
+            Stmt::Break(_) =>
+            {
+                Ok(Statement::new(StatementType::BreakStatement))
+            },
+            Stmt::Expr(expr, _) =>
+            {
+                let mut result = Statement::new(StatementType::ExpressionStatement);
 
-                            result.expr = Some(Expression::from_parse_tree_node(children[0].clone(), func)?);
+                result.expr = Some(Expression::from_ast(expr, func)?);
 
-                            Ok(result)
-                        }
-                        default =>
-                        {
-                            expected_got_error("RawToken or Expression", default.clone())?;
-                            unreachable!();
-                        }
-                    }
-                }
+                Ok(result)
             },
             // Compound Statement
-            ParseTreeNode::Statements(children) =>
+            Stmt::Block(stmts, _) =>
             {
                 let mut result = Statement::new(StatementType::CompoundStatement);
 
-                for child in children
+                for child in stmts
                 {
-                    result.add_child(Statement::from_parse_tree_node(child.clone(), func)?)
+                    result.add_child(Statement::from_ast(child, func)?)
                 }
 
                 Ok(result)
             },
             // Return Statement
-            ParseTreeNode::ReturnStatement(children) =>
+            Stmt::Return {value, ..} =>
             {
                 let mut result = Statement::new(StatementType::ReturnStatement);
 
-                result.expr = Some(Expression::from_parse_tree_node(children[0].clone(), func)?);
+                result.expr = Some(Expression::from_ast(value, func)?);
 
                 Ok(result)
             },
             // Loop Statement
-            ParseTreeNode::Loop(children) =>
+            Stmt::Loop {body, ..} =>
             {
                 let mut result = Statement::new(StatementType::LoopStatement);
 
-                result.add_child(Statement::from_parse_tree_node(children[0].clone(), func)?);
+                result.add_child(Statement::from_ast(body, func)?);
 
                 Ok(result)
             },
             // If Statement
-            ParseTreeNode::IfStatement(children) =>
+            Stmt::If {cond, body, otherwise, ..} =>
             {
                 let mut result = Statement::new(StatementType::IfStatement);
 
                 // Condition
-                result.expr = Some(Expression::from_parse_tree_node(children[0].clone(), func)?);
+                result.expr = Some(Expression::from_ast(cond, func)?);
 
                 // Body
-                result.add_child(Statement::from_parse_tree_node(children[1].clone(), func)?);
+                result.add_child(Statement::from_ast(body, func)?);
                 
                 // Clause
-                result.add_child(Statement::from_parse_tree_node(children[2].clone(), func)?);
+                result.add_child(match otherwise
+                {
+                    Some(otherwise) => Statement::from_ast(otherwise, func)?,
+                    None => Statement::new(StatementType::Empty)
+                });
 
                 Ok(result)
             },
             // While Statement
-            ParseTreeNode::WhileLoop(children) =>
+            Stmt::While {cond, body, ..} =>
             {
                 let mut result = Statement::new(StatementType::WhileStatement);
 
                 // Condition
-                result.expr = Some(Expression::from_parse_tree_node(children[0].clone(), func)?);
+                result.expr = Some(Expression::from_ast(cond, func)?);
 
                 // Body
-                result.add_child(Statement::from_parse_tree_node(children[1].clone(), func)?);
+                result.add_child(Statement::from_ast(body, func)?);
 
                 Ok(result)
             },
             // Do While Statement
-            ParseTreeNode::DoWhileLoop(children) =>
+            Stmt::DoWhile {body, cond, ..} =>
             {
                 let mut result = Statement::new(StatementType::DoWhileStatement);
 
                 // Condition
-                result.expr = Some(Expression::from_parse_tree_node(children[0].clone(), func)?);
+                result.expr = Some(Expression::from_ast(cond, func)?);
 
                 // Body
-                result.add_child(Statement::from_parse_tree_node(children[1].clone(), func)?);
+                result.add_child(Statement::from_ast(body, func)?);
                 
                 Ok(result)
             },
             // Initialization
-            ParseTreeNode::AssignmentStatement(children) =>
+            Stmt::Declaration {datatype, declarators, ..} =>
             {
                 let mut result = Statement::new(StatementType::CompoundStatement);
 
-                let datatype = type_from_parse_tree(children[0].clone())?;
+                // Declarations with let have the type of their value, which is known once it is rendered
+                let datatype = match datatype
+                {
+                    None => DataType::new(NonPtrType::Unknown, 0, false),
+                    Some(datatype) => type_from_ast(datatype)?
+                };
 
-                match &children[1]
+                for declarator in declarators
                 {
-                    ParseTreeNode::Assignments(assignment_children) =>
+                    let mut temp = Statement::new_with_span(StatementType::InitializationStatement, declarator.name.span.clone());
+                    let s = declarator.name.name.clone();
+
+                    check_progmem_pointer(&datatype, &temp.pos)?;
+
+                    temp.init_data = Some((datatype, s.clone()));
+
+                    match &declarator.value
                     {
-                        for child in assignment_children
+                        // The statements of a block come before its value, and anything declared in the block is only
+                        // visible inside of it
+                        InitValue::Block(block) =>
                         {
-                            match child
+                            let scope = func.borrow().symbol_table.clone();
+
+                            for item in &block.stmts
                             {
-                                ParseTreeNode::Assignment(assignment_data) =>
-                                {
-                                    let mut temp = Statement::new(StatementType::InitializationStatement);
+                                temp.add_child(Statement::from_ast(item, func)?);
+                            }
 
-                                    let s = identifier_from_parse_tree(assignment_data[0].clone())?;
+                            temp.expr = Some(match &block.value
+                            {
+                                Some(value) => Expression::from_ast(value, func)?,
+                                None => compiler_error_loc(ErrorCode::E0036, String::from("Block without a value"), &Some(block.span.clone()))?
+                            });
 
-                                    temp.init_data = Some((datatype, s.clone()));
-                                    temp.expr = Some(Expression::from_parse_tree_node(assignment_data[1].clone(), func)?);
+                            func.borrow_mut().symbol_table = scope;
+                        },
+                        InitValue::Expr(value) => temp.expr = Some(Expression::from_ast(value, func)?)
+                    }
 
-                                    func.borrow_mut().symbol_table.insert(s.clone(), Symbol::new(s.clone(), datatype.clone()));
+                    func.borrow_mut().symbol_table.insert(s.clone(), Symbol::new(s.clone(), datatype.clone()));
+                    func.borrow_mut().declarations.entry(s.clone()).or_insert(temp.pos.clone());
 
-                                    result.add_child(temp);
-                                },
-                                default =>
-                                {
-                                    expected_got_error("an assignment", default.clone())?;
-                                    unreachable!();
-                                }
-                            }
-                        }
-                    },
-                    default =>
+                    // Slices are stored along with their length
+                    if datatype.is_slice
                     {
-                        expected_got_error("assignments", default.clone())?;
-                        unreachable!();
+                        func.borrow_mut().symbol_table.insert(slice_length_name(&s), Symbol::new(slice_length_name(&s), DataType::slice_length()));
                     }
+
+                    result.add_child(temp);
                 }
 
                 Ok(result)
+            },
+            // Code with a syntax error is never compiled, as the syntax error stops compilation
+            Stmt::Error(_, span) =>
+            {
+                compiler_error_loc(ErrorCode::E0036, String::from("Cannot compile a statement with a syntax error"), &Some(span.clone()))
             }
-            default =>
+        }
+    }
+
+    /// Render the initialization of a symbol declared with let, which takes the type of its value (constants without
+    /// a type are i32)
+    fn render_inferred(e: &mut Expression, symbol: &mut Symbol, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        e.render(func)?;
+        let mut value = e.value(func)?;
+
+        symbol.datatype = correct_type_references(get_value_type(&value).unwrap());
+
+        if symbol.datatype.raw_type == NonPtrType::Unknown
+        {
+            // Calls to void functions and to functions which are linked in give values of no known type
+            if !matches!(value, Value::Literal(_))
             {
-                expected_got_error("a statement", default.clone())
+                compiler_error_loc(ErrorCode::E0019, format!("Cannot infer the type of '{}', give it a type instead of let", symbol.title), &e.location())?;
             }
+
+            symbol.datatype = DataType::new(NonPtrType::I32, 0, false);
+            value = Expression::implicit_conversion(value, symbol.datatype, func, &e.location(), true);
+        }
+
+        func.borrow_mut().symbol_table.insert(symbol.title.clone(), symbol.clone());
+
+        func.borrow_mut().add_instruction(Instruction::new(OpCode::Alloc, vec![
+            Value::Symbol(symbol.clone()),
+            value.clone()
+            ]));
+
+        // Slices are stored along with their length
+        if symbol.datatype.is_slice
+        {
+            let length = slice_length_name(&symbol.title);
+
+            func.borrow_mut().symbol_table.insert(length.clone(), Symbol::new(length, DataType::slice_length()));
+            func.borrow_mut().add_instruction(Instruction::new(OpCode::Alloc, vec![
+                slice_length(&Value::Symbol(symbol.clone())),
+                slice_length(&value)
+                ]));
         }
+
+        Ok(())
     }
 
-    /// Render the statement
+    /// Render the statement, marking the instructions it adds with its span
     pub fn render(&self, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let outer = func.borrow().current_span.clone();
+
+        if self.pos.is_some()
+        {
+            func.borrow_mut().current_span = self.pos.clone();
+        }
+
+        let result = self.render_instructions(func);
+        func.borrow_mut().current_span = outer;
+
+        result
+    }
+
+    /// Render the instructions of the statement
+    fn render_instructions(&self, func: &RefCell<&mut Function>) -> Result<(), Error>
     {
         match self.mode
         {
@@ -277,7 +338,7 @@ impl Statement
                     },
                     None => 
                     {
-                        compiler_error_loc(format!("Cannot use continue statement outside of loop"), &self.pos)?
+                        compiler_error_loc(ErrorCode::E0027, format!("Cannot use continue statement outside of loop"), &self.pos)?
                     }
                 }
             },
@@ -293,19 +354,51 @@ impl Statement
                     },
                     None => 
                     {
-                        compiler_error_loc(format!("Cannot use break statement outside of loop"), &self.pos)?
+                        compiler_error_loc(ErrorCode::E0027, format!("Cannot use break statement outside of loop"), &self.pos)?
                     }
                 }
             },
             StatementType::InitializationStatement =>
             {
                 let mut e = self.expr.clone().unwrap();
-                let symbol = func.borrow_mut().symbol_table.get(&self.init_data.clone().unwrap().1).unwrap().clone();
+                let (datatype, name) = self.init_data.clone().unwrap();
+                let mut symbol = Symbol::new(name, datatype);
+
+                // Statements of a block giving the value
+                for child in &self.children
+                {
+                    child.render(func)?;
+                }
+
+                if symbol.datatype.raw_type == NonPtrType::Unknown
+                {
+                    return Statement::render_inferred(&mut e, &mut symbol, func);
+                }
+
+                if symbol.datatype.is_slice
+                {
+                    let (ptr, length) = e.render_slice(func)?;
+
+                    Expression::check_slice_type(symbol.datatype, &ptr, &self.pos)?;
+
+                    func.borrow_mut().add_instruction(Instruction::new(OpCode::Alloc, vec![
+                        Value::Symbol(symbol.clone()),
+                        ptr
+                        ]));
+                    func.borrow_mut().add_instruction(Instruction::new(OpCode::Alloc, vec![
+                        slice_length(&Value::Symbol(symbol)),
+                        length
+                        ]));
+
+                    return Ok(());
+                }
 
                 // Render the expression
                 e.render(func.clone())?;
 
-                let value = attempt_mutate_type(e.value(func)?, symbol.datatype);
+                let value = Expression::implicit_conversion(e.value(func)?, symbol.datatype, func, &self.pos, true);
+
+                check_address_space(&symbol.datatype, &get_value_type(&value).unwrap(), &self.pos)?;
 
                 func.borrow_mut().add_instruction(Instruction::new(OpCode::Alloc, vec![
                     Value::Symbol(symbol),
@@ -433,8 +526,10 @@ impl Statement
                 e.render(func.clone())?;
 
                 // Then add the return statement
-                let val = e.value(func)?;
                 let ret_val = func.borrow().return_value.clone();
+                let val = Expression::implicit_conversion(e.value(func)?, get_value_type(&ret_val).unwrap(), func, &e.location(), true);
+
+                check_address_space(&get_value_type(&ret_val).unwrap(), &get_value_type(&val).unwrap(), &e.location())?;
                 func.borrow_mut().add_instruction(Instruction::new(OpCode::Mov, vec![ret_val, val]));
 
                 // Then jump to the exit
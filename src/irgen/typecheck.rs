@@ -0,0 +1,612 @@
+use super::{Statement, StatementType, Expression, ExpressionType, Function, Value, Builtin, DataType, NonPtrType, OpCode};
+use super::{correct_type_references, check_address_space, method_key, compiler_error_loc};
+
+use crate::cli::{Error, ErrorCode};
+use crate::tokenizer::Span;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// State of the type checker, which works out the type of every expression of a function before it is rendered
+struct TypeChecker<'a>
+{
+    func: &'a Function,
+    inferred: HashMap<String, DataType>
+}
+
+impl Statement
+{
+    /// Check the types of the statements of a function, reporting operands which don't fit their operator, values of
+    /// the wrong type and calls with the wrong arguments before any code is generated for them
+    pub fn check_types(&self, func: &RefCell<&mut Function>) -> Result<(), Error>
+    {
+        let function = func.borrow();
+
+        let mut checker = TypeChecker
+        {
+            func: &function,
+            inferred: HashMap::new()
+        };
+
+        checker.statement(self)
+    }
+}
+
+/// Get the type of an expression which has no value, such as a call to a void function
+fn no_value() -> DataType
+{
+    DataType::new(NonPtrType::Void, 0, false)
+}
+
+/// Get the type of an expression whose type isn't known until it is rendered, such as an integer literal
+fn unknown() -> DataType
+{
+    DataType::new(NonPtrType::Unknown, 0, false)
+}
+
+/// Is the type known before rendering
+fn is_known(datatype: &DataType) -> bool
+{
+    datatype.raw_type != NonPtrType::Unknown
+}
+
+/// Is the type a pointer (or a slice)
+fn is_pointer(datatype: &DataType) -> bool
+{
+    datatype.num_ptr > 0
+}
+
+/// Is the type a pointer to void, which can't be dereferenced or moved
+fn is_void_pointer(datatype: &DataType) -> bool
+{
+    datatype.num_ptr == 1 && datatype.raw_type == NonPtrType::Void
+}
+
+impl TypeChecker<'_>
+{
+    /// Check the types of a statement and the statements in it
+    fn statement(&mut self, statement: &Statement) -> Result<(), Error>
+    {
+        match statement.mode
+        {
+            StatementType::InitializationStatement =>
+            {
+                // Statements of a block giving the value
+                for child in &statement.children
+                {
+                    self.statement(child)?;
+                }
+
+                let expr = statement.expr.as_ref().unwrap();
+                let (datatype, name) = statement.init_data.clone().unwrap();
+                let value_type = self.value(expr)?;
+
+                // Declarations with let take the type of their value, constants without a type are i32
+                if datatype.raw_type == NonPtrType::Unknown
+                {
+                    let inferred = if expr.mode == ExpressionType::IntegerLiteral {DataType::new(NonPtrType::I32, 0, false)} else {value_type};
+
+                    self.inferred.insert(name, correct_type_references(inferred));
+                }
+                else
+                {
+                    self.convert(datatype, value_type, expr)?;
+                }
+            },
+            StatementType::ReturnStatement =>
+            {
+                let expr = statement.expr.as_ref().unwrap();
+                let return_type = self.func.return_type;
+
+                if return_type == no_value()
+                {
+                    compiler_error_loc(ErrorCode::E0021, format!("Cannot return a value from '{}', which returns void", self.func.name), &expr.location())?;
+                }
+
+                let value_type = self.value(expr)?;
+
+                self.convert(return_type, value_type, expr)?;
+            },
+            StatementType::ExpressionStatement =>
+            {
+                self.expression(statement.expr.as_ref().unwrap())?;
+            },
+            _ =>
+            {
+                // Conditions
+                if let Some(expr) = &statement.expr
+                {
+                    self.value(expr)?;
+                }
+
+                for child in &statement.children
+                {
+                    self.statement(child)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the type of an expression which is used as a value
+    fn value(&mut self, expression: &Expression) -> Result<DataType, Error>
+    {
+        let datatype = self.expression(expression)?;
+
+        if datatype == no_value()
+        {
+            compiler_error_loc(ErrorCode::E0025, String::from("Expression does not have a value"), &expression.location())?;
+        }
+
+        Ok(datatype)
+    }
+
+    /// Get the type of an expression, checking the types of the expressions in it
+    fn expression(&mut self, expression: &Expression) -> Result<DataType, Error>
+    {
+        let pos = expression.location();
+
+        let datatype = match expression.mode.clone()
+        {
+            ExpressionType::IntegerLiteral => unknown(),
+            ExpressionType::Identifier => match &expression.value
+            {
+                // Symbols declared with let have the type of their value
+                Some(Value::Symbol(symbol)) if !is_known(&symbol.datatype) => self.inferred.get(&symbol.title).copied().unwrap_or(unknown()),
+                Some(Value::Symbol(symbol)) => symbol.datatype,
+                _ => unknown()
+            },
+            ExpressionType::Global(datatype) => datatype,
+            ExpressionType::UnaryMinus | ExpressionType::BitwiseNot =>
+            {
+                let datatype = self.value(&expression.children[0])?;
+
+                if is_pointer(&datatype)
+                {
+                    let operation = if expression.mode == ExpressionType::UnaryMinus {"negate"} else {"invert the bits of"};
+
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot {} '{}', only integers can be", operation, datatype), &pos)?;
+                }
+
+                datatype
+            },
+            ExpressionType::BinaryExpression(opcode) =>
+            {
+                let type0 = self.value(&expression.children[0])?;
+                let type1 = self.value(&expression.children[1])?;
+
+                self.binary(opcode, type0, type1, &pos)?
+            },
+            ExpressionType::AssignmentExpression(None) =>
+            {
+                let target = self.expression(&expression.children[0])?;
+                let value_type = self.value(&expression.children[1])?;
+
+                self.convert(target, value_type, &expression.children[1])?;
+
+                target
+            },
+            ExpressionType::AssignmentExpression(Some(opcode)) =>
+            {
+                let target = self.value(&expression.children[0])?;
+                let value_type = self.value(&expression.children[1])?;
+
+                let result = correct_type_references(self.binary(opcode, target, value_type, &pos)?);
+
+                // Compound assignment to a pointer moves it by a number of elements
+                if is_pointer(&result) && result != correct_type_references(target)
+                {
+                    compiler_error_loc(ErrorCode::E0021, format!("Cannot assign '{}' to '{}'", result, correct_type_references(target)), &pos)?;
+                }
+
+                target
+            },
+            ExpressionType::PreExpression(_) | ExpressionType::PostExpression(_) =>
+            {
+                let datatype = correct_type_references(self.value(&expression.children[0])?);
+
+                if datatype.is_slice
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot increment or decrement the slice '{}', index it or take a range of it instead", datatype), &pos)?;
+                }
+                else if is_void_pointer(&datatype)
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot perform arithmetic on '{}'", datatype), &pos)?;
+                }
+
+                datatype
+            },
+            ExpressionType::ArrayAccess | ExpressionType::ArrayAccessLeft =>
+            {
+                let base = correct_type_references(self.value(&expression.children[0])?);
+                let index = self.value(&expression.children[1])?;
+
+                if !is_known(&base)
+                {
+                    return Ok(unknown());
+                }
+
+                if !is_pointer(&base)
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Cannot index into '{}'", base), &pos)?;
+                }
+                else if is_void_pointer(&base)
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Cannot index into '{}', the type of its elements isn't known", base), &pos)?;
+                }
+
+                if is_pointer(&index)
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Cannot index into '{}' with '{}', the index must be an integer", base, correct_type_references(index)), &expression.children[1].location())?;
+                }
+
+                base.dereferenced()
+            },
+            ExpressionType::Subslice(_) =>
+            {
+                let mut base = correct_type_references(self.value(&expression.children[0])?);
+
+                for child in &expression.children[1..]
+                {
+                    if is_pointer(&self.value(child)?)
+                    {
+                        compiler_error_loc(ErrorCode::E0023, format!("The range of a slice of '{}' must be given by integers", base), &child.location())?;
+                    }
+                }
+
+                if !is_known(&base)
+                {
+                    return Ok(unknown());
+                }
+
+                if !is_pointer(&base)
+                {
+                    compiler_error_loc(ErrorCode::E0023, format!("Cannot take a slice of '{}'", base), &pos)?;
+                }
+
+                base.is_slice = true;
+                base
+            },
+            ExpressionType::SliceLength =>
+            {
+                self.value(&expression.children[0])?;
+
+                DataType::slice_length()
+            },
+            ExpressionType::BitRange(_, _) => self.value(&expression.children[0])?,
+            ExpressionType::BitRangeAssignment(_, _) =>
+            {
+                self.value(&expression.children[1])?;
+                self.value(&expression.children[0])?
+            },
+            ExpressionType::Builtin(builtin) => self.builtin(builtin, expression)?,
+            ExpressionType::Comma =>
+            {
+                self.expression(&expression.children[0])?;
+                self.expression(&expression.children[1])?
+            },
+            ExpressionType::Cast(datatype) =>
+            {
+                self.value(&expression.children[0])?;
+
+                datatype
+            },
+            ExpressionType::Ternary =>
+            {
+                self.value(&expression.children[0])?;
+
+                let type0 = correct_type_references(self.value(&expression.children[1])?);
+                let type1 = correct_type_references(self.value(&expression.children[2])?);
+
+                // Either value may be chosen, so pointers must have the same type
+                if is_known(&type0) && is_known(&type1) && (is_pointer(&type0) || is_pointer(&type1)) && type0 != type1
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Mismatched types, cannot choose between '{}' and '{}' without a cast", type0, type1), &pos)?;
+                }
+
+                type0.common_type(&type1)
+            },
+            // The result of a condition is a constant of no particular type
+            ExpressionType::LogicalNot | ExpressionType::LogicalAnd | ExpressionType::LogicalOr =>
+            {
+                for child in &expression.children
+                {
+                    self.value(child)?;
+                }
+
+                unknown()
+            },
+            ExpressionType::UnaryOperation(OpCode::Deref, _) | ExpressionType::DereferenceLeft =>
+            {
+                let datatype = correct_type_references(self.value(&expression.children[0])?);
+
+                if !is_known(&datatype)
+                {
+                    return Ok(unknown());
+                }
+
+                if datatype.is_slice
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot dereference the slice '{}', index it or take a range of it instead", datatype), &pos)?;
+                }
+                else if !is_pointer(&datatype)
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot dereference '{}', only pointers can be dereferenced", datatype), &pos)?;
+                }
+                else if is_void_pointer(&datatype)
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot dereference '{}', the type it points to isn't known", datatype), &pos)?;
+                }
+
+                datatype.dereferenced()
+            },
+            ExpressionType::UnaryOperation(_, delta) =>
+            {
+                let mut datatype = correct_type_references(self.value(&expression.children[0])?);
+
+                if datatype.is_slice
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot take the address of the slice '{}', index it or take a range of it instead", datatype), &pos)?;
+                }
+
+                if !is_known(&datatype)
+                {
+                    return Ok(unknown());
+                }
+
+                datatype.num_ptr = (datatype.num_ptr as isize + delta) as usize;
+                datatype
+            },
+            ExpressionType::FunctionCall =>
+            {
+                let name = match &expression.value
+                {
+                    Some(Value::Label(name)) => name.clone(),
+                    _ => unreachable!()
+                };
+
+                self.call(&name, &name, &expression.children, &expression.pos)?
+            },
+            ExpressionType::MethodCall(name) =>
+            {
+                let receiver = correct_type_references(self.value(&expression.children[0])?);
+
+                if !is_known(&receiver)
+                {
+                    for child in &expression.children[1..]
+                    {
+                        self.value(child)?;
+                    }
+
+                    return Ok(unknown());
+                }
+
+                // Methods are found by the type of the receiver, which is passed as the first argument
+                let linked_name = match self.func.functions.get(&method_key(&receiver, &name))
+                {
+                    Some(linked_name) => linked_name.clone(),
+                    None => compiler_error_loc(ErrorCode::E0014, format!("No method '{}' found for '{}'", name, receiver), &expression.pos)?
+                };
+
+                self.call(&linked_name, &name, &expression.children, &expression.pos)?
+            },
+            ExpressionType::GenericCall(_) =>
+            {
+                let name = match &expression.value
+                {
+                    Some(Value::Label(name)) => name.clone(),
+                    _ => unreachable!()
+                };
+
+                for child in &expression.children
+                {
+                    self.value(child)?;
+                }
+
+                // The types of the arguments are only known once the function is instantiated, but not their number
+                let count = match self.func.generics.get(&name)
+                {
+                    Some(generic) => generic.node.params.len(),
+                    None => expression.children.len()
+                };
+
+                if count != expression.children.len()
+                {
+                    compiler_error_loc(ErrorCode::E0017, format!("Function {} expects {} arguments, got {}", name, count, expression.children.len()), &expression.pos)?;
+                }
+
+                unknown()
+            }
+        };
+
+        Ok(datatype)
+    }
+
+    /// Check the arguments of a call against the signature of the function called, giving the type of its result
+    /// (functions which aren't known are left to be linked in, so nothing is known about them)
+    fn call(&mut self, linked_name: &str, name: &str, arguments: &[Expression], pos: &Option<Span>) -> Result<DataType, Error>
+    {
+        let mut types = vec![];
+
+        for argument in arguments
+        {
+            types.push(self.value(argument)?);
+        }
+
+        let signature = match self.func.signatures.get(linked_name)
+        {
+            Some(signature) => signature.clone(),
+            None => return Ok(unknown())
+        };
+
+        if signature.arguments.len() != arguments.len()
+        {
+            compiler_error_loc(ErrorCode::E0017, format!("Function {} expects {} arguments, got {}", name, signature.arguments.len(), arguments.len()), pos)?;
+        }
+
+        for ((argument, datatype), expected) in arguments.iter().zip(types).zip(signature.arguments)
+        {
+            let (datatype, expected) = (correct_type_references(datatype), correct_type_references(expected));
+
+            // Arguments are only converted to the types of the parameters if no value is lost (or they are constants)
+            if datatype.bit_width().is_some() && expected.bit_width().is_some() && !datatype.widens_to(&expected)
+            {
+                compiler_error_loc::<()>(ErrorCode::E0021, format!("Expected '{}', got '{}'", expected, datatype), &argument.location())
+                    .map_err(|error| error.with_note(&format!("arguments are only converted to wider types of the parameters, cast it with 'as {}'", expected)))?;
+            }
+
+            self.convert(expected, datatype, argument)?;
+        }
+
+        if signature.return_type == no_value()
+        {
+            Ok(no_value())
+        }
+        else
+        {
+            Ok(signature.return_type)
+        }
+    }
+
+    /// Check the arguments of a builtin, giving the type of its result
+    fn builtin(&mut self, builtin: Builtin, expression: &Expression) -> Result<DataType, Error>
+    {
+        if expression.children.len() != builtin.argument_count()
+        {
+            compiler_error_loc(ErrorCode::E0017, format!("Builtin {} expects {} arguments, got {}", builtin.name(), builtin.argument_count(), expression.children.len()),
+                &expression.location())?;
+        }
+
+        let mut types = vec![];
+
+        for child in &expression.children
+        {
+            types.push(correct_type_references(self.value(child)?));
+        }
+
+        // Intrinsics on integers give the type of the integer, or of the amount when the integer is a constant
+        Ok(match builtin
+        {
+            Builtin::Popcount | Builtin::Clz | Builtin::Ctz | Builtin::Bswap | Builtin::RotateLeft | Builtin::RotateRight =>
+            {
+                types.iter().copied().find(is_known).unwrap_or(unknown())
+            },
+            Builtin::BitTest => DataType::new(NonPtrType::U8, 0, false),
+            _ => no_value()
+        })
+    }
+
+    /// Get the type of the result of an operator on two values, pointers may only be compared with pointers of the same
+    /// type or have integers added to or subtracted from them
+    fn binary(&self, opcode: OpCode, type0: DataType, type1: DataType, pos: &Option<Span>) -> Result<DataType, Error>
+    {
+        let (mut type0, mut type1) = (correct_type_references(type0), correct_type_references(type1));
+
+        if type0.is_slice || type1.is_slice
+        {
+            compiler_error_loc(ErrorCode::E0022, format!("Invalid operands '{}' and '{}', slices can only be indexed or have a range taken of them", type0, type1), pos)?;
+        }
+
+        // Pointers are always the first operand of an addition
+        if opcode == OpCode::Add && is_pointer(&type1) && !is_pointer(&type0)
+        {
+            std::mem::swap(&mut type0, &mut type1);
+        }
+
+        if !is_pointer(&type0) && !is_pointer(&type1)
+        {
+            // Shifts keep the type of the value being shifted
+            if (opcode == OpCode::Shl || opcode == OpCode::Shr) && is_known(&type0)
+            {
+                return Ok(type0);
+            }
+
+            return Ok(type0.common_type(&type1));
+        }
+
+        match opcode
+        {
+            OpCode::Ceq | OpCode::Cne | OpCode::Clt | OpCode::Cgt | OpCode::Cle | OpCode::Cge =>
+            {
+                if is_pointer(&type0) && is_pointer(&type1) && type0 != type1
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Mismatched pointer types, cannot compare '{}' with '{}' without a cast", type0, type1), pos)?;
+                }
+                // Pointers can be compared with constant addresses
+                else if is_known(&type0) && is_known(&type1) && is_pointer(&type0) != is_pointer(&type1)
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot compare '{}' with '{}' without a cast", type0, type1), pos)?;
+                }
+
+                Ok(DataType::new(NonPtrType::U8, 0, false))
+            },
+            OpCode::Add | OpCode::Sub if is_pointer(&type0) =>
+            {
+                if is_void_pointer(&type0)
+                {
+                    compiler_error_loc(ErrorCode::E0022, format!("Cannot perform arithmetic on '{}'", type0), pos)?;
+                }
+
+                // The difference between two pointers is the number of elements between them
+                if is_pointer(&type1)
+                {
+                    if opcode == OpCode::Add
+                    {
+                        compiler_error_loc(ErrorCode::E0022, format!("Cannot add '{}' to '{}'", type1, type0), pos)?;
+                    }
+                    else if type0 != type1
+                    {
+                        compiler_error_loc(ErrorCode::E0022, format!("Mismatched pointer types, cannot subtract '{}' from '{}' without a cast", type1, type0), pos)?;
+                    }
+
+                    return Ok(self.func.pointer_difference_type());
+                }
+
+                Ok(type0)
+            },
+            _ => compiler_error_loc(ErrorCode::E0022, format!("Invalid operands '{}' and '{}' for pointer arithmetic", type0, type1), pos)
+        }
+    }
+
+    /// Check that a value of one type can be stored in a location of another, integers are converted to one another
+    /// but pointers only convert to pointers of the same type (constants may be used as addresses)
+    fn convert(&self, target: DataType, value_type: DataType, value: &Expression) -> Result<(), Error>
+    {
+        let target = correct_type_references(target);
+        let mut source = correct_type_references(value_type);
+        let pos = value.location();
+
+        if !is_known(&target) || !is_known(&source)
+        {
+            return Ok(());
+        }
+
+        // Global arrays are given as a pointer, which is used as a slice of all of their elements once rendered
+        if target.is_slice
+        {
+            if source.is_slice && source != target
+            {
+                compiler_error_loc(ErrorCode::E0021, format!("Cannot convert '{}' to '{}'", source, target), &pos)?;
+            }
+            else if !is_pointer(&source)
+            {
+                compiler_error_loc(ErrorCode::E0021, format!("Expected a slice, got '{}'", source), &pos)?;
+            }
+
+            return Ok(());
+        }
+
+        check_address_space(&target, &source, &pos)?;
+
+        // A slice can be used as the pointer to its elements
+        source.is_slice = false;
+
+        if (is_pointer(&target) || is_pointer(&source)) && source != target
+        {
+            compiler_error_loc(ErrorCode::E0021, format!("Cannot convert '{}' to '{}' without a cast", source, target), &pos)?;
+        }
+
+        Ok(())
+    }
+}
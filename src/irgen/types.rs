@@ -1,4 +1,11 @@
 use std::fmt;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use super::OpCode;
+
+/// Widest integer type, the width of the values held by literals
+pub static MAX_INTEGER_BITS: usize = 128;
 
 /// Non Pointer Type, a raw type
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -12,18 +19,25 @@ pub enum NonPtrType
     U32,
     I64,
     U64,
+    I128,
+    U128,
+    IN(usize), // Signed integer of any other width, such as i24
+    UN(usize), // Unsigned integer of any other width, such as u24
     Void,
     Bool,
     Unknown
 }
 
-/// A datatype with the possibility of being a pointer and a reference
+/// A datatype with the possibility of being a pointer and a reference (a slice is a pointer whose length is carried
+/// alongside it, the pointer to the elements counts towards num_ptr)
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub struct DataType
 {
     pub raw_type: NonPtrType,
     pub num_ptr: usize,
-    pub is_ref: bool
+    pub is_ref: bool,
+    pub is_progmem: bool,
+    pub is_slice: bool
 }
 
 impl DataType
@@ -35,7 +49,354 @@ impl DataType
         {
             raw_type: raw,
             num_ptr: ptrs,
-            is_ref: is_ref
+            is_ref: is_ref,
+            is_progmem: false,
+            is_slice: false
+        }
+    }
+
+    /// Generate a new datatype object whose underlying data lives in program memory
+    pub fn new_progmem(raw: NonPtrType, ptrs: usize) -> Self
+    {
+        Self
+        {
+            raw_type: raw,
+            num_ptr: ptrs,
+            is_ref: false,
+            is_progmem: true,
+            is_slice: false
+        }
+    }
+
+    /// Generate a new slice datatype from the type of its elements
+    pub fn new_slice(element: DataType) -> Self
+    {
+        let mut result = element;
+        result.num_ptr += 1;
+        result.is_ref = false;
+        result.is_slice = true;
+
+        result
+    }
+
+    /// Get the type of the length carried by a slice
+    pub fn slice_length() -> Self
+    {
+        Self::new(NonPtrType::U16, 0, false)
+    }
+
+    /// Is the datatype a pointer into program memory
+    pub fn is_progmem_ptr(&self) -> bool
+    {
+        self.is_progmem && self.num_ptr > 0
+    }
+
+    /// Get the datatype produced by dereferencing a pointer of this type
+    pub fn dereferenced(&self) -> Self
+    {
+        let mut result = *self;
+        result.num_ptr -= 1;
+        result.is_slice = false;
+
+        // Once loaded, a value no longer lives in program memory
+        if result.num_ptr == 0
+        {
+            result.is_progmem = false;
+        }
+
+        result
+    }
+
+    /// Get the number of bits in an integer datatype (None for pointers and non integer types)
+    pub fn bit_width(&self) -> Option<usize>
+    {
+        if self.num_ptr > 0 || self.is_ref
+        {
+            return None;
+        }
+
+        match self.raw_type
+        {
+            NonPtrType::I8 | NonPtrType::U8 => Some(8),
+            NonPtrType::I16 | NonPtrType::U16 => Some(16),
+            NonPtrType::I32 | NonPtrType::U32 => Some(32),
+            NonPtrType::I64 | NonPtrType::U64 => Some(64),
+            NonPtrType::I128 | NonPtrType::U128 => Some(128),
+            NonPtrType::IN(bits) | NonPtrType::UN(bits) => Some(bits),
+            _ => None
+        }
+    }
+
+    /// Get the integer datatype with the given number of bits and signedness (None if the width is not between 1 and
+    /// 128 bits)
+    pub fn integer(bits: usize, signed: bool) -> Option<Self>
+    {
+        let raw = match (bits, signed)
+        {
+            (8, true) => NonPtrType::I8,
+            (8, false) => NonPtrType::U8,
+            (16, true) => NonPtrType::I16,
+            (16, false) => NonPtrType::U16,
+            (32, true) => NonPtrType::I32,
+            (32, false) => NonPtrType::U32,
+            (64, true) => NonPtrType::I64,
+            (64, false) => NonPtrType::U64,
+            (128, true) => NonPtrType::I128,
+            (128, false) => NonPtrType::U128,
+            _ if bits == 0 || bits > MAX_INTEGER_BITS => return None,
+            (_, true) => NonPtrType::IN(bits),
+            (_, false) => NonPtrType::UN(bits)
+        };
+
+        Some(Self::new(raw, 0, false))
+    }
+
+    /// Get the integer datatype named by a type such as u8, i128 or u24
+    pub fn integer_from_name(name: &str) -> Option<Self>
+    {
+        let signed = match name.chars().next()
+        {
+            Some('i') => true,
+            Some('u') => false,
+            _ => return None
+        };
+
+        let bits = &name[1..];
+
+        // Leading zeros would give a second name to the same type
+        if bits.starts_with('0') || !bits.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        Self::integer(bits.parse().ok()?, signed)
+    }
+
+    /// Check if the datatype is an unsigned 128 bit integer, whose values are held as their bit pattern (so the
+    /// largest values are held as negative numbers)
+    fn is_unsigned_128(&self) -> bool
+    {
+        self.bit_width() == Some(MAX_INTEGER_BITS) && !self.is_signed()
+    }
+
+    /// Check if a value can be represented by the datatype (types without a width accept any value)
+    pub fn can_represent(&self, value: i128) -> bool
+    {
+        match self.bit_width()
+        {
+            Some(bits) if self.is_signed() => value >= (-1i128 << (bits - 1)) && value <= !(-1i128 << (bits - 1)),
+            Some(bits) => value >= 0 && (value as u128) <= u128::MAX >> (MAX_INTEGER_BITS - bits),
+            None => true
+        }
+    }
+
+    /// Check if every value of the integer datatype can be represented by another integer datatype, so it can be
+    /// converted to it implicitly
+    pub fn widens_to(&self, other: &DataType) -> bool
+    {
+        match (self.bit_width(), other.bit_width())
+        {
+            (Some(bits), Some(other_bits)) if self.is_signed() == other.is_signed() => bits <= other_bits,
+            (Some(bits), Some(other_bits)) => !self.is_signed() && bits < other_bits,
+            _ => false
+        }
+    }
+
+    /// Get the smallest and largest values of an integer datatype (the largest u128 is given as the largest i128, which
+    /// is the largest literal)
+    pub fn value_range(&self) -> Option<(i128, i128)>
+    {
+        let bits = self.bit_width()?;
+
+        if self.is_signed()
+        {
+            Some((-1i128 << (bits - 1), !(-1i128 << (bits - 1))))
+        }
+        else
+        {
+            Some((0, (u128::MAX >> (MAX_INTEGER_BITS - bits)).min(i128::MAX as u128) as i128))
+        }
+    }
+
+    /// Truncate a value to the width of the datatype, as it would be stored at runtime (types without a width are
+    /// left unchanged)
+    pub fn wrap_value(&self, value: i128) -> i128
+    {
+        match self.bit_width()
+        {
+            Some(bits) =>
+            {
+                let mask = u128::MAX >> (MAX_INTEGER_BITS - bits);
+                let unsigned = value as u128 & mask;
+
+                // Negative values have every bit above the width set
+                if self.is_signed() && (unsigned >> (bits - 1)) & 1 == 1 {(unsigned | !mask) as i128} else {unsigned as i128}
+            },
+            None => value
+        }
+    }
+
+    /// Compare two values of the datatype
+    pub fn compare_values(&self, value0: i128, value1: i128) -> Ordering
+    {
+        if self.is_unsigned_128()
+        {
+            (value0 as u128).cmp(&(value1 as u128))
+        }
+        else
+        {
+            value0.cmp(&value1)
+        }
+    }
+
+    /// Evaluate an arithmetic operation on two values of the datatype exactly, giving None if the result can't be
+    /// represented by the datatype or the operation is not defined (such as a division by zero)
+    pub fn evaluate(&self, opcode: OpCode, value0: i128, value1: i128) -> Option<i128>
+    {
+        if self.is_unsigned_128()
+        {
+            let (value0, value1) = (value0 as u128, value1 as u128);
+
+            let result = match opcode
+            {
+                OpCode::Add => value0.checked_add(value1),
+                OpCode::Sub => value0.checked_sub(value1),
+                OpCode::Mul => value0.checked_mul(value1),
+                OpCode::Div => value0.checked_div(value1),
+                OpCode::Mod => value0.checked_rem(value1),
+                _ => None
+            };
+
+            return result.map(|value| value as i128);
+        }
+
+        let result = match opcode
+        {
+            OpCode::Add => value0.checked_add(value1),
+            OpCode::Sub => value0.checked_sub(value1),
+            OpCode::Mul => value0.checked_mul(value1),
+            OpCode::Div => value0.checked_div(value1),
+            OpCode::Mod => value0.checked_rem(value1),
+            _ => None
+        };
+
+        result.filter(|value| self.can_represent(*value))
+    }
+
+    /// Evaluate an arithmetic operation on two values of the datatype, wrapping the result to the width of the
+    /// datatype as it would be at runtime (None if the operation is not defined, such as a division by zero)
+    pub fn evaluate_wrapping(&self, opcode: OpCode, value0: i128, value1: i128) -> Option<i128>
+    {
+        if self.bit_width().is_none()
+        {
+            return self.evaluate(opcode, value0, value1);
+        }
+
+        if self.is_unsigned_128()
+        {
+            let (value0, value1) = (value0 as u128, value1 as u128);
+
+            let result = match opcode
+            {
+                OpCode::Add => value0.wrapping_add(value1),
+                OpCode::Sub => value0.wrapping_sub(value1),
+                OpCode::Mul => value0.wrapping_mul(value1),
+                OpCode::Div => value0.checked_div(value1)?,
+                OpCode::Mod => value0.checked_rem(value1)?,
+                OpCode::Shr => value0.checked_shr(u32::try_from(value1).ok()?)?,
+                _ => return None
+            };
+
+            return Some(result as i128);
+        }
+
+        // Arithmetic modulo 2^128 gives the right result modulo the width of the datatype
+        let result = match opcode
+        {
+            OpCode::Add => value0.wrapping_add(value1),
+            OpCode::Sub => value0.wrapping_sub(value1),
+            OpCode::Mul => value0.wrapping_mul(value1),
+            OpCode::Div if value1 != 0 => value0.wrapping_div(value1),
+            OpCode::Mod if value1 != 0 => value0.wrapping_rem(value1),
+            OpCode::Shr => value0.checked_shr(u32::try_from(value1).ok()?)?,
+            _ => return None
+        };
+
+        Some(self.wrap_value(result))
+    }
+
+    /// Evaluate an intrinsic on a value of the datatype as it would be at runtime, the amount is only used by rotates
+    /// (None if the datatype has no width or the intrinsic is not defined for it)
+    pub fn evaluate_intrinsic(&self, opcode: OpCode, value: i128, amount: i128) -> Option<i128>
+    {
+        let bits = self.bit_width()?;
+        let mask = u128::MAX >> (MAX_INTEGER_BITS - bits);
+        let pattern = value as u128 & mask;
+
+        let result = match opcode
+        {
+            OpCode::Rotl | OpCode::Rotr =>
+            {
+                // The amount wraps around at the width of the datatype, a rotate right is a rotate left the other way
+                let amount = ((amount as u128 & mask) % bits as u128) as usize;
+                let amount = if opcode == OpCode::Rotl {amount} else {(bits - amount) % bits};
+
+                if amount == 0 {pattern} else {((pattern << amount) | (pattern >> (bits - amount))) & mask}
+            },
+            OpCode::Popcnt => pattern.count_ones() as u128,
+            OpCode::Clz => (pattern.leading_zeros() as usize - (MAX_INTEGER_BITS - bits)) as u128,
+            OpCode::Ctz => (pattern.trailing_zeros() as usize).min(bits) as u128,
+            OpCode::Bswap if bits == 8 || bits % 16 == 0 => pattern.swap_bytes() >> (MAX_INTEGER_BITS - bits),
+            _ => return None
+        };
+
+        Some(self.wrap_value(result as i128))
+    }
+
+    /// Get the type two operands are converted to before an operation, this is the wider of two integer types,
+    /// preferring the unsigned type when both have the same width (unknown types defer to the other type)
+    pub fn common_type(&self, other: &DataType) -> DataType
+    {
+        if self.raw_type == NonPtrType::Unknown
+        {
+            return *other;
+        }
+        else if other.raw_type == NonPtrType::Unknown
+        {
+            return *self;
+        }
+
+        match (self.bit_width(), other.bit_width())
+        {
+            (Some(bits0), Some(bits1)) =>
+            {
+                if (bits0, !self.is_signed()) >= (bits1, !other.is_signed()) {*self} else {*other}
+            },
+            // Pointers and other non integer types take precedence over integers
+            (Some(_), None) => *other,
+            (None, Some(_)) => *self,
+            (None, None) => if self >= other {*self} else {*other}
+        }
+    }
+
+    /// Get the type of an integer literal on its own, an i32 if the value fits and otherwise the first of i64 and i128
+    /// which holds it (as in C)
+    pub fn literal(value: i128) -> DataType
+    {
+        [NonPtrType::I32, NonPtrType::I64].iter()
+            .map(|raw| DataType::new(*raw, 0, false))
+            .find(|datatype| datatype.can_represent(value))
+            .unwrap_or(DataType::new(NonPtrType::I128, 0, false))
+    }
+
+    /// Get the type an operand is promoted to before an operation, integers narrower than 32 bits become i32 (as in
+    /// C) and anything else keeps its type
+    pub fn promoted(&self) -> DataType
+    {
+        match self.bit_width()
+        {
+            Some(bits) if bits < 32 => DataType::new(NonPtrType::I32, 0, false),
+            _ => *self
         }
     }
 
@@ -50,7 +411,7 @@ impl DataType
         {
             match self.raw_type
             {
-                NonPtrType::I8 | NonPtrType::I16 | NonPtrType::I32 | NonPtrType::I64 => true,
+                NonPtrType::I8 | NonPtrType::I16 | NonPtrType::I32 | NonPtrType::I64 | NonPtrType::I128 | NonPtrType::IN(_) => true,
                 _ => false
             }
         }   
@@ -65,27 +426,43 @@ impl fmt::Display for DataType
         {
             write!(f, "&")?;
         }
+
+        if self.is_progmem
+        {
+            write!(f, "progmem ")?;
+        }
         
-        write!(f, "{}", match self.raw_type
-        {
-            NonPtrType::Bool => "i1",
-            NonPtrType::I8 => "i8",
-            NonPtrType::U8 => "u8",
-            NonPtrType::I16 => "i16",
-            NonPtrType::U16 => "u16",
-            NonPtrType::I32 => "i32",
-            NonPtrType::U32 => "u32",
-            NonPtrType::I64 => "i64",
-            NonPtrType::U64 => "u64",
-            NonPtrType::Void => "void",
-            NonPtrType::Unknown => "Unk"
-        })?;
-
-        for _ in 0..self.num_ptr
+        match self.raw_type
+        {
+            NonPtrType::Bool => write!(f, "i1")?,
+            NonPtrType::I8 => write!(f, "i8")?,
+            NonPtrType::U8 => write!(f, "u8")?,
+            NonPtrType::I16 => write!(f, "i16")?,
+            NonPtrType::U16 => write!(f, "u16")?,
+            NonPtrType::I32 => write!(f, "i32")?,
+            NonPtrType::U32 => write!(f, "u32")?,
+            NonPtrType::I64 => write!(f, "i64")?,
+            NonPtrType::U64 => write!(f, "u64")?,
+            NonPtrType::I128 => write!(f, "i128")?,
+            NonPtrType::U128 => write!(f, "u128")?,
+            NonPtrType::IN(bits) => write!(f, "i{}", bits)?,
+            NonPtrType::UN(bits) => write!(f, "u{}", bits)?,
+            NonPtrType::Void => write!(f, "void")?,
+            NonPtrType::Unknown => write!(f, "integer literal")?
+        }
+
+        let stars = if self.is_slice {self.num_ptr - 1} else {self.num_ptr};
+
+        for _ in 0..stars
         {
             write!(f, "*")?;
         }
 
+        if self.is_slice
+        {
+            write!(f, "[]")?;
+        }
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,11 +1,13 @@
-use super::{NonPtrType, DataType};
+use super::{NonPtrType, DataType, MAX_INTEGER_BITS};
 use super::Value;
 
-use super::{compiler_error, expected_got_error};
+use super::compiler_error_loc;
 
-use crate::parser::ParseTreeNode;
+use crate::parser::{TypeExpr, Param};
 
-use crate::cli::Error;
+use crate::tokenizer::Span;
+
+use crate::cli::{Error, ErrorCode};
 
 /// Attempt to change a value's type from unknown to a new datatype
 pub fn attempt_mutate_type(value: Value, new_type: DataType) -> Value
@@ -89,102 +91,84 @@ pub fn correct_type_references(datatype: DataType) -> DataType
     result
 }
 
-/// Extract a datatype from a parse tree node
-pub fn type_from_parse_tree(node: ParseTreeNode) -> Result<DataType, Error>
+/// Ensure a value of one type can be stored in a location of another type without converting
+/// between pointers into program memory and pointers into RAM
+pub fn check_address_space(target: &DataType, value: &DataType, pos: &Option<Span>) -> Result<(), Error>
 {
-    match node
+    if target.num_ptr > 0 && value.num_ptr > 0 && target.is_progmem != value.is_progmem
     {
-        ParseTreeNode::Type(children) =>
-        {
-            let non_ptr = match &children[0]
-            {
-                ParseTreeNode::RawType(token) =>
-                {
-                    match token.data.as_str()
-                    {
-                        "i8" => NonPtrType::I8,
-                        "u8" => NonPtrType::U8,
-                        "i16" => NonPtrType::I16,
-                        "u16" => NonPtrType::U16,
-                        "i32" => NonPtrType::I32,
-                        "u32" => NonPtrType::U32,
-                        "i64" => NonPtrType::I64,
-                        "u64" => NonPtrType::U64,
-                        "void" => NonPtrType::Void,
-
-                        default => 
-                        {
-                            compiler_error(format!("Bad type, '{}'", default))?;
-                            unreachable!();
-                        }
-                    }
-                },
-                default =>
-                {
-                    expected_got_error("Type", default.clone())?;
-                    unreachable!();
-                }
-            };
-
-            Ok(DataType::new(non_ptr, children.len() - 1, false))
-        },
-        default =>
-        {
-            expected_got_error("Type", default)
-        }
+        compiler_error_loc(ErrorCode::E0021, format!("Mismatched address spaces, cannot convert '{}' to '{}'",
+            correct_type_references(*value), correct_type_references(*target)), pos)
+    }
+    else
+    {
+        Ok(())
     }
 }
 
-/// Extract an identifier from a parse tree node
-pub fn identifier_from_parse_tree(node: ParseTreeNode) -> Result<String, Error>
+/// Ensure the progmem qualifier is only applied to pointers (only globals can be stored in program memory)
+pub fn check_progmem_pointer(datatype: &DataType, pos: &Option<Span>) -> Result<(), Error>
 {
-    match node
+    if datatype.is_progmem && datatype.num_ptr == 0
     {
-        ParseTreeNode::Identifier(token) =>
-        {
-            Ok(String::from(token.data))
-        },
-        default =>
-        {
-            expected_got_error("Identifier", default)
-        }
+        compiler_error_loc(ErrorCode::E0020, format!("Only pointers and globals can be qualified as progmem, got '{}'", datatype), pos)
+    }
+    else
+    {
+        Ok(())
     }
 }
 
-/// Extract arguments from a parse tree node
-pub fn arguments_from_parse_tree(node: ParseTreeNode) -> Result<Vec<(String, DataType)>, Error>
+/// Get the datatype written by a type of the syntax tree
+pub fn type_from_ast(datatype: &TypeExpr) -> Result<DataType, Error>
 {
-    match node
+    let name = &datatype.name;
+
+    let non_ptr = match name.name.as_str()
     {
-        ParseTreeNode::Empty =>
-        {
-            Ok(vec![])
-        },
-        ParseTreeNode::Arguments(children) =>
-        {
-            let mut result = vec![];
+        "void" => NonPtrType::Void,
 
-            for child in children
-            {
-                match &child
-                {
-                    ParseTreeNode::Argument(arg_vals) =>
-                    {
-                        result.push((identifier_from_parse_tree(arg_vals[1].clone())?,
-                                     type_from_parse_tree(arg_vals[0].clone())?))
-                    }
-                    default =>
-                    {
-                        expected_got_error("Argument", default.clone())?;
-                    }
-                }
-            }
-            
-            Ok(result)
-        }
-        default =>
+        default => match DataType::integer_from_name(default)
         {
-            expected_got_error("Arguments or Empty", default)
+            Some(datatype) => datatype.raw_type,
+            None if default.len() > 1 && default[1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                compiler_error_loc(ErrorCode::E0020, format!("Bad type, '{}', integers can be at most {} bits wide", default, MAX_INTEGER_BITS),
+                                   &Some(name.span.clone()))?
+            },
+            None => compiler_error_loc(ErrorCode::E0020, format!("Bad type, '{}'", default), &Some(name.span.clone()))?
         }
+    };
+
+    let element = if datatype.progmem
+    {
+        DataType::new_progmem(non_ptr, datatype.pointers)
     }
-}
\ No newline at end of file
+    else
+    {
+        DataType::new(non_ptr, datatype.pointers, false)
+    };
+
+    // A slice is written after any pointers, as in u8*[]
+    if datatype.slice
+    {
+        Ok(DataType::new_slice(element))
+    }
+    else
+    {
+        Ok(element)
+    }
+}
+
+/// Get the names and datatypes of the arguments of a function
+pub fn arguments_from_ast(params: &[Param]) -> Result<Vec<(String, DataType)>, Error>
+{
+    let mut result = vec![];
+
+    for param in params
+    {
+        result.push((param.name.name.clone(), type_from_ast(&param.datatype)?));
+    }
+
+    Ok(result)
+}
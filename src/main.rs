@@ -4,15 +4,35 @@ mod tokenizer;
 mod io;
 mod cli;
 mod compile;
+mod module;
+mod preprocess;
 mod parser;
 mod irgen;
 mod codegen;
+mod diagnostics;
 
 fn main()
 {
-    match cli::run(&cli::Options::new(std::env::args().collect()))
+    let options = cli::Options::new(std::env::args().collect());
+
+    match cli::run(&options)
     {
         Ok(()) => {},
-        Err(error) => {eprintln!("{}\nCompilation Terminated", error);}
+        Err(error) =>
+        {
+            // A bad error format is itself shown in the default format
+            let format = cli::ErrorFormat::from_options(&options).unwrap_or_default();
+
+            if format.json
+            {
+                eprintln!("{}", format.render(&error));
+            }
+            else
+            {
+                eprintln!("{}\nCompilation Terminated", format.render(&error));
+            }
+
+            std::process::exit(1);
+        }
     }
 }
\ No newline at end of file
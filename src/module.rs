@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::io::InputFile;
+use super::cli::{Error, ErrorCode, ErrorRecorder};
+use super::tokenizer::{tokenize, Span};
+use super::parser::{parse, Item};
+use super::preprocess::Preprocessor;
+
+/// Source module, the items parsed from a single file along with the modules it imports
+#[derive(Debug, Clone)]
+pub struct Module
+{
+    pub name: String,
+    pub filename: String,
+    pub items: Vec<Item>,
+    pub imports: Vec<(String, usize)>
+}
+
+impl Module
+{
+    /// Get the name an item of the module is linked under (items of the files given to the compiler are not
+    /// qualified by the name of the module)
+    pub fn linked_name(&self, item: &str) -> String
+    {
+        if self.name.is_empty()
+        {
+            String::from(item)
+        }
+        else
+        {
+            format!("{}.{}", self.name, item)
+        }
+    }
+}
+
+/// Loader for modules, which follows the imports of every module loaded
+struct ModuleLoader<'a>
+{
+    preprocessor: &'a Preprocessor,
+    recorder: &'a mut ErrorRecorder,
+    modules: Vec<Module>,
+    loaded: HashMap<PathBuf, usize>,
+    names: HashMap<String, PathBuf>,
+    stack: Vec<PathBuf>
+}
+
+impl<'a> ModuleLoader<'a>
+{
+    /// Generate a new module loader, which preprocesses every module with the given preprocessor and reports syntax
+    /// errors to the given recorder
+    fn new(preprocessor: &'a Preprocessor, recorder: &'a mut ErrorRecorder) -> Self
+    {
+        Self
+        {
+            preprocessor,
+            recorder,
+            modules: vec![],
+            loaded: HashMap::new(),
+            names: HashMap::new(),
+            stack: vec![]
+        }
+    }
+
+    /// Load a module along with every module it imports, returning the index of the module
+    fn load(&mut self, input: InputFile, name: String, location: &Option<Span>) -> Result<usize, Error>
+    {
+        let path = std::fs::canonicalize(&input.filename).unwrap_or(PathBuf::from(&input.filename));
+
+        // A module which imports itself (directly or through other modules) can't be ordered
+        if let Some(start) = self.stack.iter().position(|other| *other == path)
+        {
+            let mut cycle: Vec<String> = self.stack[start..].iter().map(|other| other.display().to_string()).collect();
+            cycle.push(path.display().to_string());
+
+            return import_error(ErrorCode::E0007, format!("Import cycle detected: {}", cycle.join(" -> ")), location);
+        }
+
+        if let Some(index) = self.loaded.get(&path)
+        {
+            return Ok(*index);
+        }
+
+        // Items are linked under the name of their module, so two modules can't share a name
+        if !name.is_empty()
+        {
+            if let Some(other) = self.names.get(&name)
+            {
+                return import_error(ErrorCode::E0006, format!("Modules '{}' and '{}' have the same name '{}'", other.display(), path.display(), name), location);
+            }
+
+            self.names.insert(name.clone(), path.clone());
+        }
+
+        let source = self.preprocessor.preprocess(input.data, input.filename.clone())?;
+
+        // Illegal characters are reported along with the syntax errors
+        let (tokens, mut errors) = tokenize(source, input.filename.clone());
+        let (library, parse_errors) = parse(tokens);
+        errors.extend(parse_errors);
+
+        // Every syntax error in the file is shown, compilation stops with the last of them
+        if let Some(last) = errors.pop()
+        {
+            for error in errors
+            {
+                self.recorder.report_error(error)?;
+            }
+
+            return Err(last);
+        }
+
+        let items = library.items;
+
+        // Load the imported modules, relative to the directory of the importing file
+        self.stack.push(path.clone());
+
+        let directory = Path::new(&input.filename).parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+        let mut imports = vec![];
+
+        for item in &items
+        {
+            if let Item::Import(import) = item
+            {
+                let import_location = Some(import.span.clone());
+                let file_name = &import.file;
+                let import_path = directory.join(file_name);
+
+                let module_name = match import_path.file_stem().and_then(|stem| stem.to_str())
+                {
+                    Some(stem) if is_module_name(stem) => String::from(stem),
+                    _ => return import_error(ErrorCode::E0006, format!("Cannot import '{}', the file name is not a valid module name", file_name), &import_location)
+                };
+
+                let import = match InputFile::new(import_path.display().to_string())
+                {
+                    Ok(import) => import,
+                    Err(_) => return import_error(ErrorCode::E0006, format!("Cannot import '{}', no such file", file_name), &import_location)
+                };
+
+                let index = self.load(import, module_name.clone(), &import_location)?;
+
+                if imports.iter().any(|(other, _)| *other == module_name)
+                {
+                    return import_error(ErrorCode::E0006, format!("Module '{}' imported more than once", module_name), &import_location);
+                }
+
+                imports.push((module_name, index));
+            }
+        }
+
+        self.stack.pop();
+
+        self.modules.push(Module
+        {
+            name,
+            filename: input.filename,
+            items,
+            imports
+        });
+
+        self.loaded.insert(path, self.modules.len() - 1);
+
+        Ok(self.modules.len() - 1)
+    }
+}
+
+/// Check if a file name can be used as the name of a module
+fn is_module_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
+
+    match chars.next()
+    {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
+/// Generate an error while importing a module
+fn import_error<T>(code: ErrorCode, text: String, location: &Option<Span>) -> Result<T, Error>
+{
+    match location
+    {
+        None => Err(Error::error(&format!("Import Error: {}", text)).with_code(code)),
+        Some(span) => Err(Error::error(&format!("Import Error: {}", text)).with_code(code).with_label(span.clone(), ""))
+    }
+}
+
+/// Load the given files, along with every module they import (directly or indirectly). The given files are not
+/// qualified by a module name, and imported modules are loaded before the modules which import them
+pub fn load_modules(inputs: Vec<InputFile>, preprocessor: &Preprocessor, recorder: &mut ErrorRecorder) -> Result<Vec<Module>, Error>
+{
+    let mut loader = ModuleLoader::new(preprocessor, recorder);
+
+    for input in inputs
+    {
+        loader.load(input, String::new(), &None)?;
+    }
+
+    Ok(loader.modules)
+}
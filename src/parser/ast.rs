@@ -0,0 +1,742 @@
+use std::fmt;
+
+use crate::tokenizer::{Token, Span};
+
+/// Syntax tree of a translation unit, the items of the file in order
+#[derive(Debug, Clone)]
+pub struct Library
+{
+    pub items: Vec<Item>
+}
+
+/// Item of a library
+#[derive(Debug, Clone)]
+pub enum Item
+{
+    Function(FunctionDecl),
+    Global(GlobalDecl),
+    Import(Import),
+    Use(Ident),
+    Impl(ImplBlock),
+    /// Tokens skipped after a syntax error
+    Error(Vec<Token>, Span)
+}
+
+/// Name written in the source, an identifier or a path to an item of a module, as in uart::init
+#[derive(Debug, Clone)]
+pub struct Ident
+{
+    pub name: String,
+    pub span: Span
+}
+
+/// Type written in the source, a raw type followed by any pointers and an optional '[]' for a slice, and optionally
+/// qualified as living in program memory
+/// for example progmem u8*[]
+#[derive(Debug, Clone)]
+pub struct TypeExpr
+{
+    pub progmem: bool,
+    pub name: Ident,
+    pub pointers: usize,
+    pub slice: bool,
+    pub span: Span
+}
+
+/// Argument of a function
+/// for example u8** argv
+#[derive(Debug, Clone)]
+pub struct Param
+{
+    pub datatype: TypeExpr,
+    pub name: Ident
+}
+
+/// Definition of a function, generic functions have type parameters, and attributes are written before the function
+/// as in #[noreturn]
+#[derive(Debug, Clone)]
+pub struct FunctionDecl
+{
+    pub ret: TypeExpr,
+    pub name: Ident,
+    pub type_params: Vec<Ident>,
+    pub params: Vec<Param>,
+    pub body: Box<Stmt>,
+    pub attributes: Vec<Ident>,
+    pub span: Span
+}
+
+/// Definition of a global, with an optional array length and an optional initializer
+#[derive(Debug, Clone)]
+pub struct GlobalDecl
+{
+    pub datatype: TypeExpr,
+    pub name: Ident,
+    pub length: Option<ArrayLength>,
+    pub value: Option<Initializer>,
+    pub span: Span
+}
+
+/// Length of an array global, which can be left out if it is given an initializer list
+#[derive(Debug, Clone)]
+pub enum ArrayLength
+{
+    Inferred,
+    Given(Box<Expr>)
+}
+
+/// Initial value of a global, either a single value or a list of them
+/// for example = 5 or = {1, 2, 3}
+#[derive(Debug, Clone)]
+pub enum Initializer
+{
+    Value(Box<Expr>),
+    List(Vec<Expr>, Span)
+}
+
+/// Import of another module, by the name of its file (without the quotes)
+/// for example import "uart.pc";
+#[derive(Debug, Clone)]
+pub struct Import
+{
+    pub file: String,
+    pub span: Span
+}
+
+/// Methods of a type
+/// for example impl u16* { u16 sum(u16* self, u16 n) { ... } }
+#[derive(Debug, Clone)]
+pub struct ImplBlock
+{
+    pub datatype: TypeExpr,
+    pub methods: Vec<FunctionDecl>,
+    pub span: Span
+}
+
+/// Statement
+#[derive(Debug, Clone)]
+pub enum Stmt
+{
+    /// Just a semicolon
+    Empty(Span),
+    Block(Vec<Stmt>, Span),
+    Continue(Span),
+    Break(Span),
+    /// Declaration of variables with a type, or with let to take the types of their values
+    Declaration
+    {
+        datatype: Option<TypeExpr>,
+        declarators: Vec<Declarator>,
+        span: Span
+    },
+    If
+    {
+        cond: Expr,
+        body: Box<Stmt>,
+        otherwise: Option<Box<Stmt>>,
+        span: Span
+    },
+    While
+    {
+        cond: Expr,
+        body: Box<Stmt>,
+        span: Span
+    },
+    DoWhile
+    {
+        body: Box<Stmt>,
+        cond: Expr,
+        span: Span
+    },
+    Loop
+    {
+        body: Box<Stmt>,
+        span: Span
+    },
+    Return
+    {
+        value: Expr,
+        span: Span
+    },
+    Expr(Expr, Span),
+    /// Tokens skipped after a syntax error
+    Error(Vec<Token>, Span)
+}
+
+/// Variable declared by a declaration, along with its initial value
+#[derive(Debug, Clone)]
+pub struct Declarator
+{
+    pub name: Ident,
+    pub value: InitValue
+}
+
+/// Initial value of a variable, either an expression or a block giving the value
+#[derive(Debug, Clone)]
+pub enum InitValue
+{
+    Expr(Box<Expr>),
+    Block(BlockExpr)
+}
+
+/// Block giving a value, statements followed by the expression giving the value of the block (the value is missing
+/// only if it had a syntax error)
+/// for example { u8 a = 1; a + 1 }
+#[derive(Debug, Clone)]
+pub struct BlockExpr
+{
+    pub stmts: Vec<Stmt>,
+    pub value: Option<Box<Expr>>,
+    pub span: Span
+}
+
+/// Operators applied to a single operand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp
+{
+    PreIncrement,
+    PreDecrement,
+    PostIncrement,
+    PostDecrement,
+    Plus,
+    Minus,
+    LogicalNot,
+    BitwiseNot,
+    Dereference,
+    Reference
+}
+
+/// Operators applied to two operands, the arithmetic and bitwise ones can also be used in compound assignments
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp
+{
+    Multiply,
+    Divide,
+    Modulus,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    Equal,
+    NotEqual,
+    BitwiseAnd,
+    BitwiseXor,
+    BitwiseOr,
+    LogicalAnd,
+    LogicalOr
+}
+
+/// Expression
+#[derive(Debug, Clone)]
+pub enum Expr
+{
+    /// Integer literal, as it is written
+    Integer(String, Span),
+    /// Variable or global, which may be a global of another module
+    Path(Ident),
+    Unary
+    {
+        op: UnaryOp,
+        operand: Box<Expr>,
+        span: Span
+    },
+    Binary
+    {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        span: Span
+    },
+    /// Assignment, compound assignments are given their operator
+    Assign
+    {
+        op: Option<BinaryOp>,
+        target: Box<Expr>,
+        value: Box<Expr>,
+        span: Span
+    },
+    Ternary
+    {
+        cond: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+        span: Span
+    },
+    Index
+    {
+        base: Box<Expr>,
+        index: Box<Expr>,
+        span: Span
+    },
+    /// Range of elements, either end of which can be left out
+    /// for example s[1..], s[..n]
+    Slice
+    {
+        base: Box<Expr>,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+        span: Span
+    },
+    /// Range of bits of an integer
+    /// for example reg.[3..5]
+    BitRange
+    {
+        base: Box<Expr>,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        span: Span
+    },
+    Member
+    {
+        base: Box<Expr>,
+        member: Ident,
+        span: Span
+    },
+    /// Call of a function, generic functions may be given type arguments explicitly
+    Call
+    {
+        callee: Ident,
+        type_args: Option<Vec<TypeExpr>>,
+        args: Vec<Expr>,
+        span: Span
+    },
+    MethodCall
+    {
+        receiver: Box<Expr>,
+        method: Ident,
+        args: Vec<Expr>,
+        span: Span
+    },
+    Cast
+    {
+        operand: Box<Expr>,
+        datatype: TypeExpr,
+        span: Span
+    },
+    Comma
+    {
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        span: Span
+    }
+}
+
+impl Item
+{
+    /// Get the span of the name of the item, for items which have one
+    pub fn name_span(&self) -> Option<Span>
+    {
+        match self
+        {
+            Item::Function(function) => Some(function.name.span.clone()),
+            Item::Global(global) => Some(global.name.span.clone()),
+            Item::Use(path) => Some(path.span.clone()),
+            Item::Import(_) | Item::Impl(_) | Item::Error(_, _) => None
+        }
+    }
+}
+
+impl FunctionDecl
+{
+    /// Is the function generic, which is when it has type parameters
+    pub fn is_generic(&self) -> bool
+    {
+        !self.type_params.is_empty()
+    }
+
+    /// Visit every type written in the function, its return type, the types of its arguments and those in its body
+    pub fn visit_types_mut(&mut self, visit: &mut impl FnMut(&mut TypeExpr))
+    {
+        visit(&mut self.ret);
+        self.params.iter_mut().for_each(|param| visit(&mut param.datatype));
+        self.body.visit_types_mut(visit);
+    }
+}
+
+impl Stmt
+{
+    /// Visit every type written in the statement, including those of the expressions in it
+    pub fn visit_types_mut(&mut self, visit: &mut impl FnMut(&mut TypeExpr))
+    {
+        match self
+        {
+            Stmt::Empty(_) | Stmt::Continue(_) | Stmt::Break(_) | Stmt::Error(_, _) => {},
+            Stmt::Block(stmts, _) => stmts.iter_mut().for_each(|stmt| stmt.visit_types_mut(visit)),
+            Stmt::Declaration {datatype, declarators, ..} =>
+            {
+                if let Some(datatype) = datatype
+                {
+                    visit(datatype);
+                }
+
+                for declarator in declarators
+                {
+                    match &mut declarator.value
+                    {
+                        InitValue::Expr(expr) => expr.visit_types_mut(visit),
+                        InitValue::Block(block) =>
+                        {
+                            block.stmts.iter_mut().for_each(|stmt| stmt.visit_types_mut(visit));
+                            block.value.iter_mut().for_each(|value| value.visit_types_mut(visit));
+                        }
+                    }
+                }
+            },
+            Stmt::If {cond, body, otherwise, ..} =>
+            {
+                cond.visit_types_mut(visit);
+                body.visit_types_mut(visit);
+                otherwise.iter_mut().for_each(|otherwise| otherwise.visit_types_mut(visit));
+            },
+            Stmt::While {cond, body, ..} | Stmt::DoWhile {body, cond, ..} =>
+            {
+                cond.visit_types_mut(visit);
+                body.visit_types_mut(visit);
+            },
+            Stmt::Loop {body, ..} => body.visit_types_mut(visit),
+            Stmt::Return {value: expr, ..} | Stmt::Expr(expr, _) => expr.visit_types_mut(visit)
+        }
+    }
+
+    /// Get the span of the source covered by the statement
+    pub fn span(&self) -> Span
+    {
+        match self
+        {
+            Stmt::Empty(span) | Stmt::Block(_, span) | Stmt::Continue(span) | Stmt::Break(span) | Stmt::Expr(_, span) |
+            Stmt::Error(_, span) => span.clone(),
+            Stmt::Declaration {span, ..} | Stmt::If {span, ..} | Stmt::While {span, ..} | Stmt::DoWhile {span, ..} |
+            Stmt::Loop {span, ..} | Stmt::Return {span, ..} => span.clone()
+        }
+    }
+}
+
+impl InitValue
+{
+    /// Get the span of the source covered by the value
+    pub fn span(&self) -> Span
+    {
+        match self
+        {
+            InitValue::Expr(expr) => expr.span(),
+            InitValue::Block(block) => block.span.clone()
+        }
+    }
+}
+
+impl Expr
+{
+    /// Visit every type written in the expression, the types of casts and the type arguments of calls
+    pub fn visit_types_mut(&mut self, visit: &mut impl FnMut(&mut TypeExpr))
+    {
+        match self
+        {
+            Expr::Integer(_, _) | Expr::Path(_) => {},
+            Expr::Unary {operand, ..} => operand.visit_types_mut(visit),
+            Expr::Binary {lhs, rhs, ..} | Expr::Comma {lhs, rhs, ..} | Expr::Assign {target: lhs, value: rhs, ..} |
+            Expr::Index {base: lhs, index: rhs, ..} =>
+            {
+                lhs.visit_types_mut(visit);
+                rhs.visit_types_mut(visit);
+            },
+            Expr::Ternary {cond, then, otherwise, ..} =>
+            {
+                cond.visit_types_mut(visit);
+                then.visit_types_mut(visit);
+                otherwise.visit_types_mut(visit);
+            },
+            Expr::Slice {base, start, end, ..} =>
+            {
+                base.visit_types_mut(visit);
+                start.iter_mut().chain(end.iter_mut()).for_each(|bound| bound.visit_types_mut(visit));
+            },
+            Expr::BitRange {base, start, end, ..} =>
+            {
+                base.visit_types_mut(visit);
+                start.visit_types_mut(visit);
+                end.visit_types_mut(visit);
+            },
+            Expr::Member {base, ..} => base.visit_types_mut(visit),
+            Expr::Call {type_args, args, ..} =>
+            {
+                type_args.iter_mut().flatten().for_each(&mut *visit);
+                args.iter_mut().for_each(|arg| arg.visit_types_mut(visit));
+            },
+            Expr::MethodCall {receiver, args, ..} =>
+            {
+                receiver.visit_types_mut(visit);
+                args.iter_mut().for_each(|arg| arg.visit_types_mut(visit));
+            },
+            Expr::Cast {operand, datatype, ..} =>
+            {
+                operand.visit_types_mut(visit);
+                visit(datatype);
+            }
+        }
+    }
+
+    /// Get the span of the source covered by the expression
+    pub fn span(&self) -> Span
+    {
+        match self
+        {
+            Expr::Integer(_, span) => span.clone(),
+            Expr::Path(ident) => ident.span.clone(),
+            Expr::Unary {span, ..} | Expr::Binary {span, ..} | Expr::Assign {span, ..} | Expr::Ternary {span, ..} |
+            Expr::Index {span, ..} | Expr::Slice {span, ..} | Expr::BitRange {span, ..} | Expr::Member {span, ..} |
+            Expr::Call {span, ..} | Expr::MethodCall {span, ..} | Expr::Cast {span, ..} | Expr::Comma {span, ..} => span.clone()
+        }
+    }
+}
+
+impl fmt::Display for TypeExpr
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}{}{}{}", if self.progmem {"progmem "} else {""}, self.name.name, "*".repeat(self.pointers), if self.slice {"[]"} else {""})
+    }
+}
+
+/// Node of the debug view of a syntax tree, a line of text along with the nodes under it
+struct TreeView
+{
+    text: String,
+    children: Vec<TreeView>
+}
+
+impl TreeView
+{
+    /// Generate a new node of the debug view
+    fn new(text: String, children: Vec<TreeView>) -> Self
+    {
+        Self
+        {
+            text,
+            children
+        }
+    }
+
+    /// Generate a new node of the debug view with nothing under it
+    fn leaf(text: String) -> Self
+    {
+        TreeView::new(text, vec![])
+    }
+}
+
+/// Get the debug view of a list of items, under a heading (an empty list is left out)
+fn view_list(text: &str, children: Vec<TreeView>) -> Option<TreeView>
+{
+    if children.is_empty()
+    {
+        None
+    }
+    else
+    {
+        Some(TreeView::new(String::from(text), children))
+    }
+}
+
+/// Get the debug view of an item
+fn view_item(item: &Item) -> TreeView
+{
+    match item
+    {
+        Item::Function(function) => view_function(function),
+        Item::Global(global) =>
+        {
+            let mut children = vec![view_type(&global.datatype), view_ident("Identifier", &global.name)];
+
+            match &global.length
+            {
+                Some(ArrayLength::Inferred) => children.push(TreeView::leaf(String::from("Array Length"))),
+                Some(ArrayLength::Given(length)) => children.push(TreeView::new(String::from("Array Length"), vec![view_expr(length)])),
+                None => {}
+            }
+
+            match &global.value
+            {
+                Some(Initializer::Value(value)) => children.push(view_expr(value)),
+                Some(Initializer::List(values, _)) => children.push(TreeView::new(String::from("Initializer List"), values.iter().map(view_expr).collect())),
+                None => {}
+            }
+
+            TreeView::new(String::from("Global"), children)
+        },
+        Item::Import(import) => TreeView::leaf(format!("Import (\"{}\")", import.file)),
+        Item::Use(path) => TreeView::leaf(format!("Use ({})", path.name)),
+        Item::Impl(block) =>
+        {
+            let mut children = vec![view_type(&block.datatype)];
+            children.extend(block.methods.iter().map(view_function));
+
+            TreeView::new(String::from("Impl"), children)
+        },
+        Item::Error(tokens, _) => view_error(tokens)
+    }
+}
+
+/// Get the debug view of a function
+fn view_function(function: &FunctionDecl) -> TreeView
+{
+    let mut children: Vec<TreeView> = function.attributes.iter().map(|attribute| view_ident("Attribute", attribute)).collect();
+
+    children.push(view_type(&function.ret));
+    children.push(view_ident("Identifier", &function.name));
+    children.extend(view_list("Type Parameters", function.type_params.iter().map(|parameter| view_ident("Identifier", parameter)).collect()));
+    children.extend(view_list("Arguments", function.params.iter().map(|param| TreeView::new(String::from("Argument"), vec![
+        view_type(&param.datatype),
+        view_ident("Identifier", &param.name)
+    ])).collect()));
+    children.push(view_stmt(&function.body));
+
+    TreeView::new(String::from("Function"), children)
+}
+
+/// Get the debug view of a name
+fn view_ident(text: &str, ident: &Ident) -> TreeView
+{
+    TreeView::leaf(format!("{} ({})", text, ident.name))
+}
+
+/// Get the debug view of a type
+fn view_type(datatype: &TypeExpr) -> TreeView
+{
+    TreeView::leaf(format!("Type ({})", datatype))
+}
+
+/// Get the debug view of the tokens skipped after a syntax error
+fn view_error(tokens: &[Token]) -> TreeView
+{
+    TreeView::leaf(format!("Error ({})", tokens.iter().map(|token| token.data.as_str()).collect::<Vec<_>>().join(" ")))
+}
+
+/// Get the debug view of a statement
+fn view_stmt(stmt: &Stmt) -> TreeView
+{
+    match stmt
+    {
+        Stmt::Empty(_) => TreeView::leaf(String::from("Empty Statement")),
+        Stmt::Block(stmts, _) => TreeView::new(String::from("Statements"), stmts.iter().map(view_stmt).collect()),
+        Stmt::Continue(_) => TreeView::leaf(String::from("Continue")),
+        Stmt::Break(_) => TreeView::leaf(String::from("Break")),
+        Stmt::Declaration {datatype, declarators, ..} =>
+        {
+            let mut children = vec![match datatype
+            {
+                Some(datatype) => view_type(datatype),
+                None => TreeView::leaf(String::from("Let"))
+            }];
+
+            children.extend(declarators.iter().map(|declarator| TreeView::new(String::from("Assignment"), vec![
+                view_ident("Identifier", &declarator.name),
+                match &declarator.value
+                {
+                    InitValue::Expr(expr) => view_expr(expr),
+                    InitValue::Block(block) =>
+                    {
+                        let mut items: Vec<TreeView> = block.stmts.iter().map(view_stmt).collect();
+                        items.extend(block.value.iter().map(|value| view_expr(value)));
+
+                        TreeView::new(String::from("Block Expression"), items)
+                    }
+                }
+            ])));
+
+            TreeView::new(String::from("Declaration"), children)
+        },
+        Stmt::If {cond, body, otherwise, ..} =>
+        {
+            let mut children = vec![view_expr(cond), view_stmt(body)];
+            children.extend(otherwise.iter().map(|otherwise| view_stmt(otherwise)));
+
+            TreeView::new(String::from("If Statement"), children)
+        },
+        Stmt::While {cond, body, ..} => TreeView::new(String::from("While Loop"), vec![view_expr(cond), view_stmt(body)]),
+        Stmt::DoWhile {body, cond, ..} => TreeView::new(String::from("Do While Loop"), vec![view_stmt(body), view_expr(cond)]),
+        Stmt::Loop {body, ..} => TreeView::new(String::from("Loop"), vec![view_stmt(body)]),
+        Stmt::Return {value, ..} => TreeView::new(String::from("Return Statement"), vec![view_expr(value)]),
+        Stmt::Expr(expr, _) => TreeView::new(String::from("Statement"), vec![view_expr(expr)]),
+        Stmt::Error(tokens, _) => view_error(tokens)
+    }
+}
+
+/// Get the debug view of an expression
+fn view_expr(expr: &Expr) -> TreeView
+{
+    match expr
+    {
+        Expr::Integer(value, _) => TreeView::leaf(format!("Integer ({})", value)),
+        Expr::Path(ident) => view_ident("Identifier", ident),
+        Expr::Unary {op, operand, ..} => TreeView::new(format!("Expression ({:?})", op), vec![view_expr(operand)]),
+        Expr::Binary {op, lhs, rhs, ..} => TreeView::new(format!("Expression ({:?})", op), vec![view_expr(lhs), view_expr(rhs)]),
+        Expr::Assign {op: None, target, value, ..} => TreeView::new(String::from("Expression (Assignment)"), vec![view_expr(target), view_expr(value)]),
+        Expr::Assign {op: Some(op), target, value, ..} => TreeView::new(format!("Expression ({:?}Assign)", op), vec![view_expr(target), view_expr(value)]),
+        Expr::Ternary {cond, then, otherwise, ..} => TreeView::new(String::from("Expression (Ternary)"), vec![view_expr(cond), view_expr(then), view_expr(otherwise)]),
+        Expr::Index {base, index, ..} => TreeView::new(String::from("Expression (ArrayAccess)"), vec![view_expr(base), view_expr(index)]),
+        Expr::Slice {base, start, end, ..} =>
+        {
+            let bound = |text: &str, bound: &Option<Box<Expr>>| match bound
+            {
+                Some(bound) => view_expr(bound),
+                None => TreeView::leaf(String::from(text))
+            };
+
+            TreeView::new(String::from("Expression (Slice)"), vec![view_expr(base), bound("Start", start), bound("End", end)])
+        },
+        Expr::BitRange {base, start, end, ..} => TreeView::new(String::from("Expression (BitRange)"), vec![view_expr(base), view_expr(start), view_expr(end)]),
+        Expr::Member {base, member, ..} => TreeView::new(String::from("Expression (Member)"), vec![view_expr(base), view_ident("Identifier", member)]),
+        Expr::Call {callee, type_args, args, ..} =>
+        {
+            let mut children = vec![view_ident("Identifier", callee)];
+            children.extend(type_args.iter().map(|types| TreeView::new(String::from("Type Arguments"), types.iter().map(view_type).collect())));
+            children.extend(args.iter().map(view_expr));
+
+            TreeView::new(String::from("Expression (FunctionCall)"), children)
+        },
+        Expr::MethodCall {receiver, method, args, ..} =>
+        {
+            let mut children = vec![view_expr(receiver), view_ident("Identifier", method)];
+            children.extend(args.iter().map(view_expr));
+
+            TreeView::new(String::from("Expression (MethodCall)"), children)
+        },
+        Expr::Cast {operand, datatype, ..} => TreeView::new(String::from("Expression (Cast)"), vec![view_expr(operand), view_type(datatype)]),
+        Expr::Comma {lhs, rhs, ..} => TreeView::new(String::from("Expression (Comma)"), vec![view_expr(lhs), view_expr(rhs)])
+    }
+}
+
+/// Display a node of the debug view, with the lines drawn before it
+fn display_view(view: &TreeView, prev: String, is_last: bool)
+{
+    let mut mprev = prev.clone();
+
+    if !mprev.is_empty()
+    {
+        mprev.pop();
+        mprev += if is_last {"└"} else {"├"};
+    }
+
+    println!("{}{}{}", mprev, if view.children.len() == 0 {"─"} else {"┬"}, view.text);
+
+    if mprev.len() > 0
+    {
+        mprev.pop();
+        mprev += if is_last {" "} else {"│"};
+    }
+
+    for (i, child) in view.children.iter().enumerate()
+    {
+        let new_prev = format!("{}{}", mprev, "│");
+        display_view(child, new_prev, i == view.children.len() - 1);
+    }
+}
+
+/// Display the syntax tree of a library as a tree of nodes, for debugging
+pub fn display_syntax_tree(library: &Library)
+{
+    display_view(&TreeView::new(String::from("Library"), library.items.iter().map(view_item).collect()), String::new(), false);
+}
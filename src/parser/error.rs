@@ -1,22 +1,21 @@
-use crate::cli::Error;
-use crate::tokenizer::{Token, FileLocation};
+use crate::cli::{Error, ErrorCode};
+use crate::tokenizer::{Token, Span};
 
-/// Generate a new parse error
-pub fn parse_error<T>(location: FileLocation, text: String) -> Result<T, Error>
+/// Generate a new parse error, labelling the source it is about
+pub fn parse_error<T>(code: ErrorCode, span: Span, text: String, label: &str) -> Result<T, Error>
 {
-    Err(Error::error(&format!("Parse Error: {} at {}", text, location)))
+    Err(Error::error(&format!("Parse Error: {}", text)).with_code(code).with_label(span, label))
 }
 
 /// Generate a parse error with an expected, got format
 pub fn expected_got_error<T>(expected: &str, got: &Token) -> Result<T, Error>
 {
-    parse_error(got.clone().location, format!("Expected {}, got '{}'", expected, got.data))
+    parse_error(ErrorCode::E0001, got.span(), format!("Expected {}, got '{}'", expected, got.data), &format!("expected {}", expected))
 }
 
 /// Generate an error when reaching an unexpected EOF
 pub fn unexpected_eof_error<T>(expected: &str, last: &Token) -> Result<T, Error>
 {
-    let mut loc = last.clone().location;
-    loc.col += last.data.len();
-    parse_error(loc, format!("Unexpected EOF while parsing, expected {}", expected))
+    let loc = last.span().end;
+    parse_error(ErrorCode::E0002, Span::new(loc.clone(), loc), format!("Unexpected EOF while parsing, expected {}", expected), &format!("expected {}", expected))
 }
\ No newline at end of file
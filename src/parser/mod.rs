@@ -1,6 +1,6 @@
-mod node;
+mod ast;
 mod parser;
 mod error;
 
-pub use node::*;
+pub use ast::*;
 pub use parser::*;
\ No newline at end of file
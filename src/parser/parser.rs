@@ -1,47 +1,68 @@
-use regex::Regex;
-use lazy_static::lazy_static;
-
-use crate::tokenizer::Token;
-use super::{ParseTreeNode, ExpressionType};
-use crate::cli::Error;
-use super::error::{expected_got_error, unexpected_eof_error};
+use crate::tokenizer::{Token, TokenKind, Keyword, Punct, Span};
+use super::{Library, Item, Ident, TypeExpr, Param, FunctionDecl, GlobalDecl, ArrayLength, Initializer, Import, ImplBlock};
+use super::{Stmt, Declarator, InitValue, BlockExpr, Expr, UnaryOp, BinaryOp};
+use crate::cli::{Error, ErrorCode};
+use super::error::{parse_error, expected_got_error, unexpected_eof_error};
 
 static TYPES: &[&str] = &["i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64", "void"];
-static KEYWORDS: &[&str] = &["loop", "while", "if", "break", "continue", "else", "do", "as"];
 static MAX_EXPRESSION: usize = 17;
 
-lazy_static!
+/// Token Stream, a position in a borrowed slice of tokens along with the state of the parse
+#[derive(Debug)]
+pub struct Stream<'a>
 {
-    static ref IDENTIFIER_REGEX: Regex = Regex::new(r"\A[a-zA-Z|_][a-zA-Z0-9|_]*").unwrap();
-    static ref INTEGER_REGEX: Regex = Regex::new(r"\A[0-9]+").unwrap();
+    tokens: &'a [Token],
+    index: usize,
+    type_parameters: Vec<String>,
+    errors: Vec<Error>,
+    eof_reported: bool
 }
 
-/// Token Stream
-#[derive(Debug, Clone)]
-pub struct Stream
+/// Position of a token stream to go back to when a parse fails, or after looking ahead. Errors and type parameters
+/// are only ever added to the end of their lists, so their lengths are enough to go back
+#[derive(Debug, Clone, Copy)]
+struct Checkpoint
 {
-    tokens: Vec<Token>,
-    index: usize
+    index: usize,
+    errors: usize,
+    type_parameters: usize,
+    eof_reported: bool
 }
 
-impl Stream
+impl<'a> Stream<'a>
 {
     /// Generate a new token stream object
-    pub fn new(tokens: Vec<Token>) -> Self
+    pub fn new(tokens: &'a [Token]) -> Self
     {
         Self
         {
             tokens,
-            index: 0
+            index: 0,
+            type_parameters: vec![],
+            errors: vec![],
+            eof_reported: false
         }
     }
 
+    /// Get the index of the end of file token, which ends the tokens
+    fn end(&self) -> usize
+    {
+        self.tokens.len() - 1
+    }
+
+    /// Get the last token before an index, for errors at the end of the file (the end of file token itself if there
+    /// are no tokens before it)
+    fn last_before(&self, index: usize) -> &'a Token
+    {
+        &self.tokens[index.min(self.end()).saturating_sub(1)]
+    }
+
     /// Peek at the next token
-    pub fn peek(&self) -> Option<Token>
+    pub fn peek(&self) -> Option<&'a Token>
     {
-        if self.index + 1 < self.tokens.len()
+        if self.index + 1 < self.end()
         {
-            Some(self.tokens[self.index + 1].clone())
+            Some(&self.tokens[self.index + 1])
         }
         else
         {
@@ -50,11 +71,11 @@ impl Stream
     }
 
     /// Get the current token
-    pub fn current(&self) -> Option<Token>
+    pub fn current(&self) -> Option<&'a Token>
     {
-        if self.index < self.tokens.len()
+        if self.index < self.end()
         {
-            Some(self.tokens[self.index].clone())
+            Some(&self.tokens[self.index])
         }
         else
         {
@@ -66,62 +87,61 @@ impl Stream
     pub fn consume(&mut self) -> bool
     {
         self.index += 1;
-        self.index < self.tokens.len()
+        self.index < self.end()
     }
 
-    /// Check the next token
-    pub fn check_next(&self, data: String) -> bool
+    /// Check the kind of the next token
+    pub fn check_next(&self, kind: impl Into<TokenKind>) -> bool
     {
-        (self.index + 1 < self.tokens.len()) && (self.peek().unwrap().data == data)
+        self.peek().is_some_and(|token| token.kind == kind.into())
     }
 
-    /// Check the current token
-    pub fn check_current(&self, data: String) -> bool
+    /// Check the kind of the current token
+    pub fn check_current(&self, kind: impl Into<TokenKind>) -> bool
     {
-        (self.index < self.tokens.len()) && (self.current().unwrap().data == data)
+        self.current().is_some_and(|token| token.kind == kind.into())
     }
 
-    /// Expect a token at an offset from the current token
-    fn expect_at(&self, data: String, index: usize) -> Result<(), Error>
+    /// Expect a token of a kind at an offset from the current token
+    fn expect_at(&self, kind: TokenKind, index: usize) -> Result<(), Error>
     {
-        if index < self.tokens.len()
+        if index < self.end()
         {
-            let got = self.tokens[index].data.clone();
-            if data == got
+            if self.tokens[index].kind == kind
             {
                 Ok(())
             }
             else
             {
-                expected_got_error(&format!("'{}'", data), &self.tokens[index])
+                expected_got_error(&format!("'{}'", kind), &self.tokens[index])
             }
         }
         else
         {
-            unexpected_eof_error(&format!("'{}'", data), &self.tokens[index - 1])
+            unexpected_eof_error(&format!("'{}'", kind), self.last_before(index))
         }
     }
 
-    /// Expect a token at the current position
-    pub fn expect(&self, data: String) -> Result<(), Error>
+    /// Expect a token of a kind at the current position
+    pub fn expect(&self, kind: impl Into<TokenKind>) -> Result<(), Error>
     {
-        self.expect_at(data, self.index)
+        self.expect_at(kind.into(), self.index)
     }
 
-    /// Expect and consume a token
-    pub fn expect_and_consume(&mut self, data: String) -> Result<(), Error>
+    /// Expect and consume a token of a kind
+    pub fn expect_and_consume(&mut self, kind: impl Into<TokenKind>) -> Result<(), Error>
     {
-        match self.expect_at(data, self.index)
+        match self.expect_at(kind.into(), self.index)
         {
             Ok(()) => {self.consume(); Ok(())},
             Err(e) => Err(e)
         }
     }
     
-    /// Expect the next token
-    pub fn expect_next(&self, data: String) -> Result<(), Error>
+    /// Expect the next token to be of a kind
+    pub fn expect_next(&self, kind: impl Into<TokenKind>) -> Result<(), Error>
     {
-        self.expect_at(data, self.index + 1)
+        self.expect_at(kind.into(), self.index + 1)
     }
 
     /// Expect that the EOF hasn't been reached
@@ -129,7 +149,7 @@ impl Stream
     {
         if self.current().is_none()
         {
-            unexpected_eof_error(s, &self.tokens[self.index - 1])
+            unexpected_eof_error(s, self.last_before(self.index))
         }
         else
         {
@@ -137,407 +157,436 @@ impl Stream
         }
     }
 
-    /// Accept a stream
-    pub fn accept_stream(&mut self, result: Result<(Stream, ParseTreeNode), Error>) -> Result<ParseTreeNode, Error>
+    /// Get a checkpoint at the current position
+    fn checkpoint(&self) -> Checkpoint
     {
-        let val = result?;
-        self.tokens = val.0.tokens;
-        self.index = val.0.index;
-
-        Ok(val.1)
+        Checkpoint
+        {
+            index: self.index,
+            errors: self.errors.len(),
+            type_parameters: self.type_parameters.len(),
+            eof_reported: self.eof_reported
+        }
     }
-}
 
-/// Convert a parse tree to be on the left hand side
-pub fn convert_to_left(node: ParseTreeNode)  -> Result<ParseTreeNode, Error>
-{
-    let mut tree = node.clone();
-    
-    tree = match &tree
+    /// Go back to a checkpoint
+    fn restore(&mut self, checkpoint: Checkpoint)
     {
-        ParseTreeNode::Expression(expr_type, children) =>
-        {
-            if expr_type == &ExpressionType::Dereference
-            {
-                ParseTreeNode::Expression(ExpressionType::DereferenceLeft, children.clone())
-            }
-            else
-            {
-                tree
-            }
-        },
-        _ => tree
-    };
+        self.index = checkpoint.index;
+        self.errors.truncate(checkpoint.errors);
+        self.type_parameters.truncate(checkpoint.type_parameters);
+        self.eof_reported = checkpoint.eof_reported;
+    }
 
-    tree = match tree
+    /// Run a parse, going back to where it started if it fails
+    pub fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error>
     {
-        ParseTreeNode::Library(c) =>
-        {
-            let mut new_children = vec![];
+        let checkpoint = self.checkpoint();
+        let result = parse(self);
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
-
-            ParseTreeNode::Library(new_children)
-        },
-        ParseTreeNode::Function(c) =>
+        if result.is_err()
         {
-            let mut new_children = vec![];
-
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
-
-            ParseTreeNode::Function(new_children)
-        },
-        ParseTreeNode::Arguments(c) =>
-        {
-            let mut new_children = vec![];
-
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
-
-            ParseTreeNode::Arguments(new_children)
-        },
-        ParseTreeNode::Argument(c) =>
-        {
-            let mut new_children = vec![];
+            self.restore(checkpoint);
+        }
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        result
+    }
 
-            ParseTreeNode::Argument(new_children)
-        },
-        ParseTreeNode::Type(c) =>
-        {
-            let mut new_children = vec![];
+    /// Run a parse to look ahead, always going back to where it started
+    pub fn lookahead<T>(&mut self, parse: impl FnOnce(&mut Self) -> T) -> T
+    {
+        let checkpoint = self.checkpoint();
+        let result = parse(self);
+        self.restore(checkpoint);
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        result
+    }
 
-            ParseTreeNode::Type(new_children)
-        },
-        ParseTreeNode::Statement(c) =>
+    /// Check if the end of the file was reached inside of a block, the block is cut off there so the errors found in
+    /// it are kept (only one error is given however many blocks are left open)
+    pub fn check_unclosed_block(&mut self) -> bool
+    {
+        if self.current().is_some()
         {
-            let mut new_children = vec![];
-
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+            return false;
+        }
 
-            ParseTreeNode::Statement(new_children)
-        },
-        ParseTreeNode::Statements(c) =>
+        if !self.eof_reported
         {
-            let mut new_children = vec![];
-
-            for child in c
+            if let Err(error) = self.expect_current_exists("'}'")
             {
-                new_children.push(convert_to_left(child)?);
+                self.errors.push(error);
             }
 
-            ParseTreeNode::Statements(new_children)
-        },
-        ParseTreeNode::Assignments(c) =>
-        {
-            let mut new_children = vec![];
+            self.eof_reported = true;
+        }
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        true
+    }
 
-            ParseTreeNode::Assignments(new_children)
-        },
-        ParseTreeNode::Assignment(c) =>
-        {
-            let mut new_children = vec![];
+    /// Get the span from the token at an index up to the last token consumed (just the token at the index if nothing
+    /// has been consumed since)
+    fn span_from(&self, start: usize) -> Span
+    {
+        let last = self.index.max(start + 1) - 1;
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        self.tokens[start.min(self.end())].span().join(&self.tokens[last.min(self.end())].span())
+    }
 
-            ParseTreeNode::Assignment(new_children)
-        },
-        ParseTreeNode::Expression(t, c) =>
-        {
-            let mut new_children = vec![];
+    /// Record a syntax error and skip the tokens of the statement (or top level item) it is in, giving the skipped
+    /// tokens along with their span. A statement ends at a ';' or at the '}' of its block, and an item ends at a ';',
+    /// at the '}' closing its body or before the next type or keyword starting another item
+    pub fn recover(&mut self, error: Error, top_level: bool) -> (Vec<Token>, Span)
+    {
+        self.errors.push(error);
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        let start = self.index;
+        let mut skipped = vec![];
+        let mut braces = 0;
+        let mut parens = 0;
 
-            ParseTreeNode::Expression(t, new_children)
-        },
-        ParseTreeNode::AssignmentStatement(c) =>
+        while let Some(token) = self.current()
         {
-            let mut new_children = vec![];
+            let kind = token.kind;
 
-            for child in c
+            if !skipped.is_empty() && braces == 0 && (kind == TokenKind::Punct(Punct::RightBrace) || top_level && parens == 0 && starts_item(token))
             {
-                new_children.push(convert_to_left(child)?);
+                break;
             }
 
-            ParseTreeNode::AssignmentStatement(new_children)
-        },
-
-        ParseTreeNode::IfStatement(c) =>
-        {
-            let mut new_children = vec![];
+            self.consume();
+            skipped.push(token.clone());
 
-            for child in c
+            match kind
             {
-                new_children.push(convert_to_left(child)?);
+                TokenKind::Punct(Punct::LeftBrace) => braces += 1,
+                TokenKind::Punct(Punct::RightBrace) if braces > 0 => braces -= 1,
+                TokenKind::Punct(Punct::LeftParen) => parens += 1,
+                TokenKind::Punct(Punct::RightParen) if parens > 0 => parens -= 1,
+                _ => {}
             }
 
-            ParseTreeNode::IfStatement(new_children)
-        },
-        ParseTreeNode::ReturnStatement(c) =>
-        {
-            let mut new_children = vec![];
-
-            for child in c
+            if braces == 0 && matches!(kind, TokenKind::Punct(Punct::Semicolon | Punct::RightBrace))
             {
-                new_children.push(convert_to_left(child)?);
+                break;
             }
+        }
 
-            ParseTreeNode::ReturnStatement(new_children)
-        },
-        ParseTreeNode::WhileLoop(c) =>
-        {
-            let mut new_children = vec![];
-
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+        (skipped, self.span_from(start))
+    }
+}
 
-            ParseTreeNode::WhileLoop(new_children)
-        },
-        ParseTreeNode::DoWhileLoop(c) =>
-        {
-            let mut new_children = vec![];
+/// Operator joining two expressions
+enum Infix
+{
+    Binary(BinaryOp),
+    Assign(Option<BinaryOp>),
+    Comma
+}
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+/// Get the syntax tree for a translation unit, along with every syntax error found in it. Code with a syntax error is
+/// skipped, and is left in the tree as an error node
+pub fn parse(tokens: Vec<Token>) -> (Library, Vec<Error>)
+{
+    let mut stream = Stream::new(&tokens);
+    let library = parse_library(&mut stream);
 
-            ParseTreeNode::DoWhileLoop(new_children)
-        },
-        ParseTreeNode::Loop(c) =>
-        {
-            let mut new_children = vec![];
+    (library, stream.errors)
+}
 
-            for child in c
-            {
-                new_children.push(convert_to_left(child)?);
-            }
+/// Check if a token can start an item of a library, which is where skipping an item with a syntax error stops
+fn starts_item(token: &Token) -> bool
+{
+    match token.kind
+    {
+        TokenKind::Ident => is_type_name(&token.data),
+        TokenKind::Keyword(keyword) => matches!(keyword, Keyword::Import | Keyword::Use | Keyword::Impl),
+        _ => false
+    }
+}
 
-            ParseTreeNode::Loop(new_children)
-        },
-        _ => tree
-    };
+/// Check if a name is the name of a type, integers can have any width, as in u24
+fn is_type_name(name: &str) -> bool
+{
+    let mut chars = name.chars();
 
-    Ok(tree)
+    TYPES.contains(&name) || (matches!(chars.next(), Some('i') | Some('u')) &&
+        matches!(chars.next(), Some('1'..='9')) && chars.all(|c| c.is_ascii_digit()))
 }
 
-/// Get the parse tree for a translation unit
-pub fn parse(tokens: Vec<Token>) -> Result<ParseTreeNode, Error>
+/// Get the name written by a token
+fn ident(token: &Token) -> Ident
 {
-    Ok(parse_library(&Stream::new(tokens))?.1)
+    Ident
+    {
+        name: token.data.clone(),
+        span: token.span()
+    }
 }
 
 /// Parse out a raw type
 /// (for example i8, but not i8*)
-fn parse_raw_type(orig_stream: &Stream) -> Result<(Stream, ParseTreeNode), Error>
+fn parse_raw_type(stream: &mut Stream) -> Result<Ident, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("raw type")?;
 
     let val = stream.current().unwrap();
 
-    if TYPES.contains(&val.data.as_str())
+    // The type parameters of a generic function can be used as types within it
+    if val.kind == TokenKind::Ident && (is_type_name(&val.data) || stream.type_parameters.contains(&val.data))
     {
         stream.consume();
-        Ok((stream, ParseTreeNode::RawType(val.clone())))
+        Ok(ident(val))
     }
     else
     {
-        expected_got_error("raw type", &val)
+        expected_got_error("raw type", val)
     }
 }
 
 /// Parse out an identifier
-fn parse_identifier(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_identifier(stream: &mut Stream) -> Result<Ident, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("identifier")?;
 
     let val = stream.current().unwrap();
 
-    
-        // A type instead
-    if  TYPES.contains(&val.data.as_str()) ||  
-
-        // A keyword
-        KEYWORDS.contains(&val.data.as_str()) ||
-        // Doesn't match the identifier regex
-        !IDENTIFIER_REGEX.is_match(val.data.as_str())
+    // Keywords and the names of types aren't identifiers
+    if val.kind != TokenKind::Ident || is_type_name(&val.data)
     {
-        return expected_got_error("identifier",&val);
+        return expected_got_error("identifier", val);
     }
 
     stream.consume();
-    Ok((stream, ParseTreeNode::Identifier(val.clone())))
+    Ok(ident(val))
 }
 
-/// Parse out an integer
-fn parse_integer(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+/// Parse out an identifier which may be qualified by the name of a module
+/// for example uart::init
+fn parse_path(stream: &mut Stream) -> Result<Ident, Error>
 {
-    let mut stream = orig_stream.clone();
+    let mut path = parse_identifier(stream)?;
 
-    // Make sure the stream isn't exhausted
-    stream.expect_current_exists("integer")?;
+    while stream.check_current(Punct::PathSeparator)
+    {
+        stream.consume();
 
-    let val = stream.current().unwrap();
+        let name = parse_identifier(stream)?;
 
-    if !INTEGER_REGEX.is_match(val.data.as_str())
-    {
-        return expected_got_error("integer",&val);
+        path.name = format!("{}::{}", path.name, name.name);
+        path.span = path.span.join(&name.span);
     }
 
-    stream.consume();
-    Ok((stream, ParseTreeNode::IntegerLiteral(val.clone())))
+    Ok(path)
 }
 
-/// Parse out a single token
-fn parse_token(orig_stream: &Stream, what: &str) -> Result<(Stream, ParseTreeNode), Error>
+/// Parse out an integer
+fn parse_integer(stream: &mut Stream) -> Result<Expr, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
-    stream.expect_current_exists(what)?;
+    stream.expect_current_exists("integer")?;
 
     let val = stream.current().unwrap();
-    stream.consume();
 
-    Ok((stream, ParseTreeNode::RawToken(val)))
+    if val.kind != TokenKind::IntLit
+    {
+        return expected_got_error("integer", val);
+    }
+
+    stream.consume();
+    Ok(Expr::Integer(val.data.clone(), val.span()))
 }
 
 /// Parse out a type
-/// Either just a raw type or a raw type folloed by multiple '*'s
-/// Parse out an identifier
-fn parse_type(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+/// Either just a raw type or a raw type folloed by multiple '*'s and an optional '[]', optionally
+/// preceeded by the progmem qualifier
+fn parse_type(stream: &mut Stream) -> Result<TypeExpr, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("type")?;
 
-    // First is getting the raw type
-    let raw_type = stream.accept_stream(parse_raw_type(&stream))?;
-    let mut items = vec![raw_type];
+    let start = stream.index;
+
+    // The type may be qualified as living in program memory
+    let progmem = stream.check_current(Keyword::Progmem);
 
-    while stream.check_current(String::from("*"))
+    if progmem
     {
-        items.push(ParseTreeNode::RawToken(stream.current().unwrap()));
         stream.consume();
     }
 
-    Ok((stream, ParseTreeNode::Type(items)))
+    // Next is getting the raw type
+    let name = parse_raw_type(stream)?;
+    let mut pointers = 0;
+
+    while stream.check_current(Punct::Star)
+    {
+        pointers += 1;
+        stream.consume();
+    }
+
+    // Slices carry their length along with the pointer, for example u8[]
+    let slice = stream.check_current(Punct::LeftBracket) && stream.check_next(Punct::RightBracket);
+
+    if slice
+    {
+        stream.consume();
+        stream.consume();
+    }
+
+    Ok(TypeExpr
+    {
+        progmem,
+        name,
+        pointers,
+        slice,
+        span: stream.span_from(start)
+    })
 }
 
 /// Recursive Parsing of expressions
-fn recursive_expression(orig_stream: &Stream, depth: usize) -> Result<(Stream, ParseTreeNode), Error>
+fn recursive_expression(stream: &mut Stream, depth: usize) -> Result<Expr, Error>
 {
-    let mut stream = orig_stream.clone();
+    // Make sure the stream isn't exhausted, an operator may be the last token
+    stream.expect_current_exists("expression")?;
+
+    let start = stream.index;
 
     match depth
     {
         // Number, Identifier, (expr)
         0 => 
         {
-            if stream.check_current(String::from("("))
+            if stream.check_current(Punct::LeftParen)
             {
                 // Open Paren
-                stream.expect_and_consume(String::from("("))?;
+                stream.expect_and_consume(Punct::LeftParen)?;
 
-                let val = stream.accept_stream(recursive_expression(&stream, MAX_EXPRESSION))?;
+                let val = recursive_expression(stream, MAX_EXPRESSION)?;
 
                 // Close Paren
-                stream.expect_and_consume(String::from(")"))?;
+                stream.expect_and_consume(Punct::RightParen)?;
 
-                Ok((stream, val))
+                Ok(val)
             }
-            else if let Ok(val) = parse_integer(&stream)
+            else if let Ok(val) = stream.attempt(parse_integer)
             {
                 Ok(val)
             }
-            else if let Ok(val) = parse_identifier(&stream)
+            else if let Ok(val) = stream.attempt(parse_path)
             {
-                Ok(val)
+                Ok(Expr::Path(val))
             }
             else
             {
-                expected_got_error("expression", &stream.current().unwrap())
+                expected_got_error("expression", stream.current().unwrap())
             }
         },
-        // Array Access or Function Call
+        // Array Access, Slice, Member Access or Function Call
         1 =>
         {
             // Start with a previous expression
-            let mut current = stream.accept_stream(recursive_expression(&stream, depth - 1))?;
+            let mut current = recursive_expression(stream, depth - 1)?;
 
             // Loop because this operation is left associative
-            while stream.check_current(String::from("[")) || stream.check_current(String::from("("))
+            while stream.check_current(Punct::LeftBracket) || stream.check_current(Punct::LeftParen) || stream.check_current(Punct::Dot) || is_type_arguments(stream)
             {
-                // Array access
-                if stream.check_current(String::from("["))
+                // Array access or a slice of a range of elements, either end of the range can be left out
+                if stream.check_current(Punct::LeftBracket)
                 {
                     // Open bracket
-                    stream.expect_and_consume(String::from("["))?;
+                    stream.expect_and_consume(Punct::LeftBracket)?;
 
                     // Get the internal expression
-                    let expr = stream.accept_stream(parse_expression(&stream))?;
+                    let first = if stream.check_current(Punct::DotDot) {None} else {Some(Box::new(parse_expression(stream)?))};
+
+                    let range = if stream.check_current(Punct::DotDot)
+                    {
+                        stream.consume();
+
+                        Some(if stream.check_current(Punct::RightBracket) {None} else {Some(Box::new(parse_expression(stream)?))})
+                    }
+                    else
+                    {
+                        None
+                    };
 
                     // Close bracket
-                    stream.expect_and_consume(String::from("]"))?;
+                    stream.expect_and_consume(Punct::RightBracket)?;
 
-                    current = ParseTreeNode::Expression(ExpressionType::ArrayAccess, vec![current, expr]);
+                    let base = Box::new(current);
+                    let span = stream.span_from(start);
+
+                    current = match (first, range)
+                    {
+                        (start, Some(end)) => Expr::Slice {base, start, end, span},
+                        (Some(index), None) => Expr::Index {base, index, span},
+                        (None, None) => unreachable!()
+                    };
+                }
+                // Member access or a range of bits, for example reg.[3..5]
+                else if stream.check_current(Punct::Dot)
+                {
+                    stream.consume();
+
+                    if stream.check_current(Punct::LeftBracket)
+                    {
+                        stream.expect_and_consume(Punct::LeftBracket)?;
+
+                        let first = parse_expression(stream)?;
+                        stream.expect_and_consume(Punct::DotDot)?;
+                        let last = parse_expression(stream)?;
+
+                        stream.expect_and_consume(Punct::RightBracket)?;
+
+                        current = Expr::BitRange
+                        {
+                            base: Box::new(current),
+                            start: Box::new(first),
+                            end: Box::new(last),
+                            span: stream.span_from(start)
+                        };
+
+                        continue;
+                    }
+
+                    let member = parse_identifier(stream)?;
+
+                    // A member followed by arguments is a method call, which is finished by the function call below
+                    current = Expr::Member
+                    {
+                        base: Box::new(current),
+                        member,
+                        span: stream.span_from(start)
+                    };
                 }
                 // Function Call
                 else
                 {
+                    // Generic functions may be given type arguments explicitly
+                    let type_args = if is_type_arguments(stream)
+                    {
+                        Some(parse_type_arguments(stream)?)
+                    }
+                    else
+                    {
+                        None
+                    };
+
                     // Open bracket
-                    stream.expect_and_consume(String::from("("))?;
+                    stream.expect_and_consume(Punct::LeftParen)?;
 
-                    // Array starts with the current expression
-                    let mut items = vec![current];
+                    let mut args = vec![];
 
-                    while !stream.check_current(String::from(")"))
+                    while !stream.check_current(Punct::RightParen)
                     {
-                        items.push(stream.accept_stream(recursive_expression(&stream, MAX_EXPRESSION - 1))?);
+                        args.push(recursive_expression(stream, MAX_EXPRESSION - 1)?);
 
-                        if stream.check_current(String::from(","))
+                        if stream.check_current(Punct::Comma)
                         {
-                            stream.expect_and_consume(String::from(","))?;
+                            stream.expect_and_consume(Punct::Comma)?;
                         }
                         else
                         {
@@ -546,75 +595,86 @@ fn recursive_expression(orig_stream: &Stream, depth: usize) -> Result<(Stream, P
                     }
 
                     // Close bracket
-                    stream.expect_and_consume(String::from(")"))?;
+                    stream.expect_and_consume(Punct::RightParen)?;
+
+                    let span = stream.span_from(start);
 
-                    current = ParseTreeNode::Expression(ExpressionType::FunctionCall, items);
+                    // A method call is given the receiver and the name of the method, anything else called must be
+                    // the name of a function
+                    current = match (current, type_args)
+                    {
+                        (Expr::Member {base, member, ..}, None) => Expr::MethodCall {receiver: base, method: member, args, span},
+                        (Expr::Path(callee), type_args) => Expr::Call {callee, type_args, args, span},
+                        (callee, _) => return parse_error(ErrorCode::E0001, callee.span(), String::from("Expected the name of a function to call"),
+                                                          "expected a function name")
+                    };
                 }
             }
 
-            Ok((stream, current))
+            Ok(current)
         },
         // Post Increment and Post Decrement
         2 =>
         {
             // Start with a previous expression
-            let mut current = stream.accept_stream(recursive_expression(&stream, depth - 1))?;
+            let mut current = recursive_expression(stream, depth - 1)?;
 
             // Loop because this operation is left associative
-            while stream.check_current(String::from("++")) || stream.check_current(String::from("--"))
+            while stream.check_current(Punct::PlusPlus) || stream.check_current(Punct::MinusMinus)
             {
-                // Post Increment
-                if stream.check_current(String::from("++"))
-                {
-                    // Opperation
-                    stream.expect_and_consume(String::from("++"))?;
-                    
-                    current = ParseTreeNode::Expression(ExpressionType::PostIncrement, vec![current]);
-                }
-                // Post Decrement
-                else
+                // Post Increment or Post Decrement
+                let op = if stream.check_current(Punct::PlusPlus) {UnaryOp::PostIncrement} else {UnaryOp::PostDecrement};
+
+                stream.consume();
+
+                current = Expr::Unary
                 {
-                    // Opperation
-                    stream.expect_and_consume(String::from("--"))?;
-                    
-                    current = ParseTreeNode::Expression(ExpressionType::PostDecrement, vec![current]);
-                }
+                    op,
+                    operand: Box::new(current),
+                    span: stream.span_from(start)
+                };
             }
 
-            Ok((stream, current))
+            Ok(current)
         },
         // Prefix Operators
         3 =>
         {
-            let op = match stream.current().unwrap().data.as_str()
+            let op = match stream.current().unwrap().kind
             {
-                "++" => Some(ExpressionType::PreIncrement),
-                "--" => Some(ExpressionType::PreDecrement),
-                "+" => Some(ExpressionType::UnaryPlus),
-                "-" => Some(ExpressionType::UnaryMinus),
-                "!" => Some(ExpressionType::LogicalNot),
-                "~" => Some(ExpressionType::BitwiseNot),
-                "*" => Some(ExpressionType::Dereference),
-                "&" => Some(ExpressionType::Reference),
+                TokenKind::Punct(Punct::PlusPlus) => Some(UnaryOp::PreIncrement),
+                TokenKind::Punct(Punct::MinusMinus) => Some(UnaryOp::PreDecrement),
+                TokenKind::Punct(Punct::Plus) => Some(UnaryOp::Plus),
+                TokenKind::Punct(Punct::Minus) => Some(UnaryOp::Minus),
+                TokenKind::Punct(Punct::Not) => Some(UnaryOp::LogicalNot),
+                TokenKind::Punct(Punct::Tilde) => Some(UnaryOp::BitwiseNot),
+                TokenKind::Punct(Punct::Star) => Some(UnaryOp::Dereference),
+                TokenKind::Punct(Punct::And) => Some(UnaryOp::Reference),
                 _ => None
             };
 
-            if op.is_none()
-            {
-                recursive_expression(&stream, depth - 1)
-            }
-            else
+            match op
             {
-                stream.consume();
+                None => recursive_expression(stream, depth - 1),
+                Some(op) =>
+                {
+                    stream.consume();
 
-                let post = stream.accept_stream(recursive_expression(&stream, depth))?;
-                Ok((stream, ParseTreeNode::Expression(op.unwrap(), vec![post])))
+                    let operand = recursive_expression(stream, depth)?;
+
+                    Ok(Expr::Unary
+                    {
+                        op,
+                        operand: Box::new(operand),
+                        span: stream.span_from(start)
+                    })
+                }
             }
         },
         // Binary Operators
         4..=13 | 15 | 17 =>
         {
-            let mut prev = stream.accept_stream(recursive_expression(&stream, depth - 1))?;
+            let prev = recursive_expression(stream, depth - 1)?;
             
             // Extract the operator
             let op = 
@@ -625,126 +685,126 @@ fn recursive_expression(orig_stream: &Stream, depth: usize) -> Result<(Stream, P
                     // Multiplicative Operations
                     4 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "*" => Some(ExpressionType::Multiply),
-                            "/" => Some(ExpressionType::Divide),
-                            "%" => Some(ExpressionType::Modulus),
+                            TokenKind::Punct(Punct::Star) => Some(Infix::Binary(BinaryOp::Multiply)),
+                            TokenKind::Punct(Punct::Slash) => Some(Infix::Binary(BinaryOp::Divide)),
+                            TokenKind::Punct(Punct::Percent) => Some(Infix::Binary(BinaryOp::Modulus)),
                             _ => None
                         }
                     },
                     // Additive Operations
                     5 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "+" => Some(ExpressionType::Add),
-                            "-" => Some(ExpressionType::Subtract),
+                            TokenKind::Punct(Punct::Plus) => Some(Infix::Binary(BinaryOp::Add)),
+                            TokenKind::Punct(Punct::Minus) => Some(Infix::Binary(BinaryOp::Subtract)),
                             _ => None
                         }
                     },
                     // Shift Operations
                     6 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "<<" => Some(ExpressionType::ShiftLeft),
-                            ">>" => Some(ExpressionType::ShiftRight),
+                            TokenKind::Punct(Punct::ShiftLeft) => Some(Infix::Binary(BinaryOp::ShiftLeft)),
+                            TokenKind::Punct(Punct::ShiftRight) => Some(Infix::Binary(BinaryOp::ShiftRight)),
                             _ => None
                         }
                     },
                     // Comparison Operations
                     7 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "<" => Some(ExpressionType::LessThan),
-                            "<=" => Some(ExpressionType::LessThanOrEqual),
-                            ">" => Some(ExpressionType::GreaterThan),
-                            ">=" => Some(ExpressionType::GreaterThanOrEqual),
+                            TokenKind::Punct(Punct::Less) => Some(Infix::Binary(BinaryOp::LessThan)),
+                            TokenKind::Punct(Punct::LessEq) => Some(Infix::Binary(BinaryOp::LessThanOrEqual)),
+                            TokenKind::Punct(Punct::Greater) => Some(Infix::Binary(BinaryOp::GreaterThan)),
+                            TokenKind::Punct(Punct::GreaterEq) => Some(Infix::Binary(BinaryOp::GreaterThanOrEqual)),
                             _ => None
                         }
                     },
                     // Equality Operations
                     8 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "==" => Some(ExpressionType::Equal),
-                            "!=" => Some(ExpressionType::NotEqual),
+                            TokenKind::Punct(Punct::EqEq) => Some(Infix::Binary(BinaryOp::Equal)),
+                            TokenKind::Punct(Punct::NotEq) => Some(Infix::Binary(BinaryOp::NotEqual)),
                             _ => None
                         }
                     },
                     // Bitwise And
                     9 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "&" => Some(ExpressionType::BitwiseAnd),
+                            TokenKind::Punct(Punct::And) => Some(Infix::Binary(BinaryOp::BitwiseAnd)),
                             _ => None
                         }
                     },
                     // Bitwise Xor
                     10 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "^" => Some(ExpressionType::BitwiseXor),
+                            TokenKind::Punct(Punct::Caret) => Some(Infix::Binary(BinaryOp::BitwiseXor)),
                             _ => None
                         }
                     },
                     // Bitwise Or
                     11 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "|" => Some(ExpressionType::BitwiseOr),
+                            TokenKind::Punct(Punct::Or) => Some(Infix::Binary(BinaryOp::BitwiseOr)),
                             _ => None
                         }
                     },
                     // Logical And
                     12 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "&&" => Some(ExpressionType::LogicalAnd),
+                            TokenKind::Punct(Punct::AndAnd) => Some(Infix::Binary(BinaryOp::LogicalAnd)),
                             _ => None
                         }
                     },
                     // Logical Or
                     13 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "||" => Some(ExpressionType::LogicalOr),
+                            TokenKind::Punct(Punct::OrOr) => Some(Infix::Binary(BinaryOp::LogicalOr)),
                             _ => None
                         }
                     },
                     // Assignment Operators
                     15 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "=" => Some(ExpressionType::Assignment),
-                            "+=" => Some(ExpressionType::AddAssign),
-                            "-=" => Some(ExpressionType::SubtractAssign),
-                            "*=" => Some(ExpressionType::MultiplyAssign),
-                            "/=" => Some(ExpressionType::DivideAssign),
-                            "%=" => Some(ExpressionType::ModulusAssign),
-                            "<<=" => Some(ExpressionType::ShiftLeftAssign),
-                            ">>=" => Some(ExpressionType::ShiftRightAssign),
-                            "&=" => Some(ExpressionType::BitwiseAnd),
-                            "^=" => Some(ExpressionType::BitwiseXor),
-                            "|=" => Some(ExpressionType::BitwiseOr),
+                            TokenKind::Punct(Punct::Eq) => Some(Infix::Assign(None)),
+                            TokenKind::Punct(Punct::PlusEq) => Some(Infix::Assign(Some(BinaryOp::Add))),
+                            TokenKind::Punct(Punct::MinusEq) => Some(Infix::Assign(Some(BinaryOp::Subtract))),
+                            TokenKind::Punct(Punct::StarEq) => Some(Infix::Assign(Some(BinaryOp::Multiply))),
+                            TokenKind::Punct(Punct::SlashEq) => Some(Infix::Assign(Some(BinaryOp::Divide))),
+                            TokenKind::Punct(Punct::PercentEq) => Some(Infix::Assign(Some(BinaryOp::Modulus))),
+                            TokenKind::Punct(Punct::ShiftLeftEq) => Some(Infix::Assign(Some(BinaryOp::ShiftLeft))),
+                            TokenKind::Punct(Punct::ShiftRightEq) => Some(Infix::Assign(Some(BinaryOp::ShiftRight))),
+                            TokenKind::Punct(Punct::AndEq) => Some(Infix::Assign(Some(BinaryOp::BitwiseAnd))),
+                            TokenKind::Punct(Punct::CaretEq) => Some(Infix::Assign(Some(BinaryOp::BitwiseXor))),
+                            TokenKind::Punct(Punct::OrEq) => Some(Infix::Assign(Some(BinaryOp::BitwiseOr))),
                             _ => None
                         }
                     },
                     // Comma
                     17 =>
                     {
-                        match current.data.as_str()
+                        match current.kind
                         {
-                            "," => Some(ExpressionType::Comma),
+                            TokenKind::Punct(Punct::Comma) => Some(Infix::Comma),
                             _ => None
                         }
                     },
@@ -758,58 +818,70 @@ fn recursive_expression(orig_stream: &Stream, depth: usize) -> Result<(Stream, P
                 None
             };
 
-            if op.is_none()
-            {
-                Ok((stream, prev))
-            }
-            else
+            let op = match op
             {
-                stream.consume();
+                Some(op) => op,
+                None => return Ok(prev)
+            };
 
-                // We know the expression is an assignment
-                if depth == 15
-                {
-                    prev = convert_to_left(prev)?;
-                }
+            stream.consume();
 
-                let post = stream.accept_stream(recursive_expression(&stream, depth))?;
-                Ok((stream, ParseTreeNode::Expression(op.unwrap(), vec![prev, post])))
-            }
+            let post = Box::new(recursive_expression(stream, depth)?);
+            let prev = Box::new(prev);
+            let span = stream.span_from(start);
+
+            Ok(match op
+            {
+                Infix::Binary(op) => Expr::Binary {op, lhs: prev, rhs: post, span},
+                Infix::Assign(op) => Expr::Assign {op, target: prev, value: post, span},
+                Infix::Comma => Expr::Comma {lhs: prev, rhs: post, span}
+            })
         },
         // Ternary Operator
         14 =>
         {
-            let prev = stream.accept_stream(recursive_expression(&stream, depth - 1))?;
+            let prev = recursive_expression(stream, depth - 1)?;
 
-            if stream.check_current(String::from("?"))
+            if stream.check_current(Punct::Question)
             {
-                stream.expect_and_consume(String::from("?"))?;
-                let inner = stream.accept_stream(parse_expression(&stream))?;
-                stream.expect_and_consume(String::from(":"))?;
-                let last = stream.accept_stream(recursive_expression(&stream, depth))?;
+                stream.expect_and_consume(Punct::Question)?;
+                let inner = parse_expression(stream)?;
+                stream.expect_and_consume(Punct::Colon)?;
+                let last = recursive_expression(stream, depth)?;
 
-                Ok((stream, ParseTreeNode::Expression(ExpressionType::Ternary, vec![prev, inner, last])))
+                Ok(Expr::Ternary
+                {
+                    cond: Box::new(prev),
+                    then: Box::new(inner),
+                    otherwise: Box::new(last),
+                    span: stream.span_from(start)
+                })
             }
             else
             {
-                Ok((stream, prev))
+                Ok(prev)
             }
         },
         // Cast
         16 =>
         {
-            let prev = stream.accept_stream(recursive_expression(&stream, depth - 1))?;
+            let prev = recursive_expression(stream, depth - 1)?;
 
-            if stream.check_current(String::from("as"))
+            if stream.check_current(Keyword::As)
             {
-                stream.expect_and_consume(String::from("as"))?;
-                let datatype = stream.accept_stream(parse_type(&stream))?;
+                stream.expect_and_consume(Keyword::As)?;
+                let datatype = parse_type(stream)?;
 
-                Ok((stream, ParseTreeNode::Expression(ExpressionType::Cast, vec![prev, datatype])))
+                Ok(Expr::Cast
+                {
+                    operand: Box::new(prev),
+                    datatype,
+                    span: stream.span_from(start)
+                })
             }
             else
             {
-                Ok((stream, prev))
+                Ok(prev)
             }
         },
         default => panic!("Unexpected depth value of {}", default)
@@ -817,356 +889,830 @@ fn recursive_expression(orig_stream: &Stream, depth: usize) -> Result<(Stream, P
 }
 
 /// Parse out an expression
-fn parse_expression(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_expression(stream: &mut Stream) -> Result<Expr, Error>
 {
-    let stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("expression")?;
 
     // Simplest expression is just a number
-    recursive_expression(&stream, MAX_EXPRESSION)
+    recursive_expression(stream, MAX_EXPRESSION)
 }
 
 /// Parse out an expression without comma expressions
-fn parse_expression_no_comma(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_expression_no_comma(stream: &mut Stream) -> Result<Expr, Error>
 {
-    let stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("expression")?;
 
     // Simplest expression is just a number
-    recursive_expression(&stream, MAX_EXPRESSION - 1)
+    recursive_expression(stream, MAX_EXPRESSION - 1)
 }
 
-/// Parse out an assignment
-fn parse_assignment(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+/// Parse out an assignment giving the initial value of a variable
+fn parse_assignment(stream: &mut Stream) -> Result<Declarator, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted    
     stream.expect_current_exists("assignment")?;
 
     // First is an identifier
-    let identifier = stream.accept_stream(parse_identifier(&stream))?;
-    let mut items = vec![identifier];
+    let name = parse_identifier(stream)?;
 
     // Next is an equals sign
-    stream.expect_and_consume(String::from("="))?;
+    stream.expect_and_consume(Punct::Eq)?;
 
-    // Finally, an expression
-    let expr = stream.accept_stream(parse_expression_no_comma(&stream))?;
-    items.push(expr);
+    // Finally, an expression or a block giving the value
+    let value = if stream.check_current(Punct::LeftBrace)
+    {
+        InitValue::Block(parse_block_expression(stream)?)
+    }
+    else
+    {
+        InitValue::Expr(Box::new(parse_expression_no_comma(stream)?))
+    };
 
-    Ok((stream, ParseTreeNode::Assignment(items)))
+    Ok(Declarator
+    {
+        name,
+        value
+    })
 }
 
-/// Parse out assignments
-fn parse_assignments(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+/// Parse out a block expression
+/// for example { u8 a = 1; a + 1 }, statements followed by the expression giving the value of the block
+fn parse_block_expression(stream: &mut Stream) -> Result<BlockExpr, Error>
 {
-    let mut stream = orig_stream.clone();
+    // Make sure the stream isn't exhausted
+    stream.expect_current_exists("block expression")?;
+
+    let start = stream.index;
+
+    stream.expect_and_consume(Punct::LeftBrace)?;
+
+    let mut stmts = vec![];
+    let mut value = None;
+
+    loop
+    {
+        // The value is an expression which isn't followed by a semicolon
+        let result = stream.attempt(|stream|
+        {
+            let expr = parse_expression(stream)?;
+            stream.expect(Punct::RightBrace)?;
+
+            Ok(expr)
+        });
+
+        if let Ok(expr) = result
+        {
+            value = Some(Box::new(expr));
+            break;
+        }
+
+        match stream.attempt(parse_statement)
+        {
+            Ok(val) => stmts.push(val),
+            // A block missing its value isn't skipped, as that would skip its '}'
+            Err(error) if stream.current().is_none() || stream.check_current(Punct::RightBrace) => Err(error)?,
+            Err(error) =>
+            {
+                let (tokens, span) = stream.recover(error, false);
+                stmts.push(Stmt::Error(tokens, span));
+            }
+        }
+
+        // A block whose value couldn't be parsed ends without it
+        if stream.check_current(Punct::RightBrace) && matches!(stmts.last(), Some(Stmt::Error(_, _)))
+        {
+            break;
+        }
+    }
+
+    stream.expect_and_consume(Punct::RightBrace)?;
+
+    Ok(BlockExpr
+    {
+        stmts,
+        value,
+        span: stream.span_from(start)
+    })
+}
 
+/// Parse out assignments
+fn parse_assignments(stream: &mut Stream) -> Result<Vec<Declarator>, Error>
+{
     // Purposeful removal, it doesn't make sense to say we are looking for arguments here, the error
     // message could get somewhat confusing
     //stream.expect_current_exists("arguments")?;
 
     // First is getting the return type
-    let arg = stream.accept_stream(parse_assignment(&stream))?;
-    let mut items = vec![arg];
+    let mut items = vec![parse_assignment(stream)?];
 
-    while stream.check_current(String::from(","))
+    while stream.check_current(Punct::Comma)
     {
         stream.consume();
-        items.push(stream.accept_stream(parse_assignment(&stream))?);
+        items.push(parse_assignment(stream)?);
     }
 
-    Ok((stream, ParseTreeNode::Assignments(items)))
+    Ok(items)
 }
 
 /// Parse out an if statement
-fn parse_if_statement(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_if_statement(stream: &mut Stream) -> Result<Stmt, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("if statement")?;
 
+    let start = stream.index;
+
     // Must start with an if keyword
-    stream.expect_and_consume(String::from("if"))?;
+    stream.expect_and_consume(Keyword::If)?;
 
     // Get the condition
-    let cond = stream.accept_stream(parse_expression(&stream))?;
-    let mut items = vec![cond];
+    let cond = parse_expression(stream)?;
 
     // Get the main body
-    let body = stream.accept_stream(parse_statement(&stream))?;
-    items.push(body);
+    let body = Box::new(parse_statement(stream)?);
 
     // Check if there is an else clause
-    if stream.check_current(String::from("else"))
+    let otherwise = if stream.check_current(Keyword::Else)
     {
-        stream.expect_and_consume(String::from("else"))?;
+        stream.expect_and_consume(Keyword::Else)?;
 
-        let clause = stream.accept_stream(parse_statement(&stream))?;
-        items.push(clause);
+        Some(Box::new(parse_statement(stream)?))
     }
     else
     {
-        items.push(ParseTreeNode::Empty);
-    }
+        None
+    };
 
-    Ok((stream, ParseTreeNode::IfStatement(items)))
+    Ok(Stmt::If
+    {
+        cond,
+        body,
+        otherwise,
+        span: stream.span_from(start)
+    })
 }
 
 /// Parse out a while loop
-fn parse_while_loop(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_while_loop(stream: &mut Stream) -> Result<Stmt, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("while loop")?;
 
+    let start = stream.index;
+
     // Must start with a while keyword
-    stream.expect_and_consume(String::from("while"))?;
+    stream.expect_and_consume(Keyword::While)?;
 
-    let cond = stream.accept_stream(parse_expression(&stream))?;
+    let cond = parse_expression(stream)?;
     
-    let statement = stream.accept_stream(parse_statement(&stream))?;
+    let body = Box::new(parse_statement(stream)?);
 
-    
-    Ok((stream, ParseTreeNode::WhileLoop(vec![cond, statement])))
+    Ok(Stmt::While
+    {
+        cond,
+        body,
+        span: stream.span_from(start)
+    })
 }
 
 /// Parse out a do while loop
-fn parse_do_while_loop(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_do_while_loop(stream: &mut Stream) -> Result<Stmt, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("do while loop")?;
 
+    let start = stream.index;
+
     // Must start with a do keyword
-    stream.expect_and_consume(String::from("do"))?;
+    stream.expect_and_consume(Keyword::Do)?;
 
-    let statement = stream.accept_stream(parse_statement(&stream))?;
+    let body = Box::new(parse_statement(stream)?);
 
     // Must be followed by a while keyword
-    stream.expect_and_consume(String::from("while"))?;
+    stream.expect_and_consume(Keyword::While)?;
 
-    let cond = stream.accept_stream(parse_expression(&stream))?;
+    let cond = parse_expression(stream)?;
     
-    Ok((stream, ParseTreeNode::DoWhileLoop(vec![cond, statement])))
+    Ok(Stmt::DoWhile
+    {
+        body,
+        cond,
+        span: stream.span_from(start)
+    })
 }
 
 /// Parse out a loop
-fn parse_loop(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_loop(stream: &mut Stream) -> Result<Stmt, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("loop")?;
 
+    let start = stream.index;
+
     // Must start with a while keyword
-    stream.expect_and_consume(String::from("loop"))?;
+    stream.expect_and_consume(Keyword::Loop)?;
 
-    let statement = stream.accept_stream(parse_statement(&stream))?;
+    let body = Box::new(parse_statement(stream)?);
 
-    
-    Ok((stream, ParseTreeNode::Loop(vec![statement])))
+    Ok(Stmt::Loop
+    {
+        body,
+        span: stream.span_from(start)
+    })
 }
 
 /// Parse out a statement
-fn parse_statement(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_statement(stream: &mut Stream) -> Result<Stmt, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("statement")?;
 
+    let start = stream.index;
+
     // Simplest example of a statement is just a semicolon
-    if stream.check_current(String::from(";"))
+    if stream.check_current(Punct::Semicolon)
     {
         stream.consume();
-        Ok((stream, ParseTreeNode::Statement(vec![])))
+        Ok(Stmt::Empty(stream.span_from(start)))
     }
     // The next simplest is the compound statement
-    else if stream.check_current(String::from("{"))
+    else if stream.check_current(Punct::LeftBrace)
     {
         stream.consume();
         
         let mut statements = vec![];
 
-        while !stream.check_current(String::from("}"))
+        while !stream.check_current(Punct::RightBrace)
         {
-            statements.push(stream.accept_stream(parse_statement(&stream))?);
+            if stream.check_unclosed_block()
+            {
+                return Ok(Stmt::Block(statements, stream.span_from(start)));
+            }
+
+            let statement = match stream.attempt(parse_statement)
+            {
+                Ok(val) => val,
+                Err(error) =>
+                {
+                    let (tokens, span) = stream.recover(error, false);
+                    Stmt::Error(tokens, span)
+                }
+            };
+
+            statements.push(statement);
         }
 
-        stream.expect_and_consume(String::from("}"))?;
+        stream.expect_and_consume(Punct::RightBrace)?;
 
-        Ok((stream, ParseTreeNode::Statements(statements)))
+        Ok(Stmt::Block(statements, stream.span_from(start)))
     }
     // Continue and break commands
-    else if stream.check_current(String::from("continue")) || stream.check_current(String::from("break"))
+    else if stream.check_current(Keyword::Continue) || stream.check_current(Keyword::Break)
     {
-        let items = vec![stream.accept_stream(parse_token(&stream, "command"))?];
+        let is_continue = stream.check_current(Keyword::Continue);
+
+        stream.consume();
+        stream.expect_and_consume(Punct::Semicolon)?;
 
-        stream.expect_and_consume(String::from(";"))?;
+        let span = stream.span_from(start);
 
-        Ok((stream, ParseTreeNode::Statement(items)))
+        Ok(if is_continue {Stmt::Continue(span)} else {Stmt::Break(span)})
+    }
+    // Initialization statement with the type inferred from the values
+    else if stream.check_current(Keyword::Let)
+    {
+        stream.consume();
+
+        let declarators = parse_assignments(stream)?;
+
+        stream.expect_and_consume(Punct::Semicolon)?;
+
+        Ok(Stmt::Declaration
+        {
+            datatype: None,
+            declarators,
+            span: stream.span_from(start)
+        })
     }
     // Initialization statement
-    else if let Ok(val) = parse_type(&stream)
+    else if let Ok(datatype) = stream.attempt(parse_type)
     {
-        let datatype = stream.accept_stream(Ok(val))?;
-        
-        let assignments = stream.accept_stream(parse_assignments(&stream))?;
 
-        stream.expect_and_consume(String::from(";"))?;
+        let declarators = parse_assignments(stream)?;
 
-        Ok((stream, ParseTreeNode::AssignmentStatement(vec![datatype, assignments])))
+        stream.expect_and_consume(Punct::Semicolon)?;
+
+        Ok(Stmt::Declaration
+        {
+            datatype: Some(datatype),
+            declarators,
+            span: stream.span_from(start)
+        })
     }
     // If Statement
-    else if stream.check_current(String::from("if"))
+    else if stream.check_current(Keyword::If)
     {
-        parse_if_statement(&stream)
+        parse_if_statement(stream)
     }
     // While Loop
-    else if stream.check_current(String::from("while"))
+    else if stream.check_current(Keyword::While)
     {
-        parse_while_loop(&stream)
+        parse_while_loop(stream)
     }
     // Do While Loop
-    else if stream.check_current(String::from("do"))
+    else if stream.check_current(Keyword::Do)
     {
-        parse_do_while_loop(&stream)
+        parse_do_while_loop(stream)
     }
     // Loop
-    else if stream.check_current(String::from("loop"))
+    else if stream.check_current(Keyword::Loop)
     {
-        parse_loop(&stream)
+        parse_loop(stream)
     }
     // Return statement
-    else if stream.check_current(String::from("return"))
+    else if stream.check_current(Keyword::Return)
     {
-        stream.expect_and_consume(String::from("return"))?;
+        stream.expect_and_consume(Keyword::Return)?;
 
-        let expr = stream.accept_stream(parse_expression(&stream))?;
+        let value = parse_expression(stream)?;
 
-        stream.expect_and_consume(String::from(";"))?;
+        stream.expect_and_consume(Punct::Semicolon)?;
 
-        Ok((stream, ParseTreeNode::ReturnStatement(vec![expr])))
+        Ok(Stmt::Return
+        {
+            value,
+            span: stream.span_from(start)
+        })
     }
     // Expression Statement
     else
     {
-        let expr = stream.accept_stream(parse_expression(&stream))?;
+        let expr = parse_expression(stream)?;
 
-        stream.expect_and_consume(String::from(";"))?;
+        stream.expect_and_consume(Punct::Semicolon)?;
 
-        Ok((stream, ParseTreeNode::Statement(vec![expr])))
+        Ok(Stmt::Expr(expr, stream.span_from(start)))
     }
 }
 
 /// Parse out an argument
 /// for example u8** argv
 /// in otherwords, a type and an identifier
-fn parse_argument(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_argument(stream: &mut Stream) -> Result<Param, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("argument")?;
 
     // First is getting the return type
-    let datatype = stream.accept_stream(parse_type(&stream))?;
-    let mut items = vec![datatype];
+    let datatype = parse_type(stream)?;
+    let name = parse_identifier(stream)?;
 
-    let name = stream.accept_stream(parse_identifier(&stream))?;
-    items.push(name);
-
-    Ok((stream, ParseTreeNode::Argument(items)))
+    Ok(Param
+    {
+        datatype,
+        name
+    })
 }
 
 /// Parse out an argument list
 /// for example the argument list i32 argc, u8** argv
-fn parse_arguments(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_arguments(stream: &mut Stream) -> Result<Vec<Param>, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Purposeful removal, it doesn't make sense to say we are looking for arguments here, the error
     // message could get somewhat confusing
     //stream.expect_current_exists("arguments")?;
 
     // First is getting the return type
-    let arg = stream.accept_stream(parse_argument(&stream))?;
-    let mut items = vec![arg];
+    let mut items = vec![parse_argument(stream)?];
 
-    while stream.check_current(String::from(","))
+    while stream.check_current(Punct::Comma)
     {
         stream.consume();
-        items.push(stream.accept_stream(parse_argument(&stream))?);
+        items.push(parse_argument(stream)?);
     }
 
-    Ok((stream, ParseTreeNode::Arguments(items)))
+    Ok(items)
 }
 
 /// Parse out a function
 /// Specifically, the return type, the function name, the arguments and a statement
-fn parse_function(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+fn parse_function(stream: &mut Stream) -> Result<FunctionDecl, Error>
 {
-    let mut stream = orig_stream.clone();
-
     // Make sure the stream isn't exhausted
     stream.expect_current_exists("function")?;
 
+    let start = stream.index;
+
+    // Attributes are written before the function
+    let mut attributes = vec![];
+
+    while stream.check_current(Punct::Hash)
+    {
+        attributes.push(parse_attribute(stream)?);
+    }
+
+    // The type parameters of a generic function can be used in its return type, so they are found first
+    let type_params = stream.lookahead(|stream|
+    {
+        skip_to_type_parameters(stream);
+        parse_type_parameters(stream)
+    })?;
+
+    stream.type_parameters.extend(type_params.iter().map(|parameter| parameter.name.clone()));
+
     // First is getting the return type
-    let return_type = stream.accept_stream(parse_type(&stream))?;
-    let mut items = vec![return_type];
+    let ret = parse_type(stream)?;
 
     // Next the function name
-    let func_name = stream.accept_stream(parse_identifier(&stream))?;
-    items.push(func_name);
+    let name = parse_identifier(stream)?;
+
+    if stream.check_current(Punct::Less)
+    {
+        parse_type_parameters(stream)?;
+    }
 
     // Next there must be a '('
-    stream.expect(String::from("("))?;
+    stream.expect(Punct::LeftParen)?;
     stream.consume();
 
     // If the next token is a ')', there is no argument list
-    if stream.check_current(String::from(")"))
+    let params = if stream.check_current(Punct::RightParen)
     {
-        items.push(ParseTreeNode::Empty);
+        vec![]
     }
     // Otherwise, get the arguments list
     else
     {
-        let arg_list = stream.accept_stream(parse_arguments(&stream))?;
-        items.push(arg_list);
-    }
+        parse_arguments(stream)?
+    };
 
     // Consume the ')'
-    stream.expect(String::from(")"))?;
+    stream.expect(Punct::RightParen)?;
     stream.consume();
 
     // Finally, there should be a statement here
-    let statement = stream.accept_stream(parse_statement(&stream))?;
-    items.push(statement);
+    let body = Box::new(parse_statement(stream)?);
 
-    Ok((stream, ParseTreeNode::Function(items)))
+    // The type parameters are only types within the function
+    stream.type_parameters.clear();
+
+    Ok(FunctionDecl
+    {
+        ret,
+        name,
+        type_params,
+        params,
+        body,
+        attributes,
+        span: stream.span_from(start)
+    })
+}
+
+/// Parse out an attribute of a function
+/// for example #[noreturn]
+fn parse_attribute(stream: &mut Stream) -> Result<Ident, Error>
+{
+    stream.expect_and_consume(Punct::Hash)?;
+    stream.expect_and_consume(Punct::LeftBracket)?;
+
+    let name = parse_identifier(stream)?;
+
+    stream.expect_and_consume(Punct::RightBracket)?;
+
+    Ok(name)
+}
+
+/// Move a stream to the type parameters of a function, if it has any
+/// for example the <T> of T max<T>(T a, T b)
+fn skip_to_type_parameters(stream: &mut Stream)
+{
+    // Skip over the return type, which may use the type parameters, and the name of the function
+    if stream.check_current(Keyword::Progmem)
+    {
+        stream.consume();
+    }
+
+    stream.consume();
+
+    while stream.check_current(Punct::Star)
+    {
+        stream.consume();
+    }
+
+    stream.consume();
 }
 
-/// Parse out a library
-fn parse_library(orig_stream: &Stream)  -> Result<(Stream, ParseTreeNode), Error>
+/// Parse out the type parameters of a generic function, giving no parameters if there are none
+/// for example <T, U>
+fn parse_type_parameters(stream: &mut Stream) -> Result<Vec<Ident>, Error>
 {
-    let mut stream = orig_stream.clone();
+    if !stream.check_current(Punct::Less)
+    {
+        return Ok(vec![]);
+    }
+
+    stream.consume();
+
+    let mut items: Vec<Ident> = vec![];
+
+    loop
+    {
+        let parameter = parse_identifier(stream)?;
+
+        if items.iter().any(|item| item.name == parameter.name)
+        {
+            return expected_got_error("a new type parameter", stream.last_before(stream.index));
+        }
+
+        items.push(parameter);
+
+        if stream.check_current(Punct::Comma)
+        {
+            stream.consume();
+        }
+        else
+        {
+            break;
+        }
+    }
+
+    stream.expect_and_consume(Punct::Greater)?;
+
+    Ok(items)
+}
+
+/// Check if the stream is at the type arguments of a call to a generic function (types between angle brackets,
+/// followed by the argument list)
+fn is_type_arguments(stream: &mut Stream) -> bool
+{
+    stream.lookahead(|stream|
+    {
+        stream.check_current(Punct::Less) &&
+            parse_type_arguments(stream).is_ok() &&
+            stream.check_current(Punct::LeftParen)
+    })
+}
+
+/// Parse out the type arguments given to a generic function
+/// for example <u8, i16*>
+fn parse_type_arguments(stream: &mut Stream) -> Result<Vec<TypeExpr>, Error>
+{
+    stream.expect_and_consume(Punct::Less)?;
+
+    let mut items = vec![parse_type(stream)?];
+
+    while stream.check_current(Punct::Comma)
+    {
+        stream.consume();
+        items.push(parse_type(stream)?);
+    }
+
+    stream.expect_and_consume(Punct::Greater)?;
+
+    Ok(items)
+}
+
+/// Parse out an initializer list
+/// for example {1, 2, 3}
+fn parse_initializer_list(stream: &mut Stream) -> Result<Initializer, Error>
+{
+    // Make sure the stream isn't exhausted
+    stream.expect_current_exists("initializer list")?;
+
+    let start = stream.index;
+
+    stream.expect_and_consume(Punct::LeftBrace)?;
 
     let mut items = vec![];
-    
-    while stream.peek().is_some()
+
+    while !stream.check_current(Punct::RightBrace)
     {
-        let func = stream.accept_stream(parse_function(&stream))?;
-        items.push(func);
+        items.push(parse_expression_no_comma(stream)?);
+
+        if stream.check_current(Punct::Comma)
+        {
+            stream.consume();
+        }
+        else
+        {
+            break;
+        }
     }
 
-    Ok((stream, ParseTreeNode::Library(items)))
-}
\ No newline at end of file
+    stream.expect_and_consume(Punct::RightBrace)?;
+
+    Ok(Initializer::List(items, stream.span_from(start)))
+}
+
+/// Parse out a global
+/// Specifically, the type, the name, an optional array length and an optional initializer
+fn parse_global(stream: &mut Stream) -> Result<GlobalDecl, Error>
+{
+    // Make sure the stream isn't exhausted
+    stream.expect_current_exists("global")?;
+
+    let start = stream.index;
+
+    // First is getting the type
+    let datatype = parse_type(stream)?;
+
+    // Next the global name
+    let name = parse_identifier(stream)?;
+
+    // Arrays are followed by their length, which can be left out if there is an initializer list
+    let length = if stream.check_current(Punct::LeftBracket)
+    {
+        stream.consume();
+
+        let length = if stream.check_current(Punct::RightBracket)
+        {
+            ArrayLength::Inferred
+        }
+        else
+        {
+            ArrayLength::Given(Box::new(parse_expression_no_comma(stream)?))
+        };
+
+        stream.expect_and_consume(Punct::RightBracket)?;
+
+        Some(length)
+    }
+    else
+    {
+        None
+    };
+
+    // Finally, the optional initializer
+    let value = if stream.check_current(Punct::Eq)
+    {
+        stream.consume();
+
+        if stream.check_current(Punct::LeftBrace)
+        {
+            Some(parse_initializer_list(stream)?)
+        }
+        else
+        {
+            Some(Initializer::Value(Box::new(parse_expression_no_comma(stream)?)))
+        }
+    }
+    else
+    {
+        None
+    };
+
+    stream.expect_and_consume(Punct::Semicolon)?;
+
+    Ok(GlobalDecl
+    {
+        datatype,
+        name,
+        length,
+        value,
+        span: stream.span_from(start)
+    })
+}
+
+/// Check if the stream is at a global rather than a function (a global is not followed by an argument list)
+fn is_global(stream: &mut Stream) -> bool
+{
+    stream.lookahead(|stream|
+    {
+        parse_type(stream).is_ok() &&
+            parse_identifier(stream).is_ok() &&
+            !stream.check_current(Punct::LeftParen) && !stream.check_current(Punct::Less)
+    })
+}
+
+/// Parse out an import of another module
+/// for example import "uart.pc";
+fn parse_import(stream: &mut Stream) -> Result<Import, Error>
+{
+    stream.expect_and_consume(Keyword::Import)?;
+
+    // The file name is given as a string
+    stream.expect_current_exists("file name")?;
+    let file_name = stream.current().unwrap();
+
+    if file_name.kind != TokenKind::StrLit || file_name.data.len() < 2 || !file_name.data.ends_with('"')
+    {
+        return expected_got_error("file name", file_name);
+    }
+
+    stream.consume();
+    stream.expect_and_consume(Punct::Semicolon)?;
+
+    Ok(Import
+    {
+        file: String::from(&file_name.data[1..file_name.data.len() - 1]),
+        span: file_name.span()
+    })
+}
+
+/// Parse out a use of an item from another module
+/// for example use uart::init;
+fn parse_use(stream: &mut Stream) -> Result<Ident, Error>
+{
+    stream.expect_and_consume(Keyword::Use)?;
+
+    let path = parse_path(stream)?;
+
+    if !path.name.contains("::")
+    {
+        return parse_error(ErrorCode::E0001, path.span, format!("Expected path to an item of a module, got '{}'", path.name),
+                           "expected path to an item of a module");
+    }
+
+    stream.expect_and_consume(Punct::Semicolon)?;
+
+    Ok(path)
+}
+
+/// Parse out the methods of a type
+/// for example impl u16* { u16 sum(u16* self, u16 n) { ... } }
+fn parse_impl(stream: &mut Stream) -> Result<ImplBlock, Error>
+{
+    let start = stream.index;
+
+    stream.expect_and_consume(Keyword::Impl)?;
+
+    // The type the methods belong to comes first, followed by the methods
+    let datatype = parse_type(stream)?;
+    let mut methods = vec![];
+
+    stream.expect_and_consume(Punct::LeftBrace)?;
+
+    while !stream.check_current(Punct::RightBrace)
+    {
+        stream.expect_current_exists("'}'")?;
+
+        // A method with a syntax error is left out, the error stops the module from being compiled
+        match stream.attempt(parse_function)
+        {
+            Ok(val) => methods.push(val),
+            Err(error) => {stream.recover(error, true);}
+        }
+    }
+
+    stream.expect_and_consume(Punct::RightBrace)?;
+
+    Ok(ImplBlock
+    {
+        datatype,
+        methods,
+        span: stream.span_from(start)
+    })
+}
+
+/// Parse out an item of a library
+fn parse_item(stream: &mut Stream) -> Result<Item, Error>
+{
+    if stream.check_current(Keyword::Import)
+    {
+        Ok(Item::Import(parse_import(stream)?))
+    }
+    else if stream.check_current(Keyword::Use)
+    {
+        Ok(Item::Use(parse_use(stream)?))
+    }
+    else if stream.check_current(Keyword::Impl)
+    {
+        Ok(Item::Impl(parse_impl(stream)?))
+    }
+    else if is_global(stream)
+    {
+        Ok(Item::Global(parse_global(stream)?))
+    }
+    else
+    {
+        Ok(Item::Function(parse_function(stream)?))
+    }
+}
+
+/// Parse out a library, an item with a syntax error is recorded and skipped so the items after it are still parsed
+fn parse_library(stream: &mut Stream) -> Library
+{
+    let mut items = vec![];
+
+    while stream.current().is_some()
+    {
+        let item = match stream.attempt(parse_item)
+        {
+            Ok(item) => item,
+            Err(error) =>
+            {
+                let (tokens, span) = stream.recover(error, true);
+                Item::Error(tokens, span)
+            }
+        };
+
+        items.push(item);
+    }
+
+    Library
+    {
+        items
+    }
+}
@@ -0,0 +1,447 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::cli::{Error, ErrorCode, Options};
+use super::io::read_from_file;
+use super::tokenizer::{FileLocation, Span};
+
+/// Preprocessor, which includes files, expands macros and removes code excluded by conditional compilation before
+/// tokenization
+#[derive(Debug, Clone)]
+pub struct Preprocessor
+{
+    defines: HashMap<String, String>,
+    include_dirs: Vec<PathBuf>
+}
+
+/// Conditional compilation block (`#ifdef` or `#ifndef`) which is currently open
+struct Conditional
+{
+    directive: String,
+    location: Span,
+    parent_active: bool,
+    condition: bool,
+    seen_else: bool
+}
+
+impl Conditional
+{
+    /// Check if the lines of the current branch of the block are compiled
+    fn active(&self) -> bool
+    {
+        self.parent_active && (self.condition != self.seen_else)
+    }
+}
+
+/// State of the preprocessor while a file (and the files it includes) is being processed
+struct PreprocessorState<'a>
+{
+    preprocessor: &'a Preprocessor,
+    defines: HashMap<String, String>,
+    stack: Vec<PathBuf>,
+    in_comment: bool
+}
+
+impl Preprocessor
+{
+    /// Generate a new preprocessor with the given predefined macros and include directories
+    pub fn new(defines: HashMap<String, String>, include_dirs: Vec<PathBuf>) -> Self
+    {
+        Self
+        {
+            defines,
+            include_dirs
+        }
+    }
+
+    /// Generate a new preprocessor from the `-D NAME=VAL` and `-I DIR` command line options (a macro defined without
+    /// a value is defined as 1)
+    pub fn from_options(options: &Options) -> Result<Self, Error>
+    {
+        let mut defines = HashMap::new();
+        let mut include_dirs = vec![];
+
+        for definition in options.map.get("-D").cloned().unwrap_or_default()
+        {
+            let (name, value) = definition.split_once('=').unwrap_or((&definition, "1"));
+
+            if !is_identifier(name)
+            {
+                Err(Error::fatal_error(&format!("Bad macro definition '{}'", definition)))?
+            }
+
+            defines.insert(String::from(name), String::from(value));
+        }
+
+        for dir in options.map.get("-I").cloned().unwrap_or_default()
+        {
+            include_dirs.push(PathBuf::from(dir));
+        }
+
+        Ok(Self::new(defines, include_dirs))
+    }
+
+    /// Preprocess a file, giving source for the tokenizer. Lines which come from included files, or which follow a
+    /// line changed by the preprocessor, are preceded by a `#line ROW "FILE" OFFSET` marker, so the tokenizer can keep
+    /// the original location of every token
+    pub fn preprocess(&self, data: String, file_name: String) -> Result<String, Error>
+    {
+        let mut state = PreprocessorState
+        {
+            preprocessor: self,
+            defines: self.defines.clone(),
+            stack: vec![],
+            in_comment: false
+        };
+
+        let mut output = String::new();
+
+        state.process_file(&data, &file_name, &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Find a file given to `#include`, quoted files are searched for relative to the including file before the
+    /// include directories
+    fn find_include(&self, name: &str, directory: &Path, quoted: bool) -> Option<PathBuf>
+    {
+        let local = if quoted {Some(directory.join(name))} else {None};
+
+        local.into_iter()
+            .chain(self.include_dirs.iter().map(|dir| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+}
+
+impl PreprocessorState<'_>
+{
+    /// Process the lines of a file, appending the result to the output
+    fn process_file(&mut self, data: &str, file_name: &str, output: &mut String) -> Result<(), Error>
+    {
+        let path = std::fs::canonicalize(file_name).unwrap_or(PathBuf::from(file_name));
+        let directory = Path::new(file_name).parent().map(|dir| dir.to_path_buf()).unwrap_or_default();
+
+        self.stack.push(path);
+
+        let mut conditionals: Vec<Conditional> = vec![];
+
+        // Offset in bytes of the start of the next line
+        let mut offset = 0;
+
+        for (index, line) in data.split('\n').enumerate()
+        {
+            let mut location = FileLocation::from_name(file_name);
+            location.row = index + 1;
+            location.offset = offset;
+            offset += line.len() + 1;
+
+            if index > 0
+            {
+                output.push('\n');
+            }
+
+            let active = conditionals.last().is_none_or(|conditional| conditional.active());
+            let directive = line.trim_start();
+
+            // Lines inside of a comment are never directives, and neither are attributes such as #[noreturn]
+            if self.in_comment || !directive.starts_with('#') || directive.starts_with("#[")
+            {
+                let expanded = self.expand_line(line);
+                let text = if active {expanded.as_str()} else {""};
+
+                output.push_str(text);
+
+                // A line which changes in length moves the lines after it, so the location of the next line is given
+                if text.len() != line.len()
+                {
+                    output.push_str(&line_marker(index + 2, offset, file_name));
+                }
+
+                continue;
+            }
+
+            // Errors in a directive point at the whole directive
+            location.col = line.chars().count() - directive.chars().count() + 1;
+            location.offset += line.len() - directive.len();
+            let span = Span::from_text(&location, strip_comment(directive).trim_end());
+
+            let directive = directive[1..].trim();
+            let (name, argument) = directive.split_once(char::is_whitespace).unwrap_or((directive, ""));
+            let argument = strip_comment(argument).trim();
+
+            match name
+            {
+                "ifdef" | "ifndef" =>
+                {
+                    let condition = self.defines.contains_key(macro_name(name, argument, &span)?);
+
+                    conditionals.push(Conditional
+                    {
+                        directive: String::from(name),
+                        location: span.clone(),
+                        parent_active: active,
+                        condition: if name == "ifdef" {condition} else {!condition},
+                        seen_else: false
+                    });
+                },
+                "else" =>
+                {
+                    match conditionals.last_mut()
+                    {
+                        Some(conditional) if !conditional.seen_else => conditional.seen_else = true,
+                        Some(_) => return preprocessor_error(ErrorCode::E0003, String::from("'#else' after '#else'"), &span),
+                        None => return preprocessor_error(ErrorCode::E0003, String::from("'#else' without '#ifdef'"), &span)
+                    }
+                },
+                "endif" =>
+                {
+                    if conditionals.pop().is_none()
+                    {
+                        return preprocessor_error(ErrorCode::E0003, String::from("'#endif' without '#ifdef'"), &span);
+                    }
+                },
+                // Other directives are ignored in code which is not compiled
+                _ if !active => {},
+                "define" =>
+                {
+                    let (macro_name, value) = argument.split_once(char::is_whitespace).unwrap_or((argument, ""));
+
+                    if !is_identifier(macro_name)
+                    {
+                        return preprocessor_error(ErrorCode::E0003, format!("Bad macro name '{}'", macro_name), &span);
+                    }
+
+                    self.defines.insert(String::from(macro_name), String::from(value.trim()));
+                },
+                "undef" =>
+                {
+                    let macro_name = macro_name(name, argument, &span)?;
+
+                    self.defines.remove(macro_name);
+                },
+                "include" =>
+                {
+                    let quoted = argument.len() >= 2 && argument.starts_with('"') && argument.ends_with('"');
+                    let bracketed = argument.len() >= 2 && argument.starts_with('<') && argument.ends_with('>');
+
+                    if !quoted && !bracketed
+                    {
+                        return preprocessor_error(ErrorCode::E0003, format!("Expected \"FILE\" or <FILE> after '#include', got '{}'", argument), &span);
+                    }
+
+                    let include_name = &argument[1..argument.len() - 1];
+
+                    let include_path = match self.preprocessor.find_include(include_name, &directory, quoted)
+                    {
+                        Some(include_path) => include_path,
+                        None => return preprocessor_error(ErrorCode::E0004, format!("Cannot include '{}', no such file", include_name), &span)
+                    };
+
+                    if self.stack.contains(&std::fs::canonicalize(&include_path).unwrap_or(include_path.clone()))
+                    {
+                        return preprocessor_error(ErrorCode::E0004, format!("'{}' includes itself", include_name), &span);
+                    }
+
+                    let include_file = include_path.display().to_string();
+                    let include_data = read_from_file(include_file.clone())?;
+
+                    output.push_str(&line_marker(1, 0, &include_file));
+                    output.push('\n');
+                    self.process_file(&include_data, &include_file, output)?;
+                },
+                "error" =>
+                {
+                    return preprocessor_error(ErrorCode::E0005, format!("#error {}", argument), &span);
+                },
+                _ =>
+                {
+                    return preprocessor_error(ErrorCode::E0003, format!("Unknown directive '#{}'", name), &span);
+                }
+            }
+
+            // Directives are left out of the output
+            output.push_str(&line_marker(index + 2, offset, file_name));
+        }
+
+        if let Some(conditional) = conditionals.pop()
+        {
+            return preprocessor_error(ErrorCode::E0003, format!("Unterminated '#{}'", conditional.directive), &conditional.location);
+        }
+
+        self.stack.pop();
+
+        Ok(())
+    }
+
+    /// Expand the macros used in a line, skipping over comments and string literals
+    fn expand_line(&mut self, line: &str) -> String
+    {
+        let chars: Vec<char> = line.chars().collect();
+        let mut result = String::new();
+        let mut i = 0;
+
+        while i < chars.len()
+        {
+            let c = chars[i];
+
+            if self.in_comment
+            {
+                if c == '*' && chars.get(i + 1) == Some(&'/')
+                {
+                    self.in_comment = false;
+                    result.push_str("*/");
+                    i += 2;
+                }
+                else
+                {
+                    result.push(c);
+                    i += 1;
+                }
+            }
+            else if c == '/' && chars.get(i + 1) == Some(&'/')
+            {
+                result.extend(&chars[i..]);
+                break;
+            }
+            else if c == '/' && chars.get(i + 1) == Some(&'*')
+            {
+                self.in_comment = true;
+                result.push_str("/*");
+                i += 2;
+            }
+            else if c == '"'
+            {
+                let end = chars[i + 1..].iter().position(|c| *c == '"').map(|end| i + end + 2).unwrap_or(chars.len());
+
+                result.extend(&chars[i..end]);
+                i = end;
+            }
+            // Numbers may contain letters, but are never expanded
+            else if c.is_ascii_alphanumeric() || c == '_'
+            {
+                let end = chars[i..].iter().position(|c| !(c.is_ascii_alphanumeric() || *c == '_')).map(|end| i + end).unwrap_or(chars.len());
+                let word: String = chars[i..end].iter().collect();
+
+                if c.is_ascii_digit()
+                {
+                    result.push_str(&word);
+                }
+                else
+                {
+                    result.push_str(&self.expand_macro(&word, &mut HashSet::new()));
+                }
+
+                i = end;
+            }
+            else
+            {
+                result.push(c);
+                i += 1;
+            }
+        }
+
+        result
+    }
+
+    /// Expand a single identifier, along with any macros used in its value (a macro is not expanded inside of its own
+    /// value, so self referencing macros terminate)
+    fn expand_macro(&self, word: &str, expanding: &mut HashSet<String>) -> String
+    {
+        let value = match self.defines.get(word)
+        {
+            Some(value) if !expanding.contains(word) => value,
+            _ => return String::from(word)
+        };
+
+        expanding.insert(String::from(word));
+
+        let mut result = String::new();
+        let mut current = String::new();
+        let mut quoted = false;
+
+        for c in value.chars().chain(std::iter::once(' '))
+        {
+            if c == '"'
+            {
+                quoted = !quoted;
+            }
+
+            if !quoted && (c.is_ascii_alphanumeric() || c == '_')
+            {
+                current.push(c);
+                continue;
+            }
+
+            if !current.is_empty()
+            {
+                if current.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    result.push_str(&current);
+                }
+                else
+                {
+                    result.push_str(&self.expand_macro(&current, expanding));
+                }
+
+                current.clear();
+            }
+
+            result.push(c);
+        }
+
+        expanding.remove(word);
+
+        // Remove the space used to end the last word
+        result.pop();
+
+        result
+    }
+}
+
+/// Get the name of the macro given to a directive
+fn macro_name<'a>(directive: &str, argument: &'a str, span: &Span) -> Result<&'a str, Error>
+{
+    if is_identifier(argument)
+    {
+        Ok(argument)
+    }
+    else
+    {
+        preprocessor_error(ErrorCode::E0003, format!("Expected a macro name after '#{}', got '{}'", directive, argument), span)
+    }
+}
+
+/// Generate a `#line ROW "FILE" OFFSET` marker on a line of its own (after the current line), giving the location
+/// of the line which follows it
+fn line_marker(row: usize, offset: usize, file_name: &str) -> String
+{
+    format!("\n#line {} \"{}\" {}", row, file_name, offset)
+}
+
+/// Remove a trailing line comment from the text of a directive
+fn strip_comment(text: &str) -> &str
+{
+    match text.find("//")
+    {
+        Some(index) => &text[..index],
+        None => text
+    }
+}
+
+/// Check if a string is a valid identifier (and so can be the name of a macro)
+fn is_identifier(name: &str) -> bool
+{
+    let mut chars = name.chars();
+
+    match chars.next()
+    {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
+/// Generate an error while preprocessing
+fn preprocessor_error<T>(code: ErrorCode, text: String, span: &Span) -> Result<T, Error>
+{
+    Err(Error::error(&format!("Preprocessor Error: {}", text)).with_code(code).with_label(span.clone(), ""))
+}
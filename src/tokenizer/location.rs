@@ -0,0 +1,66 @@
+use std::fmt;
+
+static DEFAULT_FILE_NAME: &'static str = "[unknown]";
+
+/// Location within a file (name, line, column and offset in bytes from the start of the file)
+#[derive(Debug, Clone)]
+pub struct FileLocation
+{
+    name: String,
+    pub col: usize,
+    pub row: usize,
+    pub offset: usize
+}
+
+impl FileLocation
+{
+    /// Generate a new FileLocation object
+    pub fn new() -> Self
+    {
+        FileLocation
+        {
+            name: String::from(DEFAULT_FILE_NAME),
+            col: 1,
+            row: 1,
+            offset: 0
+        }
+    }
+
+    /// Generate new FileLocation from a file name
+    pub fn from_name(name: &str) -> Self
+    {
+        FileLocation
+        {
+            name: String::from(name),
+            col: 1,
+            row: 1,
+            offset: 0
+        }
+    }
+
+    /// Get the name of the file
+    pub fn name(&self) -> &str
+    {
+        &self.name
+    }
+
+    /// Consume a character and move the file location accordingly
+    pub fn consume_char(&mut self, value: char)
+    {
+        self.col += 1;
+        self.offset += value.len_utf8();
+        match value
+        {
+            '\n' => {self.row += 1; self.col = 1;},
+            _ => {}
+        };
+    }
+}
+
+impl fmt::Display for FileLocation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "Line {}:{} in file '{}'", self.row, self.col, self.name)
+    }
+}
@@ -1,7 +1,9 @@
 mod token;
 mod tokenize;
-mod stream;
+mod location;
+mod span;
 
 pub use token::*;
 pub use tokenize::*;
-pub use stream::*;
\ No newline at end of file
+pub use location::*;
+pub use span::*;
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::fmt;
+use super::FileLocation;
+
+/// Range of the source covered by a token, or by a construct made of tokens, from its first character up to (but not
+/// including) the character after its last
+#[derive(Debug, Clone)]
+pub struct Span
+{
+    pub start: FileLocation,
+    pub end: FileLocation
+}
+
+impl Span
+{
+    /// Generate a new span between two locations
+    pub fn new(start: FileLocation, end: FileLocation) -> Self
+    {
+        Self
+        {
+            start,
+            end
+        }
+    }
+
+    /// Generate a span covering some text, starting at a location
+    pub fn from_text(location: &FileLocation, text: &str) -> Self
+    {
+        let mut end = location.clone();
+
+        for c in text.chars()
+        {
+            end.consume_char(c);
+        }
+
+        Self::new(location.clone(), end)
+    }
+
+    /// Get the span covering both spans, a span in another file (such as an included file) isn't covered
+    pub fn join(&self, other: &Span) -> Span
+    {
+        if self.start.name() != other.start.name()
+        {
+            return self.clone();
+        }
+
+        let start = if position(&other.start) < position(&self.start) {&other.start} else {&self.start};
+        let end = if position(&other.end) > position(&self.end) {&other.end} else {&self.end};
+
+        Span::new(start.clone(), end.clone())
+    }
+
+    /// Get the range of bytes the span covers in the source of its file, if the span is within the source
+    pub fn byte_range(&self, source: &str) -> Option<(usize, usize)>
+    {
+        let start = self.start.offset;
+        let end = self.end.offset.min(source.len());
+
+        if !source.is_char_boundary(start) || !source.is_char_boundary(end)
+        {
+            return None;
+        }
+
+        Some((start, end.max(start)))
+    }
+}
+
+/// Get the row and column of a location, in the order they appear in the source
+fn position(location: &FileLocation) -> (usize, usize)
+{
+    (location.row, location.col)
+}
+
+impl fmt::Display for Span
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.start)
+    }
+}
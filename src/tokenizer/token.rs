@@ -1,25 +1,192 @@
 use std::fmt;
-use super::FileLocation;
+use super::{FileLocation, Span};
 
-/// Token Object (data and FileLocation)
+/// Keywords of the language, names of types aren't keywords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword
+{
+    Loop,
+    While,
+    If,
+    Break,
+    Continue,
+    Else,
+    Do,
+    As,
+    Progmem,
+    Import,
+    Use,
+    Impl,
+    Let,
+    Return
+}
+
+static KEYWORDS: &[(&str, Keyword)] = &[
+    ("loop", Keyword::Loop), ("while", Keyword::While), ("if", Keyword::If), ("break", Keyword::Break),
+    ("continue", Keyword::Continue), ("else", Keyword::Else), ("do", Keyword::Do), ("as", Keyword::As),
+    ("progmem", Keyword::Progmem), ("import", Keyword::Import), ("use", Keyword::Use), ("impl", Keyword::Impl),
+    ("let", Keyword::Let), ("return", Keyword::Return)
+    ];
+
+impl Keyword
+{
+    /// Get the keyword spelled by some text
+    pub fn from_str(text: &str) -> Option<Self>
+    {
+        KEYWORDS.iter().find(|(name, _)| *name == text).map(|(_, keyword)| *keyword)
+    }
+}
+
+/// Punctuation and operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Punct
+{
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Semicolon,
+    Comma,
+    Colon,
+    PathSeparator,
+    Dot,
+    DotDot,
+    Question,
+    Tilde,
+    Hash,
+    Plus,
+    PlusPlus,
+    PlusEq,
+    Minus,
+    MinusMinus,
+    MinusEq,
+    Arrow,
+    And,
+    AndAnd,
+    AndEq,
+    Or,
+    OrOr,
+    OrEq,
+    Less,
+    LessEq,
+    ShiftLeft,
+    ShiftLeftEq,
+    Greater,
+    GreaterEq,
+    ShiftRight,
+    ShiftRightEq,
+    Eq,
+    EqEq,
+    Star,
+    StarEq,
+    Percent,
+    PercentEq,
+    Caret,
+    CaretEq,
+    Not,
+    NotEq,
+    Slash,
+    SlashEq
+}
+
+static PUNCTUATION: &[(&str, Punct)] = &[
+    ("{", Punct::LeftBrace), ("}", Punct::RightBrace), ("(", Punct::LeftParen), (")", Punct::RightParen),
+    ("[", Punct::LeftBracket), ("]", Punct::RightBracket), (";", Punct::Semicolon), (",", Punct::Comma),
+    (":", Punct::Colon), ("::", Punct::PathSeparator), (".", Punct::Dot), ("..", Punct::DotDot),
+    ("?", Punct::Question), ("~", Punct::Tilde), ("#", Punct::Hash), ("+", Punct::Plus), ("++", Punct::PlusPlus),
+    ("+=", Punct::PlusEq), ("-", Punct::Minus), ("--", Punct::MinusMinus), ("-=", Punct::MinusEq),
+    ("->", Punct::Arrow), ("&", Punct::And), ("&&", Punct::AndAnd), ("&=", Punct::AndEq), ("|", Punct::Or),
+    ("||", Punct::OrOr), ("|=", Punct::OrEq), ("<", Punct::Less), ("<=", Punct::LessEq), ("<<", Punct::ShiftLeft),
+    ("<<=", Punct::ShiftLeftEq), (">", Punct::Greater), (">=", Punct::GreaterEq), (">>", Punct::ShiftRight),
+    (">>=", Punct::ShiftRightEq), ("=", Punct::Eq), ("==", Punct::EqEq), ("*", Punct::Star), ("*=", Punct::StarEq),
+    ("%", Punct::Percent), ("%=", Punct::PercentEq), ("^", Punct::Caret), ("^=", Punct::CaretEq), ("!", Punct::Not),
+    ("!=", Punct::NotEq), ("/", Punct::Slash), ("/=", Punct::SlashEq)
+    ];
+
+impl Punct
+{
+    /// Get the punctuation spelled by some text
+    pub fn from_str(text: &str) -> Option<Self>
+    {
+        PUNCTUATION.iter().find(|(name, _)| *name == text).map(|(_, punct)| *punct)
+    }
+}
+
+/// Kind of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind
+{
+    Ident,
+    IntLit,
+    StrLit,
+    Keyword(Keyword),
+    Punct(Punct),
+    Eof
+}
+
+impl From<Keyword> for TokenKind
+{
+    fn from(keyword: Keyword) -> Self
+    {
+        TokenKind::Keyword(keyword)
+    }
+}
+
+impl From<Punct> for TokenKind
+{
+    fn from(punct: Punct) -> Self
+    {
+        TokenKind::Punct(punct)
+    }
+}
+
+impl fmt::Display for TokenKind
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            TokenKind::Ident => write!(f, "identifier"),
+            TokenKind::IntLit => write!(f, "integer"),
+            TokenKind::StrLit => write!(f, "string"),
+            TokenKind::Keyword(keyword) => write!(f, "{}", KEYWORDS.iter().find(|(_, k)| k == keyword).unwrap().0),
+            TokenKind::Punct(punct) => write!(f, "{}", PUNCTUATION.iter().find(|(_, p)| p == punct).unwrap().0),
+            TokenKind::Eof => write!(f, "end of file")
+        }
+    }
+}
+
+/// Token Object (kind, data and the FileLocations it starts and ends at)
 #[derive(Debug, Clone)]
 pub struct Token
 {
+    pub kind: TokenKind,
     pub location: FileLocation,
+    pub end: FileLocation,
     pub data: String
 }
 
 impl Token
 {
-    /// Generate a new token object
-    pub fn new(location: FileLocation, data: String) -> Self
+    /// Generate a new token object of a kind, covering the source between two locations
+    pub fn with_kind(kind: TokenKind, data: String, location: FileLocation, end: FileLocation) -> Self
     {
         Self
         {
+            kind,
             location,
+            end,
             data
         }
     }
+
+    /// Get the span of the source covered by the token
+    pub fn span(&self) -> Span
+    {
+        Span::new(self.location.clone(), self.end.clone())
+    }
 }
 
 impl fmt::Display for Token
@@ -28,4 +195,4 @@ impl fmt::Display for Token
     {
         write!(f, "{:?}{}\t{}", self.data, if format!("{:?}", self.data).len() >= 8 {""} else {"\t"}, self.location)
     }
-}
\ No newline at end of file
+}
@@ -1,198 +1,197 @@
-use super::{Token, FileLocation, Stream};
+use super::{Token, TokenKind, Keyword, Punct, FileLocation, Span};
+use crate::cli::{Error, ErrorCode};
 
-/// Tokenizer wrapper object
-struct Tokenizer
+/// Lexer wrapper object, reading the source a character at a time
+struct Lexer<'a>
 {
-    current_data: String,
-    source: Stream,
-    pos: FileLocation,
-    tokens: Vec<Token>
+    source: &'a str,
+    index: usize,
+    location: FileLocation,
+    tokens: Vec<Token>,
+    errors: Vec<Error>
 }
 
-impl Tokenizer
+impl<'a> Lexer<'a>
 {
-    /// Generate a new tokenizer
-    fn new(source: Stream) -> Self
+    /// Generate a new lexer
+    fn new(source: &'a str, file_name: &str) -> Self
     {
         Self
         {
-            current_data: String::new(),
-            pos: source.current_location().clone(),
-            source: source,
-            tokens: vec![]
+            source,
+            index: 0,
+            location: FileLocation::from_name(file_name),
+            tokens: vec![],
+            errors: vec![]
         }
     }
 
-    /// Push the current token
-    fn push_current(&mut self)
+    /// Get the source which is left
+    fn rest(&self) -> &'a str
     {
-        if self.current_data.len() != 0
+        &self.source[self.index..]
+    }
+
+    /// Get the current character
+    fn current(&self) -> Option<char>
+    {
+        self.rest().chars().next()
+    }
+
+    /// Consume the current character
+    fn consume(&mut self)
+    {
+        if let Some(c) = self.current()
         {
-            self.tokens.push(Token::new(self.pos.clone(), self.current_data.clone()));
-            self.current_data = String::new();
+            self.index += c.len_utf8();
+            self.location.consume_char(c);
         }
+    }
 
-        self.pos = self.source.current_location();
-        
+    /// Consume characters while they match a predicate
+    fn consume_while(&mut self, predicate: impl Fn(char) -> bool)
+    {
+        while self.current().is_some_and(&predicate)
+        {
+            self.consume();
+        }
     }
 
-    /// Push the current character
-    fn push_char(&mut self, c: char)
+    /// Push a token of the source from a byte index and location up to the current character
+    fn push(&mut self, kind: TokenKind, start: usize, location: FileLocation)
     {
-        self.current_data.push(c);
+        let data = String::from(&self.source[start..self.index]);
+        self.tokens.push(Token::with_kind(kind, data, location, self.location.clone()));
     }
 
-    /// Consume the next character
-    fn consume(&mut self)
+    /// Find the longest punctuation at the current character, along with its length
+    fn punctuation(&self) -> Option<(Punct, usize)>
     {
-        self.source.consume();
-        if self.current_data.len() == 0
+        (1..=3).rev().find_map(|length| self.rest().get(..length).and_then(Punct::from_str).map(|punct| (punct, length)))
+    }
+
+    /// Read a `#line ROW "FILE" OFFSET` marker from the preprocessor (after the newline before it), the location
+    /// moves to the start of the line which follows the marker
+    fn line_marker(&mut self)
+    {
+        let start = self.index;
+        self.consume_while(|c| c != '\n');
+        let marker = &self.source[start..self.index];
+        self.consume();
+
+        if let Some((row, offset, name)) = parse_line_marker(marker)
         {
-            self.pos = self.source.current_location();
+            self.location = FileLocation::from_name(&name);
+            self.location.row = row;
+            self.location.offset = offset;
         }
     }
+}
+
+/// Parse a `#line ROW "FILE" OFFSET` marker, giving the row, byte offset and file name
+fn parse_line_marker(marker: &str) -> Option<(usize, usize, String)>
+{
+    let (row, rest) = marker.strip_prefix("#line ")?.split_once(' ')?;
+    let (name, offset) = rest.rsplit_once(' ')?;
+    let name = name.strip_prefix('"')?.strip_suffix('"')?;
 
-    /// Move the file position back
-    fn move_back(&mut self)
+    match (row.parse::<usize>(), offset.parse::<usize>())
     {
-        self.pos.col -= 1;
+        (Ok(row), Ok(offset)) if row > 0 => Some((row, offset, String::from(name))),
+        _ => None
     }
 }
 
-/// Tokenize input from a file
-pub fn tokenize(input: String, file_name: String) -> Vec<Token>
+/// Tokenize input from a file, giving its tokens (ending with an end of file token) and an error for every illegal
+/// character in it
+pub fn tokenize(input: String, file_name: String) -> (Vec<Token>, Vec<Error>)
 {
-    let mut tokenizer = Tokenizer::new(Stream::new(input, file_name));
+    let mut lexer = Lexer::new(&input, &file_name);
 
-    let mut single_line_comment: bool = false;
-    let mut multi_line_comment: bool = false;
-
-    loop 
+    while let Some(current) = lexer.current()
     {
-        match tokenizer.source.current()
+        let start = lexer.index;
+        let location = lexer.location.clone();
+
+        // Line markers from the preprocessor give the location of the line which follows them
+        if current == '\n' && lexer.rest()[1..].starts_with("#line ")
+        {
+            lexer.consume();
+            lexer.line_marker();
+        }
+        else if current.is_whitespace()
+        {
+            lexer.consume();
+        }
+        else if lexer.rest().starts_with("//")
         {
-            Some(current) =>
+            lexer.consume_while(|c| c != '\n');
+        }
+        else if lexer.rest().starts_with("/*")
+        {
+            lexer.consume();
+            lexer.consume();
+
+            while lexer.current().is_some() && !lexer.rest().starts_with("*/")
+            {
+                lexer.consume();
+            }
+
+            lexer.consume();
+            lexer.consume();
+        }
+        // String literals are kept as a single token, including the quotes, and end at the end of the line if they
+        // aren't closed
+        else if current == '"'
+        {
+            lexer.consume();
+            lexer.consume_while(|c| c != '"' && c != '\n');
+
+            if lexer.current() == Some('"')
+            {
+                lexer.consume();
+            }
+
+            lexer.push(TokenKind::StrLit, start, location);
+        }
+        else if current.is_ascii_digit()
+        {
+            lexer.consume_while(|c| c.is_ascii_digit());
+            lexer.push(TokenKind::IntLit, start, location);
+        }
+        else if current.is_ascii_alphabetic() || current == '_'
+        {
+            lexer.consume_while(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            let kind = match Keyword::from_str(&lexer.source[start..lexer.index])
+            {
+                Some(keyword) => TokenKind::Keyword(keyword),
+                None => TokenKind::Ident
+            };
+
+            lexer.push(kind, start, location);
+        }
+        else if let Some((punct, length)) = lexer.punctuation()
+        {
+            for _ in 0..length
             {
-                if single_line_comment
-                {
-                    if current.0 == '/' && tokenizer.source.check_next('/')
-                    {
-                        tokenizer.source.consume();
-                        single_line_comment = false;
-                    }
-                    else if current.0 == '\n'
-                    {
-                        single_line_comment = false;
-                    }
-                }
-                else if multi_line_comment
-                {
-                    if current.0 == '*' && tokenizer.source.check_next('/')
-                    {
-                        tokenizer.source.consume();
-                        multi_line_comment = false;
-                    }
-                }
-                else
-                {
-                    match current.0
-                    {
-                        ' ' | '\n' => {tokenizer.push_current();},
-                        '{' | '}' | '(' | ')' | '[' | ']' | ';' | ',' | ':' | '.' | '?' | '~' => 
-                            {
-                                tokenizer.push_current();
-                                tokenizer.push_char(current.0);
-                                tokenizer.move_back();
-                                tokenizer.push_current();
-                            },
-                        '+' | '-' | '&' | '|' | '<' | '>' | '=' =>
-                        {
-                            tokenizer.push_current();
-
-                            // Repeated
-                            if tokenizer.source.check_next_vec(vec![current.0, '=']) || 
-                                    current.0 == '-' && tokenizer.source.check_next('>')
-                            {
-                                tokenizer.push_char(current.0);
-                                tokenizer.move_back();
-                                tokenizer.source.consume();
-                                tokenizer.push_char(tokenizer.source.current().unwrap().0);
-                                if vec!['<', '>'].contains(&current.0) && tokenizer.source.check_next('=')
-                                {
-                                    tokenizer.source.consume();
-                                    tokenizer.push_char(tokenizer.source.current().unwrap().0);
-                                }
-                                tokenizer.push_current();
-                            }
-                            else
-                            {
-                                tokenizer.move_back();
-                                tokenizer.push_char(current.0);
-                                tokenizer.push_current();
-                            }
-                        },
-                        '*' | '%' | '^' | '!' =>
-                        {
-                            tokenizer.push_current();
-
-                            if tokenizer.source.check_next('=')
-                            {
-                                tokenizer.push_char(current.0);
-                                tokenizer.move_back();
-                                tokenizer.source.consume();
-                                tokenizer.push_char('=');
-                                tokenizer.push_current();
-                            }
-                            else
-                            {
-                                tokenizer.move_back();
-                                tokenizer.push_char(current.0);
-                                tokenizer.push_current();
-                            }
-                        }
-                        '/' =>
-                        {
-                            tokenizer.push_current();
-
-                            if tokenizer.source.check_next('*')
-                            {
-                                tokenizer.consume();
-                                multi_line_comment = true;
-                            }
-                            else if tokenizer.source.check_next('/')
-                            {
-                                tokenizer.consume();
-                                single_line_comment = true;
-                            }
-                            else
-                            {
-                                if tokenizer.source.check_next('=')
-                                {
-                                    tokenizer.push_char(current.0);
-                                    tokenizer.move_back();
-                                    tokenizer.source.consume();
-                                    tokenizer.push_char('=');
-                                    tokenizer.push_current();
-                                }
-                                else
-                                {
-                                    tokenizer.move_back();
-                                    tokenizer.push_char(current.0);
-                                    tokenizer.push_current();
-                                }
-                            }
-                        }
-                        default => {tokenizer.push_char(default);}
-                    }
-                }
-
-                tokenizer.source.consume();
-            },
-            None => {break;}
+                lexer.consume();
+            }
+
+            lexer.push(TokenKind::Punct(punct), start, location);
+        }
+        else
+        {
+            lexer.consume();
+
+            let span = Span::new(location, lexer.location.clone());
+            lexer.errors.push(Error::error(&format!("Parse Error: Illegal character {:?}", current))
+                .with_code(ErrorCode::E0038).with_label(span, "not allowed here"));
         }
     }
 
-    tokenizer.push_current();
+    let location = lexer.location.clone();
+    lexer.tokens.push(Token::with_kind(TokenKind::Eof, String::new(), location.clone(), location));
 
-    tokenizer.tokens
-}
\ No newline at end of file
+    (lexer.tokens, lexer.errors)
+}
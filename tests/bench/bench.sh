@@ -0,0 +1,52 @@
+#!/usr/bin/env bash
+
+# Generates the benchmark corpus in ./out/bench and times compiling each file of it with a release build
+# usage: tests/bench/bench.sh [LINES]
+
+lines=${1:-50000}
+out=./out/bench
+
+mkdir -p $out
+cargo build --release || exit 1
+
+# Lookup tables in program memory, as generated for a target, with a function reading each of them
+awk -v lines=$lines 'BEGIN {
+    rows = 120;
+    for (t = 0; t * (rows + 6) < lines; t++)
+    {
+        printf "progmem u8 TABLE_%d[] = {\n", t;
+        for (r = 0; r < rows; r++)
+        {
+            printf "   ";
+            for (c = 0; c < 16; c++) printf " %d,", (t * 7 + r * 16 + c) % 256;
+            printf "\n";
+        }
+        printf "};\n\n";
+        printf "u8 lookup_%d(u16 i) { return TABLE_%d[i]; }\n\n", t, t;
+    }
+    printf "i32 main() { return 0; }\n";
+}' > $out/tables.pc
+
+# Functions made of many short statements
+awk -v lines=$lines 'BEGIN {
+    body = 20;
+    for (f = 0; f * (body + 8) < lines; f++)
+    {
+        printf "u16 step_%d(u16 x, u16* buffer)\n{\n    u16 total = 0;\n\n", f;
+        for (s = 0; s < body; s++)
+        {
+            if (s % 4 == 0) printf "    total += buffer[%d] * %d;\n", s % 64, s + 1;
+            else if (s % 4 == 1) printf "    if (total > %d) { total = total - x; }\n", s * 3;
+            else if (s % 4 == 2) printf "    buffer[%d] = (total >> 2) ^ %d;\n", s % 64, f;
+            else printf "    x = x + 1;\n";
+        }
+        printf "\n    return total;\n}\n\n";
+    }
+    printf "i32 main() { return 0; }\n";
+}' > $out/statements.pc
+
+for file in $out/tables.pc $out/statements.pc
+do
+    echo "$file ($(wc -l < $file) lines)"
+    time ./target/release/compiler $file -o $out/out.ll -g llvm -A unused-function
+done